@@ -0,0 +1,195 @@
+use crate::config::LoadConfig;
+use crate::make_payload;
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::time::{timeout, timeout_at};
+
+// 基准模式所有连接共享的统计
+#[derive(Default)]
+pub struct BenchStats {
+    succeeded: AtomicUsize, // 持续发送到结束的连接数
+    failed: AtomicUsize,    // 连接失败或中途出错的连接数
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    blocked_nanos: AtomicU64, // 写缓冲区满、等待对端读取的总时间
+}
+
+fn mb_per_sec(bytes: u64, secs: f64) -> f64 {
+    bytes as f64 / secs / (1024.0 * 1024.0)
+}
+
+impl BenchStats {
+    // 结束时打印的统计，发送与接收的差值即服务器或网络中积压的数据
+    pub fn report(&self, config: &LoadConfig, elapsed: Duration, interrupted: bool) {
+        let sent = self.bytes_sent.load(Ordering::Relaxed);
+        let received = self.bytes_received.load(Ordering::Relaxed);
+        let blocked = Duration::from_nanos(self.blocked_nanos.load(Ordering::Relaxed));
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+
+        let title = if interrupted {
+            "吞吐基准结果 (已提前结束)"
+        } else {
+            "吞吐基准结果"
+        };
+        println!("\n==== {} ====", title);
+        println!("总耗时: {:.3} 秒", elapsed.as_secs_f64());
+        println!(
+            "连接: 成功 {}, 失败 {} (共 {})",
+            self.succeeded.load(Ordering::Relaxed),
+            self.failed.load(Ordering::Relaxed),
+            config.connections
+        );
+        println!("发送: {} 字节, {:.2} MB/s", sent, mb_per_sec(sent, secs));
+        println!("接收: {} 字节, {:.2} MB/s", received, mb_per_sec(received, secs));
+        println!(
+            "差值: {} 字节, {:.2} MB/s (服务器或网络积压)",
+            sent.saturating_sub(received),
+            mb_per_sec(sent.saturating_sub(received), secs)
+        );
+        // 多条连接的阻塞时间会叠加，按连接平均后再与总耗时比较
+        let per_connection = blocked / config.connections.max(1) as u32;
+        println!(
+            "写阻塞: 合计 {:.3} 秒, 平均每条连接 {:.3} 秒 (占 {:.1}%)",
+            blocked.as_secs_f64(),
+            per_connection.as_secs_f64(),
+            per_connection.as_secs_f64() / secs * 100.0
+        );
+    }
+}
+
+// 写入一个数据块，写缓冲区满时等待可写并累计等待时间；到达截止时间返回 Ok(false)
+async fn write_chunk(
+    writer: &OwnedWriteHalf,
+    chunk: &[u8],
+    deadline: tokio::time::Instant,
+    stats: &BenchStats,
+) -> Result<bool, String> {
+    let mut offset = 0;
+    while offset < chunk.len() {
+        match writer.try_write(&chunk[offset..]) {
+            Ok(0) => return Err("连接已被对端关闭".to_string()),
+            Ok(n) => {
+                offset += n;
+                stats.bytes_sent.fetch_add(n as u64, Ordering::Relaxed);
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                let waiting = Instant::now();
+                let ready = timeout_at(deadline, writer.as_ref().writable()).await;
+                stats
+                    .blocked_nanos
+                    .fetch_add(waiting.elapsed().as_nanos() as u64, Ordering::Relaxed);
+                match ready {
+                    Ok(result) => result.map_err(|e| format!("等待可写失败: {}", e))?,
+                    Err(_) => return Ok(false),
+                }
+            }
+            Err(e) => return Err(format!("发送失败: {}", e)),
+        }
+    }
+    Ok(true)
+}
+
+// 持续写入直到截止时间
+async fn write_until(
+    writer: OwnedWriteHalf,
+    chunk: Vec<u8>,
+    deadline: tokio::time::Instant,
+    stats: &BenchStats,
+) -> Result<(), String> {
+    while tokio::time::Instant::now() < deadline {
+        if !write_chunk(&writer, &chunk, deadline, stats).await? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// 并行读取并统计回收的字节，到截止时间或对端关闭时结束
+async fn read_until(
+    mut reader: OwnedReadHalf,
+    size: usize,
+    deadline: tokio::time::Instant,
+    stats: &BenchStats,
+) -> Result<(), String> {
+    let mut buf = vec![0; size.max(64 * 1024)];
+    loop {
+        match timeout_at(deadline, reader.read(&mut buf)).await {
+            Err(_) | Ok(Ok(0)) => return Ok(()),
+            Ok(Ok(n)) => {
+                stats.bytes_received.fetch_add(n as u64, Ordering::Relaxed);
+            }
+            Ok(Err(e)) => return Err(format!("接收失败: {}", e)),
+        }
+    }
+}
+
+// 单条连接：写任务与读任务并行，直到持续时间结束
+async fn run_connection(config: &LoadConfig, stats: &BenchStats) -> Result<(), String> {
+    let stream = timeout(config.timeout, TcpStream::connect(&config.addr))
+        .await
+        .map_err(|_| "连接超时".to_string())?
+        .map_err(|e| format!("无法连接: {}", e))?;
+    let (reader, writer) = stream.into_split();
+    let chunk = make_payload(&config.payload, config.size, 0);
+    let deadline = tokio::time::Instant::now() + config.bench_duration();
+
+    let (written, read) = tokio::join!(
+        write_until(writer, chunk, deadline, stats),
+        read_until(reader, config.size, deadline, stats)
+    );
+    written.and(read)
+}
+
+// 启动所有连接并在结束或 Ctrl+C 后打印统计
+pub async fn run(config: LoadConfig) {
+    println!(
+        "吞吐基准 {}: {} 条连接, 持续 {} 秒, 每次写入 {} 字节 ({})",
+        config.addr,
+        config.connections,
+        config.bench_duration().as_secs(),
+        config.size,
+        config.payload.name()
+    );
+
+    let config = Arc::new(config);
+    let stats = Arc::new(BenchStats::default());
+    let started_at = Instant::now();
+    let tasks: Vec<_> = (0..config.connections)
+        .map(|id| {
+            let config = Arc::clone(&config);
+            let stats = Arc::clone(&stats);
+            tokio::spawn(async move {
+                match run_connection(&config, &stats).await {
+                    Ok(()) => {
+                        stats.succeeded.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(e) => {
+                        stats.failed.fetch_add(1, Ordering::Relaxed);
+                        eprintln!("连接 #{} 失败: {}", id + 1, e);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    let all_done = async {
+        for task in tasks {
+            let _ = task.await;
+        }
+    };
+    let interrupted = tokio::select! {
+        _ = all_done => false,
+        _ = tokio::signal::ctrl_c() => true,
+    };
+    stats.report(&config, started_at.elapsed(), interrupted);
+
+    // 提前结束时不等待仍在进行的连接
+    if interrupted {
+        std::process::exit(0);
+    }
+}
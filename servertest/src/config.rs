@@ -0,0 +1,300 @@
+use std::time::Duration;
+use tcpcommon::hexdump::parse_hex;
+
+// 默认的服务器地址
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8888";
+
+// 默认每条消息的字节数
+pub const DEFAULT_SIZE: usize = 64;
+
+// 默认等待每条 echo 的时间
+pub const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+// 吞吐基准模式默认的持续时间
+pub const DEFAULT_BENCH_SECS: u64 = 10;
+
+// 浸泡测试默认每轮的间隔
+pub const DEFAULT_SOAK_INTERVAL_SECS: u64 = 1;
+
+// 浸泡测试默认连续失败多少次时告警
+pub const DEFAULT_ALERT_AFTER: usize = 3;
+
+pub const USAGE: &str = "用法: servertest [选项]
+
+并发建立多条连接，每条连接发送若干条消息并等待服务器 echo 回来，结束后打印统计和延迟分布。
+使用 --bench 时改为吞吐基准：不等待响应，持续写入数据块，另一个任务并行读取并统计回收的字节。
+使用 --soak 时改为浸泡测试：周期性地建立连接、交换一轮消息后正常关闭，每分钟打印一行心跳统计，
+结束时列出所有失败的时间点，用于观察服务器长时间运行后是否泄漏连接或内存。
+按 Ctrl+C 提前结束并打印已完成部分的统计。
+
+选项:
+  --addr <主机:端口>   服务器地址 (默认 127.0.0.1:8888)
+  --connections <N>    并发连接数 (默认 1)
+  --messages <M>       每条连接发送的消息数 (默认 1)
+  --size <字节>        每条消息的字节数 (默认 64)
+  --payload <类型>     消息内容 (默认 pattern)
+                         pattern  可打印字符循环组成的固定内容
+                         random   每条消息重新生成的随机字节
+  --text <文本>        发送指定的文本，消息大小为文本的字节数
+  --hex <十六进制>     发送指定的字节，如 \"01 02 03\"
+  --file <路径>        发送文件的内容
+                       --text、--hex、--file 只能指定一个，且不能与 --payload、--size 同时使用
+  --repeat <N>         每条连接重复发送 N 次并逐条打印响应 (不能与 --messages 同时使用)
+  --display <格式>     打印响应的格式: utf8 或 hex (默认 utf8)
+  --verify             在每条消息前加上长度、递增序号和校验和，按长度重新分帧后校验回包
+  --timeout <秒>       连接和等待每条 echo 的超时时间 (默认 10)
+  --bench              吞吐基准模式，--size 为每次写入的数据块大小 (不能与 --messages、--repeat 同时使用)
+  --soak               浸泡测试模式，每轮只建立一条连接 (不能与 --bench、--connections 同时使用)
+  --interval <秒>      浸泡测试每轮的间隔 (默认 1)
+  --alert-after <N>    浸泡测试连续失败 N 次时告警，测试继续进行 (默认 3)
+  --duration <秒>      基准模式持续发送的时间 (默认 10)；浸泡测试的总时长 (默认直到 Ctrl+C)
+  -h, --help           显示帮助信息";
+
+// 消息内容的生成方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    Pattern,        // 可打印字符循环
+    Random,         // 随机字节
+    Fixed(Vec<u8>), // 命令行指定的内容 (--text、--hex 或 --file)
+}
+
+impl Payload {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pattern" => Some(Payload::Pattern),
+            "random" => Some(Payload::Random),
+            _ => None,
+        }
+    }
+
+    // 启动信息中显示的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Payload::Pattern => "pattern",
+            Payload::Random => "random",
+            Payload::Fixed(_) => "自定义",
+        }
+    }
+}
+
+// 打印响应的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Display {
+    Utf8, // 按 UTF-8 显示，非法字节替换为 U+FFFD
+    Hex,  // 大写、空格分隔的十六进制
+}
+
+impl Display {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "utf8" => Some(Display::Utf8),
+            "hex" => Some(Display::Hex),
+            _ => None,
+        }
+    }
+}
+
+// 压测参数
+#[derive(Debug, Clone)]
+pub struct LoadConfig {
+    pub addr: String,
+    pub connections: usize,
+    pub messages: usize, // 每条连接发送的消息数
+    pub size: usize,     // 每条消息的字节数
+    pub payload: Payload,
+    pub timeout: Duration, // 连接和等待每条 echo 的超时时间
+    pub print_responses: bool, // 逐条打印收到的响应 (--repeat)
+    pub display: Display,
+    pub verify: bool,       // 消息带帧头，校验回包的序号和内容 (--verify)
+    pub bench: bool,                // 吞吐基准模式 (--bench)
+    pub soak: bool,                 // 浸泡测试模式 (--soak)
+    pub interval: Duration,         // 浸泡测试每轮的间隔
+    pub alert_after: usize,         // 浸泡测试连续失败多少次时告警
+    pub duration: Option<Duration>, // 基准或浸泡模式的持续时间，未指定时分别为默认值和直到 Ctrl+C
+}
+
+impl LoadConfig {
+    // 基准模式持续发送的时间
+    pub fn bench_duration(&self) -> Duration {
+        self.duration
+            .unwrap_or(Duration::from_secs(DEFAULT_BENCH_SECS))
+    }
+}
+
+// 参数解析失败的原因
+#[derive(Debug)]
+pub enum ArgsError {
+    Help,            // 用户请求显示帮助
+    Invalid(String), // 参数非法
+}
+
+// 取出选项的值，支持 "--size 1024" 和 "--size=1024" 两种写法
+fn option_value(
+    name: &str,
+    inline: Option<&str>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, ArgsError> {
+    match inline {
+        Some(value) => Ok(value.to_string()),
+        None => args
+            .next()
+            .ok_or_else(|| ArgsError::Invalid(format!("{} 缺少参数值", name))),
+    }
+}
+
+// 解析大于 0 的整数选项
+fn positive(value: &str, what: &str) -> Result<usize, ArgsError> {
+    value
+        .parse()
+        .ok()
+        .filter(|&n| n > 0)
+        .ok_or_else(|| ArgsError::Invalid(format!("无效的{}: {}", what, value)))
+}
+
+// 解析命令行参数 (不含程序名)
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<LoadConfig, ArgsError> {
+    let mut config = LoadConfig {
+        addr: DEFAULT_ADDR.to_string(),
+        connections: 1,
+        messages: 1,
+        size: DEFAULT_SIZE,
+        payload: Payload::Pattern,
+        timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+        print_responses: false,
+        display: Display::Utf8,
+        verify: false,
+        bench: false,
+        soak: false,
+        interval: Duration::from_secs(DEFAULT_SOAK_INTERVAL_SECS),
+        alert_after: DEFAULT_ALERT_AFTER,
+        duration: None,
+    };
+    // 用于检查互斥的选项
+    let mut fixed: Option<Vec<u8>> = None;
+    let (mut size_given, mut payload_given, mut messages_given) = (false, false, false);
+    let (mut connections_given, mut interval_given, mut alert_given) = (false, false, false);
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value)),
+            None => (arg.clone(), None),
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => return Err(ArgsError::Help),
+            "--addr" => config.addr = option_value(&name, inline, &mut args)?,
+            "--connections" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.connections = positive(&value, "连接数")?;
+                connections_given = true;
+            }
+            "--messages" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.messages = positive(&value, "消息数")?;
+                messages_given = true;
+            }
+            "--size" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.size = positive(&value, "消息大小")?;
+                size_given = true;
+            }
+            "--payload" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.payload = Payload::parse(&value)
+                    .ok_or_else(|| ArgsError::Invalid(format!("未知的消息类型: {}", value)))?;
+                payload_given = true;
+            }
+            "--text" | "--hex" | "--file" => {
+                if fixed.is_some() {
+                    return Err(ArgsError::Invalid(
+                        "--text、--hex、--file 只能指定一个".to_string(),
+                    ));
+                }
+                let value = option_value(&name, inline, &mut args)?;
+                let data = match name.as_str() {
+                    "--text" => value.into_bytes(),
+                    "--hex" => parse_hex(&value).ok_or_else(|| {
+                        ArgsError::Invalid(format!("无效的十六进制内容: {}", value))
+                    })?,
+                    _ => std::fs::read(&value).map_err(|e| {
+                        ArgsError::Invalid(format!("无法读取载荷文件 {}: {}", value, e))
+                    })?,
+                };
+                if data.is_empty() {
+                    return Err(ArgsError::Invalid(format!("{} 的内容为空", name)));
+                }
+                fixed = Some(data);
+            }
+            "--repeat" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.messages = positive(&value, "重复次数")?;
+                config.print_responses = true;
+            }
+            "--display" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.display = Display::parse(&value)
+                    .ok_or_else(|| ArgsError::Invalid(format!("未知的显示格式: {}", value)))?;
+            }
+            "--timeout" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.timeout = Duration::from_secs(positive(&value, "超时时间")? as u64);
+            }
+            "--verify" => config.verify = true,
+            "--bench" => config.bench = true,
+            "--soak" => config.soak = true,
+            "--interval" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.interval = Duration::from_secs(positive(&value, "间隔")? as u64);
+                interval_given = true;
+            }
+            "--alert-after" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.alert_after = positive(&value, "告警阈值")?;
+                alert_given = true;
+            }
+            "--duration" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.duration = Some(Duration::from_secs(positive(&value, "持续时间")? as u64));
+            }
+            _ => return Err(ArgsError::Invalid(format!("未知参数: {}", arg))),
+        }
+    }
+
+    if messages_given && config.print_responses {
+        return Err(ArgsError::Invalid("--repeat 不能与 --messages 同时使用".to_string()));
+    }
+    if config.bench && (messages_given || config.print_responses) {
+        return Err(ArgsError::Invalid(
+            "--bench 不能与 --messages、--repeat 同时使用".to_string(),
+        ));
+    }
+    if config.bench && config.verify {
+        return Err(ArgsError::Invalid("--verify 不能与 --bench 同时使用".to_string()));
+    }
+    if config.soak && (config.bench || connections_given) {
+        return Err(ArgsError::Invalid(
+            "--soak 不能与 --bench、--connections 同时使用".to_string(),
+        ));
+    }
+    if (interval_given || alert_given) && !config.soak {
+        return Err(ArgsError::Invalid(
+            "--interval、--alert-after 只能在 --soak 模式下使用".to_string(),
+        ));
+    }
+    if config.duration.is_some() && !config.bench && !config.soak {
+        return Err(ArgsError::Invalid(
+            "--duration 只能在 --bench 或 --soak 模式下使用".to_string(),
+        ));
+    }
+    if let Some(data) = fixed {
+        if size_given || payload_given {
+            return Err(ArgsError::Invalid(
+                "--text、--hex、--file 不能与 --payload、--size 同时使用".to_string(),
+            ));
+        }
+        config.size = data.len();
+        config.payload = Payload::Fixed(data);
+    }
+    Ok(config)
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+// 直方图柱子的最大宽度 (字符数)
+const BAR_WIDTH: usize = 40;
+
+// 以微秒为单位显示延迟，1 毫秒以上换算为毫秒
+fn format_micros(micros: u64) -> String {
+    if micros < 1000 {
+        format!("{}us", micros)
+    } else {
+        format!("{:.1}ms", micros as f64 / 1000.0)
+    }
+}
+
+fn format_latency(latency: Duration) -> String {
+    format!("{:.3}ms", latency.as_secs_f64() * 1000.0)
+}
+
+// 已排序样本的百分位数 (最近秩法)
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+// 每条消息从发送到收齐 echo 的耗时
+#[derive(Default)]
+pub struct Latencies {
+    samples: Vec<Duration>,
+}
+
+impl Latencies {
+    pub fn record(&mut self, latency: Duration) {
+        self.samples.push(latency);
+    }
+
+    // 最近记录的一个样本
+    pub fn last(&self) -> Option<Duration> {
+        self.samples.last().copied()
+    }
+
+    // 合并单条连接收集的样本
+    pub fn extend(&mut self, other: Latencies) {
+        self.samples.extend(other.samples);
+    }
+
+    // 打印 min/avg/p50/p90/p99/max 以及按 2 的幂分桶的文本直方图
+    pub fn report(&self) {
+        if self.samples.is_empty() {
+            println!("延迟: 无样本");
+            return;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        let total: Duration = sorted.iter().sum();
+        println!(
+            "延迟: min {}, avg {}, p50 {}, p90 {}, p99 {}, max {}",
+            format_latency(sorted[0]),
+            format_latency(total / sorted.len() as u32),
+            format_latency(percentile(&sorted, 50.0)),
+            format_latency(percentile(&sorted, 90.0)),
+            format_latency(percentile(&sorted, 99.0)),
+            format_latency(sorted[sorted.len() - 1]),
+        );
+
+        // 第 i 个桶统计 [2^i, 2^(i+1)) 微秒的样本，不足 1 微秒的计入第一个桶
+        let mut buckets = [0usize; 64];
+        for latency in &sorted {
+            let micros = (latency.as_micros() as u64).max(1);
+            buckets[micros.ilog2() as usize] += 1;
+        }
+        let first = buckets.iter().position(|&n| n > 0).unwrap_or(0);
+        let last = buckets.iter().rposition(|&n| n > 0).unwrap_or(0);
+        let peak = buckets[first..=last].iter().copied().max().unwrap_or(1);
+
+        println!("延迟分布:");
+        for (index, &count) in buckets.iter().enumerate().take(last + 1).skip(first) {
+            let bar = "#".repeat((count * BAR_WIDTH).div_ceil(peak));
+            println!(
+                "  {:>8} - {:<8} {:<width$} {}",
+                format_micros(1 << index),
+                format_micros((1u64 << index).saturating_mul(2)),
+                bar,
+                count,
+                width = BAR_WIDTH
+            );
+        }
+    }
+}
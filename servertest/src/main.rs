@@ -1,71 +1,305 @@
+mod bench;
+mod config;
+mod latency;
+mod soak;
+mod verify;
+
+use config::{parse_args, ArgsError, Display, LoadConfig, Payload, USAGE};
+use latency::Latencies;
+use rand::RngCore;
 use std::error::Error;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tcpcommon::hexdump::to_hex;
+use tokio::time::timeout;
+use verify::{encode_frame, Frame, FrameDecoder, Verifier, VerifyStats};
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    // 连接到服务器
-    let server_addr = "127.0.0.1:8888";
-    println!("尝试连接到服务器 {}", server_addr);
-
-    match TcpStream::connect(server_addr).await {
-        Ok(mut stream) => {
-            println!("成功连接到服务器!");
-
-            // 要发送的消息
-            let message = "Hello, TCP Server!";
-            println!("发送消息: {}", message);
-
-            // 发送消息
-            stream.write_all(message.as_bytes()).await?;
-
-            // 接收响应
-            let mut buffer = vec![0; 1024];
-            let n = stream.read(&mut buffer).await?;
-
-            if n > 0 {
-                let response = String::from_utf8_lossy(&buffer[0..n]);
-                println!("接收到响应: {}", response);
-            } else {
-                println!("服务器关闭了连接，没有接收到响应");
-            }
+// 所有连接任务共享的统计
+#[derive(Default)]
+struct LoadStats {
+    succeeded: AtomicUsize, // 所有消息都收到正确 echo 的连接数
+    failed: AtomicUsize,    // 连接失败或中途出错的连接数
+    messages: AtomicU64,    // 收到正确 echo 的消息数
+    mismatched: AtomicU64,  // echo 内容与请求不一致的消息数，不计入延迟统计
+    latencies: Mutex<Latencies>,
+    verify: Mutex<VerifyStats>, // --verify 时的回包校验统计
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl LoadStats {
+    // 结束时打印的统计，interrupted 为 true 时说明是提前结束
+    fn report(&self, config: &LoadConfig, elapsed: Duration, interrupted: bool) {
+        let succeeded = self.succeeded.load(Ordering::Relaxed);
+        let failed = self.failed.load(Ordering::Relaxed);
+        let messages = self.messages.load(Ordering::Relaxed);
+        let bytes =
+            self.bytes_sent.load(Ordering::Relaxed) + self.bytes_received.load(Ordering::Relaxed);
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+
+        let title = if interrupted {
+            "压测结果 (已提前结束)"
+        } else {
+            "压测结果"
+        };
+        println!("\n==== {} ====", title);
+        println!("总耗时: {:.3} 秒", elapsed.as_secs_f64());
+        println!(
+            "连接: 成功 {}, 失败 {}, 未完成 {} (共 {})",
+            succeeded,
+            failed,
+            config.connections - succeeded - failed,
+            config.connections
+        );
+        println!(
+            "消息: 完成 {} / {} 条, 每秒 {:.1} 条",
+            messages,
+            config.connections * config.messages,
+            messages as f64 / secs
+        );
+        println!(
+            "吞吐: 发送 {} 字节, 接收 {} 字节, 合计 {:.2} MB/s",
+            self.bytes_sent.load(Ordering::Relaxed),
+            self.bytes_received.load(Ordering::Relaxed),
+            bytes as f64 / secs / (1024.0 * 1024.0)
+        );
+        println!("校验失败: {} 条", self.mismatched.load(Ordering::Relaxed));
+        if config.verify {
+            self.verify.lock().unwrap_or_else(|e| e.into_inner()).report();
         }
-        Err(e) => {
-            eprintln!("无法连接到服务器: {}", e);
+        self.latencies
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .report();
+    }
+}
 
-            // 尝试备用地址
-            let backup_addr = "0.0.0.0:8888";
-            println!("尝试连接到备用地址 {}", backup_addr);
+// 生成一条消息；pattern 模式下由可打印字符循环组成，从 seed 处开始
+fn make_payload(payload: &Payload, size: usize, seed: usize) -> Vec<u8> {
+    match payload {
+        Payload::Pattern => (0..size).map(|i| b' ' + ((seed + i) % 95) as u8).collect(),
+        Payload::Random => {
+            let mut data = vec![0; size];
+            rand::thread_rng().fill_bytes(&mut data);
+            data
+        }
+        Payload::Fixed(data) => data.clone(),
+    }
+}
 
-            match TcpStream::connect(backup_addr).await {
-                Ok(mut stream) => {
-                    println!("成功连接到备用服务器!");
+// 按 --display 指定的格式显示响应
+fn format_response(data: &[u8], display: Display) -> String {
+    match display {
+        Display::Utf8 => String::from_utf8_lossy(data).into_owned(),
+        Display::Hex => to_hex(data),
+    }
+}
 
-                    // 要发送的消息
-                    let message = "Hello, TCP Server!";
-                    println!("发送消息: {}", message);
+// 读取直到解出一帧，同时到达的后续数据留在 decoder 中
+async fn read_frame(
+    stream: &mut TcpStream,
+    decoder: &mut FrameDecoder,
+    verifier: &mut Verifier,
+    stats: &LoadStats,
+) -> Result<Frame, String> {
+    let mut buf = vec![0; 8192];
+    loop {
+        match decoder.next_frame() {
+            Ok(Some(frame)) => return Ok(frame),
+            Ok(None) => {}
+            Err(offset) => {
+                verifier.unframeable(offset);
+                return Err(format!("接收偏移 {} 字节处的帧长度无效", offset));
+            }
+        }
+        let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("连接已被对端关闭".to_string());
+        }
+        stats.bytes_received.fetch_add(n as u64, Ordering::Relaxed);
+        decoder.push(&buf[..n]);
+    }
+}
 
-                    // 发送消息
-                    stream.write_all(message.as_bytes()).await?;
+// 单条连接：依次发送消息并等待完整的 echo，内容不一致或超时都视为失败
+// 每条正确 echo 从发送到收齐的耗时记入 latencies；verifier 不为 None 时消息带帧头并逐帧校验回包
+async fn run_connection(
+    id: usize,
+    config: &LoadConfig,
+    stats: &LoadStats,
+    latencies: &mut Latencies,
+    mut verifier: Option<&mut Verifier>,
+) -> Result<(), String> {
+    let mut stream = timeout(config.timeout, TcpStream::connect(&config.addr))
+        .await
+        .map_err(|_| "连接超时".to_string())?
+        .map_err(|e| format!("无法连接: {}", e))?;
+    let mut echo = vec![0; config.size];
+    let mut decoder = FrameDecoder::default();
 
-                    // 接收响应
-                    let mut buffer = vec![0; 1024];
-                    let n = stream.read(&mut buffer).await?;
+    for index in 0..config.messages {
+        let body = make_payload(&config.payload, config.size, index);
+        let message = match verifier {
+            Some(_) => encode_frame(index as u32, &body),
+            None => body,
+        };
+        let sent_at = Instant::now();
+        stream
+            .write_all(&message)
+            .await
+            .map_err(|e| format!("第 {} 条消息发送失败: {}", index + 1, e))?;
+        stats
+            .bytes_sent
+            .fetch_add(message.len() as u64, Ordering::Relaxed);
 
-                    if n > 0 {
-                        let response = String::from_utf8_lossy(&buffer[0..n]);
-                        println!("接收到响应: {}", response);
-                    } else {
-                        println!("服务器关闭了连接，没有接收到响应");
-                    }
-                }
-                Err(e) => {
-                    eprintln!("无法连接到备用服务器: {}", e);
-                    return Err(e.into());
+        // 服务器可能分多次回包，读满整条消息 (校验模式下按帧头分帧) 再比较
+        let (response, correct) = match verifier.as_deref_mut() {
+            Some(verifier) => {
+                verifier.record_sent();
+                let frame = match timeout(
+                    config.timeout,
+                    read_frame(&mut stream, &mut decoder, verifier, stats),
+                )
+                .await
+                {
+                    Ok(Ok(frame)) => frame,
+                    Ok(Err(e)) => return Err(format!("第 {} 条消息等待 echo 失败: {}", index + 1, e)),
+                    Err(_) => return Err(format!("第 {} 条消息等待 echo 超时", index + 1)),
+                };
+                let correct = verifier.check(&frame);
+                (frame.body, correct)
+            }
+            None => {
+                match timeout(config.timeout, stream.read_exact(&mut echo)).await {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => return Err(format!("第 {} 条消息等待 echo 失败: {}", index + 1, e)),
+                    Err(_) => return Err(format!("第 {} 条消息等待 echo 超时", index + 1)),
                 }
+                stats
+                    .bytes_received
+                    .fetch_add(echo.len() as u64, Ordering::Relaxed);
+                (echo.clone(), echo == message)
             }
+        };
+        let latency = sent_at.elapsed();
+        if config.print_responses {
+            println!(
+                "连接 #{} 第 {} 条响应 ({:.3}ms): {}",
+                id + 1,
+                index + 1,
+                latency.as_secs_f64() * 1000.0,
+                format_response(&response, config.display)
+            );
         }
+        // 校验模式下错误由 verifier 分类统计，继续发送后面的消息
+        if !correct {
+            if verifier.is_some() {
+                continue;
+            }
+            stats.mismatched.fetch_add(1, Ordering::Relaxed);
+            return Err(format!("第 {} 条消息的 echo 内容不一致", index + 1));
+        }
+        stats.messages.fetch_add(1, Ordering::Relaxed);
+        latencies.record(latency);
+    }
+    let _ = stream.shutdown().await;
+    if verifier.is_some_and(|verifier| !verifier.is_clean()) {
+        return Err("回包校验发现错误".to_string());
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    // 解析命令行参数，参数非法时打印用法并退出
+    let config = match parse_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(ArgsError::Help) => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Err(ArgsError::Invalid(e)) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if config.bench {
+        bench::run(config).await;
+        return Ok(());
+    }
+    if config.soak {
+        soak::run(config).await;
+        return Ok(());
     }
 
+    println!(
+        "压测 {}: {} 条连接, 每条 {} 条消息, 每条 {} 字节 ({})",
+        config.addr,
+        config.connections,
+        config.messages,
+        config.size,
+        config.payload.name()
+    );
+
+    // 每条连接一个任务，单个连接失败只记录原因，不影响其他连接
+    let config = Arc::new(config);
+    let stats = Arc::new(LoadStats::default());
+    let started_at = Instant::now();
+    let tasks: Vec<_> = (0..config.connections)
+        .map(|id| {
+            let config = Arc::clone(&config);
+            let stats = Arc::clone(&stats);
+            tokio::spawn(async move {
+                let mut latencies = Latencies::default();
+                let mut verifier = config
+                    .verify
+                    .then(|| Verifier::new(format!("连接 #{}", id + 1)));
+                let result =
+                    run_connection(id, &config, &stats, &mut latencies, verifier.as_mut()).await;
+                // 连接中途失败时，之前完成的消息仍计入延迟和校验统计
+                stats
+                    .latencies
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .extend(latencies);
+                if let Some(verifier) = verifier {
+                    stats
+                        .verify
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .merge(verifier.finish());
+                }
+                match result {
+                    Ok(()) => {
+                        stats.succeeded.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(e) => {
+                        stats.failed.fetch_add(1, Ordering::Relaxed);
+                        eprintln!("连接 #{} 失败: {}", id + 1, e);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    let all_done = async {
+        for task in tasks {
+            let _ = task.await;
+        }
+    };
+    let interrupted = tokio::select! {
+        _ = all_done => false,
+        _ = tokio::signal::ctrl_c() => true,
+    };
+    stats.report(&config, started_at.elapsed(), interrupted);
+
+    // 提前结束时不等待仍在进行的连接
+    if interrupted {
+        std::process::exit(0);
+    }
     Ok(())
 }
@@ -0,0 +1,229 @@
+use crate::config::LoadConfig;
+use crate::latency::Latencies;
+use crate::verify::{Verifier, VerifyStats};
+use crate::{run_connection, LoadStats};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time::{interval, interval_at, MissedTickBehavior};
+
+// 心跳统计的打印周期
+const HEARTBEAT_SECS: u64 = 60;
+
+fn now() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// 一轮失败的时间点和原因
+struct Failure {
+    at: String,
+    cycle: u64,
+    reason: String,
+}
+
+// 浸泡测试的累计统计，只由执行各轮的任务更新
+#[derive(Default)]
+struct SoakState {
+    cycles: u64,                // 已结束的轮数，含失败的
+    failures: Vec<Failure>,
+    consecutive: usize,         // 当前连续失败的次数
+    longest_streak: usize,      // 最长的连续失败次数
+    last_rtt: Option<Duration>, // 最近一次正确 echo 的往返时间
+    latencies: Latencies,
+    verify: VerifyStats,
+}
+
+impl SoakState {
+    // 记录一轮的结果，返回记录后连续失败的次数
+    fn record(&mut self, at: String, result: Result<(), String>) -> usize {
+        self.cycles += 1;
+        match result {
+            Ok(()) => self.consecutive = 0,
+            Err(reason) => {
+                self.consecutive += 1;
+                self.longest_streak = self.longest_streak.max(self.consecutive);
+                self.failures.push(Failure {
+                    at,
+                    cycle: self.cycles,
+                    reason,
+                });
+            }
+        }
+        self.consecutive
+    }
+
+    // 每分钟打印的一行心跳统计
+    fn heartbeat(&self, elapsed: Duration) {
+        let rtt = match self.last_rtt {
+            Some(rtt) => format!("{:.3}ms", rtt.as_secs_f64() * 1000.0),
+            None => "-".to_string(),
+        };
+        println!(
+            "[{}] 已运行 {} 分钟: 连接 {} 次, 失败 {} 次, 最近 RTT {}",
+            now(),
+            elapsed.as_secs() / 60,
+            self.cycles,
+            self.failures.len(),
+            rtt
+        );
+    }
+
+    fn report(&self, config: &LoadConfig, elapsed: Duration, interrupted: bool) {
+        let title = if interrupted {
+            "浸泡测试结果 (按 Ctrl+C 结束)"
+        } else {
+            "浸泡测试结果"
+        };
+        println!("\n==== {} ====", title);
+        println!("总耗时: {:.3} 秒", elapsed.as_secs_f64());
+        println!(
+            "连接: 共 {} 次, 成功 {}, 失败 {}, 最长连续失败 {} 次",
+            self.cycles,
+            self.cycles - self.failures.len() as u64,
+            self.failures.len(),
+            self.longest_streak
+        );
+        if config.verify {
+            self.verify.report();
+        }
+        self.latencies.report();
+
+        if self.failures.is_empty() {
+            println!("失败时间点: 无");
+        } else {
+            println!("失败时间点:");
+            for failure in &self.failures {
+                println!("  {} 第 {} 轮: {}", failure.at, failure.cycle, failure.reason);
+            }
+        }
+    }
+}
+
+// 执行一轮：建立连接、交换消息后正常关闭，并把结果计入统计
+async fn run_cycle(config: &LoadConfig, load: &LoadStats, state: &Mutex<SoakState>) {
+    let cycle = state.lock().unwrap_or_else(|e| e.into_inner()).cycles + 1;
+    let mut latencies = Latencies::default();
+    let mut verifier = config
+        .verify
+        .then(|| Verifier::new(format!("第 {} 轮", cycle)));
+    let result = run_connection(
+        cycle as usize - 1,
+        config,
+        load,
+        &mut latencies,
+        verifier.as_mut(),
+    )
+    .await;
+    let at = now();
+    if let Err(e) = &result {
+        eprintln!("[{}] 第 {} 轮失败: {}", at, cycle, e);
+    }
+
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(rtt) = latencies.last() {
+        state.last_rtt = Some(rtt);
+    }
+    state.latencies.extend(latencies);
+    if let Some(verifier) = verifier {
+        state.verify.merge(verifier.finish());
+    }
+
+    // 连续失败达到阈值时告警一次，之后恢复时再提示一次，测试始终继续
+    let failed_before = state.consecutive;
+    let streak = state.record(at.clone(), result);
+    if streak == config.alert_after {
+        eprintln!("[{}] 警告: 已连续失败 {} 次，测试继续进行", at, streak);
+    } else if streak == 0 && failed_before >= config.alert_after {
+        eprintln!("[{}] 连续失败 {} 次后已恢复", at, failed_before);
+    }
+}
+
+// 按间隔循环执行直到持续时间结束或 Ctrl+C，然后打印统计
+pub async fn run(config: LoadConfig) {
+    let until = match config.duration {
+        Some(duration) => format!("持续 {} 秒", duration.as_secs()),
+        None => "直到 Ctrl+C".to_string(),
+    };
+    println!(
+        "浸泡测试 {}: 每 {} 秒一轮, 每轮 {} 条消息, 每条 {} 字节 ({}), {}",
+        config.addr,
+        config.interval.as_secs(),
+        config.messages,
+        config.size,
+        config.payload.name(),
+        until
+    );
+
+    let config = Arc::new(config);
+    let load = Arc::new(LoadStats::default());
+    let state = Arc::new(Mutex::new(SoakState::default()));
+    let started_at = Instant::now();
+
+    // 上一轮耗时超过间隔时，下一轮紧接着开始，不补做错过的轮次
+    let cycles = {
+        let config = Arc::clone(&config);
+        let state = Arc::clone(&state);
+        tokio::spawn(async move {
+            let mut ticker = interval(config.interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                run_cycle(&config, &load, &state).await;
+            }
+        })
+    };
+
+    let period = Duration::from_secs(HEARTBEAT_SECS);
+    let mut heartbeat = interval_at(tokio::time::Instant::now() + period, period);
+    let finished = async {
+        match config.duration {
+            Some(duration) => tokio::time::sleep(duration).await,
+            None => std::future::pending().await,
+        }
+    };
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(finished, ctrl_c);
+    let interrupted = loop {
+        tokio::select! {
+            _ = &mut finished => break false,
+            _ = &mut ctrl_c => break true,
+            _ = heartbeat.tick() => {
+                state
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .heartbeat(started_at.elapsed());
+            }
+        }
+    };
+
+    // 进行中的一轮直接放弃，不计入统计
+    cycles.abort();
+    state
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .report(&config, started_at.elapsed(), interrupted);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_tracks_consecutive_failures_and_time_points() {
+        let mut state = SoakState::default();
+        let fail = || Err("无法连接: Connection refused".to_string());
+        assert_eq!(state.record("10:00:00".to_string(), Ok(())), 0);
+        assert_eq!(state.record("10:00:01".to_string(), fail()), 1);
+        assert_eq!(state.record("10:00:02".to_string(), fail()), 2);
+        assert_eq!(state.record("10:00:03".to_string(), Ok(())), 0);
+        assert_eq!(state.record("10:00:04".to_string(), fail()), 1);
+
+        assert_eq!(state.cycles, 5);
+        assert_eq!(state.longest_streak, 2);
+        let points: Vec<(&str, u64)> = state
+            .failures
+            .iter()
+            .map(|failure| (failure.at.as_str(), failure.cycle))
+            .collect();
+        assert_eq!(points, [("10:00:01", 2), ("10:00:02", 3), ("10:00:04", 5)]);
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::BTreeSet;
+use std::time::Instant;
+
+// 帧头: 帧总长度 (含帧头)、序号、校验和，均为大端 u32
+pub const HEADER_LEN: usize = 12;
+
+// 帧长度上限，超出视为帧头损坏，无法继续分帧
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+// FNV-1a 校验和，覆盖长度、序号和内容
+fn checksum(len: u32, seq: u32, body: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for &byte in len.to_be_bytes().iter().chain(&seq.to_be_bytes()).chain(body) {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+// 在内容前加上帧头
+pub fn encode_frame(seq: u32, body: &[u8]) -> Vec<u8> {
+    let len = (HEADER_LEN + body.len()) as u32;
+    let mut frame = Vec::with_capacity(len as usize);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(&seq.to_be_bytes());
+    frame.extend_from_slice(&checksum(len, seq, body).to_be_bytes());
+    frame.extend_from_slice(body);
+    frame
+}
+
+// 从接收的字节流中解出的一帧
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub seq: u32,
+    pub body: Vec<u8>,
+    pub intact: bool, // 校验和与内容一致
+    pub offset: u64,  // 帧在接收流中的起始偏移
+}
+
+// 按帧头中的长度重新分帧，处理 TCP 粘包和拆包
+#[derive(Default)]
+pub struct FrameDecoder {
+    buf: Vec<u8>,
+    start: usize, // buf 中尚未解析的数据的起点
+    offset: u64,  // buf[start] 在接收流中的偏移
+}
+
+impl FrameDecoder {
+    pub fn push(&mut self, data: &[u8]) {
+        self.buf.drain(..self.start);
+        self.start = 0;
+        self.buf.extend_from_slice(data);
+    }
+
+    // 取出下一个完整的帧，数据不足时返回 Ok(None)；长度字段非法时返回该帧的偏移，之后无法再分帧
+    pub fn next_frame(&mut self) -> Result<Option<Frame>, u64> {
+        let pending = &self.buf[self.start..];
+        if pending.len() < HEADER_LEN {
+            return Ok(None);
+        }
+        let field = |i: usize| u32::from_be_bytes([pending[i], pending[i + 1], pending[i + 2], pending[i + 3]]);
+        let len = field(0) as usize;
+        if !(HEADER_LEN..=MAX_FRAME_LEN).contains(&len) {
+            return Err(self.offset);
+        }
+        if pending.len() < len {
+            return Ok(None);
+        }
+
+        let seq = field(4);
+        let body = pending[HEADER_LEN..len].to_vec();
+        let frame = Frame {
+            seq,
+            intact: field(8) == checksum(len as u32, seq, &body),
+            body,
+            offset: self.offset,
+        };
+        self.start += len;
+        self.offset += len as u64;
+        Ok(Some(frame))
+    }
+}
+
+// 回包校验的统计，每一帧只计入正确、乱序、损坏中的一项，从未收到的计为丢失
+#[derive(Debug, Clone, Default)]
+pub struct VerifyStats {
+    pub ok: u64,
+    pub out_of_order: u64, // 晚于后面的序号到达或重复到达
+    pub lost: u64,
+    pub corrupted: u64,
+    pub first_error: Option<(Instant, String)>, // 最早发现的错误及其位置
+}
+
+impl VerifyStats {
+    // 合并单条连接的统计，首个错误取发现时间最早的
+    pub fn merge(&mut self, other: VerifyStats) {
+        self.ok += other.ok;
+        self.out_of_order += other.out_of_order;
+        self.lost += other.lost;
+        self.corrupted += other.corrupted;
+        if let Some((at, error)) = other.first_error {
+            if self.first_error.as_ref().is_none_or(|(first, _)| at < *first) {
+                self.first_error = Some((at, error));
+            }
+        }
+    }
+
+    pub fn report(&self) {
+        println!(
+            "回包校验: 正确 {}, 乱序 {}, 丢失 {}, 损坏 {}",
+            self.ok, self.out_of_order, self.lost, self.corrupted
+        );
+        if let Some((_, error)) = &self.first_error {
+            println!("首个错误: {}", error);
+        }
+    }
+}
+
+// 单条连接的回包校验：序号应从 0 开始连续递增
+pub struct Verifier {
+    label: String, // 错误位置中的连接名称，如 "连接 #1"
+    sent: u32,
+    next_seq: u32,
+    missing: BTreeSet<u32>, // 被跳过、仍可能晚到的序号
+    stats: VerifyStats,
+}
+
+impl Verifier {
+    pub fn new(label: String) -> Self {
+        Self {
+            label,
+            sent: 0,
+            next_seq: 0,
+            missing: BTreeSet::new(),
+            stats: VerifyStats::default(),
+        }
+    }
+
+    // 记录一条已完整发送的消息
+    pub fn record_sent(&mut self) {
+        self.sent += 1;
+    }
+
+    fn error(&mut self, offset: u64, what: String) {
+        if self.stats.first_error.is_none() {
+            let error = format!("{}, 接收偏移 {} 字节: {}", self.label, offset, what);
+            self.stats.first_error = Some((Instant::now(), error));
+        }
+    }
+
+    // 帧长度字段非法，之后的数据无法再分帧
+    pub fn unframeable(&mut self, offset: u64) {
+        self.stats.corrupted += 1;
+        self.error(offset, "帧长度无效，无法继续分帧".to_string());
+    }
+
+    // 检查一帧，按序且内容完整时返回 true
+    pub fn check(&mut self, frame: &Frame) -> bool {
+        if !frame.intact {
+            self.stats.corrupted += 1;
+            // 序号本身可能已损坏，只在与预期一致或是缺失的序号时采信
+            if frame.seq == self.next_seq {
+                self.next_seq += 1;
+            } else {
+                self.missing.remove(&frame.seq);
+            }
+            self.error(frame.offset, format!("序号 {} 的内容损坏 (校验和不一致)", frame.seq));
+            return false;
+        }
+
+        if frame.seq >= self.next_seq {
+            if frame.seq > self.next_seq {
+                self.missing.extend(self.next_seq..frame.seq);
+                self.error(
+                    frame.offset,
+                    format!("期望序号 {}，收到 {}", self.next_seq, frame.seq),
+                );
+            }
+            self.next_seq = frame.seq + 1;
+            self.stats.ok += 1;
+            return true;
+        }
+
+        self.stats.out_of_order += 1;
+        let what = if self.missing.remove(&frame.seq) {
+            format!("序号 {} 乱序到达", frame.seq)
+        } else {
+            format!("序号 {} 重复到达", frame.seq)
+        };
+        self.error(frame.offset, what);
+        false
+    }
+
+    // 没有发现任何错误，且已发送的消息都已收到
+    pub fn is_clean(&self) -> bool {
+        self.stats.out_of_order == 0
+            && self.stats.corrupted == 0
+            && self.missing.is_empty()
+            && self.next_seq >= self.sent
+    }
+
+    // 连接结束时，已发送但始终未收到回包的消息计为丢失
+    pub fn finish(mut self) -> VerifyStats {
+        self.stats.lost = self.missing.len() as u64 + self.sent.saturating_sub(self.next_seq) as u64;
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(seqs: &[u32]) -> Vec<u8> {
+        seqs.iter()
+            .flat_map(|&seq| encode_frame(seq, format!("message {}", seq).as_bytes()))
+            .collect()
+    }
+
+    fn decode_all(decoder: &mut FrameDecoder) -> Vec<Frame> {
+        let mut decoded = Vec::new();
+        while let Some(frame) = decoder.next_frame().unwrap() {
+            decoded.push(frame);
+        }
+        decoded
+    }
+
+    #[test]
+    fn decoder_reassembles_split_frames() {
+        let stream = frames(&[0, 1, 2]);
+        let mut decoder = FrameDecoder::default();
+        let mut decoded = Vec::new();
+        // 每次只到达一个字节
+        for byte in &stream {
+            decoder.push(std::slice::from_ref(byte));
+            decoded.extend(decode_all(&mut decoder));
+        }
+
+        assert_eq!(decoded.len(), 3);
+        assert!(decoded.iter().all(|frame| frame.intact));
+        assert_eq!(decoded[1].body, b"message 1");
+        let frame_len = (HEADER_LEN + "message 0".len()) as u64;
+        assert_eq!(decoded[2].offset, 2 * frame_len);
+    }
+
+    #[test]
+    fn decoder_splits_coalesced_frames() {
+        let mut decoder = FrameDecoder::default();
+        let stream = frames(&[0, 1, 2]);
+        // 一次到达两帧半
+        let cut = stream.len() - 5;
+        decoder.push(&stream[..cut]);
+        let seqs: Vec<u32> = decode_all(&mut decoder).iter().map(|frame| frame.seq).collect();
+        assert_eq!(seqs, [0, 1]);
+        decoder.push(&stream[cut..]);
+        assert_eq!(decode_all(&mut decoder)[0].seq, 2);
+    }
+
+    #[test]
+    fn decoder_flags_corruption_and_bad_length() {
+        let mut stream = frames(&[0]);
+        *stream.last_mut().unwrap() ^= 0xFF;
+        let mut decoder = FrameDecoder::default();
+        decoder.push(&stream);
+        assert!(!decoder.next_frame().unwrap().unwrap().intact);
+
+        // 长度字段小于帧头，无法继续分帧
+        decoder.push(&[0, 0, 0, 4, 0, 0, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(decoder.next_frame(), Err(stream.len() as u64));
+    }
+
+    #[test]
+    fn verifier_classifies_out_of_order_lost_and_corrupted() {
+        let mut verifier = Verifier::new("连接 #1".to_string());
+        for _ in 0..8 {
+            verifier.record_sent();
+        }
+
+        let mut decoder = FrameDecoder::default();
+        decoder.push(&frames(&[0, 1, 3, 2, 2, 4]));
+        let mut corrupted = encode_frame(5, b"message 5");
+        corrupted[HEADER_LEN] ^= 0x01;
+        decoder.push(&corrupted);
+        decoder.push(&frames(&[7]));
+
+        let results: Vec<bool> = decode_all(&mut decoder)
+            .iter()
+            .map(|frame| verifier.check(frame))
+            .collect();
+        assert_eq!(results, [true, true, true, false, false, true, false, true]);
+        assert!(!verifier.is_clean());
+
+        let stats = verifier.finish();
+        assert_eq!(
+            (stats.ok, stats.out_of_order, stats.lost, stats.corrupted),
+            (5, 2, 1, 1)
+        );
+        // 首个错误是跳过了序号 2，位于第三帧
+        let frame_len = HEADER_LEN + "message 0".len();
+        assert_eq!(
+            stats.first_error.unwrap().1,
+            format!("连接 #1, 接收偏移 {} 字节: 期望序号 2，收到 3", 2 * frame_len)
+        );
+    }
+
+    #[test]
+    fn unanswered_messages_count_as_lost() {
+        let mut verifier = Verifier::new("连接 #2".to_string());
+        for _ in 0..3 {
+            verifier.record_sent();
+        }
+        let mut decoder = FrameDecoder::default();
+        decoder.push(&frames(&[0]));
+        let frame = decoder.next_frame().unwrap().unwrap();
+        assert!(verifier.check(&frame));
+        assert!(!verifier.is_clean());
+        assert_eq!(verifier.finish().lost, 2);
+    }
+}
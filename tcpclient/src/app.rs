@@ -1,125 +1,710 @@
-use crate::message::Message;
+use crate::batch::BatchProgress;
+use crate::desktop_notify::{DisconnectAlerts, SharedDisconnectAlerts};
+use crate::follow::MessageFollow;
+use crate::i18n::{set_language, tr, trf, Language};
+use crate::loopback::{SharedLoopback, DEFAULT_LOOPBACK_PAYLOAD};
+use crate::message::{Message, SharedMessages};
+use crate::message_cache::MessageCache;
+use crate::metrics::{spawn_metrics_server, ScanState, SharedMetrics};
 use crate::network::handle_network_communications;
+use crate::network::scanner::{
+    ScanConfig, ScanProgress, ScanResult, DEFAULT_MAX_CONNECTIONS, DEFAULT_MAX_SCAN_IPS,
+    DEFAULT_MAX_SCAN_PORTS,
+};
+use crate::notifications::{notify, Notification, NotificationKind, NotificationQueue};
+use crate::profiles::{load_profiles, ConnectionProfile};
+use crate::repaint::Repaint;
+use crate::rules::{load_rules, AutoReplyRule};
+use crate::triggers::{load_triggers, SharedTriggers, TriggerSet};
+use crate::scan_history::{load_scan_history, ScanRecord};
+use crate::search::LogSearch;
+use crate::session::{load_sessions, SessionRecorder, SessionRecording};
+use crate::settings::{load_settings, save_settings, ClientSettings};
+use crate::shortcuts::{BindError, ShortcutAction, Shortcuts};
 use crate::ui::panels::{
-    render_messages_panel, render_scan_left_panel, render_scan_logs, render_scan_panel,
-    render_send_panel, render_settings_panel,
+    render_messages_panel, render_notification_menu, render_shortcuts_window, render_settings_menu, render_theme_menu, render_language_menu, render_session_tag, render_status_bar, render_profiles_window, render_rules_window, render_toasts, render_trigger_flash, render_triggers_window, render_scan_confirm_window, render_scan_left_panel,
+    render_scan_logs, render_scan_panel, render_send_panel, render_settings_panel, request_clear_messages, send_input,
+    toggle_connection,
+};
+use crate::ui::styles::{
+    apply_font_scale, apply_fonts, load_font_file, setup_style, step_font_scale, ThemeChoice, FONT_SCALE_RANGE,
 };
-use crate::ui::styles::setup_style;
+use crate::utils::{lock_or_recover, DATA_DIR};
 use eframe::{egui, App, CreationContext, Frame};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+// eframe 保存界面主题使用的键
+const THEME_KEY: &str = "theme";
+
+// eframe 保存界面语言使用的键
+const LANGUAGE_KEY: &str = "language";
+
+// eframe 保存界面字号缩放比例使用的键
+const FONT_SCALE_KEY: &str = "font_scale";
+
+// eframe 保存用户字体文件路径使用的键
+const FONT_FILE_KEY: &str = "font_file";
+
+// eframe 保存界面布局补充字段使用的键
+const LAYOUT_KEY: &str = "layout";
+
+// eframe 保存快捷键绑定使用的键
+const SHORTCUTS_KEY: &str = "shortcuts";
+
+// 窗口标题，连接意外断开后加上 [已断开] 前缀
+pub const WINDOW_TITLE: &str = "TCP 客户端";
+
+// 连接或扫描期间刷新连接时长、扫描耗时等计时显示的间隔
+pub const CLOCK_REPAINT_INTERVAL: Duration = Duration::from_secs(1);
+
+// 每条消息默认最多显示的字符数，过长的单行标签会拖慢界面
+const DEFAULT_MAX_DISPLAY_LEN: usize = 2000;
+
 // 定义应用状态
 pub struct TcpClientApp {
     // 连接相关状态
     pub ip: String,
     pub port: String,
     pub is_connected: bool,
+    pub connected_at: Option<Instant>, // 本次连接开始的时间，断开时清除，用于显示连接时长
+    pub connection_metrics: SharedMetrics, // 网络任务更新的连接状态，监听模式下用于显示已接入的客户端
     pub tx: Option<mpsc::Sender<Message>>,
-    pub received_messages: Arc<Mutex<Vec<(String, String)>>>, // (时间戳, 消息)
-    pub send_text: String,
+    pub received_messages: SharedMessages, // (时间戳, 消息)
+    pub send_text: String, // 发送框内容，只由用户清空或在发送成功后按 clear_after_send 清空
+    pub clear_after_send: bool, // 发送成功后清空发送框
     pub should_scroll_to_bottom: bool,
+    pub dual_view: bool, // 收发的数据同时显示文本和十六进制
+    pub max_display_len: usize, // 每条消息最多显示的字符数，超出部分折叠，0 表示不限制
+    pub message_detail: Option<usize>, // 在详情窗口中查看完整内容的消息序号
+    pub message_cache: MessageCache, // 消息列表各行预先格式化的文本
+    pub bookmark_jump: Option<BookmarkJump>, // 最近一次从书签列表跳转的目标
+    pub confirm_clear_messages: bool, // 清空含书签的消息前等待确认
+    pub message_follow: MessageFollow, // 接收消息列表是否停留在底部及离开底部后的新消息数
+    pub idle_timeout_secs: u64, // 空闲超时时间，0 表示不检测
+    pub disconnect_on_idle: bool, // 空闲超时后是否自动断开
+    pub keepalive_secs: u64,      // 心跳间隔，0 表示不发送心跳
+    pub keepalive_payload: String, // 心跳发送的内容
+    pub keepalive_mode: EncodingMode, // 心跳内容的格式
+    pub source_addr: String,      // 连接和扫描使用的本地源地址，留空表示默认路由
+    pub listen_mode: bool,        // 作为服务器监听并接受连接，而不是主动连接
+    pub session_tag: SessionTag,  // 当前会话的标签和颜色
     pub shared_encoding_mode: Arc<Mutex<EncodingMode>>, // 共享的编码模式，用于网络通信
 
+    // 连接配置相关状态
+    pub profiles: Vec<ConnectionProfile>, // 已保存的命名连接配置
+    pub selected_profile: Option<usize>, // 最近一次选中或保存的配置
+    pub new_profile_name: String, // 保存当前设置时使用的名称
+    pub show_profiles_window: bool,
+    pub profiles_status: Option<String>, // 配置保存结果提示
+
+    // 自动应答规则相关状态
+    pub auto_reply_rules: Arc<Mutex<Vec<AutoReplyRule>>>, // 与接收任务共享的规则列表
+    pub show_rules_window: bool,
+    pub rules_status: Option<String>, // 规则保存结果提示
+
+    // 触发器相关状态
+    pub triggers: SharedTriggers, // 与接收任务共享的触发器列表和最近一次命中
+    pub show_triggers_window: bool,
+    pub triggers_status: Option<String>, // 触发器保存结果提示
+
+    // 会话录制与回放相关状态
+    pub session_recorder: Option<SessionRecorder>, // 正在进行的录制
+    pub saved_sessions: Vec<SessionRecording>, // 已保存的会话，最新的在前
+    pub is_replaying: Arc<Mutex<bool>>, // 回放任务是否在运行
+    pub session_status: Option<String>, // 会话保存结果提示
+
+    // 本地回环测试相关状态
+    pub loopback_payload: String, // 回环测试发送的内容
+    pub loopback_status: SharedLoopback, // 测试任务更新的结果，未测试过时为 None
+
+    pub escape_sequences: bool, // UTF-8 模式下发送前解析 \n \t \0 \xNN 等转义序列
+
+    // 批量发送相关状态
+    pub batch_mode: bool, // 发送框中每行作为一条消息依次发送
+    pub batch_interval_ms: u64, // 两条之间的间隔
+    pub batch_progress: Arc<Mutex<BatchProgress>>, // 与网络任务共享的发送进度
+
+    // 随机数据发送相关状态
+    pub random_len: usize,       // 每次生成的字节数
+    pub random_printable: bool,  // 只生成可打印 ASCII 字符
+
     // IP扫描相关状态
     pub start_ip: String,
     pub end_ip: String,
     pub start_port: String,
     pub end_port: String,
     pub timeout_ms: String,
-    pub is_scanning: bool,
-    pub scan_results: Arc<Mutex<Vec<String>>>, // 扫描结果列表
+    pub scan_excludes: String, // 扫描时跳过的IP，每行一个IP或CIDR网段
+    pub scan_retries: u8, // 端口探测失败后的重试次数
+    pub scan_rate_limit: u32, // 每秒最多新建的连接数，0 表示不限速
+    pub scan_max_connections: usize, // 最大并发连接尝试数
+    pub scan_max_ips: u64, // 单次扫描允许的最大IP数量
+    pub scan_max_ports: u64, // 单次扫描允许的最大端口数量
+    pub scan_verbose_logs: bool, // 扫描日志是否包含进度等详细信息
+    pub export_results_with_logs: bool, // 保存日志时是否同时导出扫描结果
+    pub scan_logs_status: Option<String>, // 日志保存结果提示
+    pub scan_log_search: LogSearch, // 扫描日志搜索
+    pub pending_scan: Option<ScanConfig>, // 等待用户确认的超大范围扫描
+    pub is_scanning: Arc<AtomicBool>, // 扫描状态标志，随扫描命令传给扫描任务，置为 false 即取消扫描
+    pub scan_results: Arc<Mutex<Vec<ScanResult>>>, // 扫描结果列表
     pub scan_logs: Arc<Mutex<Vec<(String, String)>>>, // 扫描日志列表 (时间戳, 日志内容)
+    pub scan_history: Vec<ScanRecord>, // 已保存的扫描历史记录，最新的在前
+    pub scan_results_page: usize, // 扫描结果当前页
+    pub scan_results_grouped: bool, // 扫描结果按主机分组显示，否则按 IP:端口 平铺
+    pub scan_progress: Arc<Mutex<ScanProgress>>, // 扫描耗时与进度
+    pub scan_record: Arc<Mutex<Option<ScanRecord>>>, // 当前结果对应的扫描记录，扫描结束或加载历史时设置，用于复制报告
+
+    // 通知相关状态
+    pub notifications: NotificationQueue, // 网络任务推送的待显示通知
+    pub toasts: Vec<Notification>, // 正在屏幕上显示的通知
+    pub muted_notifications: HashSet<NotificationKind>, // 已屏蔽的通知类别
+    pub disconnect_alerts: SharedDisconnectAlerts, // 与接收任务共享的断线桌面通知设置和断开状态
+    pub window_title: String, // 窗口标题当前显示的文字
 
     // 界面相关状态
     pub current_view: AppView, // 当前显示的界面
     pub encoding_mode: EncodingMode, // UI中显示的编码模式
+    pub display_mode: DisplayMode, // 收发数据的显示格式，切换编码模式时跟随
+    pub theme: ThemeChoice, // 界面主题，由 eframe 保存
+    pub language: Language, // 界面语言，由 eframe 保存
+    pub font_scale: f32, // 界面字号的缩放比例，由 eframe 保存
+    pub font_file: Option<PathBuf>, // 优先于内置字体使用的字体文件，由 eframe 保存
+    pub shortcuts: Shortcuts, // 快捷键绑定，由 eframe 保存
+    pub show_shortcuts_window: bool,
+    pub shortcut_capture: Option<ShortcutAction>, // 正在等待新组合键的操作
+    pub shortcuts_status: Option<String>, // 修改快捷键失败的原因
+    pub window_position_checked: bool, // 是否已检查恢复的窗口位置在屏幕内
+    pub monitor_size: Option<egui::Vec2>, // 窗口所在显示器的大小，启动时为上次退出时保存的值
+}
+
+// 从书签列表跳转到的消息，滚动到该条后短暂高亮
+pub struct BookmarkJump {
+    pub index: usize, // 消息序号
+    pub at: Instant,  // 跳转的时间，高亮随时间淡出
+    pub scroll: bool, // 下一帧滚动到该条消息
 }
 
 // 定义应用界面类型
-#[derive(PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
 pub enum AppView {
+    #[default]
     Connection, // 连接和数据界面
     Scan,       // 扫描界面
 }
 
+// 窗口大小位置和面板尺寸由 eframe 和 egui 自己保存，这里保存其余的布局状态
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SavedLayout {
+    view: AppView,
+    monitor_size: Option<egui::Vec2>, // 退出时窗口所在显示器的大小
+}
+
+// 恢复的窗口是否需要移回屏幕内：不在任何显示器上，或者显示器与上次不同且标题栏已不在当前显示器范围内
+// 只有一块显示器的信息，不知道它在桌面上的偏移，显示器没有变化时相信保存的位置
+fn window_off_screen(outer: egui::Rect, monitor: Option<egui::Vec2>, saved_monitor: Option<egui::Vec2>) -> bool {
+    let Some(monitor) = monitor else {
+        return true;
+    };
+    if saved_monitor == Some(monitor) {
+        return false;
+    }
+    let title_bar = egui::pos2(outer.center().x, outer.min.y + 10.0);
+    !egui::Rect::from_min_size(egui::Pos2::ZERO, monitor).contains(title_bar)
+}
+
 // 定义数据编码模式
-#[derive(PartialEq, Clone, Copy, Debug)]
+// 会被复制、比较、放入 Message 并随会话和规则保存，新增的模式也要满足这些派生
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum EncodingMode {
-    Utf8,  // UTF-8编码
-    Hex    // 十六进制编码
+    #[default]
+    Utf8, // UTF-8编码
+    Hex,  // 十六进制编码
+}
+
+// 收发数据的显示格式，绘制时按当前选择重新解码保存的原始字节
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Utf8,    // UTF-8 文本，不是合法 UTF-8 的数据显示为十六进制
+    Hex,     // 十六进制
+    Decimal, // 十进制字节值
+}
+
+// 会话的标签和颜色，显示在顶部菜单栏和连接状态中，同时打开多个客户端时用来区分
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionTag {
+    pub label: String, // 留空时不显示
+    pub color: [u8; 3],
+}
+
+impl Default for SessionTag {
+    fn default() -> Self {
+        Self {
+            label: String::new(),
+            color: [70, 130, 180],
+        }
+    }
+}
+
+impl SessionTag {
+    pub fn color32(&self) -> egui::Color32 {
+        let [r, g, b] = self.color;
+        egui::Color32::from_rgb(r, g, b)
+    }
+}
+
+impl From<EncodingMode> for DisplayMode {
+    fn from(mode: EncodingMode) -> Self {
+        match mode {
+            EncodingMode::Utf8 => DisplayMode::Utf8,
+            EncodingMode::Hex => DisplayMode::Hex,
+        }
+    }
 }
 
 impl Default for TcpClientApp {
     fn default() -> Self {
         // 创建默认的编码模式
-        let default_encoding_mode = Arc::new(Mutex::new(EncodingMode::Utf8));
+        let default_encoding_mode = Arc::new(Mutex::new(EncodingMode::default()));
 
         Self {
             ip: "127.0.0.1".to_string(),
             port: "8888".to_string(),
             is_connected: false,
+            connected_at: None,
+            connection_metrics: SharedMetrics::default(),
             tx: None,
             received_messages: Arc::new(Mutex::new(Vec::new())),
             send_text: String::new(),
+            clear_after_send: true,
             should_scroll_to_bottom: true,
+            dual_view: false,
+            max_display_len: DEFAULT_MAX_DISPLAY_LEN,
+            message_detail: None,
+            message_cache: MessageCache::default(),
+            bookmark_jump: None,
+            confirm_clear_messages: false,
+            message_follow: MessageFollow::default(),
+            idle_timeout_secs: 0,
+            disconnect_on_idle: false,
+            keepalive_secs: 0,
+            keepalive_payload: String::new(),
+            keepalive_mode: EncodingMode::Utf8,
+            source_addr: String::new(),
+            listen_mode: false,
+            session_tag: SessionTag::default(),
             shared_encoding_mode: default_encoding_mode,
 
+            // 连接配置相关状态初始化
+            profiles: Vec::new(),
+            selected_profile: None,
+            new_profile_name: String::new(),
+            show_profiles_window: false,
+            profiles_status: None,
+
+            // 自动应答规则相关状态初始化
+            auto_reply_rules: Arc::new(Mutex::new(Vec::new())),
+            show_rules_window: false,
+            rules_status: None,
+
+            // 触发器相关状态初始化
+            triggers: Arc::new(Mutex::new(TriggerSet::default())),
+            show_triggers_window: false,
+            triggers_status: None,
+
             // IP扫描相关状态初始化
             start_ip: "127.0.0.1".to_string(),
             end_ip: "127.0.0.10".to_string(),
             start_port: "8888".to_string(),
             end_port: "8889".to_string(),
             timeout_ms: "500".to_string(),
-            is_scanning: false,
+            session_recorder: None,
+            saved_sessions: Vec::new(),
+            is_replaying: Arc::new(Mutex::new(false)),
+            loopback_payload: DEFAULT_LOOPBACK_PAYLOAD.to_string(),
+            loopback_status: SharedLoopback::default(),
+            session_status: None,
+            escape_sequences: false,
+            batch_mode: false,
+            batch_interval_ms: 100,
+            batch_progress: Arc::new(Mutex::new(BatchProgress::default())),
+            random_len: 16,
+            random_printable: false,
+            scan_excludes: String::new(),
+            scan_verbose_logs: false,
+            export_results_with_logs: false,
+            scan_logs_status: None,
+            scan_log_search: LogSearch::default(),
+            scan_retries: 0,
+            scan_rate_limit: 0,
+            scan_max_connections: DEFAULT_MAX_CONNECTIONS,
+            scan_max_ips: DEFAULT_MAX_SCAN_IPS,
+            scan_max_ports: DEFAULT_MAX_SCAN_PORTS,
+            pending_scan: None,
+            is_scanning: Arc::new(AtomicBool::new(false)),
             scan_results: Arc::new(Mutex::new(Vec::new())),
             scan_logs: Arc::new(Mutex::new(Vec::new())),
+            scan_history: Vec::new(),
+            scan_results_page: 0,
+            scan_results_grouped: true,
+            scan_progress: Arc::new(Mutex::new(ScanProgress::default())),
+            scan_record: Arc::new(Mutex::new(None)),
+            notifications: Arc::new(Mutex::new(Default::default())),
+            toasts: Vec::new(),
+            muted_notifications: HashSet::new(),
+            disconnect_alerts: Arc::new(DisconnectAlerts::new(true)),
+            window_title: tr(WINDOW_TITLE).to_string(),
 
             // 界面相关状态初始化
             current_view: AppView::Connection,
-            encoding_mode: EncodingMode::Utf8,
+            encoding_mode: EncodingMode::default(),
+            display_mode: DisplayMode::default(),
+            theme: ThemeChoice::default(),
+            language: Language::default(),
+            font_scale: 1.0,
+            font_file: None,
+            shortcuts: Shortcuts::default(),
+            show_shortcuts_window: false,
+            shortcut_capture: None,
+            shortcuts_status: None,
+            window_position_checked: false,
+            monitor_size: None,
         }
     }
 }
 
 impl TcpClientApp {
-    pub fn new(cc: &CreationContext<'_>) -> Self {
-        // 设置UI样式
-        setup_style(&cc.egui_ctx);
+    // 切换编码模式，同时更新界面使用的模式、接收任务共享的模式和数据的显示格式
+    pub fn set_encoding_mode(&mut self, mode: EncodingMode) {
+        self.encoding_mode = mode;
+        self.display_mode = mode.into();
+        *lock_or_recover(&self.shared_encoding_mode) = mode;
+    }
+
+    // 发送成功后调用，开启了发送后清空时清空发送框，否则保留内容以便再次发送
+    pub fn finish_send(&mut self) {
+        if self.clear_after_send {
+            self.send_text.clear();
+        }
+    }
+
+    // 用选中的配置填充连接设置，编码模式立即生效，其余设置下次连接时生效
+    pub fn apply_profile(&mut self, index: usize) {
+        let Some(profile) = self.profiles.get(index).cloned() else {
+            return;
+        };
+        self.ip = profile.ip;
+        self.port = profile.port;
+        self.source_addr = profile.source_addr;
+        self.idle_timeout_secs = profile.idle_timeout_secs;
+        self.disconnect_on_idle = profile.disconnect_on_idle;
+        self.keepalive_secs = profile.keepalive_secs;
+        self.keepalive_payload = profile.keepalive_payload;
+        self.keepalive_mode = profile.keepalive_mode;
+        self.session_tag = profile.tag;
+        self.set_encoding_mode(profile.encoding);
+        self.selected_profile = Some(index);
+    }
+
+    // 把当前的连接设置保存为命名配置
+    pub fn capture_profile(&self, name: &str) -> ConnectionProfile {
+        ConnectionProfile {
+            name: name.trim().to_string(),
+            ip: self.ip.clone(),
+            port: self.port.clone(),
+            encoding: self.encoding_mode,
+            source_addr: self.source_addr.clone(),
+            idle_timeout_secs: self.idle_timeout_secs,
+            disconnect_on_idle: self.disconnect_on_idle,
+            keepalive_secs: self.keepalive_secs,
+            keepalive_payload: self.keepalive_payload.clone(),
+            keepalive_mode: self.keepalive_mode,
+            tag: self.session_tag.clone(),
+        }
+    }
+
+    // metrics_port 为 Some 时在该端口启动 HTTP 指标接口
+    pub fn new(cc: &CreationContext<'_>, metrics_port: Option<u16>) -> Self {
+        // 恢复保存的主题和字体，没有保存过时跟随系统、使用内置字体，再设置UI样式
+        let theme: ThemeChoice = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, THEME_KEY))
+            .unwrap_or_default();
+        let font_file: Option<PathBuf> = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, FONT_FILE_KEY))
+            .unwrap_or_default();
+        let font_warnings = setup_style(&cc.egui_ctx, theme, font_file.as_deref());
+        let language: Language = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, LANGUAGE_KEY))
+            .unwrap_or_default();
+        set_language(language);
+        let font_scale: f32 = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, FONT_SCALE_KEY))
+            .unwrap_or(1.0_f32)
+            .clamp(*FONT_SCALE_RANGE.start(), *FONT_SCALE_RANGE.end());
+        apply_font_scale(&cc.egui_ctx, font_scale);
+        // 字号快捷键由 handle_font_shortcuts 处理，关闭 egui 自带的整体缩放
+        cc.egui_ctx.options_mut(|options| options.zoom_with_keyboard = false);
+        let layout: SavedLayout = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, LAYOUT_KEY))
+            .unwrap_or_default();
+        let shortcuts: Shortcuts = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, SHORTCUTS_KEY))
+            .unwrap_or_default();
 
         // 创建通信通道和共享状态
         let (tx, rx) = mpsc::channel::<Message>(100);
         let received_messages = Arc::new(Mutex::new(Vec::new()));
 
         // 创建共享的编码模式
-        let encoding_mode = Arc::new(Mutex::new(EncodingMode::Utf8));
+        let encoding_mode = Arc::new(Mutex::new(EncodingMode::default()));
+
+        // 网络任务与界面共享的通知队列
+        let notifications: NotificationQueue = Arc::new(Mutex::new(Default::default()));
+
+        // 网络任务更新、指标接口读取的连接状态
+        let metrics = SharedMetrics::default();
+
+        // 加载已保存的自动应答规则
+        let auto_reply_rules = Arc::new(Mutex::new(load_rules()));
+
+        // 加载已保存的触发器
+        let triggers = Arc::new(Mutex::new(TriggerSet {
+            triggers: load_triggers(),
+            last_hit: None,
+        }));
 
         // 启动异步任务处理网络通信
         let messages_clone = received_messages.clone();
         let encoding_mode_clone = encoding_mode.clone();
+        let rules_clone = auto_reply_rules.clone();
+        let triggers_clone = triggers.clone();
+        let reply_tx = tx.clone();
+        let notifications_clone = notifications.clone();
+        let metrics_clone = metrics.clone();
+        let disconnect_alerts = Arc::new(DisconnectAlerts::new(true));
+        let alerts_clone = disconnect_alerts.clone();
+        let repaint = Repaint::new(&cc.egui_ctx);
         tokio::spawn(async move {
-            handle_network_communications(rx, messages_clone, encoding_mode_clone).await;
+            handle_network_communications(
+                rx,
+                messages_clone,
+                encoding_mode_clone,
+                rules_clone,
+                triggers_clone,
+                reply_tx,
+                notifications_clone,
+                metrics_clone,
+                alerts_clone,
+                PathBuf::from(DATA_DIR),
+                repaint,
+            )
+            .await;
         });
 
-        Self {
+        let mut app = Self {
             is_connected: false,
+            connected_at: None,
+            connection_metrics: metrics.clone(),
             tx: Some(tx),
             received_messages,
             send_text: String::new(),
             should_scroll_to_bottom: true,
+            dual_view: false,
+            max_display_len: DEFAULT_MAX_DISPLAY_LEN,
+            message_detail: None,
+            message_cache: MessageCache::default(),
             shared_encoding_mode: encoding_mode,
+            auto_reply_rules,
+            triggers,
+            notifications,
+            disconnect_alerts,
+            saved_sessions: load_sessions(),
+            profiles: load_profiles(),
 
             // IP扫描相关状态初始化
-            is_scanning: false,
             scan_results: Arc::new(Mutex::new(Vec::new())),
             scan_logs: Arc::new(Mutex::new(Vec::new())),
+            scan_history: load_scan_history(),
 
-            // 界面相关状态初始化
-            current_view: AppView::Connection,
-            encoding_mode: EncodingMode::Utf8, // 默认编码模式，与共享的encoding_mode保持一致
+            // 界面相关状态初始化，恢复上次退出时的界面
+            current_view: layout.view,
+            encoding_mode: EncodingMode::default(), // 默认编码模式，与共享的encoding_mode保持一致
+            display_mode: DisplayMode::default(),
+            theme,
+            language,
+            font_scale,
+            font_file,
+            shortcuts,
+            monitor_size: layout.monitor_size,
 
             ..Default::default()
+        };
+
+        // 恢复上次退出时的连接和扫描设置
+        load_settings().apply(&mut app);
+
+        // 字体加载失败时已退回默认字体，界面文字可能无法显示，同时输出到终端
+        for warning in font_warnings {
+            eprintln!("{}", warning);
+            notify(&app.notifications, NotificationKind::Error, warning);
+        }
+
+        // 指标接口默认关闭
+        if let Some(port) = metrics_port {
+            let scan = ScanState {
+                is_scanning: app.is_scanning.clone(),
+                progress: app.scan_progress.clone(),
+                results: app.scan_results.clone(),
+            };
+            spawn_metrics_server(port, metrics, scan, app.received_messages.clone());
         }
+        app
+    }
+
+    // 网络和扫描任务更新共享状态后通过 Repaint 请求重绘，键鼠输入也会立即触发重绘
+    // 只有连接时长、扫描耗时这类随时间变化的显示需要定时刷新，其余时间空闲时不重绘
+    // 通知、触发器闪烁和回环测试的进度动画在绘制时自行请求重绘
+    pub fn repaint_interval(&self) -> Option<Duration> {
+        let clock_running = self.is_connected || self.is_scanning.load(Ordering::Relaxed);
+        clock_running.then_some(CLOCK_REPAINT_INTERVAL)
+    }
+
+    // 连接意外断开后在窗口标题前加 [已断开]，重新连接后恢复；切换语言后标题随之翻译
+    // 只在标题变化时设置
+    fn update_window_title(&mut self, ctx: &egui::Context) {
+        let title = if self.disconnect_alerts.is_lost() {
+            trf!("[已断开] {}", tr(WINDOW_TITLE))
+        } else {
+            tr(WINDOW_TITLE).to_string()
+        };
+        if title == self.window_title {
+            return;
+        }
+        self.window_title = title.clone();
+        ctx.send_viewport_cmd(egui::ViewportCommand::Title(title));
+    }
+
+    // 恢复的窗口位置可能在已断开的显示器上，此时移回主显示器左上角
+    // 窗口大小由 eframe 按显示器大小限制，这里只处理位置，且只在启动后检查一次
+    // 之后每帧记录窗口所在显示器的大小，退出时随布局保存
+    fn ensure_window_visible(&mut self, ctx: &egui::Context) {
+        let (outer_rect, monitor_size) = ctx.input(|i| (i.viewport().outer_rect, i.viewport().monitor_size));
+        if !self.window_position_checked {
+            self.window_position_checked = true;
+            let off_screen = outer_rect
+                .is_some_and(|outer| window_off_screen(outer, monitor_size, self.monitor_size));
+            if off_screen {
+                ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(50.0, 50.0)));
+            }
+        }
+        if monitor_size.is_some() {
+            self.monitor_size = monitor_size;
+        }
+    }
+
+    // 修改界面字号，立即生效并在退出时保存
+    pub fn set_font_scale(&mut self, ctx: &egui::Context, scale: f32) {
+        self.font_scale = scale.clamp(*FONT_SCALE_RANGE.start(), *FONT_SCALE_RANGE.end());
+        apply_font_scale(ctx, self.font_scale);
+    }
+
+    // 切换用户字体文件，None 表示只使用内置字体；文件无法加载时保留原来的字体并通知
+    pub fn set_font_file(&mut self, ctx: &egui::Context, path: Option<PathBuf>) {
+        if let Some(Err(e)) = path.as_deref().map(load_font_file) {
+            notify(&self.notifications, NotificationKind::Error, e);
+            return;
+        }
+        for warning in apply_fonts(ctx, path.as_deref()) {
+            notify(&self.notifications, NotificationKind::Error, warning);
+        }
+        self.font_file = path;
+    }
+
+    // Ctrl + 加号/等号放大字号，Ctrl + 减号缩小，Ctrl + 0 恢复默认
+    fn handle_font_shortcuts(&mut self, ctx: &egui::Context) {
+        use egui::gui_zoom::kb_shortcuts::{ZOOM_IN, ZOOM_IN_SECONDARY, ZOOM_OUT, ZOOM_RESET};
+        let (zoom_in, zoom_out, reset) = ctx.input_mut(|i| {
+            (
+                i.consume_shortcut(&ZOOM_IN) || i.consume_shortcut(&ZOOM_IN_SECONDARY),
+                i.consume_shortcut(&ZOOM_OUT),
+                i.consume_shortcut(&ZOOM_RESET),
+            )
+        });
+        if reset {
+            self.set_font_scale(ctx, 1.0);
+        } else if zoom_in {
+            self.set_font_scale(ctx, step_font_scale(self.font_scale, 1));
+        } else if zoom_out {
+            self.set_font_scale(ctx, step_font_scale(self.font_scale, -1));
+        }
+    }
+
+    // 在各面板绘制前处理快捷键，匹配的按键被消耗，不会再输入到输入框中
+    // 快捷键设置窗口等待新组合键时，下一次按键用于修改绑定
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if let Some(action) = self.shortcut_capture {
+            self.capture_shortcut(ctx, action);
+            return;
+        }
+
+        let typing = ctx.wants_keyboard_input();
+        let pressed = ctx.input_mut(|i| self.shortcuts.take_pressed(i, typing));
+        for action in pressed {
+            match action {
+                // 发送框和消息列表只在连接界面显示
+                ShortcutAction::Send | ShortcutAction::ClearMessages if self.current_view != AppView::Connection => {}
+                ShortcutAction::Send => send_input(self),
+                ShortcutAction::ClearMessages => request_clear_messages(self),
+                ShortcutAction::ToggleConnection => toggle_connection(self),
+                ShortcutAction::ConnectionView => self.current_view = AppView::Connection,
+                ShortcutAction::ScanView => self.current_view = AppView::Scan,
+                ShortcutAction::Search => {
+                    self.current_view = AppView::Scan;
+                    self.scan_log_search.focus_requested = true;
+                }
+            }
+        }
+    }
+
+    // 读取本帧第一个按键作为 action 的新快捷键，Esc 取消修改
+    fn capture_shortcut(&mut self, ctx: &egui::Context, action: ShortcutAction) {
+        let pressed = ctx.input_mut(|i| {
+            let index = i
+                .events
+                .iter()
+                .position(|event| matches!(event, egui::Event::Key { pressed: true, .. }))?;
+            match i.events.remove(index) {
+                egui::Event::Key { key, modifiers, .. } => Some(egui::KeyboardShortcut::new(modifiers, key)),
+                _ => None,
+            }
+        });
+        let Some(shortcut) = pressed else {
+            return;
+        };
+        self.shortcut_capture = None;
+        if shortcut.logical_key == egui::Key::Escape {
+            return;
+        }
+        self.shortcuts_status = match self.shortcuts.set(action, shortcut) {
+            Ok(()) => None,
+            Err(BindError::NoModifier) => Some(tr("快捷键需要包含 Ctrl 或 Alt").to_string()),
+            Err(BindError::Reserved) => Some(tr("该组合键用于调整字号").to_string()),
+            Err(BindError::Conflict(other)) => Some(trf!("与“{}”的快捷键冲突", other.label())),
+        };
     }
 
     /// 渲染连接界面
@@ -168,26 +753,287 @@ impl TcpClientApp {
         egui::CentralPanel::default().show(ctx, |ui| {
             render_scan_panel(self, ui);
         });
+
+        // 超大范围扫描确认对话框
+        render_scan_confirm_window(self, ctx);
     }
 }
 
 impl App for TcpClientApp {
+    // 保存界面主题、语言、布局和客户端设置，窗口大小和位置由 eframe 自己保存
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, THEME_KEY, &self.theme);
+        eframe::set_value(storage, LANGUAGE_KEY, &self.language);
+        eframe::set_value(storage, FONT_SCALE_KEY, &self.font_scale);
+        eframe::set_value(storage, FONT_FILE_KEY, &self.font_file);
+        eframe::set_value(storage, SHORTCUTS_KEY, &self.shortcuts);
+        let layout = SavedLayout {
+            view: self.current_view,
+            monitor_size: self.monitor_size,
+        };
+        eframe::set_value(storage, LAYOUT_KEY, &layout);
+        // 退出时无处提示，保存失败只能忽略
+        let _ = save_settings(&ClientSettings::capture(self));
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+        self.ensure_window_visible(ctx);
+        self.update_window_title(ctx);
+        self.handle_font_shortcuts(ctx);
+        self.handle_shortcuts(ctx);
+
         // 顶部菜单栏 - 切换不同界面
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                ui.selectable_value(&mut self.current_view, AppView::Connection, "连接");
-                ui.selectable_value(&mut self.current_view, AppView::Scan, "IP扫描");
+                ui.selectable_value(&mut self.current_view, AppView::Connection, tr("连接"));
+                ui.selectable_value(&mut self.current_view, AppView::Scan, tr("IP扫描"));
+                render_session_tag(&self.session_tag, ui);
+                ui.separator();
+                render_notification_menu(self, ui);
+                render_theme_menu(self, ui);
+                render_language_menu(self, ui);
+                render_settings_menu(self, ui);
             });
         });
 
+        // 底部状态栏，先于各界面的面板添加，占满窗口宽度
+        render_status_bar(self, ctx);
+
         // 根据当前界面类型显示不同内容
         match self.current_view {
             AppView::Connection => self.render_connection_view(ctx),
             AppView::Scan => self.render_scan_view(ctx),
         }
 
-        // 强制每帧重绘，确保消息及时显示
-        ctx.request_repaint();
+        // 自动应答规则、触发器和连接配置编辑窗口
+        render_rules_window(self, ctx);
+        render_triggers_window(self, ctx);
+        render_profiles_window(self, ctx);
+        render_shortcuts_window(self, ctx);
+
+        // 触发器命中时闪烁窗口边框
+        render_trigger_flash(self, ctx);
+
+        // 右下角的通知
+        render_toasts(self, ctx);
+
+        // 没有新数据和输入事件时只按计时显示的需要重绘
+        if let Some(interval) = self.repaint_interval() {
+            ctx.request_repaint_after(interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::scanner::scan_ip_range;
+    use crate::network::{handle_data_reception, ConnectOptions};
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::{TcpListener, TcpStream};
+
+    // 等待消息列表中出现指定内容
+    async fn wait_for_message(messages: &SharedMessages, expected: &str) {
+        for _ in 0..100 {
+            if messages.lock().unwrap().iter().any(|entry| entry.text == expected) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("未收到消息: {}", expected);
+    }
+
+    // 会话和规则文件按变体名保存编码模式，格式变化会导致旧文件无法读取
+    #[test]
+    fn encoding_mode_serializes_by_variant_name() {
+        assert_eq!(serde_json::to_string(&EncodingMode::Utf8).unwrap(), "\"Utf8\"");
+        assert_eq!(serde_json::to_string(&EncodingMode::Hex).unwrap(), "\"Hex\"");
+        let mode: EncodingMode = serde_json::from_str("\"Hex\"").unwrap();
+        assert_eq!(mode, EncodingMode::Hex);
+        assert_eq!(EncodingMode::default(), EncodingMode::Utf8);
+    }
+
+    #[test]
+    fn window_on_missing_monitor_is_moved_back() {
+        let laptop = Some(egui::vec2(1366.0, 768.0));
+        let desktop = Some(egui::vec2(2560.0, 1440.0));
+        let window = |x: f32, y: f32| egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(1000.0, 700.0));
+
+        // 显示器没有变化时相信保存的位置，包括位于副屏上的负坐标
+        assert!(!window_off_screen(window(-1800.0, 100.0), desktop, desktop));
+        // 上次在大显示器的右下方，换到小显示器后标题栏已看不到
+        assert!(window_off_screen(window(1500.0, 700.0), laptop, desktop));
+        assert!(!window_off_screen(window(100.0, 30.0), laptop, desktop));
+        assert!(window_off_screen(window(100.0, 30.0), None, desktop));
+    }
+
+    // 以前空闲时也每秒重绘一次，现在只在网络或扫描任务通知时重绘；连接和扫描期间每秒刷新计时
+    #[test]
+    fn idle_app_does_not_repaint_on_timer() {
+        let app = TcpClientApp::default();
+        assert_eq!(app.repaint_interval(), None);
+        lock_or_recover(&app.batch_progress).running = true;
+        assert_eq!(app.repaint_interval(), None);
+
+        let connected = TcpClientApp {
+            is_connected: true,
+            ..Default::default()
+        };
+        assert_eq!(connected.repaint_interval(), Some(CLOCK_REPAINT_INTERVAL));
+
+        let scanning = TcpClientApp::default();
+        scanning.is_scanning.store(true, Ordering::Relaxed);
+        assert_eq!(scanning.repaint_interval(), Some(CLOCK_REPAINT_INTERVAL));
+    }
+
+    // 连接状态变化、切换界面、载入配置和恢复默认设置都不应清空发送框
+    #[test]
+    fn send_text_is_only_cleared_by_send_option() {
+        let mut app = TcpClientApp {
+            send_text: "AT+RST".to_string(),
+            ..Default::default()
+        };
+        app.profiles.push(app.capture_profile("plc"));
+
+        app.is_connected = true;
+        app.current_view = AppView::Scan;
+        app.apply_profile(0);
+        ClientSettings::default().apply(&mut app);
+        app.current_view = AppView::Connection;
+        app.is_connected = false;
+        app.connected_at = None;
+        assert_eq!(app.send_text, "AT+RST");
+
+        app.clear_after_send = false;
+        app.finish_send();
+        assert_eq!(app.send_text, "AT+RST");
+        app.clear_after_send = true;
+        app.finish_send();
+        assert!(app.send_text.is_empty());
+    }
+
+    #[test]
+    fn profile_round_trips_connection_settings() {
+        let mut app = TcpClientApp {
+            ip: "10.0.0.5".to_string(),
+            port: "502".to_string(),
+            idle_timeout_secs: 30,
+            keepalive_secs: 15,
+            keepalive_payload: "00".to_string(),
+            keepalive_mode: EncodingMode::Hex,
+            ..Default::default()
+        };
+        app.set_encoding_mode(EncodingMode::Hex);
+        let profile = app.capture_profile("  plc ");
+        assert_eq!(profile.name, "plc");
+
+        let mut other = TcpClientApp::default();
+        other.profiles.push(profile);
+        other.apply_profile(0);
+        assert_eq!((other.ip.as_str(), other.port.as_str()), ("10.0.0.5", "502"));
+        assert_eq!(other.idle_timeout_secs, 30);
+        assert_eq!((other.keepalive_secs, other.keepalive_mode), (15, EncodingMode::Hex));
+        assert_eq!(*other.shared_encoding_mode.lock().unwrap(), EncodingMode::Hex);
+        assert_eq!(other.selected_profile, Some(0));
+
+        // 不存在的配置不改变当前设置
+        other.apply_profile(5);
+        assert_eq!(other.selected_profile, Some(0));
+    }
+
+    #[tokio::test]
+    async fn receiver_observes_encoding_mode_toggle() {
+        let mut app = TcpClientApp::default();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+        let (read_half, _write_half) = client.into_split();
+        let (tx, _rx) = mpsc::channel(8);
+
+        tokio::spawn(handle_data_reception(
+            app.received_messages.clone(),
+            read_half,
+            app.shared_encoding_mode.clone(),
+            None,
+            app.auto_reply_rules.clone(),
+            app.triggers.clone(),
+            tx,
+            ConnectOptions::default(),
+            app.notifications.clone(),
+            SharedMetrics::default().connected("test".to_string()),
+            Default::default(),
+            Repaint::default(),
+        ));
+
+        server.write_all(b"hi").await.unwrap();
+        wait_for_message(&app.received_messages, "收到(UTF-8): hi").await;
+
+        // 在界面上切换为十六进制后，接收任务应按新模式显示
+        app.set_encoding_mode(EncodingMode::Hex);
+        server.write_all(b"hi").await.unwrap();
+        wait_for_message(&app.received_messages, "收到(HEX): 68 69").await;
+    }
+
+    #[tokio::test]
+    async fn stop_button_cancels_scan_across_channel() {
+        let app = TcpClientApp::default();
+        let config = ScanConfig {
+            start_ip: "127.0.0.1".to_string(),
+            end_ip: "127.0.0.1".to_string(),
+            start_port: 1,
+            end_port: 65535,
+            timeout_ms: 200,
+            retries: 0,
+            rate_limit: 200, // 限速使扫描足够慢，确保取消时仍在进行
+            max_connections: 8,
+            source_addr: None,
+            excludes: Vec::new(),
+            verbose_logs: false,
+        };
+
+        // 与界面一样置位后通过命令通道发送，网络任务一侧只拿到标志的克隆
+        let (tx, mut rx) = mpsc::channel(1);
+        app.is_scanning.store(true, Ordering::Relaxed);
+        tx.send(Message::ScanIp(
+            config,
+            app.scan_results.clone(),
+            app.scan_logs.clone(),
+            app.scan_progress.clone(),
+            app.is_scanning.clone(),
+            app.scan_record.clone(),
+        ))
+        .await
+        .unwrap();
+        let Some(Message::ScanIp(config, results, logs, progress, is_scanning, _)) = rx.recv().await else {
+            panic!("未收到扫描命令");
+        };
+        let scan = tokio::spawn(scan_ip_range(
+            config,
+            Arc::new(Mutex::new(Vec::new())),
+            results,
+            logs,
+            is_scanning,
+            progress,
+            Repaint::default(),
+        ));
+
+        // 等扫描开始后在界面一侧取消
+        for _ in 0..100 {
+            if app.scan_progress.lock().unwrap().completed_probes > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        app.is_scanning.store(false, Ordering::Relaxed);
+
+        tokio::time::timeout(Duration::from_secs(5), scan)
+            .await
+            .expect("取消后扫描任务应尽快结束")
+            .unwrap();
+        let progress = app.scan_progress.lock().unwrap();
+        assert!(progress.cancelled);
+        assert!(progress.completed_probes < progress.total_probes);
+        assert!(!app.is_scanning.load(Ordering::Relaxed));
     }
 }
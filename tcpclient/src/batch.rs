@@ -0,0 +1,64 @@
+use crate::app::EncodingMode;
+use crate::utils::{is_valid_hex_string, strip_hex_annotations};
+
+// 批量发送的进度，由网络任务更新，界面读取
+#[derive(Debug, Clone, Default)]
+pub struct BatchProgress {
+    pub running: bool, // 界面置为 false 即在下一条之前停止
+    pub sent: usize,   // 已发送的条数
+    pub total: usize,
+}
+
+// 把输入拆分为逐条发送的消息，每个非空行一条
+// 十六进制模式下每行单独校验，跳过只有偏移列或注释的行；有无效行时返回其行号（从 1 开始）
+pub fn split_batch_lines(text: &str, mode: EncodingMode) -> Result<Vec<String>, usize> {
+    let mut lines = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        match mode {
+            EncodingMode::Utf8 => {
+                if !line.trim().is_empty() {
+                    lines.push(line.to_string());
+                }
+            }
+            EncodingMode::Hex => {
+                if strip_hex_annotations(line).trim().is_empty() {
+                    continue;
+                }
+                if !is_valid_hex_string(line) {
+                    return Err(index + 1);
+                }
+                lines.push(line.to_string());
+            }
+        }
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hex_to_bytes;
+
+    #[test]
+    fn utf8_lines_skip_blank_lines() {
+        let text = "AT\r\n\r\nAT+CWMODE=1\n   \nAT+RST";
+        assert_eq!(
+            split_batch_lines(text, EncodingMode::Utf8).unwrap(),
+            vec!["AT", "AT+CWMODE=1", "AT+RST"]
+        );
+        assert!(split_batch_lines("", EncodingMode::Utf8).unwrap().is_empty());
+    }
+
+    #[test]
+    fn hex_lines_are_parsed_independently() {
+        let text = "; 初始化序列\n01 03 00 00 00 0A  ; 读寄存器\n\n0000  02 06 00 01\n";
+        let lines = split_batch_lines(text, EncodingMode::Hex).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(hex_to_bytes(&lines[0]), [0x01, 0x03, 0x00, 0x00, 0x00, 0x0A]);
+        assert_eq!(hex_to_bytes(&lines[1]), [0x02, 0x06, 0x00, 0x01]);
+
+        // 整体位数为偶数，但每行单独都不完整
+        assert_eq!(split_batch_lines("A\nB", EncodingMode::Hex), Err(1));
+        assert_eq!(split_batch_lines("AA\nBB C\nDD", EncodingMode::Hex), Err(2));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::i18n::{tr, trf};
+use crate::message::{MessageEntry, MessageKind, SharedMessages};
+use crate::utils::{get_timestamp, lock_or_recover};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// 两次断线桌面通知的最小间隔，期间的断开只计数，在下一次通知中说明
+pub const DISCONNECT_ALERT_INTERVAL: Duration = Duration::from_secs(60);
+
+// 连接被对端关闭或因错误断开时的系统桌面通知，窗口在后台时也能看到
+// 同时记录连接已断开，重新连接前窗口标题显示 [已断开]
+// 默认值不发送桌面通知，用于测试；界面按设置开启
+#[derive(Default)]
+pub struct DisconnectAlerts {
+    enabled: AtomicBool,
+    lost: AtomicBool, // 连接意外断开后尚未重新连接
+    throttle: Mutex<AlertThrottle>,
+}
+
+pub type SharedDisconnectAlerts = Arc<DisconnectAlerts>;
+
+// 断线通知的节流状态
+#[derive(Default)]
+struct AlertThrottle {
+    last_sent: Option<Instant>,
+    suppressed: usize, // 上次通知后因节流未通知的断开次数
+}
+
+impl AlertThrottle {
+    // 距上次通知超过间隔时返回此前被跳过的次数并记录本次通知，否则计数后返回 None
+    fn check(&mut self, now: Instant) -> Option<usize> {
+        if self
+            .last_sent
+            .is_some_and(|last| now.duration_since(last) < DISCONNECT_ALERT_INTERVAL)
+        {
+            self.suppressed += 1;
+            return None;
+        }
+        self.last_sent = Some(now);
+        Some(std::mem::take(&mut self.suppressed))
+    }
+}
+
+impl DisconnectAlerts {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: AtomicBool::new(enabled),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    // 连接意外断开且尚未重新连接
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    // 建立新连接时调用，清除断开状态
+    pub fn connected(&self) {
+        self.lost.store(false, Ordering::Relaxed);
+    }
+
+    // 接收任务发现连接被关闭或出错时调用；开启通知且未被节流时在后台发送桌面通知
+    // 发送失败 (如没有通知服务) 时在消息列表中提示
+    pub fn disconnected(&self, peer: Option<SocketAddr>, reason: &str, messages: &SharedMessages) {
+        self.lost.store(true, Ordering::Relaxed);
+        if !self.is_enabled() {
+            return;
+        }
+        let Some(suppressed) = lock_or_recover(&self.throttle).check(Instant::now()) else {
+            return;
+        };
+        let body = alert_body(peer, reason, suppressed);
+        let messages = messages.clone();
+        // D-Bus 等系统接口是阻塞调用，不占用网络任务的线程
+        tokio::task::spawn_blocking(move || {
+            let result = notify_rust::Notification::new()
+                .appname("tcpclient")
+                .summary(tr("TCP 连接已断开"))
+                .body(&body)
+                .show();
+            if let Err(e) = result {
+                lock_or_recover(&messages).push(MessageEntry::new(
+                    get_timestamp(),
+                    MessageKind::Error,
+                    trf!("发送桌面通知失败: {}", e),
+                ));
+            }
+        });
+    }
+}
+
+// 通知正文：对端地址、断开原因，以及节流期间未通知的断开次数
+fn alert_body(peer: Option<SocketAddr>, reason: &str, suppressed: usize) -> String {
+    let mut body = match peer {
+        Some(peer) => format!("{}: {}", peer, reason),
+        None => reason.to_string(),
+    };
+    if suppressed > 0 {
+        body.push('\n');
+        body.push_str(&trf!("此前 {} 次断开未通知", suppressed));
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_disconnects_are_throttled() {
+        let mut throttle = AlertThrottle::default();
+        let start = Instant::now();
+        assert_eq!(throttle.check(start), Some(0));
+        assert_eq!(throttle.check(start + Duration::from_secs(1)), None);
+        assert_eq!(throttle.check(start + Duration::from_secs(30)), None);
+        assert_eq!(throttle.check(start + DISCONNECT_ALERT_INTERVAL), Some(2));
+        assert_eq!(throttle.check(start + DISCONNECT_ALERT_INTERVAL * 3), Some(0));
+
+        let peer = "192.168.1.10:9000".parse().ok();
+        assert_eq!(alert_body(peer, "对端关闭了连接", 0), "192.168.1.10:9000: 对端关闭了连接");
+        assert_eq!(alert_body(None, "连接被对端重置", 2), "连接被对端重置\n此前 2 次断开未通知");
+    }
+
+    // 关闭通知时只记录断开状态，重新连接后清除
+    #[test]
+    fn lost_state_is_cleared_on_reconnect() {
+        let alerts = DisconnectAlerts::default();
+        let messages: SharedMessages = Arc::new(Mutex::new(Vec::new()));
+        alerts.disconnected(None, "对端关闭了连接", &messages);
+        assert!(alerts.is_lost());
+        assert!(lock_or_recover(&alerts.throttle).last_sent.is_none());
+        alerts.connected();
+        assert!(!alerts.is_lost());
+    }
+}
@@ -0,0 +1,99 @@
+// 距离底部多少像素以内视为停留在底部
+pub const DEFAULT_FOLLOW_THRESHOLD: f32 = 30.0;
+
+// 接收消息列表的跟随状态：停留在底部时新消息自动滚动到底部；
+// 用户向上翻阅时不再打扰，只统计离开底部后收到的新消息数量
+pub struct MessageFollow {
+    pub threshold: f32, // 距离底部多少像素以内视为在底部
+    at_bottom: bool,
+    seen: usize,       // 最后一次停留在底部时的消息数
+    last_count: usize, // 上一帧的消息数，用于判断是否有新消息
+    jump: bool,        // 下一帧滚动到底部
+}
+
+impl Default for MessageFollow {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_FOLLOW_THRESHOLD,
+            at_bottom: true,
+            seen: 0,
+            last_count: 0,
+            jump: false,
+        }
+    }
+}
+
+impl MessageFollow {
+    // 本帧是否需要滚动到底部：点击了新消息按钮，或启用自动滚动且停留在底部时收到了新消息
+    pub fn should_scroll(&mut self, count: usize, auto_scroll: bool) -> bool {
+        let jump = std::mem::take(&mut self.jump);
+        let grew = count > self.last_count;
+        self.last_count = count;
+        jump || (auto_scroll && self.at_bottom && grew)
+    }
+
+    // 根据本帧的滚动位置更新状态；scrolled 表示本帧已请求滚动到底部，滚动在下一帧才生效
+    pub fn update(&mut self, offset: f32, viewport: f32, content: f32, count: usize, scrolled: bool) {
+        self.at_bottom = scrolled || offset + viewport >= content - self.threshold;
+        // 消息被清空后重新计数
+        if self.at_bottom || count < self.seen {
+            self.seen = count;
+        }
+    }
+
+    // 离开底部后收到的新消息数
+    pub fn unread(&self, count: usize) -> usize {
+        if self.at_bottom {
+            0
+        } else {
+            count.saturating_sub(self.seen)
+        }
+    }
+
+    // 点击新消息按钮后在下一帧跳到底部
+    pub fn request_jump(&mut self) {
+        self.jump = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_messages_after_leaving_bottom() {
+        let mut follow = MessageFollow::default();
+        // 停留在底部时新消息会滚动到底部，不计入未读
+        assert!(follow.should_scroll(5, true));
+        follow.update(0.0, 100.0, 120.0, 5, true);
+        assert_eq!(follow.unread(5), 0);
+
+        // 向上翻阅后不再滚动，只统计新消息
+        follow.update(0.0, 100.0, 300.0, 5, false);
+        assert!(!follow.should_scroll(8, true));
+        follow.update(0.0, 100.0, 360.0, 8, false);
+        assert_eq!(follow.unread(8), 3);
+
+        // 点击按钮后跳到底部，未读清零
+        follow.request_jump();
+        assert!(follow.should_scroll(8, true));
+        follow.update(0.0, 100.0, 360.0, 8, true);
+        assert_eq!(follow.unread(8), 0);
+    }
+
+    #[test]
+    fn threshold_counts_as_bottom() {
+        let mut follow = MessageFollow::default();
+        follow.update(175.0, 100.0, 300.0, 4, false);
+        assert_eq!(follow.unread(6), 0);
+        assert!(!follow.should_scroll(6, false));
+
+        // 阈值调小后同样的位置不再算作底部
+        follow.threshold = 10.0;
+        follow.update(175.0, 100.0, 300.0, 4, false);
+        assert_eq!(follow.unread(7), 3);
+        // 消息被清空后重新计数
+        follow.update(0.0, 100.0, 300.0, 0, false);
+        assert_eq!(follow.unread(2), 2);
+    }
+}
@@ -0,0 +1,39 @@
+use rand::Rng;
+use tcpcommon::hexdump::to_hex;
+
+// 一次最多生成的随机数据长度
+pub const MAX_RANDOM_LEN: usize = 65536;
+
+// 生成 len 字节的随机数据，printable 为 true 时只包含可打印 ASCII 字符 (空格到 ~)
+pub fn random_payload(len: usize, printable: bool) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+    (0..len)
+        .map(|_| if printable { rng.gen_range(0x20..=0x7E) } else { rng.gen() })
+        .collect()
+}
+
+// 转换为发送框使用的十六进制格式 (大写、空格分隔)，发送记录中按此格式保存以便重放
+pub fn payload_hex(data: &[u8]) -> String {
+    to_hex(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hex_to_bytes;
+
+    #[test]
+    fn printable_payload_stays_in_ascii_range() {
+        let data = random_payload(1000, true);
+        assert_eq!(data.len(), 1000);
+        assert!(data.iter().all(|&b| (0x20..=0x7E).contains(&b)));
+        assert!(random_payload(0, false).is_empty());
+    }
+
+    #[test]
+    fn hex_round_trips_to_same_bytes() {
+        let data = random_payload(64, false);
+        assert_eq!(hex_to_bytes(&payload_hex(&data)), data);
+        assert_eq!(payload_hex(&[0x0A, 0xFF]), "0A FF");
+    }
+}
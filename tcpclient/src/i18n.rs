@@ -0,0 +1,678 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+// 界面语言，用户选择后由 eframe 保存，下次启动时恢复
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    Chinese,
+    English,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::Chinese, Language::English];
+
+    // 菜单中显示的名称，始终使用该语言本身的写法
+    pub fn label(&self) -> &'static str {
+        match self {
+            Language::Chinese => "中文",
+            Language::English => "English",
+        }
+    }
+}
+
+// 当前界面语言，界面线程和网络任务都会读取
+static CURRENT: AtomicU8 = AtomicU8::new(Language::Chinese as u8);
+
+pub fn set_language(language: Language) {
+    CURRENT.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    match CURRENT.load(Ordering::Relaxed) {
+        1 => Language::English,
+        _ => Language::Chinese,
+    }
+}
+
+// 按当前语言取文案，中文原文同时作为查表的键
+pub fn tr(text: &'static str) -> &'static str {
+    translate(language(), text)
+}
+
+// 没有译文的文案显示中文原文
+fn translate(language: Language, text: &'static str) -> &'static str {
+    static ENGLISH_MAP: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+    match language {
+        Language::Chinese => text,
+        Language::English => ENGLISH_MAP
+            .get_or_init(|| ENGLISH.iter().copied().collect())
+            .get(text)
+            .copied()
+            .unwrap_or(text),
+    }
+}
+
+// 依次用参数替换模板中的 {}，供 trf! 使用
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut parts = template.split("{}");
+    let mut text = parts.next().unwrap_or_default().to_string();
+    for (index, part) in parts.enumerate() {
+        if let Some(arg) = args.get(index) {
+            text.push_str(&arg.to_string());
+        }
+        text.push_str(part);
+    }
+    text
+}
+
+// 带参数的文案，用法与 format! 相同，但模板中只能使用 {}
+// 参数的引用在块内释放，可以直接用在 .await 所在的语句中
+macro_rules! trf {
+    ($template:literal $(, $arg:expr)* $(,)?) => {{
+        let text = $crate::i18n::fill(
+            $crate::i18n::tr($template),
+            &[$(&$arg as &dyn std::fmt::Display),*],
+        );
+        text
+    }};
+}
+pub(crate) use trf;
+
+// 中文原文到英文译文的对照表
+const ENGLISH: &[(&str, &str)] = &[
+    // 顶部菜单
+    ("TCP 客户端", "TCP Client"),
+    ("连接", "Connect"),
+    ("IP扫描", "IP Scan"),
+    ("主题", "Theme"),
+    ("跟随系统", "System"),
+    ("浅色", "Light"),
+    ("深色", "Dark"),
+    ("切换明暗主题", "Toggle light/dark theme"),
+    ("语言", "Language"),
+    ("设置", "Settings"),
+    ("恢复默认设置", "Restore defaults"),
+    ("字号:", "Font size:"),
+    ("重置", "Reset"),
+    ("恢复默认字号，也可按 Ctrl+0；Ctrl 加减号调整字号", "Restore the default font size (Ctrl+0); Ctrl +/- adjusts it"),
+    ("断线桌面通知", "Desktop alert on disconnect"),
+    (
+        "连接被对端关闭或出错断开时发送系统通知，1 分钟内最多通知一次",
+        "Show a system notification when the peer closes the connection or it fails; at most once per minute",
+    ),
+    ("[已断开] {}", "[Disconnected] {}"),
+    ("TCP 连接已断开", "TCP connection lost"),
+    ("发送桌面通知失败: {}", "Failed to show desktop notification: {}"),
+    ("此前 {} 次断开未通知", "{} earlier disconnects were not notified"),
+    ("字体:", "Font:"),
+    ("内置字体", "Built-in font"),
+    ("选择文件...", "Choose file..."),
+    ("使用 TTF/OTF 字体文件，缺少的字符仍由内置字体显示", "Use a TTF/OTF font file; missing characters still come from the built-in font"),
+    ("字体", "Fonts"),
+    ("使用内置字体", "Use built-in font"),
+    ("内置字体无法加载，使用默认字体，中文可能无法显示: {}", "The built-in font failed to load, using the default font; Chinese text may not display: {}"),
+    ("无法加载字体文件 {}: {}", "Failed to load font file {}: {}"),
+    ("快捷键...", "Shortcuts..."),
+    ("快捷键", "Shortcuts"),
+    ("清空消息", "Clear messages"),
+    ("连接/断开", "Connect/disconnect"),
+    ("切换到连接界面", "Switch to connection view"),
+    ("切换到扫描界面", "Switch to scan view"),
+    ("搜索扫描日志", "Search scan logs"),
+    (
+        "输入框有焦点时只响应发送和搜索，其余快捷键留给输入框使用。",
+        "While a text box has focus only Send and Search respond; other keys are left to the text box.",
+    ),
+    ("请按下新的组合键，Esc 取消", "Press the new key combination, Esc to cancel"),
+    ("已禁用", "Disabled"),
+    ("修改", "Change"),
+    ("禁用", "Disable"),
+    ("默认", "Default"),
+    ("全部恢复默认", "Restore all defaults"),
+    ("快捷键需要包含 Ctrl 或 Alt", "Shortcuts must include Ctrl or Alt"),
+    ("该组合键用于调整字号", "This combination adjusts the font size"),
+    ("与“{}”的快捷键冲突", "Conflicts with the shortcut for \"{}\""),
+    (
+        "连接和扫描设置恢复为初始值，已保存的连接配置、规则和触发器不受影响",
+        "Reset connection and scan settings to their initial values; saved profiles, rules and triggers are kept",
+    ),
+    ("删除配置文件失败: {}", "Failed to delete the settings file: {}"),
+    ("通知", "Notifications"),
+    ("连接状态", "Connection status"),
+    ("扫描完成", "Scan finished"),
+    ("错误", "Errors"),
+    ("点击关闭", "Click to dismiss"),
+    // 状态栏
+    ("● 已连接 {}", "● Connected to {}"),
+    ("本地 {}", "Local {}"),
+    ("● 监听中", "● Listening"),
+    ("● 已连接", "● Connected"),
+    ("● 未连接", "● Disconnected"),
+    ("发送 {} / 接收 {}", "Sent {} / Received {}"),
+    ("打开所在目录", "Open the containing folder"),
+    ("扫描中 {}/{} ({}%)", "Scanning {}/{} ({}%)"),
+    ("打开目录失败: {}", "Failed to open the folder: {}"),
+    // 连接设置
+    ("连接设置", "Connection Settings"),
+    ("角色:", "Role:"),
+    ("客户端", "Client"),
+    ("服务器", "Server"),
+    (
+        "监听指定地址和端口，接受客户端连接后收发数据",
+        "Listen on the given address and port and exchange data with the accepted client",
+    ),
+    ("监听地址:", "Listen address:"),
+    ("如 0.0.0.0", "e.g. 0.0.0.0"),
+    ("IP 地址:", "IP address:"),
+    ("输入服务器IP", "Server IP"),
+    ("端口号:", "Port:"),
+    ("输入端口", "Port"),
+    ("数据编码模式:", "Encoding:"),
+    ("十六进制(HEX)", "Hexadecimal (HEX)"),
+    ("会话标签:", "Session label:"),
+    ("标签颜色", "Label color"),
+    ("如 产线PLC", "e.g. Line PLC"),
+    ("会话:", "Session:"),
+    ("源地址:", "Source address:"),
+    ("默认路由", "Default route"),
+    (
+        "绑定的本地IP地址，留空表示由系统选择",
+        "Local IP address to bind; leave empty to let the system choose",
+    ),
+    ("空闲超时(秒):", "Idle timeout (s):"),
+    (
+        "超过该时间未收到数据时发出警告，0 表示不检测，下次连接时生效",
+        "Warn when no data arrives for this long; 0 disables the check. Applies to the next connection",
+    ),
+    ("超时后自动断开", "Disconnect on timeout"),
+    ("心跳间隔(秒):", "Keepalive interval (s):"),
+    (
+        "超过该时间没有发送数据时自动发送心跳内容，手动发送会重新计时，0 表示不发送",
+        "Send the keepalive payload after this long without sending; manual sends restart the timer. 0 disables keepalive",
+    ),
+    ("文本", "Text"),
+    ("心跳内容", "Keepalive payload"),
+    ("自动应答规则 ({})", "Auto-reply rules ({})"),
+    ("触发器 ({})", "Triggers ({})"),
+    ("监听", "Listen"),
+    ("停止监听", "Stop listening"),
+    ("断开", "Disconnect"),
+    ("状态:", "Status:"),
+    ("监听中", "Listening"),
+    ("已连接", "Connected"),
+    ("未连接", "Disconnected"),
+    ("本地地址:", "Local address:"),
+    ("远端地址:", "Remote address:"),
+    ("客户端:", "Client:"),
+    ("等待接入", "Waiting for a client"),
+    ("连接时长:", "Connected for:"),
+    ("消息数量:", "Messages:"),
+    (
+        "心跳内容为空，请填写心跳内容或将心跳间隔设为 0",
+        "The keepalive payload is empty; fill it in or set the keepalive interval to 0",
+    ),
+    ("心跳内容不是有效的十六进制数据: {}", "The keepalive payload is not valid hex: {}"),
+    // 连接配置
+    ("连接配置:", "Profile:"),
+    ("选择配置", "Select a profile"),
+    ("暂无配置", "No profiles"),
+    ("管理", "Manage"),
+    ("配置名称", "Profile name"),
+    ("保存为配置", "Save as profile"),
+    (
+        "保存当前的地址、端口、编码、源地址、空闲超时和会话标签设置，同名配置会被覆盖",
+        "Save the current address, port, encoding, source address, idle timeout and session label; a profile with the same name is overwritten",
+    ),
+    ("配置已保存", "Profiles saved"),
+    ("保存失败: {}", "Failed to save: {}"),
+    ("连接配置", "Connection Profiles"),
+    (
+        "暂无配置，在连接设置中填写名称后点击\"保存为配置\"",
+        "No profiles yet. Enter a name in the connection settings and click \"Save as profile\"",
+    ),
+    ("覆盖", "Overwrite"),
+    ("用连接设置面板中的当前设置替换该配置", "Replace this profile with the current connection settings"),
+    ("删除", "Delete"),
+    ("保存", "Save"),
+    // 会话录制
+    ("会话录制与回放", "Session Recording and Replay"),
+    ("本地回环测试", "Loopback test"),
+    ("测试内容", "Test payload"),
+    ("开始测试", "Run test"),
+    ("连接到进程内的回显服务器，检查连接、发送和接收是否正常", "Connect to an in-process echo server and check that connecting, sending and receiving work"),
+    ("断开当前连接后才能测试", "Disconnect first to run the test"),
+    ("测试中...", "Testing..."),
+    ("✔ 通过，往返 {} ms", "✔ Passed, round trip {} ms"),
+    ("✘ 失败: {}", "✘ Failed: {}"),
+    ("回显内容与发送的数据不一致", "Echoed data does not match what was sent"),
+    ("无法启动回显服务器: {}", "Failed to start the echo server: {}"),
+    ("{} 秒内未收到回显", "No echo received within {} s"),
+    ("开始录制", "Start recording"),
+    ("停止并保存 ({} 条)", "Stop and save ({} sends)"),
+    ("会话已保存: {}", "Session saved: {}"),
+    ("保存会话失败: {}", "Failed to save the session: {}"),
+    ("● 正在录制", "● Recording"),
+    ("停止回放", "Stop replay"),
+    ("暂无已保存的会话", "No saved sessions"),
+    ("回放", "Replay"),
+    ("连接后才能回放", "Connect before replaying"),
+    // 自动应答规则和触发器
+    ("自动应答规则", "Auto-reply Rules"),
+    (
+        "收到的数据包含匹配内容时，自动发送对应的应答内容。",
+        "When received data contains the match text, the reply is sent automatically.",
+    ),
+    (
+        "对端回显的应答不会再次触发规则，且每秒最多自动应答10次。",
+        "Replies echoed back by the peer do not trigger rules again, and at most 10 replies are sent per second.",
+    ),
+    ("暂无规则", "No rules"),
+    ("匹配", "Match"),
+    ("应答", "Reply"),
+    ("添加规则", "Add rule"),
+    ("规则已保存", "Rules saved"),
+    ("触发器", "Triggers"),
+    (
+        "收到的数据包含匹配内容时，在消息列表中插入高亮标记并闪烁窗口边框。",
+        "When received data contains the match text, a highlighted marker is inserted into the message list and the window border flashes.",
+    ),
+    ("暂无触发器", "No triggers"),
+    ("标记和闪烁的颜色", "Marker and flash color"),
+    ("添加触发器", "Add trigger"),
+    ("触发器已保存", "Triggers saved"),
+    // 接收消息
+    ("接收消息", "Received Messages"),
+    ("📌 禁用自动滚动", "📌 Disable auto-scroll"),
+    ("📌 启用自动滚动", "📌 Enable auto-scroll"),
+    ("🗑️ 清空消息", "🗑️ Clear messages"),
+    ("📤 导出消息", "📤 Export messages"),
+    ("显示:", "Display:"),
+    ("十进制", "Decimal"),
+    ("双视图", "Dual view"),
+    ("收发的数据同时显示文本和十六进制", "Show sent and received data as both text and hex"),
+    ("贴底阈值:", "Stick-to-bottom threshold:"),
+    (
+        "向上翻阅超过该距离后，新消息不再自动滚动到底部",
+        "After scrolling up further than this, new messages no longer scroll to the bottom",
+    ),
+    ("最大显示长度:", "Max display length:"),
+    (" 字符", " chars"),
+    (
+        "超出部分折叠，可点击“展开”查看完整内容；0 表示不限制",
+        "Longer messages are folded; click \"Expand\" to see everything. 0 means no limit",
+    ),
+    ("暂无消息...", "No messages yet..."),
+    ("{} 字节", "{} bytes"),
+    ("{} 字符", "{} chars"),
+    ("[{}] {}… (共 {})", "[{}] {}… ({} total)"),
+    ("取消书签", "Remove bookmark"),
+    ("添加书签", "Add bookmark"),
+    ("展开", "Expand"),
+    ("消息详情", "Message details"),
+    ("时间: {}", "Time: {}"),
+    ("显示完整内容", "Show the full message"),
+    ("⬇ {} 条新消息", "⬇ {} new messages"),
+    ("确认清空", "Confirm Clear"),
+    (
+        "消息中有 {} 个书签，清空后书签将一并丢失。",
+        "The messages contain {} bookmarks, which will be lost as well.",
+    ),
+    ("确定要清空消息吗？", "Clear all messages?"),
+    ("清空", "Clear"),
+    ("发送后清空", "Clear after send"),
+    ("关闭时发送成功后保留输入内容，便于重复发送", "When off, the input is kept after sending so it can be sent again"),
+    ("取消", "Cancel"),
+    ("🔖 书签 ({})", "🔖 Bookmarks ({})"),
+    ("点击消息左侧的 🔖 添加书签", "Click 🔖 to the left of a message to bookmark it"),
+    ("消息已导出: {}", "Messages exported: {}"),
+    ("导出消息失败: {}", "Failed to export messages: {}"),
+    ("收到", "Received"),
+    ("已发送", "Sent"),
+    ("心跳", "Keepalive"),
+    ("非UTF-8数据", "non-UTF-8 data"),
+    // 发送消息
+    ("发送消息", "Send Message"),
+    ("输入要发送的UTF-8消息...", "Enter a UTF-8 message to send..."),
+    (
+        "输入要发送的十六进制数据(如: 48 65 6C 6C 6F)...",
+        "Enter hex data to send (e.g. 48 65 6C 6C 6F)...",
+    ),
+    ("格式化", "Format"),
+    ("包含非法字符: {}", "Invalid characters: {}"),
+    ("十六进制位数为奇数，最后一个字节不完整", "Odd number of hex digits; the last byte is incomplete"),
+    ("逐行批量发送", "Send line by line"),
+    ("间隔", "Interval"),
+    ("共 {} 条", "{} lines"),
+    ("第 {} 行十六进制格式无效", "Line {} is not valid hex"),
+    ("停止", "Stop"),
+    ("解析转义序列", "Parse escape sequences"),
+    (
+        "支持 \\n \\r \\t \\0 \\\\ \\xNN，含转义的内容按十六进制发送",
+        "Supports \\n \\r \\t \\0 \\\\ \\xNN; text with escapes is sent as hex",
+    ),
+    ("无效的转义序列: {}", "Invalid escape sequence: {}"),
+    ("随机数据", "Random data"),
+    (
+        "生成随机字节并发送，发送记录中以十六进制保存，便于复现",
+        "Generate and send random bytes; the send is recorded as hex so it can be reproduced",
+    ),
+    (" 字节", " bytes"),
+    ("仅可打印ASCII", "Printable ASCII only"),
+    ("发送", "Send"),
+    ("无法发送: 十六进制格式无效", "Cannot send: invalid hex"),
+    ("无法发送: 无效的转义序列 {}", "Cannot send: invalid escape sequence {}"),
+    ("可打印ASCII", "printable ASCII"),
+    ("任意字节", "any bytes"),
+    ("生成随机数据: {} 字节 ({})", "Generated random data: {} bytes ({})"),
+    ("命令队列已满，请稍后重试", "The command queue is full; try again later"),
+    ("网络任务已停止，无法执行操作", "The network task has stopped; the action cannot be performed"),
+    // IP 扫描
+    ("IP扫描工具", "IP Scanner"),
+    ("扫描网络中的开放端口，快速发现可用服务", "Scan the network for open ports to find available services"),
+    ("扫描设置", "Scan Settings"),
+    ("起始IP:", "Start IP:"),
+    ("结束IP:", "End IP:"),
+    ("起始端口:", "Start port:"),
+    ("结束端口:", "End port:"),
+    ("超时时间(ms):", "Timeout (ms):"),
+    ("超出 {}-{} ms 的范围，将按 {} ms 扫描", "Outside {}-{} ms; {} ms will be used"),
+    ("请输入毫秒数", "Enter a number of milliseconds"),
+    ("超时时间 {} ms 超出范围，已按 {} ms 扫描", "Timeout {} ms is out of range; scanning with {} ms"),
+    ("排除IP:", "Excluded IPs:"),
+    ("每行一个IP或CIDR网段，扫描时跳过这些地址", "One IP or CIDR block per line; these addresses are skipped"),
+    ("高级设置", "Advanced Settings"),
+    ("重试次数:", "Retries:"),
+    (
+        "端口探测失败后的重试次数，可减少丢包导致的漏报，但会增加扫描时间",
+        "Retries after a failed probe; reduces ports missed due to packet loss but makes the scan slower",
+    ),
+    ("限速(连接/秒):", "Rate limit (conn/s):"),
+    ("每秒最多新建的连接数，0 表示不限速", "Maximum new connections per second; 0 means unlimited"),
+    ("最大并发:", "Max concurrency:"),
+    (
+        "同时进行的连接尝试上限，过高可能耗尽系统文件描述符导致端口被误判为关闭",
+        "Maximum simultaneous connection attempts; too high may exhaust file descriptors and report open ports as closed",
+    ),
+    ("IP数量上限:", "Max IPs:"),
+    ("端口数量上限:", "Max ports:"),
+    ("详细日志:", "Verbose log:"),
+    ("显示进度、限速等信息", "Show progress, rate limiting and more"),
+    (
+        "关闭时日志只保留开始、发现端口、错误、取消和完成等关键事件",
+        "When off, the log only keeps key events such as start, open ports, errors, cancellation and completion",
+    ),
+    ("停止扫描", "Stop scan"),
+    ("开始扫描", "Start scan"),
+    ("用户取消扫描", "Scan cancelled by user"),
+    ("扫描命令发送失败", "Failed to send the scan command"),
+    ("确认扫描", "Confirm Scan"),
+    (
+        "本次扫描共需 {} 次探测 ({} - {}, 端口 {} - {})，可能耗时很长并产生大量网络流量。",
+        "This scan needs {} probes ({} - {}, ports {} - {}) and may take a long time and generate heavy network traffic.",
+    ),
+    ("确定要开始扫描吗？", "Start the scan?"),
+    ("正在扫描", "Scanning"),
+    ("就绪", "Ready"),
+    ("发现端口:", "Open ports:"),
+    ("已完成 {} / {} 次探测", "{} / {} probes done"),
+    ("已耗时:", "Elapsed:"),
+    ("已取消:", "Cancelled:"),
+    ("已进行 {}", "after {}"),
+    ("总耗时:", "Total time:"),
+    ("预计剩余:", "Remaining:"),
+    ("计算中...", "Calculating..."),
+    ("历史记录 ({})", "History ({})"),
+    ("刷新", "Refresh"),
+    ("暂无历史记录", "No history"),
+    ("加载该次扫描结果", "Load the results of this scan"),
+    ("已加载历史记录: {}", "Loaded history: {}"),
+    ("使用说明", "How to Use"),
+    ("输入IP范围和端口范围后点击开始扫描。", "Enter an IP range and port range, then click Start scan."),
+    ("扫描结果将实时显示在右侧。", "Results appear on the right as they are found."),
+    (
+        "默认最多扫描65536个IP地址和全部端口，可在高级设置中调整上限。",
+        "By default up to 65536 IP addresses and all ports are scanned; adjust the limits in Advanced Settings.",
+    ),
+    ("多线程扫描可显著提高扫描速度。", "Concurrent probing speeds up the scan considerably."),
+    (
+        "超时时间可调整扫描的等待时间，过短可能遗漏端口，过长会降低扫描速度。",
+        "The timeout sets how long each probe waits; too short may miss ports, too long slows the scan.",
+    ),
+    ("扫描结果", "Scan Results"),
+    ("{} 台主机, {} 个开放端口", "{} hosts, {} open ports"),
+    ("按主机分组", "Group by host"),
+    ("全部展开", "Expand all"),
+    ("全部折叠", "Collapse all"),
+    ("复制报告", "Copy report"),
+    ("复制按主机分组的纯文本扫描报告", "Copy a plain-text scan report grouped by host"),
+    ("扫描结束后可复制报告", "The report can be copied after the scan ends"),
+    ("扫描报告已复制到剪贴板", "Scan report copied to the clipboard"),
+    ("上一页", "Previous"),
+    ("第 {} / {} 页", "Page {} / {}"),
+    ("下一页", "Next"),
+    ("正在扫描中...", "Scanning..."),
+    ("IP扫描进行中{}", "IP scan in progress{}"),
+    ("暂无扫描结果", "No scan results"),
+    ("开始扫描后将在此显示发现的开放端口", "Open ports found by the scan will be shown here"),
+    ("{}{}  ({} 个开放端口)", "{}{}  ({} open ports)"),
+    ("{}:{} 开放", "{}:{} open"),
+    ("端口 {} 开放", "Port {} open"),
+    ("点击填入连接参数", "Click to fill in the connection settings"),
+    ("日志已保存: {}", "Log saved: {}"),
+    ("保存日志失败: {}", "Failed to save the log: {}"),
+    ("扫描日志", "Scan Log"),
+    ("保存日志", "Save log"),
+    ("同时导出扫描结果", "Include scan results"),
+    ("暂无扫描日志", "No scan log"),
+    ("开始扫描后将在此显示详细日志", "The detailed log will be shown here once a scan starts"),
+    // 日志搜索
+    ("搜索日志", "Search log"),
+    ("上一个匹配项", "Previous match"),
+    ("下一个匹配项", "Next match"),
+    ("区分大小写", "Match case"),
+    ("只显示匹配项", "Only show matches"),
+    ("{}/{} 条匹配", "{}/{} matches"),
+    ("无匹配", "No matches"),
+    ("清除搜索", "Clear search"),
+    // 网络任务
+    ("写入文件失败: {}", "Failed to write the data file: {}"),
+    ("发送失败: {}", "Send failed: {}"),
+    ("连接已断开，无法发送数据", "Connection closed, cannot send data"),
+    ("开始批量发送 {} 条", "Batch send started: {} lines"),
+    ("批量发送结束: 已发送 {}/{} 条", "Batch send finished: {}/{} lines sent"),
+    ("已启用心跳: 每 {} 秒空闲时发送", "Keepalive enabled: sent after {} s idle"),
+    ("设置TCP_NODELAY失败: {}", "Failed to set TCP_NODELAY: {}"),
+    ("创建数据文件: {}", "Data file created: {}"),
+    ("创建数据文件失败: {}", "Failed to create the data file: {}"),
+    ("连接地址: 本地 {}, 远端 {}", "Addresses: local {}, remote {}"),
+    ("读取连接地址失败: {}", "Failed to read the connection addresses: {}"),
+    ("接受连接失败: {}", "Failed to accept a connection: {}"),
+    ("使用源地址 {} 发起连接", "Connecting from source address {}"),
+    ("已连接到 {} ({})", "Connected to {} ({})"),
+    ("已连接到 {}", "Connected to {}"),
+    ("正在监听 {}，等待客户端连接", "Listening on {}, waiting for a client"),
+    ("监听 {} 失败: {}", "Failed to listen on {}: {}"),
+    ("已有客户端连接，拒绝来自 {} 的连接", "A client is already connected, rejected the connection from {}"),
+    ("接受 {} 的连接失败: {}", "Failed to accept the connection from {}: {}"),
+    ("客户端 {} 已连接", "Client {} connected"),
+    ("已停止监听", "Stopped listening"),
+    ("已断开客户端", "Client disconnected"),
+    ("已断开连接", "Disconnected"),
+    ("尚无客户端接入，无法发送数据", "No client connected yet, cannot send data"),
+    ("未连接，无法发送数据", "Not connected, cannot send data"),
+    ("自动应答规则命中: {}", "Auto-reply rule matched: {}"),
+    ("自动应答触发过于频繁，已暂停应答", "Auto-replies triggered too often, replies paused"),
+    ("数据接收通道已建立", "Receive channel opened"),
+    ("空闲超时: {} 秒内未收到数据", "Idle timeout: no data received for {} s"),
+    ("因空闲超时断开连接", "Disconnected after idle timeout"),
+    ("对端关闭了连接", "The peer closed the connection"),
+    ("连接被对端重置", "Connection reset by the peer"),
+    ("操作会阻塞", "Operation would block"),
+    ("操作被中断", "Operation interrupted"),
+    ("读取错误: {}", "Read error: {}"),
+    ("连接中断", "Connection interrupted"),
+    ("数据接收通道已关闭", "Receive channel closed"),
+    // 连接错误
+    ("连接被拒绝", "Connection refused"),
+    ("连接超时", "Connection timed out"),
+    ("主机不可达", "Host unreachable"),
+    ("网络不可达", "Network unreachable"),
+    ("连接被重置", "Connection reset"),
+    ("连接被中止", "Connection aborted"),
+    ("地址不可用", "Address not available"),
+    ("权限不足", "Permission denied"),
+    ("其他错误", "Other error"),
+    ("连接失败", "Connection failed"),
+    ("源地址格式无效: {}", "Invalid source address: {}"),
+    ("源地址 {} 不属于本机网络接口: {}", "Source address {} does not belong to a local interface: {}"),
+    ("{} 没有与源地址 {} 同类型的地址", "{} has no address of the same family as source address {}"),
+    // 扫描任务
+    ("{}无效: \"{}\"，应为 0-65535 之间的整数", "{} is invalid: \"{}\", expected an integer from 0 to 65535"),
+    ("起始端口", "Start port"),
+    ("结束端口", "End port"),
+    ("起始IP格式无效: \"{}\"", "Invalid start IP: \"{}\""),
+    ("结束IP格式无效: \"{}\"", "Invalid end IP: \"{}\""),
+    ("结束IP {} 小于起始IP {}", "End IP {} is lower than start IP {}"),
+    ("IP数量 {} 超过单次扫描上限 {}", "{} IPs exceed the per-scan limit of {}"),
+    ("结束端口 {} 小于起始端口 {}", "End port {} is lower than start port {}"),
+    ("端口数量 {} 超过单次扫描上限 {}", "{} ports exceed the per-scan limit of {}"),
+    ("超时时间无效: \"{}\"，应为毫秒数", "Invalid timeout: \"{}\", expected milliseconds"),
+    ("排除列表第 {} 行格式无效: {}", "Invalid exclude list entry on line {}: {}"),
+    ("端口: {}", "Port: {}"),
+    ("端口范围: {} 到 {}", "Ports: {} to {}"),
+    ("IP扫描任务已启动: {} 到 {}, {}", "IP scan started: {} to {}, {}"),
+    ("开始扫描IP范围: {} 到 {}, {}", "Scanning IP range: {} to {}, {}"),
+    ("扫描已取消, 已发现 {} 个开放端口", "Scan cancelled, {} open ports found"),
+    ("扫描完成, 发现 {} 个开放端口", "Scan finished, {} open ports found"),
+    ("扫描记录已保存: {}", "Scan record saved: {}"),
+    ("保存扫描记录失败: {}", "Failed to save the scan record: {}"),
+    ("并发连接数已达上限 ({}), 后续探测将排队等待", "Connection limit reached ({}), further probes will wait"),
+    ("{}:{} 第 {} 次重试成功", "{}:{} succeeded on retry {}"),
+    ("使用源地址 {} 发起探测", "Probing from source address {}"),
+    ("IP地址格式无效，无法开始扫描", "Invalid IP address, cannot start the scan"),
+    ("已排除 {} 个地址", "{} addresses excluded"),
+    ("总共需要扫描 {} 个IP地址, {} 个端口, 共 {} 次扫描", "Scanning {} IPs, {} ports, {} probes in total"),
+    (
+        "总共需要扫描 {} 个IP地址, {} 个端口, 共 {} 次扫描 (不含重试; 每次失败最多重试 {} 次, 最多 {} 次探测)",
+        "Scanning {} IPs, {} ports, {} probes in total (excluding retries; up to {} retries per failure, at most {} probes)",
+    ),
+    ("最大并发连接数: {}", "Max concurrent connections: {}"),
+    ("单个主机, 按端口并行扫描, 每批 {} 个端口", "Single host, scanning ports in parallel, {} ports per batch"),
+    ("限速: 不限", "Rate limit: none"),
+    ("限速: 每秒最多 {} 个连接, 预计至少需要 {} 秒", "Rate limit: at most {} connections per second, at least {} s expected"),
+    ("使用 {} 个线程进行扫描", "Scanning with {} threads"),
+    ("扫描进度: {}/{} ({}%)", "Scan progress: {}/{} ({}%)"),
+    ("发现开放端口: {}:{} ({} ms)", "Open port found: {}:{} ({} ms)"),
+    ("扫描已取消, 已进行 {}", "Scan cancelled after {}"),
+    ("未开放端口的失败原因: {}", "Failure reasons for closed ports: {}"),
+    (
+        "共有 {} 次探测因并发连接上限排队等待，可适当调高并发数以加快扫描",
+        "{} probes waited for the connection limit; raise the concurrency to scan faster",
+    ),
+    ("已从ARP表获取 {} 台主机的MAC地址", "MAC addresses of {} hosts read from the ARP table"),
+    ("扫描完成. 共扫描 {} 个IP, 发现 {} 个开放端口, 耗时 {}", "Scan finished. {} IPs scanned, {} open ports found in {}"),
+    ("IP,端口,服务,延迟(ms),MAC", "IP,Port,Service,Latency(ms),MAC"),
+    ("时间,日志内容", "Time,Log"),
+    // 指标接口
+    ("指标接口启动失败 (端口 {}): {}", "Failed to start the metrics endpoint (port {}): {}"),
+    ("指标接口已启动: http://127.0.0.1:{}/metrics", "Metrics endpoint started: http://127.0.0.1:{}/metrics"),
+    ("指标接口接受连接失败: {}", "Metrics endpoint failed to accept a connection: {}"),
+    // 时长
+    ("{}小时{}分{}秒", "{}h {}m {}s"),
+    ("{}分{}秒", "{}m {}s"),
+    ("{}秒", "{}s"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // 源码中 tr("...") 和 trf!("...") 的文案，按 Rust 字符串字面量的规则还原转义
+    fn used_keys(source: &str) -> Vec<String> {
+        let mut keys = Vec::new();
+        for start in ["tr(", "trf!("] {
+            for (index, _) in source.match_indices(start) {
+                // 跳过 from_str("...") 这类名字以 tr 结尾的调用
+                if source[..index].ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                    continue;
+                }
+                // 文案可以另起一行，与 format! 的写法相同
+                let Some(rest) = source[index + start.len()..].trim_start().strip_prefix('"') else {
+                    continue;
+                };
+                let mut key = String::new();
+                let mut chars = rest.chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => key.push('\n'),
+                            Some(escaped) => key.push(escaped),
+                            None => break,
+                        },
+                        c => key.push(c),
+                    }
+                }
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn every_ui_text_has_an_english_translation() {
+        let sources = [
+            include_str!("app.rs"),
+            include_str!("desktop_notify.rs"),
+            include_str!("loopback.rs"),
+            include_str!("message.rs"),
+            include_str!("message_cache.rs"),
+            include_str!("metrics.rs"),
+            include_str!("network/connection.rs"),
+            include_str!("network/receiver.rs"),
+            include_str!("network/scanner.rs"),
+            include_str!("network/source.rs"),
+            include_str!("notifications.rs"),
+            include_str!("shortcuts.rs"),
+            include_str!("utils.rs"),
+            include_str!("ui/panels.rs"),
+            include_str!("ui/styles.rs"),
+        ];
+        let table: HashSet<&str> = ENGLISH.iter().map(|(zh, _)| *zh).collect();
+        let missing: Vec<String> = sources
+            .iter()
+            .flat_map(|source| used_keys(source))
+            .filter(|key| !table.contains(key.as_str()))
+            .collect();
+        assert!(missing.is_empty(), "缺少英文译文: {:?}", missing);
+    }
+
+    #[test]
+    fn translations_keep_keys_unique_and_placeholders_aligned() {
+        let mut seen = HashSet::new();
+        for (zh, en) in ENGLISH {
+            assert!(seen.insert(*zh), "重复的文案: {}", zh);
+            assert_eq!(zh.matches("{}").count(), en.matches("{}").count(), "{}", zh);
+        }
+    }
+
+    #[test]
+    fn english_lookup_falls_back_to_chinese() {
+        assert_eq!(translate(Language::Chinese, "断开"), "断开");
+        assert_eq!(translate(Language::English, "断开"), "Disconnect");
+        assert_eq!(translate(Language::English, "没有译文的文案"), "没有译文的文案");
+        // 网络任务的消息同时用作断线桌面通知的正文
+        assert_eq!(translate(Language::English, "对端关闭了连接"), "The peer closed the connection");
+        // 窗口标题以常量传给 tr，源码扫描找不到
+        assert_eq!(translate(Language::English, crate::app::WINDOW_TITLE), "TCP Client");
+        assert_eq!(
+            fill(translate(Language::English, "第 {} / {} 页"), &[&2, &"5"]),
+            "Page 2 / 5"
+        );
+    }
+}
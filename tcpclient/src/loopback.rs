@@ -0,0 +1,183 @@
+use crate::app::EncodingMode;
+use crate::i18n::{tr, trf};
+use crate::message::{Message, MessageKind, SharedMessages};
+use crate::network::ConnectOptions;
+use crate::utils::lock_or_recover;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+// 回环测试默认发送的内容
+pub const DEFAULT_LOOPBACK_PAYLOAD: &str = "tcpclient loopback test";
+
+// 等待回显数据的最长时间
+const LOOPBACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 检查消息列表的间隔，决定往返时间的精度
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+// 本地回环测试的状态，由测试任务更新，界面读取
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoopbackStatus {
+    Running,
+    Passed(Duration), // 从发送到收到完整回显的时间
+    Failed(String),
+}
+
+pub type SharedLoopback = Arc<Mutex<Option<LoopbackStatus>>>;
+
+// 只接受一个连接的回显服务器，把收到的数据原样发回，对端关闭后结束
+async fn run_echo_server(listener: TcpListener) {
+    let Ok((stream, _)) = listener.accept().await else {
+        return;
+    };
+    let (mut reader, mut writer) = stream.into_split();
+    let _ = tokio::io::copy(&mut reader, &mut writer).await;
+}
+
+// 检查测试开始后的新消息：收到的数据拼接后与发送内容一致时返回 Ok(true)
+// 连接建立前的错误提示即连接失败，返回其内容；之后的错误 (如创建数据文件失败) 不影响收发
+fn check_echo(messages: &SharedMessages, start: usize, payload: &[u8]) -> Result<bool, String> {
+    let messages = lock_or_recover(messages);
+    let mut connected = false;
+    let mut received = Vec::new();
+    for entry in messages.iter().skip(start) {
+        match (entry.kind, &entry.raw) {
+            (MessageKind::Connection, _) => connected = true,
+            (MessageKind::Error, _) if !connected => return Err(entry.text.clone()),
+            (MessageKind::Received, Some(raw)) => received.extend_from_slice(raw),
+            _ => {}
+        }
+    }
+    if received.len() > payload.len() || !payload.starts_with(&received) {
+        return Err(tr("回显内容与发送的数据不一致").to_string());
+    }
+    Ok(received.len() == payload.len())
+}
+
+// 在临时端口启动进程内的回显服务器，通过网络任务连接并发送 payload，
+// 在消息列表中等待相同的数据返回；完成后断开连接，结果写入 status
+pub fn spawn_loopback_test(
+    payload: String,
+    tx: mpsc::Sender<Message>,
+    messages: SharedMessages,
+    status: SharedLoopback,
+) {
+    *lock_or_recover(&status) = Some(LoopbackStatus::Running);
+    tokio::spawn(async move {
+        let result = run_loopback_test(&payload, &tx, &messages).await;
+        let _ = tx.send(Message::Disconnect).await;
+        *lock_or_recover(&status) = Some(match result {
+            Ok(elapsed) => LoopbackStatus::Passed(elapsed),
+            Err(e) => LoopbackStatus::Failed(e),
+        });
+    });
+}
+
+async fn run_loopback_test(
+    payload: &str,
+    tx: &mpsc::Sender<Message>,
+    messages: &SharedMessages,
+) -> Result<Duration, String> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| trf!("无法启动回显服务器: {}", e))?;
+    let port = listener.local_addr().map_err(|e| trf!("无法启动回显服务器: {}", e))?.port();
+    let server = tokio::spawn(run_echo_server(listener));
+
+    let start = lock_or_recover(messages).len();
+    let stopped = || tr("网络任务已停止，无法执行操作").to_string();
+    tx.send(Message::Connect("127.0.0.1".to_string(), port, ConnectOptions::default()))
+        .await
+        .map_err(|_| stopped())?;
+    tx.send(Message::Send(payload.to_string(), EncodingMode::Utf8))
+        .await
+        .map_err(|_| stopped())?;
+    let sent_at = Instant::now();
+
+    let result = loop {
+        match check_echo(messages, start, payload.as_bytes()) {
+            Ok(true) => break Ok(sent_at.elapsed()),
+            Ok(false) if sent_at.elapsed() >= LOOPBACK_TIMEOUT => {
+                break Err(trf!("{} 秒内未收到回显", LOOPBACK_TIMEOUT.as_secs()))
+            }
+            Ok(false) => tokio::time::sleep(POLL_INTERVAL).await,
+            Err(e) => break Err(e),
+        }
+    };
+    server.abort();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::MessageEntry;
+    use crate::network::handle_network_communications;
+
+    #[test]
+    fn echo_check_waits_for_complete_payload() {
+        let messages: SharedMessages = Arc::new(Mutex::new(vec![MessageEntry::new(
+            "12:00:00.000".to_string(),
+            MessageKind::Error,
+            "上一次的错误".to_string(),
+        )]));
+        let received = |bytes: &[u8]| {
+            MessageEntry::data("12:00:00.001".to_string(), MessageKind::Received, bytes.to_vec(), Default::default())
+        };
+        assert_eq!(check_echo(&messages, 1, b"hello"), Ok(false));
+        messages.lock().unwrap().push(MessageEntry::new(
+            "12:00:00.001".to_string(),
+            MessageKind::Connection,
+            "已连接到 127.0.0.1:9000".to_string(),
+        ));
+        messages.lock().unwrap().push(MessageEntry::new(
+            "12:00:00.001".to_string(),
+            MessageKind::Error,
+            "创建数据文件失败: Permission denied".to_string(),
+        ));
+        messages.lock().unwrap().push(received(b"hel"));
+        assert_eq!(check_echo(&messages, 1, b"hello"), Ok(false));
+        messages.lock().unwrap().push(received(b"lo"));
+        assert_eq!(check_echo(&messages, 1, b"hello"), Ok(true));
+        messages.lock().unwrap().push(received(b"!"));
+        assert!(check_echo(&messages, 1, b"hello").is_err());
+        assert_eq!(check_echo(&messages, 0, b"hello"), Err("上一次的错误".to_string()));
+    }
+
+    // 经过网络任务的连接、发送和接收完整走一遍
+    #[tokio::test]
+    async fn loopback_test_passes_through_network_task() {
+        let (tx, rx) = mpsc::channel(100);
+        let messages: SharedMessages = Arc::new(Mutex::new(Vec::new()));
+        let data_dir = std::env::temp_dir().join(format!("tcpclient-loopback-{}", std::process::id()));
+        tokio::spawn(handle_network_communications(
+            rx,
+            messages.clone(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            tx.clone(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            data_dir.clone(),
+            Default::default(),
+        ));
+
+        let status = SharedLoopback::default();
+        spawn_loopback_test(DEFAULT_LOOPBACK_PAYLOAD.to_string(), tx, messages.clone(), status.clone());
+        for _ in 0..500 {
+            if !matches!(*status.lock().unwrap(), Some(LoopbackStatus::Running)) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let result = status.lock().unwrap().clone();
+
+        // 数据文件只写入本测试的临时目录
+        let _ = std::fs::remove_dir_all(&data_dir);
+        assert!(matches!(result, Some(LoopbackStatus::Passed(_))), "{:?}", result);
+    }
+}
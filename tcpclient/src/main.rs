@@ -1,27 +1,125 @@
 mod app;
+mod batch;
+mod desktop_notify;
+mod follow;
+mod fuzz;
+mod i18n;
+mod loopback;
 mod message;
+mod message_cache;
+mod metrics;
 mod network;
+mod notifications;
+mod profiles;
+mod repaint;
+mod rules;
+mod scan_history;
+mod search;
+mod session;
+mod settings;
+mod shortcuts;
+mod triggers;
 mod ui;
 mod utils;
 
+// 读取命令行参数 name 的值，支持 "name value" 和 "name=value" 两种写法
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.split_once('=') {
+            Some((key, value)) if key == name => return Some(value.to_string()),
+            None if arg == name => return Some(args.next().unwrap_or_default()),
+            _ => {}
+        }
+    }
+    None
+}
+
+// 解析命令行中的 --metrics-port，未指定时不启动指标接口
+fn metrics_port_from_args() -> Option<u16> {
+    let value = arg_value("--metrics-port")?;
+    match value.parse() {
+        Ok(port) => Some(port),
+        Err(_) => {
+            eprintln!("无效的指标接口端口: {}", value);
+            std::process::exit(2);
+        }
+    }
+}
+
+// 指定 tokio 运行时工作线程数的环境变量，命令行的 --worker-threads 优先
+const WORKER_THREADS_ENV: &str = "TCPCLIENT_WORKER_THREADS";
+
+// 工作线程数的上限，防止误填过大的值创建大量线程
+const MAX_WORKER_THREADS: usize = 256;
+
+// 工作线程数须为 1 到 MAX_WORKER_THREADS 之间的整数
+fn parse_worker_threads(value: &str) -> Option<usize> {
+    value.trim().parse().ok().filter(|n| (1..=MAX_WORKER_THREADS).contains(n))
+}
+
+// 从 --worker-threads 或 TCPCLIENT_WORKER_THREADS 读取工作线程数，都未指定时返回 None，
+// 使用 tokio 的默认值 (CPU 核心数)
+// 扫描和收发主要等待网络，线程数不是越多越快：大范围扫描时同时进行的连接受扫描并发数限制，
+// 增加线程只在处理结果占满 CPU 时有帮助；固定线程数便于在不同机器上得到可比较的扫描耗时，
+// 在核心少或与其他程序共用的机器上可设为 1~2，减少对界面和其他程序的影响
+fn worker_threads_from_args() -> Option<usize> {
+    let (value, source) = match arg_value("--worker-threads") {
+        Some(value) => (value, "--worker-threads"),
+        None => (std::env::var(WORKER_THREADS_ENV).ok()?, WORKER_THREADS_ENV),
+    };
+    match parse_worker_threads(&value) {
+        Some(threads) => Some(threads),
+        None => {
+            eprintln!("无效的工作线程数 ({}): {}，应为 1 到 {} 之间的整数", source, value, MAX_WORKER_THREADS);
+            std::process::exit(2);
+        }
+    }
+}
+
 fn main() -> Result<(), eframe::Error> {
+    let metrics_port = metrics_port_from_args();
+
     // 设置tokio运行时
-    let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    if let Some(threads) = worker_threads_from_args() {
+        builder.worker_threads(threads);
+    }
+    let runtime = builder.enable_all().build().expect("Failed to create Tokio runtime");
     let _guard = runtime.enter();
 
     // 设置eframe选项
+    // 窗口大小、位置和面板宽度由 eframe 保存，下次启动时恢复；首次启动使用这里的默认大小
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1000.0, 700.0])
             .with_min_inner_size([800.0, 600.0])
-            .with_title("TCP 客户端"),
+            .with_title(i18n::tr(app::WINDOW_TITLE))
+            .with_app_id("tcpclient"), // 决定保存目录，使用 ASCII 名称
+        persist_window: true,
         ..Default::default()
     };
 
     // 运行应用
     eframe::run_native(
-        "TCP 客户端",
+        i18n::tr(app::WINDOW_TITLE),
         options,
-        Box::new(|cc| Ok(Box::<app::TcpClientApp>::new(app::TcpClientApp::new(cc)))),
+        Box::new(|cc| Ok(Box::<app::TcpClientApp>::new(app::TcpClientApp::new(cc, metrics_port)))),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worker_threads_must_be_in_range() {
+        assert_eq!(parse_worker_threads("4"), Some(4));
+        assert_eq!(parse_worker_threads(" 1 "), Some(1));
+        assert_eq!(parse_worker_threads("256"), Some(MAX_WORKER_THREADS));
+        assert_eq!(parse_worker_threads("0"), None);
+        assert_eq!(parse_worker_threads("257"), None);
+        assert_eq!(parse_worker_threads("-1"), None);
+        assert_eq!(parse_worker_threads("auto"), None);
+    }
+}
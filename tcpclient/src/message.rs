@@ -1,18 +1,224 @@
-use crate::app::EncodingMode;
+use crate::app::{DisplayMode, EncodingMode};
+use crate::batch::BatchProgress;
+use crate::i18n::tr;
+use crate::network::scanner::{ScanConfig, ScanProgress, ScanResult};
+use crate::network::ConnectOptions;
+use crate::scan_history::ScanRecord;
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+use tcpcommon::hexdump::to_hex;
+
+// 消息的类别，决定显示颜色；收发数据的类别同时决定显示前缀
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageKind {
+    #[default]
+    Info, // 一般提示
+    Connection, // 连接建立、断开等状态变化
+    Error,
+    Received,
+    Sent,
+    Keepalive,
+}
+
+impl MessageKind {
+    // 收发数据的显示前缀，按当前界面语言取文案
+    pub fn data_label(self) -> &'static str {
+        match self {
+            MessageKind::Received => tr("收到"),
+            MessageKind::Sent => tr("已发送"),
+            MessageKind::Keepalive => tr("心跳"),
+            MessageKind::Info | MessageKind::Connection | MessageKind::Error => "",
+        }
+    }
+}
+
+// 按显示格式格式化收发的数据，如 "收到(HEX): 68 69"
+pub fn format_data(label: &str, bytes: &[u8], mode: DisplayMode) -> String {
+    match mode {
+        DisplayMode::Utf8 => match std::str::from_utf8(bytes) {
+            Ok(text) => format!("{}(UTF-8): {}", label, text),
+            Err(_) => format!("{}({}): {}", label, tr("非UTF-8数据"), to_hex(bytes)),
+        },
+        DisplayMode::Hex => format!("{}(HEX): {}", label, to_hex(bytes)),
+        DisplayMode::Decimal => {
+            let values: Vec<String> = bytes.iter().map(u8::to_string).collect();
+            format!("{}(DEC): {}", label, values.join(" "))
+        }
+    }
+}
+
+// 消息列表中的一条记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageEntry {
+    pub timestamp: String,
+    pub text: String,         // 记录时格式化的内容，写入数据文件和匹配消息时使用
+    pub kind: MessageKind,
+    pub raw: Option<Vec<u8>>, // 收发的原始数据，连接状态等提示没有原始数据
+    pub bookmarked: bool,     // 用户标记的书签
+}
+
+impl MessageEntry {
+    pub fn new(timestamp: String, kind: MessageKind, text: String) -> Self {
+        Self {
+            timestamp,
+            text,
+            kind,
+            raw: None,
+            bookmarked: false,
+        }
+    }
+
+    // 收发数据的记录，text 按记录时的显示格式生成
+    pub fn data(timestamp: String, kind: MessageKind, bytes: Vec<u8>, mode: DisplayMode) -> Self {
+        Self {
+            timestamp,
+            text: format_data(kind.data_label(), &bytes, mode),
+            kind,
+            raw: Some(bytes),
+            bookmarked: false,
+        }
+    }
+
+    // 导出到文本文件的一行，书签条目以星号开头
+    pub fn export_line(&self) -> String {
+        let mark = if self.bookmarked { "* " } else { "" };
+        format!("{}[{}] {}", mark, self.timestamp, self.text)
+    }
+
+    // 按当前显示格式重新解码原始数据，没有原始数据的提示原样显示
+    pub fn display(&self, mode: DisplayMode) -> Cow<'_, str> {
+        match &self.raw {
+            Some(bytes) => Cow::Owned(format_data(self.kind.data_label(), bytes, mode)),
+            None => Cow::Borrowed(&self.text),
+        }
+    }
+}
+
+// 界面与网络任务共享的消息列表
+pub type SharedMessages = Arc<Mutex<Vec<MessageEntry>>>;
 
 // 定义消息类型
 #[derive(Debug)]
 pub enum Message {
-    Connect(String, u16),
+    Connect(String, u16, ConnectOptions), // (地址, 端口, 连接参数)
     Disconnect,
+    Listen(String, u16, ConnectOptions), // 监听模式，(绑定地址, 端口, 接受的连接使用的参数)
+    StopListening, // 停止监听并断开当前客户端
+    Accepted(std::net::TcpStream, std::net::SocketAddr), // 监听任务接受的客户端，由网络任务按连接处理
     Send(String, EncodingMode), // 发送数据，包含编码模式
+    Keepalive(String, EncodingMode), // 心跳任务在连接空闲时发送的数据，不重置心跳计时
+    // 按顺序逐条发送，每两条之间等待指定间隔；发送期间占用连接，进度写入共享的 BatchProgress
+    SendBatch(
+        Vec<String>,
+        EncodingMode,
+        std::time::Duration,
+        std::sync::Arc<std::sync::Mutex<BatchProgress>>,
+    ), // (逐条发送的数据, 编码模式, 间隔, 进度)
+    // 扫描状态标志由界面持有并随命令传给扫描任务：界面置为 false 即取消扫描，扫描结束时由扫描任务置为 false
     ScanIp(
-        String,
-        String,
-        u16,
-        u16,
-        u64,
-        std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+        ScanConfig,
+        std::sync::Arc<std::sync::Mutex<Vec<ScanResult>>>,
         std::sync::Arc<std::sync::Mutex<Vec<(String, String)>>>,
-    ), // (起始IP, 结束IP, 起始端口, 结束端口, 超时时间, 扫描结果, 扫描日志)
+        std::sync::Arc<std::sync::Mutex<ScanProgress>>,
+        std::sync::Arc<std::sync::atomic::AtomicBool>,
+        std::sync::Arc<std::sync::Mutex<Option<ScanRecord>>>,
+    ), // (扫描参数, 扫描结果, 扫描日志, 扫描进度, 扫描状态标志, 扫描结束后的记录)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::scanner::ScanProgress;
+    use std::sync::atomic::AtomicBool;
+
+    // 构造每一种消息并做穷尽匹配，新增或修改变体时这里会编译失败
+    #[test]
+    fn construct_every_variant() {
+        let config = ScanConfig {
+            start_ip: "127.0.0.1".to_string(),
+            end_ip: "127.0.0.1".to_string(),
+            start_port: 80,
+            end_port: 80,
+            timeout_ms: 100,
+            retries: 0,
+            rate_limit: 0,
+            max_connections: 1,
+            source_addr: None,
+            excludes: Vec::new(),
+            verbose_logs: false,
+        };
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let peer = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let messages = vec![
+            Message::Connect("127.0.0.1".to_string(), 8888, ConnectOptions::default()),
+            Message::Disconnect,
+            Message::Listen("0.0.0.0".to_string(), 8888, ConnectOptions::default()),
+            Message::StopListening,
+            Message::Accepted(listener.accept().unwrap().0, peer.local_addr().unwrap()),
+            Message::Send("hello".to_string(), EncodingMode::Utf8),
+            Message::Send("68 69".to_string(), EncodingMode::Hex),
+            Message::Keepalive("PING".to_string(), EncodingMode::Utf8),
+            Message::SendBatch(
+                vec!["AT".to_string(), "AT+RST".to_string()],
+                EncodingMode::Utf8,
+                std::time::Duration::from_millis(100),
+                Arc::new(Mutex::new(BatchProgress::default())),
+            ),
+            Message::ScanIp(
+                config,
+                Arc::new(Mutex::new(Vec::new())),
+                Arc::new(Mutex::new(Vec::new())),
+                Arc::new(Mutex::new(ScanProgress::default())),
+                Arc::new(AtomicBool::new(false)),
+                Arc::new(Mutex::new(None)),
+            ),
+        ];
+
+        for message in &messages {
+            // Debug 输出不应为空
+            assert!(!format!("{:?}", message).is_empty());
+            match message {
+                Message::Connect(addr, port, _) => {
+                    assert_eq!((addr.as_str(), *port), ("127.0.0.1", 8888));
+                }
+                Message::Listen(addr, port, _) => {
+                    assert_eq!((addr.as_str(), *port), ("0.0.0.0", 8888));
+                }
+                Message::Disconnect | Message::StopListening => {}
+                Message::Accepted(stream, peer) => assert_eq!(stream.peer_addr().unwrap(), *peer),
+                Message::Send(data, _) | Message::Keepalive(data, _) => assert!(!data.is_empty()),
+                Message::SendBatch(lines, _, _, progress) => {
+                    assert_eq!(lines.len(), 2);
+                    assert!(!progress.lock().unwrap().running);
+                }
+                Message::ScanIp(config, results, logs, _, _, _) => {
+                    assert_eq!(config.start_port, 80);
+                    assert!(results.lock().unwrap().is_empty() && logs.lock().unwrap().is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn data_entries_redecode_in_current_display_mode() {
+        let entry = MessageEntry::data("t".to_string(), MessageKind::Received, b"hi".to_vec(), DisplayMode::Utf8);
+        assert_eq!(entry.text, "收到(UTF-8): hi");
+        assert_eq!(entry.display(DisplayMode::Hex), "收到(HEX): 68 69");
+        assert_eq!(entry.display(DisplayMode::Decimal), "收到(DEC): 104 105");
+
+        let binary = MessageEntry::data("t".to_string(), MessageKind::Received, vec![0xFF, 0x00], DisplayMode::Hex);
+        assert_eq!(binary.display(DisplayMode::Utf8), "收到(非UTF-8数据): FF 00");
+
+        // 没有原始数据的提示不受显示格式影响
+        let note = MessageEntry::new("t".to_string(), MessageKind::Connection, "连接已断开".to_string());
+        assert_eq!(note.display(DisplayMode::Hex), "连接已断开");
+    }
+
+    #[test]
+    fn bookmarked_entries_are_starred_on_export() {
+        let mut entry = MessageEntry::data("12:00:00.000".to_string(), MessageKind::Received, b"OK".to_vec(), DisplayMode::Utf8);
+        assert_eq!(entry.export_line(), "[12:00:00.000] 收到(UTF-8): OK");
+        entry.bookmarked = true;
+        assert_eq!(entry.export_line(), "* [12:00:00.000] 收到(UTF-8): OK");
+    }
 }
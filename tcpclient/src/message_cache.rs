@@ -0,0 +1,159 @@
+use crate::app::DisplayMode;
+use crate::i18n::{tr, trf, Language};
+use crate::message::MessageEntry;
+use crate::utils::{printable_text, truncate_chars};
+use tcpcommon::hexdump::to_hex;
+
+// 影响格式化结果的显示设置，任一项变化后缓存全部重新生成
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LineFormat {
+    pub mode: DisplayMode,
+    pub max_len: usize, // 每条消息最多显示的字符数，0 表示不限制
+    pub dual_view: bool,
+    pub language: Language, // 收发前缀和长度说明随界面语言变化
+}
+
+// 消息列表中一行预先格式化的文本
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageLine {
+    pub content: String,           // 按显示格式解码的完整内容，用于匹配触发器和详情窗口
+    pub text: String,              // "[时间戳] 内容"，超出最大显示长度时截断并注明总长度
+    pub dual: Option<[String; 2]>, // 双视图下的文本和十六进制两行，没有原始数据时为 None
+    pub folded: bool,              // 显示的内容不完整，可在详情窗口查看
+}
+
+impl MessageLine {
+    pub fn new(entry: &MessageEntry, format: LineFormat) -> Self {
+        let content = entry.display(format.mode).into_owned();
+        let shown = truncate_chars(&content, format.max_len);
+        let mut folded = shown.is_some();
+        let text = match shown {
+            Some(prefix) => {
+                let total = match &entry.raw {
+                    Some(bytes) => trf!("{} 字节", bytes.len()),
+                    None => trf!("{} 字符", content.chars().count()),
+                };
+                trf!("[{}] {}… (共 {})", entry.timestamp, prefix, total)
+            }
+            None => format!("[{}] {}", entry.timestamp, content),
+        };
+
+        let dual = match (format.dual_view, &entry.raw) {
+            (true, Some(raw)) => {
+                // 每个字节至少对应一个字符，只需转换前 max_len 个字节
+                let bytes = match format.max_len {
+                    0 => &raw[..],
+                    n => &raw[..raw.len().min(n)],
+                };
+                let [text, hex] = [(tr("文本"), printable_text(bytes)), ("HEX ", to_hex(bytes))].map(|(title, line)| {
+                    let line = match truncate_chars(&line, format.max_len) {
+                        Some(prefix) => format!("{}…", prefix),
+                        None if bytes.len() < raw.len() => format!("{}…", line),
+                        None => line,
+                    };
+                    folded |= line.ends_with('…');
+                    format!("{} | {}", title, line)
+                });
+                Some([text, hex])
+            }
+            _ => None,
+        };
+
+        Self {
+            content,
+            text,
+            dual,
+            folded,
+        }
+    }
+}
+
+// 消息列表的格式化缓存，按消息序号保存，只在绘制到某一行时才生成该行
+// 消息只会追加或整体清空，因此序号不变时缓存的内容始终有效
+#[derive(Default)]
+pub struct MessageCache {
+    format: LineFormat,
+    lines: Vec<Option<MessageLine>>,
+}
+
+impl MessageCache {
+    // 每帧绘制前调用：显示设置变化或消息被清空后丢弃已缓存的行
+    pub fn sync(&mut self, count: usize, format: LineFormat) {
+        if self.format != format || count < self.lines.len() {
+            self.lines.clear();
+            self.format = format;
+        }
+        self.lines.resize_with(count, || None);
+    }
+
+    // 第 index 条消息的格式化结果，没有缓存时生成；index 须小于 sync 时的消息数
+    pub fn line(&mut self, index: usize, entry: &MessageEntry) -> &MessageLine {
+        let format = self.format;
+        self.lines[index].get_or_insert_with(|| MessageLine::new(entry, format))
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::MessageKind;
+
+    // 已生成的行数
+    fn cached(cache: &MessageCache) -> usize {
+        cache.lines.iter().filter(|line| line.is_some()).count()
+    }
+
+    fn received(bytes: &[u8]) -> MessageEntry {
+        MessageEntry::data("12:00:00.000".to_string(), MessageKind::Received, bytes.to_vec(), DisplayMode::Utf8)
+    }
+
+    #[test]
+    fn long_messages_are_folded_with_total_length() {
+        let format = LineFormat {
+            max_len: 16,
+            ..Default::default()
+        };
+        let short = MessageLine::new(&received(b"hi"), format);
+        assert_eq!(short.text, "[12:00:00.000] 收到(UTF-8): hi");
+        assert!(!short.folded);
+
+        let long = MessageLine::new(&received(b"0123456789"), format);
+        assert_eq!(long.text, "[12:00:00.000] 收到(UTF-8): 01234… (共 10 字节)");
+        assert_eq!(long.content, "收到(UTF-8): 0123456789");
+        assert!(long.folded);
+
+        let dual = MessageLine::new(&received(b"0123456789"), LineFormat { dual_view: true, ..format });
+        assert_eq!(dual.dual.unwrap()[1], "HEX  | 30 31 32 33 34 3…");
+        let info = MessageEntry::new("12:00:00.000".to_string(), MessageKind::Info, "已连接".to_string());
+        assert_eq!(MessageLine::new(&info, LineFormat { dual_view: true, ..format }).dual, None);
+    }
+
+    // 一万条消息时只生成绘制到的行，设置变化或清空后重新生成
+    #[test]
+    fn only_requested_lines_are_formatted() {
+        let messages: Vec<MessageEntry> = (0..10_000).map(|i| received(format!("{}", i).as_bytes())).collect();
+        let mut cache = MessageCache::default();
+        let format = LineFormat::default();
+        cache.sync(messages.len(), format);
+        for (index, entry) in messages.iter().enumerate().skip(9_980) {
+            cache.line(index, entry);
+        }
+        assert_eq!(cached(&cache), 20);
+        assert_eq!(cache.line(9_999, &messages[9_999]).text, "[12:00:00.000] 收到(UTF-8): 9999");
+
+        // 新消息追加时保留已有的行
+        cache.sync(messages.len() + 1, format);
+        assert_eq!(cached(&cache), 20);
+
+        cache.sync(messages.len(), LineFormat { mode: DisplayMode::Hex, ..format });
+        assert_eq!(cached(&cache), 0);
+        assert_eq!(cache.line(0, &messages[0]).text, "[12:00:00.000] 收到(HEX): 30");
+
+        cache.sync(3, LineFormat { mode: DisplayMode::Hex, ..format });
+        assert_eq!(cached(&cache), 0);
+    }
+}
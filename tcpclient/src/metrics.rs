@@ -0,0 +1,382 @@
+use crate::i18n::trf;
+use crate::message::{MessageEntry, MessageKind, SharedMessages};
+use crate::network::scanner::{ScanProgress, ScanResult};
+use crate::utils::{get_timestamp, lock_or_recover};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// 请求头的最大长度，超过后不再读取
+const MAX_REQUEST_HEAD: usize = 4096;
+
+// 读取请求的超时时间，避免连接后不发请求的客户端一直占用任务
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 连接状态与收发字节数，由网络任务更新，指标接口读取
+#[derive(Default)]
+pub struct ConnectionMetrics {
+    generation: AtomicU64, // 每次建立连接加一，用于忽略旧连接的断开通知
+    connected: AtomicBool,
+    target: Mutex<Option<String>>,
+    connected_at: Mutex<Option<Instant>>,
+    addresses: Mutex<Option<SocketAddrs>>, // 连接建立后读取的两端地址
+    data_file: Mutex<Option<PathBuf>>,     // 当前连接的数据文件，创建失败时为 None
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+pub type SharedMetrics = Arc<ConnectionMetrics>;
+
+// 连接两端的地址，本地端口在主动连接时由系统分配
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SocketAddrs {
+    pub local: SocketAddr,
+    pub peer: SocketAddr,
+}
+
+impl ConnectionMetrics {
+    // 记录新连接并清零字节数，返回接收任务使用的连接句柄
+    pub fn connected(self: &Arc<Self>, target: String) -> ActiveConnection {
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        *lock_or_recover(&self.target) = Some(target);
+        *lock_or_recover(&self.connected_at) = Some(Instant::now());
+        *lock_or_recover(&self.addresses) = None;
+        *lock_or_recover(&self.data_file) = None;
+        self.bytes_sent.store(0, Ordering::Relaxed);
+        self.bytes_received.store(0, Ordering::Relaxed);
+        self.connected.store(true, Ordering::Relaxed);
+        ActiveConnection {
+            metrics: Arc::clone(self),
+            generation,
+        }
+    }
+
+    // 用户主动断开当前连接
+    pub fn disconnect_current(&self) {
+        self.disconnected(self.generation.load(Ordering::Relaxed));
+    }
+
+    // 记录连接断开；generation 不是当前连接时忽略（已经建立了新连接）
+    fn disconnected(&self, generation: u64) {
+        if self.generation.load(Ordering::Relaxed) == generation {
+            self.connected.store(false, Ordering::Relaxed);
+            *lock_or_recover(&self.connected_at) = None;
+        }
+    }
+
+    // 当前是否有连接，接收任务结束或用户断开后为 false
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    // 当前连接两端的地址，没有连接时返回 None
+    pub fn current_addresses(&self) -> Option<SocketAddrs> {
+        if self.is_connected() {
+            *lock_or_recover(&self.addresses)
+        } else {
+            None
+        }
+    }
+
+    // 当前连接的数据文件路径，没有连接时返回 None
+    pub fn current_data_file(&self) -> Option<PathBuf> {
+        if self.is_connected() {
+            lock_or_recover(&self.data_file).clone()
+        } else {
+            None
+        }
+    }
+
+    // 最近一次连接的 (发送, 接收) 字节数，断开后保留到下次连接
+    pub fn byte_counts(&self) -> (u64, u64) {
+        (
+            self.bytes_sent.load(Ordering::Relaxed),
+            self.bytes_received.load(Ordering::Relaxed),
+        )
+    }
+
+    pub fn add_sent(&self, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+// 一次连接的指标句柄，由接收任务持有
+#[derive(Clone)]
+pub struct ActiveConnection {
+    metrics: SharedMetrics,
+    generation: u64,
+}
+
+impl ActiveConnection {
+    // 累计收到的字节数，连接已被新连接取代时不再计入
+    pub fn add_received(&self, bytes: usize) {
+        if self.metrics.generation.load(Ordering::Relaxed) == self.generation {
+            self.metrics.bytes_received.fetch_add(bytes as u64, Ordering::Relaxed);
+        }
+    }
+
+    // 连接是否仍是当前连接且没有断开
+    pub fn is_open(&self) -> bool {
+        self.metrics.generation.load(Ordering::Relaxed) == self.generation
+            && self.metrics.connected.load(Ordering::Relaxed)
+    }
+
+    // 记录连接两端的地址，连接已被新连接取代时忽略
+    pub fn set_addresses(&self, addresses: SocketAddrs) {
+        if self.metrics.generation.load(Ordering::Relaxed) == self.generation {
+            *lock_or_recover(&self.metrics.addresses) = Some(addresses);
+        }
+    }
+
+    // 记录连接的数据文件，连接已被新连接取代时忽略
+    pub fn set_data_file(&self, path: PathBuf) {
+        if self.metrics.generation.load(Ordering::Relaxed) == self.generation {
+            *lock_or_recover(&self.metrics.data_file) = Some(path);
+        }
+    }
+
+    // 接收任务结束时调用
+    pub fn closed(&self) {
+        self.metrics.disconnected(self.generation);
+    }
+}
+
+// 指标接口需要读取的扫描状态，与界面共享
+#[derive(Clone)]
+pub struct ScanState {
+    pub is_scanning: Arc<AtomicBool>,
+    pub progress: Arc<Mutex<ScanProgress>>,
+    pub results: Arc<Mutex<Vec<ScanResult>>>,
+}
+
+#[derive(Serialize)]
+struct ConnectionSnapshot {
+    connected: bool,
+    target: Option<String>,
+    local_addr: Option<String>,
+    peer_addr: Option<String>,
+    connected_secs: Option<f64>,
+    bytes_sent: u64,
+    bytes_received: u64,
+}
+
+#[derive(Serialize)]
+struct ScanSnapshot {
+    running: bool,
+    completed_probes: u64,
+    total_probes: u64,
+    percent: f64,
+    elapsed_secs: f64,
+    cancelled: bool,
+    open_ports: usize,
+}
+
+#[derive(Serialize)]
+struct MetricsSnapshot {
+    timestamp: String,
+    connection: ConnectionSnapshot,
+    scan: ScanSnapshot,
+}
+
+// 生成当前状态的 JSON
+fn snapshot_json(metrics: &ConnectionMetrics, scan: &ScanState) -> String {
+    let progress = lock_or_recover(&scan.progress).clone();
+    let percent = if progress.total_probes > 0 {
+        progress.completed_probes as f64 / progress.total_probes as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    let addresses = metrics.current_addresses();
+    let snapshot = MetricsSnapshot {
+        timestamp: get_timestamp(),
+        connection: ConnectionSnapshot {
+            connected: metrics.connected.load(Ordering::Relaxed),
+            target: lock_or_recover(&metrics.target).clone(),
+            local_addr: addresses.map(|addrs| addrs.local.to_string()),
+            peer_addr: addresses.map(|addrs| addrs.peer.to_string()),
+            connected_secs: lock_or_recover(&metrics.connected_at).map(|at| at.elapsed().as_secs_f64()),
+            bytes_sent: metrics.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: metrics.bytes_received.load(Ordering::Relaxed),
+        },
+        scan: ScanSnapshot {
+            running: scan.is_scanning.load(Ordering::Relaxed),
+            completed_probes: progress.completed_probes,
+            total_probes: progress.total_probes,
+            percent,
+            elapsed_secs: progress.elapsed().as_secs_f64(),
+            cancelled: progress.cancelled,
+            open_ports: lock_or_recover(&scan.results).len(),
+        },
+    };
+    serde_json::to_string_pretty(&snapshot).unwrap_or_default()
+}
+
+// 读取请求行，返回 (方法, 路径)
+async fn read_request_line(socket: &mut TcpStream) -> Option<(String, String)> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_HEAD {
+        let n = socket.read(&mut buffer).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buffer[..n]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut parts = head.lines().next()?.split_whitespace();
+    Some((parts.next()?.to_string(), parts.next()?.to_string()))
+}
+
+// 处理一个 HTTP 请求：GET / 或 GET /metrics 返回 JSON，其余返回错误
+async fn handle_request(mut socket: TcpStream, metrics: SharedMetrics, scan: ScanState) {
+    let Ok(Some((method, path))) = tokio::time::timeout(REQUEST_TIMEOUT, read_request_line(&mut socket)).await else {
+        return;
+    };
+
+    let (status, content_type, body) = match (method.as_str(), path.as_str()) {
+        ("GET", "/" | "/metrics") => ("200 OK", "application/json", snapshot_json(&metrics, &scan)),
+        ("GET", _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "method not allowed\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+// 在 127.0.0.1:port 上启动指标接口，启动结果写入消息列表
+pub fn spawn_metrics_server(
+    port: u16,
+    metrics: SharedMetrics,
+    scan: ScanState,
+    messages: SharedMessages,
+) {
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                let error_msg = trf!("指标接口启动失败 (端口 {}): {}", port, e);
+                eprintln!("{}", error_msg);
+                lock_or_recover(&messages).push(MessageEntry::new(get_timestamp(), MessageKind::Error, error_msg));
+                return;
+            }
+        };
+        lock_or_recover(&messages).push(MessageEntry::new(
+            get_timestamp(),
+            MessageKind::Info,
+            trf!("指标接口已启动: http://127.0.0.1:{}/metrics", port),
+        ));
+
+        loop {
+            match listener.accept().await {
+                Ok((socket, _)) => {
+                    tokio::spawn(handle_request(socket, metrics.clone(), scan.clone()));
+                }
+                Err(e) => {
+                    // 文件描述符耗尽等错误会立即重现，稍等后再接受
+                    lock_or_recover(&messages).push(MessageEntry::new(
+                        get_timestamp(),
+                        MessageKind::Error,
+                        trf!("指标接口接受连接失败: {}", e),
+                    ));
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan_state() -> ScanState {
+        ScanState {
+            is_scanning: Arc::new(AtomicBool::new(false)),
+            progress: Arc::new(Mutex::new(ScanProgress::default())),
+            results: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    async fn http_get(port: u16, path: &str) -> String {
+        let mut socket = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+        socket.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        socket.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn old_connection_does_not_clear_new_one() {
+        let metrics = SharedMetrics::default();
+        let old = metrics.connected("127.0.0.1:1".to_string());
+        let new = metrics.connected("127.0.0.1:2".to_string());
+
+        // 旧连接的接收任务晚于新连接结束，不应影响新连接的状态和计数
+        old.add_received(10);
+        old.closed();
+        new.add_received(5);
+        assert!(metrics.connected.load(Ordering::Relaxed));
+        assert_eq!(metrics.bytes_received.load(Ordering::Relaxed), 5);
+        let addrs = SocketAddrs {
+            local: "127.0.0.1:50000".parse().unwrap(),
+            peer: "127.0.0.1:2".parse().unwrap(),
+        };
+        old.set_addresses(SocketAddrs { peer: "127.0.0.1:1".parse().unwrap(), ..addrs });
+        new.set_addresses(addrs);
+        assert_eq!(metrics.current_addresses(), Some(addrs));
+        old.set_data_file("data/old.txt".into());
+        new.set_data_file("data/new.txt".into());
+        assert_eq!(metrics.current_data_file(), Some("data/new.txt".into()));
+
+        new.closed();
+        assert!(!metrics.connected.load(Ordering::Relaxed));
+        assert_eq!(metrics.current_addresses(), None);
+        assert_eq!(metrics.current_data_file(), None);
+        assert_eq!(metrics.byte_counts(), (0, 5));
+    }
+
+    #[tokio::test]
+    async fn endpoint_reports_connection_state() {
+        // 先占用一个空闲端口再释放，供指标接口使用
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let metrics = SharedMetrics::default();
+        let connection = metrics.connected("127.0.0.1:8888".to_string());
+        metrics.add_sent(3);
+        connection.add_received(7);
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        spawn_metrics_server(port, metrics, scan_state(), messages.clone());
+        for _ in 0..50 {
+            if !lock_or_recover(&messages).is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let response = http_get(port, "/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        let body = &response[response.find("\r\n\r\n").unwrap() + 4..];
+        let json: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(json["connection"]["connected"], true);
+        assert_eq!(json["connection"]["target"], "127.0.0.1:8888");
+        assert_eq!(json["connection"]["bytes_sent"], 3);
+        assert_eq!(json["connection"]["bytes_received"], 7);
+        assert_eq!(json["scan"]["running"], false);
+
+        assert!(http_get(port, "/other").await.starts_with("HTTP/1.1 404"));
+    }
+}
@@ -1,55 +1,446 @@
-use crate::app::EncodingMode;
-use crate::message::Message;
+use crate::app::{DisplayMode, EncodingMode};
+use crate::batch::BatchProgress;
+use crate::desktop_notify::SharedDisconnectAlerts;
+use crate::i18n::{tr, trf};
+use crate::message::{Message, MessageEntry, MessageKind, SharedMessages};
+use crate::metrics::SharedMetrics;
+use crate::notifications::{notify, NotificationKind, NotificationQueue};
 use crate::network::handle_data_reception;
 use crate::network::scanner::scan_ip_range;
-use crate::utils::{get_timestamp, create_data_file, write_to_file};
+use crate::network::services::service_name;
+use crate::network::source::{connect_tcp, describe_connect_error};
+use crate::repaint::Repaint;
+use crate::rules::AutoReplyRule;
+use crate::triggers::SharedTriggers;
+use crate::scan_history::{save_scan_record, ScanRecord};
+use crate::utils::{get_datetime, get_timestamp, create_data_file, hex_to_bytes, lock_or_recover, write_to_file};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use crate::metrics::{ActiveConnection, SocketAddrs};
 use std::sync::{Arc, Mutex};
-use tokio::io::{AsyncWriteExt, BufWriter};
-use tokio::net::TcpStream;
+use tokio::io::AsyncWriteExt;
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::mpsc;
-use std::time::Instant;
+use tokio::task::JoinHandle;
+use std::time::{Duration, Instant};
+
+// 连接参数，在发起连接时随 Message::Connect 传入
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    pub idle_timeout_secs: u64,   // 空闲超时时间，0 表示不检测
+    pub disconnect_on_idle: bool, // 空闲超时后是否自动断开
+    pub source_addr: Option<IpAddr>, // 绑定的本地源地址，None 表示使用系统默认路由
+    pub keepalive: Option<Keepalive>, // None 表示不发送心跳
+}
+
+// 心跳设置：连接上超过 interval 没有发送数据时自动发送 payload
+#[derive(Debug, Clone)]
+pub struct Keepalive {
+    pub interval: Duration,
+    pub payload: String,
+    pub encoding: EncodingMode,
+}
 
 // 优化的消息添加函数，减少锁定时间
-fn add_message(messages: &Arc<Mutex<Vec<(String, String)>>>, message: String) {
+// 返回消息的时间戳，供写入文件时复用
+fn add_message(messages: &SharedMessages, kind: MessageKind, message: String) -> String {
     let timestamp = get_timestamp();
-    messages.lock().unwrap().push((timestamp, message));
+    lock_or_recover(messages).push(MessageEntry::new(timestamp.clone(), kind, message));
+    timestamp
+}
+
+// 添加收发数据的消息并保留原始字节，供切换显示格式和双视图时重新解码
+// 返回消息的时间戳和按 mode 格式化的内容，供写入文件时复用
+fn add_data_message(messages: &SharedMessages, kind: MessageKind, raw: &[u8], mode: DisplayMode) -> (String, String) {
+    let entry = MessageEntry::data(get_timestamp(), kind, raw.to_vec(), mode);
+    let logged = (entry.timestamp.clone(), entry.text.clone());
+    lock_or_recover(messages).push(entry);
+    logged
 }
 
 // 优化的文件写入函数，减少锁定时间
-async fn log_to_file(file: &Option<Arc<Mutex<std::fs::File>>>, message: &str, messages: &Arc<Mutex<Vec<(String, String)>>>) {
+async fn log_to_file(
+    file: &Option<Arc<Mutex<std::fs::File>>>,
+    timestamp: &str,
+    message: &str,
+    messages: &SharedMessages,
+) {
     if let Some(file_arc) = file {
         if let Ok(mut file_guard) = file_arc.try_lock() {
-            if let Err(e) = write_to_file(&mut file_guard, message) {
-                add_message(messages, format!("写入文件失败: {}", e));
+            if let Err(e) = write_to_file(&mut *file_guard, timestamp, message) {
+                add_message(messages, MessageKind::Error, trf!("写入文件失败: {}", e));
+            }
+        }
+    }
+}
+
+// 发送一条数据，成功时在界面和数据文件中记录，失败时提示错误
+// kind 为记录的类别 (发送或心跳)；返回是否发送成功，失败后写任务不再继续发送
+#[allow(clippy::too_many_arguments)]
+async fn write_and_report(
+    writer: &mut OwnedWriteHalf,
+    kind: MessageKind,
+    data: &str,
+    encoding_mode: EncodingMode,
+    messages: &SharedMessages,
+    file: &Option<Arc<Mutex<std::fs::File>>>,
+    notifications: &NotificationQueue,
+    metrics: &SharedMetrics,
+) -> bool {
+    // 根据编码模式处理数据
+    let bytes_to_send = match encoding_mode {
+        EncodingMode::Utf8 => data.as_bytes().to_vec(),
+        EncodingMode::Hex => hex_to_bytes(data),
+    };
+
+    // 发送数据
+    let result = async {
+        writer.write_all(&bytes_to_send).await?;
+        writer.flush().await
+    }.await;
+
+    match result {
+        Ok(()) => {
+            metrics.add_sent(bytes_to_send.len());
+
+            // 将消息添加到UI显示，按发送时的编码模式格式化
+            let (timestamp, display_msg) =
+                add_data_message(messages, kind, &bytes_to_send, encoding_mode.into());
+
+            // 如果有文件句柄，将发送的数据写入文件，使用与界面相同的时间戳
+            log_to_file(file, &timestamp, &display_msg, messages).await;
+            true
+        }
+        Err(e) => {
+            let error_msg = trf!("发送失败: {}", e);
+            add_message(messages, MessageKind::Error, error_msg.clone());
+            notify(notifications, NotificationKind::Error, error_msg);
+            false
+        }
+    }
+}
+
+// 待发送的数据，由连接的写任务按入队顺序发出
+#[derive(Debug)]
+enum Outbound {
+    Data(MessageKind, String, EncodingMode), // (记录的类别, 数据, 编码模式)
+    Batch(Vec<String>, EncodingMode, Duration, Arc<Mutex<BatchProgress>>), // (逐条发送的数据, 编码模式, 间隔, 进度)
+}
+
+// 连接的写任务及其发送队列
+struct Writer {
+    queue: mpsc::UnboundedSender<Outbound>,
+    task: JoinHandle<()>,
+}
+
+// 将数据放入当前连接的发送队列，写任务已因发送失败结束时提示并返回 false
+fn enqueue(writer: &Option<Writer>, item: Outbound, messages: &SharedMessages) -> bool {
+    let queued = writer.as_ref().is_some_and(|writer| writer.queue.send(item).is_ok());
+    if !queued {
+        add_message(messages, MessageKind::Error, tr("连接已断开，无法发送数据").to_string());
+    }
+    queued
+}
+
+// 结束写任务，队列中尚未发出的数据一并丢弃
+fn stop_writer(writer: &mut Option<Writer>) {
+    if let Some(writer) = writer.take() {
+        writer.task.abort();
+    }
+}
+
+// 逐条批量发送，每两条之间等待间隔；进度被置为停止时提前结束，返回连接是否仍然可用
+#[allow(clippy::too_many_arguments)]
+async fn send_batch(
+    writer: &mut OwnedWriteHalf,
+    lines: &[String],
+    encoding_mode: EncodingMode,
+    interval: Duration,
+    progress: &Mutex<BatchProgress>,
+    messages: &SharedMessages,
+    file: &Option<Arc<Mutex<std::fs::File>>>,
+    notifications: &NotificationQueue,
+    metrics: &SharedMetrics,
+    repaint: &Repaint,
+) -> bool {
+    let total = lines.len();
+    add_message(messages, MessageKind::Info, trf!("开始批量发送 {} 条", total));
+
+    let mut ok = true;
+    for (index, line) in lines.iter().enumerate() {
+        if index > 0 && !interval.is_zero() {
+            tokio::time::sleep(interval).await;
+        }
+        if !lock_or_recover(progress).running {
+            break;
+        }
+        ok = write_and_report(writer, MessageKind::Sent, line, encoding_mode, messages, file, notifications, metrics).await;
+        if !ok {
+            break;
+        }
+        lock_or_recover(progress).sent = index + 1;
+        repaint.request();
+    }
+
+    let sent = {
+        let mut progress = lock_or_recover(progress);
+        progress.running = false;
+        progress.sent
+    };
+    add_message(messages, MessageKind::Info, trf!("批量发送结束: 已发送 {}/{} 条", sent, total));
+    ok
+}
+
+// 写任务：独占连接的写端，按入队顺序逐条发送，连续的快速发送不会被丢弃
+// 发送失败后结束，之后入队的数据由 enqueue 提示无法发送
+async fn run_writer(
+    mut writer: OwnedWriteHalf,
+    mut queue: mpsc::UnboundedReceiver<Outbound>,
+    messages: SharedMessages,
+    file: Option<Arc<Mutex<std::fs::File>>>,
+    notifications: NotificationQueue,
+    metrics: SharedMetrics,
+    repaint: Repaint,
+) {
+    while let Some(item) = queue.recv().await {
+        let ok = match item {
+            Outbound::Data(kind, data, encoding_mode) => {
+                write_and_report(&mut writer, kind, &data, encoding_mode, &messages, &file, &notifications, &metrics)
+                    .await
             }
+            Outbound::Batch(lines, encoding_mode, interval, progress) => {
+                send_batch(
+                    &mut writer,
+                    &lines,
+                    encoding_mode,
+                    interval,
+                    &progress,
+                    &messages,
+                    &file,
+                    &notifications,
+                    &metrics,
+                    &repaint,
+                )
+                .await
+            }
+        };
+        repaint.request();
+        if !ok {
+            break;
+        }
+    }
+}
+
+// 结束正在进行或排队中的批量发送：连接断开或重连后不再继续
+fn close_batch(batch: &mut Option<Arc<Mutex<BatchProgress>>>) {
+    if let Some(progress) = batch.take() {
+        lock_or_recover(&progress).running = false;
+    }
+}
+
+// 心跳任务：最近一次发送超过间隔后通过消息通道发送心跳，手动发送会更新 last_send 重新计时
+// 连接断开或被新连接取代后结束，用户断开时由网络任务直接取消
+async fn run_keepalive(
+    keepalive: Keepalive,
+    last_send: Arc<Mutex<Instant>>,
+    connection: ActiveConnection,
+    tx: mpsc::Sender<Message>,
+) {
+    loop {
+        let due = *lock_or_recover(&last_send) + keepalive.interval;
+        tokio::time::sleep_until(due.into()).await;
+        if !connection.is_open() {
+            break;
+        }
+        // 等待期间发送过数据时重新计时
+        if lock_or_recover(&last_send).elapsed() < keepalive.interval {
+            continue;
         }
+        *lock_or_recover(&last_send) = Instant::now();
+        let message = Message::Keepalive(keepalive.payload.clone(), keepalive.encoding);
+        if tx.send(message).await.is_err() {
+            break;
+        }
+    }
+}
+
+// 取消当前连接的心跳任务
+fn stop_keepalive(task: &mut Option<JoinHandle<()>>) {
+    if let Some(task) = task.take() {
+        task.abort();
     }
 }
 
-// 高效的十六进制转换函数
-fn hex_to_bytes(hex_str: &str) -> Vec<u8> {
-    let hex_str = hex_str.replace(" ", ""); // 移除空格
-    let mut bytes = Vec::with_capacity(hex_str.len() / 2);
+// 启动当前连接的心跳任务，未启用心跳时返回 None
+fn start_keepalive(
+    options: &ConnectOptions,
+    last_send: &Arc<Mutex<Instant>>,
+    connection: &ActiveConnection,
+    tx: &mpsc::Sender<Message>,
+    messages: &SharedMessages,
+) -> Option<JoinHandle<()>> {
+    let keepalive = options.keepalive.clone()?;
+    add_message(
+        messages,
+        MessageKind::Info,
+        trf!("已启用心跳: 每 {} 秒空闲时发送", keepalive.interval.as_secs()),
+    );
+    *lock_or_recover(last_send) = Instant::now();
+    Some(tokio::spawn(run_keepalive(
+        keepalive,
+        last_send.clone(),
+        connection.clone(),
+        tx.clone(),
+    )))
+}
+
+// 每个连接的接收任务共享的状态
+struct SessionContext {
+    messages: SharedMessages,
+    encoding_mode: Arc<Mutex<EncodingMode>>,
+    rules: Arc<Mutex<Vec<AutoReplyRule>>>,
+    triggers: SharedTriggers,
+    tx: mpsc::Sender<Message>,
+    notifications: NotificationQueue,
+    metrics: SharedMetrics,
+    alerts: SharedDisconnectAlerts,
+    data_dir: PathBuf, // 数据文件保存的目录
+    repaint: Repaint,
+}
+
+// 连接建立后创建数据文件并启动写任务和接收任务，主动连接和监听接受的连接共用
+// 返回数据文件句柄 (创建失败时不保存数据)、写任务和接收任务
+async fn start_session(
+    stream: TcpStream,
+    file_ip: &str,
+    file_port: u16,
+    options: &ConnectOptions,
+    connection: ActiveConnection,
+    session: &SessionContext,
+) -> (Option<Arc<Mutex<std::fs::File>>>, Writer, JoinHandle<()>) {
+    // 设置TCP选项以优化性能
+    if let Err(e) = stream.set_nodelay(true) {
+        add_message(&session.messages, MessageKind::Error, trf!("设置TCP_NODELAY失败: {}", e));
+    }
+
+    // 创建数据保存文件
+    let data_file = match create_data_file(&session.data_dir, file_ip, file_port) {
+        Ok((file, filepath)) => {
+            add_message(&session.messages, MessageKind::Info, trf!("创建数据文件: {}", filepath));
+            connection.set_data_file(filepath.into());
+            Some(Arc::new(Mutex::new(file)))
+        }
+        Err(e) => {
+            add_message(&session.messages, MessageKind::Error, trf!("创建数据文件失败: {}", e));
+            None
+        }
+    };
+
+    // 记录连接两端的地址，本地端口与防火墙规则相关时便于核对
+    match (stream.local_addr(), stream.peer_addr()) {
+        (Ok(local), Ok(peer)) => {
+            connection.set_addresses(SocketAddrs { local, peer });
+            let message = trf!("连接地址: 本地 {}, 远端 {}", local, peer);
+            let timestamp = add_message(&session.messages, MessageKind::Connection, message.clone());
+            log_to_file(&data_file, &timestamp, &message, &session.messages).await;
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            add_message(&session.messages, MessageKind::Error, trf!("读取连接地址失败: {}", e));
+        }
+    }
 
-    // 每两个字符转换为一个字节
-    for i in (0..hex_str.len()).step_by(2) {
-        if i + 1 < hex_str.len() {
-            if let Ok(byte) = u8::from_str_radix(&hex_str[i..i+2], 16) {
-                bytes.push(byte);
+    // 将stream分为发送和接收两个部分，写端交给写任务
+    let (read_half, write_half) = stream.into_split();
+    let (queue, queue_rx) = mpsc::unbounded_channel();
+    let writer = Writer {
+        queue,
+        task: tokio::spawn(run_writer(
+            write_half,
+            queue_rx,
+            session.messages.clone(),
+            data_file.clone(),
+            session.notifications.clone(),
+            session.metrics.clone(),
+            session.repaint.clone(),
+        )),
+    };
+
+    // 启动单独的异步任务处理数据接收
+    let receive_task = tokio::spawn(handle_data_reception(
+        session.messages.clone(),
+        read_half,
+        session.encoding_mode.clone(),
+        data_file.clone(),
+        session.rules.clone(),
+        session.triggers.clone(),
+        session.tx.clone(),
+        options.clone(),
+        session.notifications.clone(),
+        connection,
+        session.alerts.clone(),
+        session.repaint.clone(),
+    ));
+    (data_file, writer, receive_task)
+}
+
+// 监听任务：接受的连接交给网络任务处理，由它决定接受还是拒绝
+async fn run_listener(listener: TcpListener, tx: mpsc::Sender<Message>, messages: SharedMessages) {
+    loop {
+        let accepted = listener
+            .accept()
+            .await
+            .and_then(|(stream, peer)| Ok((stream.into_std()?, peer)));
+        match accepted {
+            Ok((stream, peer)) => {
+                if tx.send(Message::Accepted(stream, peer)).await.is_err() {
+                    break;
+                }
+            }
+            Err(e) => {
+                // 文件描述符耗尽等错误会立即重现，稍等后再接受
+                add_message(&messages, MessageKind::Error, trf!("接受连接失败: {}", e));
+                tokio::time::sleep(Duration::from_secs(1)).await;
             }
         }
     }
-    bytes
+}
+
+// 结束当前连接的接收任务，丢弃读端后连接完全关闭，之后收到的数据不再显示或触发自动应答
+fn abort_task(task: &mut Option<JoinHandle<()>>) {
+    if let Some(task) = task.take() {
+        task.abort();
+    }
+}
+
+// 停止监听，返回之前是否在监听
+fn stop_listener(task: &mut Option<JoinHandle<()>>) -> bool {
+    match task.take() {
+        Some(task) => {
+            task.abort();
+            true
+        }
+        None => false,
+    }
 }
 
 // 异步处理网络通信的函数
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_network_communications(
     mut rx: mpsc::Receiver<Message>,
-    messages: Arc<Mutex<Vec<(String, String)>>>,
+    messages: SharedMessages,
     encoding_mode: Arc<Mutex<EncodingMode>>,
+    rules: Arc<Mutex<Vec<AutoReplyRule>>>,
+    triggers: SharedTriggers,
+    tx: mpsc::Sender<Message>,
+    notifications: NotificationQueue,
+    metrics: SharedMetrics,
+    alerts: SharedDisconnectAlerts,
+    data_dir: PathBuf,
+    repaint: Repaint,
 ) {
-    // 创建一个通道来管理TcpStream的所有权，增加缓冲区大小
-    let (conn_tx, mut conn_rx) = mpsc::channel::<tokio::net::tcp::OwnedWriteHalf>(20);
+    // 当前连接的写任务，所有发送都经它的队列按顺序发出
+    let mut writer: Option<Writer> = None;
     let mut has_connection = false;
 
     // 创建一个可选的文件句柄，用于在发送数据时使用
@@ -58,153 +449,188 @@ pub async fn handle_network_communications(
     // 用于批量处理消息的计时器
     let mut last_ui_update = Instant::now();
 
+    // 最近一次批量发送的进度，断开连接时用来停止它
+    let mut current_batch: Option<Arc<Mutex<BatchProgress>>> = None;
+
+    // 当前连接的心跳任务和最近一次手动发送的时间
+    let mut keepalive_task: Option<JoinHandle<()>> = None;
+    let last_send = Arc::new(Mutex::new(Instant::now()));
+
+    // 监听模式下接受连接的任务、当前连接的接收任务，以及接受的客户端使用的连接参数
+    let mut listener_task: Option<JoinHandle<()>> = None;
+    let mut client_task: Option<JoinHandle<()>> = None;
+    let mut listen_options = ConnectOptions::default();
+
+    // 每个连接的接收任务共享的状态
+    let session = SessionContext {
+        messages: messages.clone(),
+        encoding_mode: encoding_mode.clone(),
+        rules: rules.clone(),
+        triggers: triggers.clone(),
+        tx: tx.clone(),
+        notifications: notifications.clone(),
+        metrics: metrics.clone(),
+        alerts: alerts.clone(),
+        data_dir,
+        repaint: repaint.clone(),
+    };
+
     while let Some(msg) = rx.recv().await {
         match msg {
-            Message::Connect(addr, port) => {
+            Message::Connect(addr, port, options) => {
                 // 如果已经连接，放弃现有连接
                 has_connection = false;
-                // 清空通道
-                while conn_rx.try_recv().is_ok() {}
+                close_batch(&mut current_batch);
+                stop_keepalive(&mut keepalive_task);
+                stop_listener(&mut listener_task);
+                abort_task(&mut client_task);
+                stop_writer(&mut writer);
 
                 let connect_addr = format!("{}:{}", addr, port);
-                match TcpStream::connect(&connect_addr).await {
+                if let Some(source) = options.source_addr {
+                    add_message(&messages, MessageKind::Info, trf!("使用源地址 {} 发起连接", source));
+                }
+                match connect_tcp(&connect_addr, options.source_addr).await {
                     Ok(stream) => {
-                        // 设置TCP选项以优化性能
-                        if let Ok(socket) = stream.into_std() {
-                            if let Err(e) = socket.set_nodelay(true) {
-                                add_message(&messages, format!("设置TCP_NODELAY失败: {}", e));
-                            }
-
-                            // 转回TcpStream
-                            let stream = TcpStream::from_std(socket).unwrap();
-                            add_message(&messages, format!("已连接到 {}", connect_addr));
-                            has_connection = true;
-
-                            // 创建数据保存文件
-                            let file_result = create_data_file(&addr, port);
-                            match file_result {
-                                Ok((file, filepath)) => {
-                                    add_message(&messages, format!("创建数据文件: {}", filepath));
-
-                                    // 将stream分为发送和接收两个部分
-                                    let (read_half, write_half) = stream.into_split();
-
-                                    // 将新连接放入通道
-                                    let _ = conn_tx.send(write_half).await;
-
-                                    // 创建文件句柄并保存到全局变量
-                                    let file_arc = Arc::new(Mutex::new(file));
-                                    data_file = Some(file_arc.clone());
-
-                                    // 启动单独的异步任务处理数据接收
-                                    let recv_messages = messages.clone();
-                                    let recv_encoding_mode = encoding_mode.clone();
-                                    tokio::spawn(async move {
-                                        handle_data_reception(recv_messages, read_half, recv_encoding_mode, Some(file_arc)).await;
-                                    });
-                                },
-                                Err(e) => {
-                                    add_message(&messages, format!("创建数据文件失败: {}", e));
-
-                                    // 将stream分为发送和接收两个部分
-                                    let (read_half, write_half) = stream.into_split();
-
-                                    // 将新连接放入通道
-                                    let _ = conn_tx.send(write_half).await;
-
-                                    // 启动单独的异步任务处理数据接收（不带文件）
-                                    let recv_messages = messages.clone();
-                                    let recv_encoding_mode = encoding_mode.clone();
-                                    tokio::spawn(async move {
-                                        handle_data_reception(recv_messages, read_half, recv_encoding_mode, None).await;
-                                    });
-                                }
-                            }
-                        } else {
-                            add_message(&messages, "获取底层socket失败".to_string());
-                        }
+                        let connected_msg = match service_name(port) {
+                            Some(name) => trf!("已连接到 {} ({})", connect_addr, name),
+                            None => trf!("已连接到 {}", connect_addr),
+                        };
+                        add_message(&messages, MessageKind::Connection, connected_msg.clone());
+                        notify(&notifications, NotificationKind::Connection, connected_msg);
+                        has_connection = true;
+                        alerts.connected();
+                        let connection = metrics.connected(connect_addr.clone());
+                        keepalive_task = start_keepalive(&options, &last_send, &connection, &tx, &messages);
+                        let (file, session_writer, receive_task) =
+                            start_session(stream, &addr, port, &options, connection, &session).await;
+                        data_file = file;
+                        writer = Some(session_writer);
+                        client_task = Some(receive_task);
                     }
                     Err(e) => {
                         // 清除文件句柄
                         data_file = None;
-                        add_message(&messages, format!("连接失败: {}", e));
+                        let error_msg = describe_connect_error(&e);
+                        add_message(&messages, MessageKind::Error, error_msg.clone());
+                        notify(&notifications, NotificationKind::Error, error_msg);
+                    }
+                }
+            }
+            Message::Listen(addr, port, options) => {
+                // 放弃现有的连接和监听
+                has_connection = false;
+                close_batch(&mut current_batch);
+                stop_keepalive(&mut keepalive_task);
+                stop_listener(&mut listener_task);
+                abort_task(&mut client_task);
+                stop_writer(&mut writer);
+                data_file = None;
+
+                let bind_addr = format!("{}:{}", addr, port);
+                match TcpListener::bind(&bind_addr).await {
+                    Ok(listener) => {
+                        let message = trf!("正在监听 {}，等待客户端连接", bind_addr);
+                        add_message(&messages, MessageKind::Connection, message.clone());
+                        notify(&notifications, NotificationKind::Connection, message);
+                        listen_options = options;
+                        listener_task = Some(tokio::spawn(run_listener(listener, tx.clone(), messages.clone())));
+                    }
+                    Err(e) => {
+                        let error_msg = trf!("监听 {} 失败: {}", bind_addr, e);
+                        add_message(&messages, MessageKind::Error, error_msg.clone());
+                        notify(&notifications, NotificationKind::Error, error_msg);
+                    }
+                }
+            }
+            Message::Accepted(stream, peer) => {
+                // 已停止监听时丢弃尚未处理的连接；同一时间只服务一个客户端，后来的直接关闭
+                if listener_task.is_none() {
+                    continue;
+                }
+                if metrics.is_connected() {
+                    add_message(&messages, MessageKind::Error, trf!("已有客户端连接，拒绝来自 {} 的连接", peer));
+                    continue;
+                }
+                let stream = match TcpStream::from_std(stream) {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        add_message(&messages, MessageKind::Error, trf!("接受 {} 的连接失败: {}", peer, e));
+                        continue;
+                    }
+                };
+
+                // 上一个客户端的写端、接收任务和批量发送已失效
+                close_batch(&mut current_batch);
+                stop_keepalive(&mut keepalive_task);
+                stop_writer(&mut writer);
+                abort_task(&mut client_task);
+
+                let connected_msg = trf!("客户端 {} 已连接", peer);
+                add_message(&messages, MessageKind::Connection, connected_msg.clone());
+                notify(&notifications, NotificationKind::Connection, connected_msg);
+                has_connection = true;
+                alerts.connected();
+                let connection = metrics.connected(peer.to_string());
+                keepalive_task = start_keepalive(&listen_options, &last_send, &connection, &tx, &messages);
+                let ip = peer.ip().to_string();
+                let (file, session_writer, receive_task) =
+                    start_session(stream, &ip, peer.port(), &listen_options, connection, &session).await;
+                data_file = file;
+                writer = Some(session_writer);
+                client_task = Some(receive_task);
+            }
+            Message::StopListening => {
+                if stop_listener(&mut listener_task) {
+                    let message = tr("已停止监听");
+                    add_message(&messages, MessageKind::Connection, message.to_string());
+                    notify(&notifications, NotificationKind::Connection, message);
+                }
+                if has_connection {
+                    stop_writer(&mut writer);
+                    has_connection = false;
+                    close_batch(&mut current_batch);
+                    stop_keepalive(&mut keepalive_task);
+
+                    // 同时结束接收任务，丢弃读端后连接完全关闭
+                    abort_task(&mut client_task);
+                    if metrics.is_connected() {
+                        let disconnect_msg = tr("已断开客户端");
+                        let timestamp = add_message(&messages, MessageKind::Connection, disconnect_msg.to_string());
+                        log_to_file(&data_file, &timestamp, disconnect_msg, &messages).await;
+                        metrics.disconnect_current();
                     }
+                    data_file = None;
                 }
             }
             Message::Disconnect => {
                 if has_connection {
-                    // 清空通道
-                    while conn_rx.try_recv().is_ok() {}
+                    stop_writer(&mut writer);
+                    abort_task(&mut client_task);
                     has_connection = false;
+                    close_batch(&mut current_batch);
+                    stop_keepalive(&mut keepalive_task);
 
                     // 在文件中记录断开连接信息
-                    let disconnect_msg = "已断开连接";
-                    log_to_file(&data_file, disconnect_msg, &messages).await;
-                    add_message(&messages, disconnect_msg.to_string());
+                    let disconnect_msg = tr("已断开连接");
+                    let timestamp = add_message(&messages, MessageKind::Connection, disconnect_msg.to_string());
+                    log_to_file(&data_file, &timestamp, disconnect_msg, &messages).await;
+                    notify(&notifications, NotificationKind::Connection, disconnect_msg);
+                    metrics.disconnect_current();
 
                     // 清除文件句柄
                     data_file = None;
                 }
             }
             Message::Send(data, encoding_mode) => {
-                if has_connection {
-                    // 尝试从通道获取连接
-                    match conn_rx.try_recv() {
-                        Ok(stream) => {
-                            let send_messages = messages.clone();
-                            let send_data = data.clone();
-                            let conn_tx_clone = conn_tx.clone();
-                            let file_clone = data_file.clone();
-
-                            // 在单独的任务中发送数据
-                            tokio::spawn(async move {
-                                // 使用BufWriter提高写入性能
-                                let mut writer = BufWriter::with_capacity(8192, stream);
-
-                                // 根据编码模式处理数据
-                                let bytes_to_send = match encoding_mode {
-                                    EncodingMode::Utf8 => send_data.as_bytes().to_vec(),
-                                    EncodingMode::Hex => hex_to_bytes(&send_data),
-                                };
-
-                                // 发送数据
-                                let result = async {
-                                    writer.write_all(&bytes_to_send).await?;
-                                    writer.flush().await?;
-                                    Ok::<_, std::io::Error>(writer.into_inner())
-                                }.await;
-
-                                match result {
-                                    Ok(stream) => {
-                                        // 根据编码模式显示不同的消息
-                                        let display_msg = match encoding_mode {
-                                            EncodingMode::Utf8 => format!("已发送(UTF-8): {}", send_data),
-                                            EncodingMode::Hex => format!("已发送(HEX): {}", send_data),
-                                        };
-
-                                        // 将消息添加到UI显示
-                                        add_message(&send_messages, display_msg.clone());
-
-                                        // 如果有文件句柄，将发送的数据写入文件
-                                        log_to_file(&file_clone, &display_msg, &send_messages).await;
-
-                                        // 将连接放回通道
-                                        let _ = conn_tx_clone.send(stream).await;
-                                    }
-                                    Err(e) => {
-                                        add_message(&send_messages, format!("发送失败: {}", e));
-                                        // 发送失败，不放回通道
-                                    }
-                                }
-                            });
-                        }
-                        Err(_) => {
-                            // 通道中没有连接，可能正在被另一个任务使用
-                            add_message(&messages, "连接正忙，请稍后再试".to_string());
-                        }
-                    }
+                if listener_task.is_some() && !metrics.is_connected() {
+                    add_message(&messages, MessageKind::Error, tr("尚无客户端接入，无法发送数据").to_string());
+                } else if has_connection {
+                    // 手动发送 (包括自动应答) 重新开始心跳计时
+                    *lock_or_recover(&last_send) = Instant::now();
+                    enqueue(&writer, Outbound::Data(MessageKind::Sent, data, encoding_mode), &messages);
                 } else {
-                    add_message(&messages, "未连接，无法发送数据".to_string());
+                    add_message(&messages, MessageKind::Error, tr("未连接，无法发送数据").to_string());
                 }
 
                 // 如果距离上次UI更新超过100ms，强制更新UI
@@ -213,43 +639,275 @@ pub async fn handle_network_communications(
                     last_ui_update = Instant::now();
                 }
             }
-            Message::ScanIp(start_ip, end_ip, start_port, end_port, timeout_ms, scan_results, scan_logs) => {
-                // 创建扫描状态标志
-                let is_scanning = Arc::new(Mutex::new(true));
+            Message::Keepalive(data, encoding_mode) => {
+                // 心跳任务只在空闲时发出心跳，排在队列中尚未发出的数据之后
+                if has_connection {
+                    enqueue(&writer, Outbound::Data(MessageKind::Keepalive, data, encoding_mode), &messages);
+                }
+            }
+            Message::SendBatch(lines, encoding_mode, interval, progress) => {
+                if listener_task.is_some() && !metrics.is_connected() {
+                    add_message(&messages, MessageKind::Error, tr("尚无客户端接入，无法发送数据").to_string());
+                    lock_or_recover(&progress).running = false;
+                    continue;
+                }
+                if !has_connection {
+                    add_message(&messages, MessageKind::Error, tr("未连接，无法发送数据").to_string());
+                    lock_or_recover(&progress).running = false;
+                    continue;
+                }
+
+                // 整个批量发送在写任务中连续进行，期间入队的数据在批量发送结束后发出
+                *lock_or_recover(&last_send) = Instant::now();
+                current_batch = Some(progress.clone());
+                let batch = Outbound::Batch(lines, encoding_mode, interval, progress.clone());
+                if !enqueue(&writer, batch, &messages) {
+                    lock_or_recover(&progress).running = false;
+                }
+            }
+            Message::ScanIp(config, scan_results, scan_logs, scan_progress, is_scanning, scan_record) => {
+                // 扫描状态标志由界面在发送命令前置为 true，这里不再改写，以免覆盖发送后立即的取消
 
                 // 记录扫描开始
-                let port_range_msg = if start_port == end_port {
-                    format!("端口: {}", start_port)
+                let port_range_msg = if config.start_port == config.end_port {
+                    trf!("端口: {}", config.start_port)
                 } else {
-                    format!("端口范围: {} 到 {}", start_port, end_port)
+                    trf!("端口范围: {} 到 {}", config.start_port, config.end_port)
                 };
 
-                let start_msg = format!(
+                let start_msg = trf!(
                     "IP扫描任务已启动: {} 到 {}, {}",
-                    start_ip, end_ip, port_range_msg
+                    config.start_ip, config.end_ip, port_range_msg
                 );
 
-                scan_logs.lock().unwrap().push((get_timestamp(), start_msg));
+                lock_or_recover(&scan_logs).push((get_timestamp(), start_msg));
 
                 // 复制消息列表传递给扫描任务
                 let scan_messages = messages.clone();
+                let scan_notifications = notifications.clone();
+                let scan_repaint = repaint.clone();
 
                 // 启动扫描任务
                 tokio::spawn(async move {
+                    let started_at = get_datetime();
+                    let scan_start = Instant::now();
+
                     scan_ip_range(
-                        &start_ip,
-                        &end_ip,
-                        start_port,
-                        end_port,
-                        timeout_ms,
+                        config.clone(),
                         scan_messages,
-                        scan_results,
-                        scan_logs,
+                        scan_results.clone(),
+                        scan_logs.clone(),
                         is_scanning,
+                        scan_progress.clone(),
+                        scan_repaint.clone(),
                     )
                     .await;
+
+                    // 通知扫描结果
+                    let found = lock_or_recover(&scan_results).len();
+                    let notice = if lock_or_recover(&scan_progress).cancelled {
+                        trf!("扫描已取消, 已发现 {} 个开放端口", found)
+                    } else {
+                        trf!("扫描完成, 发现 {} 个开放端口", found)
+                    };
+                    notify(&scan_notifications, NotificationKind::Scan, notice);
+
+                    // 扫描结束后保存历史记录
+                    let record = ScanRecord {
+                        started_at,
+                        start_ip: config.start_ip,
+                        end_ip: config.end_ip,
+                        start_port: config.start_port,
+                        end_port: config.end_port,
+                        elapsed_ms: scan_start.elapsed().as_millis() as u64,
+                        results: lock_or_recover(&scan_results).clone(),
+                    };
+                    let history_msg = match save_scan_record(&record) {
+                        Ok(filepath) => trf!("扫描记录已保存: {}", filepath),
+                        Err(e) => trf!("保存扫描记录失败: {}", e),
+                    };
+                    lock_or_recover(&scan_logs).push((get_timestamp(), history_msg));
+                    *lock_or_recover(&scan_record) = Some(record);
+                    scan_repaint.request();
                 });
             }
         }
+
+        // 命令处理完毕，连接状态或消息列表可能已变化
+        repaint.request();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::SharedMetrics;
+
+    #[tokio::test]
+    async fn keepalive_waits_for_idle_and_stops_with_connection() {
+        let connection = SharedMetrics::default().connected("test".to_string());
+        let last_send = Arc::new(Mutex::new(Instant::now()));
+        let (tx, mut rx) = mpsc::channel(4);
+        let keepalive = Keepalive {
+            interval: Duration::from_millis(100),
+            payload: "PING".to_string(),
+            encoding: EncodingMode::Utf8,
+        };
+        let task = tokio::spawn(run_keepalive(keepalive, last_send.clone(), connection.clone(), tx));
+
+        // 手动发送后重新计时，间隔内不发送心跳
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        *last_send.lock().unwrap() = Instant::now();
+        let started = Instant::now();
+        match rx.recv().await {
+            Some(Message::Keepalive(data, EncodingMode::Utf8)) => assert_eq!(data, "PING"),
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert!(started.elapsed() >= Duration::from_millis(90));
+
+        // 连接关闭后心跳任务结束
+        connection.closed();
+        tokio::time::timeout(Duration::from_secs(1), task).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn rapid_sends_arrive_in_order() {
+        use tokio::io::AsyncReadExt;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+        let (_read_half, write_half) = client.into_split();
+
+        let messages: SharedMessages = Arc::new(Mutex::new(Vec::new()));
+        let (queue, queue_rx) = mpsc::unbounded_channel();
+        let writer = Some(Writer {
+            queue,
+            task: tokio::spawn(run_writer(
+                write_half,
+                queue_rx,
+                messages.clone(),
+                None,
+                NotificationQueue::default(),
+                SharedMetrics::default(),
+                Repaint::default(),
+            )),
+        });
+
+        // 连续入队，不等待前一条发出；以前第二条起会因连接正忙被丢弃
+        let mut expected = String::new();
+        for i in 0..200 {
+            let line = format!("msg-{};", i);
+            expected.push_str(&line);
+            assert!(enqueue(&writer, Outbound::Data(MessageKind::Sent, line, EncodingMode::Utf8), &messages));
+        }
+
+        let mut received = vec![0; expected.len()];
+        tokio::time::timeout(Duration::from_secs(5), server.read_exact(&mut received))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(String::from_utf8(received).unwrap(), expected);
+
+        let logged: Vec<String> = messages.lock().unwrap().iter().map(|entry| entry.text.clone()).collect();
+        assert_eq!(logged.len(), 200);
+        assert_eq!(logged[199], "已发送(UTF-8): msg-199;");
+    }
+
+    #[tokio::test]
+    async fn listener_forwards_accepted_clients() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, mut rx) = mpsc::channel(4);
+        let messages: SharedMessages = Arc::new(Mutex::new(Vec::new()));
+        let mut task = Some(tokio::spawn(run_listener(listener, tx, messages)));
+
+        // 监听任务只转发，不限制连接数，拒绝多余的客户端由网络任务决定
+        for _ in 0..2 {
+            let client = TcpStream::connect(addr).await.unwrap();
+            match rx.recv().await {
+                Some(Message::Accepted(stream, peer)) => {
+                    assert_eq!(peer, client.local_addr().unwrap());
+                    assert_eq!(stream.local_addr().unwrap(), addr);
+                }
+                other => panic!("unexpected message: {:?}", other),
+            }
+        }
+
+        assert!(stop_listener(&mut task));
+        assert!(!stop_listener(&mut task));
+    }
+
+    // 断开后接受的第二个客户端不应收到第一个客户端的数据触发的自动应答，第一个客户端的连接应完全关闭
+    #[tokio::test]
+    async fn accepted_client_is_isolated_from_previous_client() {
+        use tokio::io::AsyncReadExt;
+
+        let (tx, rx) = mpsc::channel(100);
+        let messages: SharedMessages = Arc::new(Mutex::new(Vec::new()));
+        let rules = Arc::new(Mutex::new(vec![AutoReplyRule {
+            pattern: "ping".to_string(),
+            reply: "pong".to_string(),
+            ..Default::default()
+        }]));
+        let data_dir = std::env::temp_dir().join(format!("tcpclient-isolated-{}", std::process::id()));
+        tokio::spawn(handle_network_communications(
+            rx,
+            messages.clone(),
+            Default::default(),
+            rules,
+            Default::default(),
+            tx.clone(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            data_dir.clone(),
+            Default::default(),
+        ));
+        let wait_for = |prefix: &'static str, count: usize| {
+            let messages = messages.clone();
+            async move {
+                for _ in 0..250 {
+                    if messages.lock().unwrap().iter().filter(|entry| entry.text.starts_with(prefix)).count() >= count {
+                        return;
+                    }
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+                panic!("未收到消息: {}", prefix);
+            }
+        };
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        tx.send(Message::Listen("127.0.0.1".to_string(), port, ConnectOptions::default())).await.unwrap();
+        wait_for("正在监听", 1).await;
+
+        let mut first = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        wait_for("客户端", 1).await;
+        first.write_all(b"ping").await.unwrap();
+        let mut reply = [0; 4];
+        tokio::time::timeout(Duration::from_secs(5), first.read_exact(&mut reply)).await.unwrap().unwrap();
+        assert_eq!(&reply, b"pong");
+
+        // 断开后第一个客户端读到连接关闭
+        tx.send(Message::Disconnect).await.unwrap();
+        let mut buf = [0; 16];
+        let closed = tokio::time::timeout(Duration::from_secs(5), first.read(&mut buf)).await.unwrap();
+        assert!(matches!(closed, Ok(0) | Err(_)), "{:?}", closed);
+
+        let mut second = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        wait_for("客户端", 2).await;
+        let received_before = messages.lock().unwrap().iter().filter(|entry| entry.kind == MessageKind::Received).count();
+
+        // 第一个客户端之后发送的数据既不显示，也不会把应答发给第二个客户端
+        let _ = first.write_all(b"ping").await;
+        let read = tokio::time::timeout(Duration::from_millis(300), second.read(&mut buf)).await;
+        assert!(read.is_err(), "{:?}", read);
+        let received_after = messages.lock().unwrap().iter().filter(|entry| entry.kind == MessageKind::Received).count();
+        assert_eq!(received_after, received_before);
+
+        // 两个客户端各创建了一个数据文件
+        tx.send(Message::StopListening).await.unwrap();
+        assert_eq!(std::fs::read_dir(&data_dir).unwrap().count(), 2);
+        std::fs::remove_dir_all(&data_dir).unwrap();
     }
 }
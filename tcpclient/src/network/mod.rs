@@ -1,7 +1,9 @@
 pub mod connection;
 pub mod receiver;
 pub mod scanner;
+pub mod services;
+pub mod source;
 
-pub use connection::handle_network_communications;
+pub use connection::{handle_network_communications, ConnectOptions, Keepalive};
 pub use receiver::handle_data_reception;
 // 导出扫描器模块的函数
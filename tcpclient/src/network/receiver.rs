@@ -1,49 +1,131 @@
-use crate::app::EncodingMode;
-use crate::utils::{get_timestamp, write_to_file};
+use crate::app::{DisplayMode, EncodingMode};
+use crate::desktop_notify::SharedDisconnectAlerts;
+use crate::i18n::{tr, trf};
+use crate::message::{Message, MessageEntry, MessageKind, SharedMessages};
+use crate::metrics::ActiveConnection;
+use crate::notifications::{notify, NotificationKind, NotificationQueue};
+use crate::network::ConnectOptions;
+use crate::repaint::Repaint;
+use crate::rules::{find_matching_rule, AutoReplyGuard, AutoReplyRule};
+use crate::triggers::{marker_text, SharedTriggers};
+use crate::utils::{get_timestamp, lock_or_recover, write_to_file};
 use std::sync::{Arc, Mutex};
 use tokio::io::{AsyncReadExt, BufReader};
+use tokio::sync::mpsc;
 use std::fs::File;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
 
 // 优化的文件写入函数，减少锁定时间
-async fn log_to_file(file: &Option<Arc<Mutex<File>>>, message: &str, messages: &Arc<Mutex<Vec<(String, String)>>>) {
+async fn log_to_file(
+    file: &Option<Arc<Mutex<File>>>,
+    timestamp: &str,
+    message: &str,
+    messages: &SharedMessages,
+) {
     if let Some(file_arc) = file {
         if let Ok(mut file_guard) = file_arc.try_lock() {
-            if let Err(e) = write_to_file(&mut file_guard, message) {
-                let error_msg = format!("写入文件失败: {}", e);
+            if let Err(e) = write_to_file(&mut *file_guard, timestamp, message) {
+                let error_msg = trf!("写入文件失败: {}", e);
                 let timestamp = get_timestamp();
-                messages.lock().unwrap().push((timestamp, error_msg));
+                lock_or_recover(messages).push(MessageEntry::new(timestamp, MessageKind::Error, error_msg));
             }
         }
     }
 }
 
 // 优化的消息添加函数，批量处理消息
-fn add_message(messages: &Arc<Mutex<Vec<(String, String)>>>, message: String) {
+// 返回消息的时间戳，供写入文件时复用
+fn add_message(messages: &SharedMessages, kind: MessageKind, message: String) -> String {
     let timestamp = get_timestamp();
-    messages.lock().unwrap().push((timestamp, message));
+    lock_or_recover(messages).push(MessageEntry::new(timestamp.clone(), kind, message));
+    timestamp
 }
 
-// 高效的十六进制转换函数
-fn bytes_to_hex(bytes: &[u8]) -> String {
-    let mut hex_string = String::with_capacity(bytes.len() * 3);
-    for (i, b) in bytes.iter().enumerate() {
-        if i > 0 {
-            hex_string.push(' ');
-        }
-        hex_string.push_str(&format!("{:02X}", b));
+// 添加收发数据的消息并保留原始字节，供切换显示格式和双视图时重新解码
+// 返回消息的时间戳和按 mode 格式化的内容，供写入文件时复用
+fn add_data_message(messages: &SharedMessages, kind: MessageKind, raw: &[u8], mode: DisplayMode) -> (String, String) {
+    let entry = MessageEntry::data(get_timestamp(), kind, raw.to_vec(), mode);
+    let logged = (entry.timestamp.clone(), entry.text.clone());
+    lock_or_recover(messages).push(entry);
+    logged
+}
+
+// 按自动应答规则处理收到的数据，命中时将应答放入发送队列
+async fn handle_auto_reply(
+    data: &[u8],
+    rules: &Arc<Mutex<Vec<AutoReplyRule>>>,
+    tx: &mpsc::Sender<Message>,
+    guard: &mut AutoReplyGuard,
+    messages: &SharedMessages,
+) {
+    // 对端回显的自动应答不再参与匹配，防止规则互相触发
+    if guard.is_own_reply(data) {
+        return;
+    }
+
+    let rule = match find_matching_rule(&lock_or_recover(rules), data) {
+        Some(rule) => rule.clone(),
+        None => return,
+    };
+
+    let was_suspended = guard.is_suspended();
+    if guard.try_record(&rule) {
+        add_message(messages, MessageKind::Info, trf!("自动应答规则命中: {}", rule.pattern));
+        let _ = tx.send(Message::Send(rule.reply, rule.reply_mode)).await;
+    } else if !was_suspended {
+        add_message(messages, MessageKind::Error, tr("自动应答触发过于频繁，已暂停应答").to_string());
+    }
+}
+
+// 检查触发器，每个命中的触发器插入一条标记条目并发出通知
+async fn handle_triggers(
+    data: &[u8],
+    triggers: &SharedTriggers,
+    file: &Option<Arc<Mutex<File>>>,
+    messages: &SharedMessages,
+    notifications: &NotificationQueue,
+) {
+    let markers: Vec<String> = {
+        let mut set = lock_or_recover(triggers);
+        set.check(data)
+            .into_iter()
+            .map(|index| marker_text(index, &set.triggers[index]))
+            .collect()
+    };
+    for marker in markers {
+        let timestamp = add_message(messages, MessageKind::Info, marker.clone());
+        log_to_file(file, &timestamp, &marker, messages).await;
+        notify(notifications, NotificationKind::Trigger, marker);
     }
-    hex_string
 }
 
 // 改进的异步处理数据接收的函数
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_data_reception(
-    messages: Arc<Mutex<Vec<(String, String)>>>,
+    messages: SharedMessages,
     port: tokio::net::tcp::OwnedReadHalf,
     encoding_mode: Arc<Mutex<EncodingMode>>,
     file: Option<Arc<Mutex<File>>>,
+    rules: Arc<Mutex<Vec<AutoReplyRule>>>,
+    triggers: SharedTriggers,
+    tx: mpsc::Sender<Message>,
+    options: ConnectOptions,
+    notifications: NotificationQueue,
+    connection: ActiveConnection,
+    alerts: SharedDisconnectAlerts,
+    repaint: Repaint,
 ) {
-    add_message(&messages, "数据接收通道已建立".to_string());
+    add_message(&messages, MessageKind::Connection, tr("数据接收通道已建立").to_string());
+
+    // 断线通知中显示的对端地址
+    let peer = port.peer_addr().ok();
+
+    // 空闲超时检测，0 表示禁用
+    let idle_timeout = Duration::from_secs(options.idle_timeout_secs);
+
+    // 自动应答防护状态，随连接生命周期存在
+    let mut reply_guard = AutoReplyGuard::new();
 
     // 使用更大的缓冲区和BufReader提高性能
     let mut reader = BufReader::with_capacity(8192, port);
@@ -54,41 +136,62 @@ pub async fn handle_data_reception(
 
     // 持续从读取半部分读取数据，直到连接关闭或发生错误
     loop {
-        // 从读取半部分读取数据
-        match reader.read(&mut read_buffer).await {
+        // 上一次读取的结果已加入消息列表，通知界面重绘
+        repaint.request();
+
+        // 从读取半部分读取数据，启用空闲超时时用 timeout 包裹读取
+        let read_result = if idle_timeout.is_zero() {
+            reader.read(&mut read_buffer).await
+        } else {
+            match timeout(idle_timeout, reader.read(&mut read_buffer)).await {
+                Ok(result) => result,
+                Err(_) => {
+                    // 空闲超时与读取错误分开处理，连接本身仍然有效
+                    let message = trf!("空闲超时: {} 秒内未收到数据", options.idle_timeout_secs);
+                    let timestamp = add_message(&messages, MessageKind::Info, message.clone());
+                    log_to_file(&file, &timestamp, &message, &messages).await;
+
+                    if options.disconnect_on_idle {
+                        let message = tr("因空闲超时断开连接").to_string();
+                        let timestamp = add_message(&messages, MessageKind::Connection, message.clone());
+                        log_to_file(&file, &timestamp, &message, &messages).await;
+                        notify(&notifications, NotificationKind::Connection, message);
+                        let _ = tx.send(Message::Disconnect).await;
+                        break;
+                    }
+                    continue;
+                }
+            }
+        };
+
+        match read_result {
             Ok(0) => {
-                let message = "服务器关闭了连接".to_string();
-                add_message(&messages, message.clone());
-                log_to_file(&file, &message, &messages).await;
+                let message = tr("对端关闭了连接").to_string();
+                let timestamp = add_message(&messages, MessageKind::Connection, message.clone());
+                log_to_file(&file, &timestamp, &message, &messages).await;
+                notify(&notifications, NotificationKind::Connection, message.clone());
+                // 用户主动断开后对端随之关闭，此时连接已不是打开状态，不发桌面通知
+                if connection.is_open() {
+                    alerts.disconnected(peer, &message, &messages);
+                }
                 break;
             }
             Ok(n) => {
+                connection.add_received(n);
+
                 // 获取当前编码模式，减少锁定时间
-                let current_mode = *encoding_mode.lock().unwrap();
-
-                // 处理接收到的数据
-                let message = match current_mode {
-                    EncodingMode::Utf8 => {
-                        // UTF-8模式下尝试解析为UTF-8文本
-                        match String::from_utf8(read_buffer[..n].to_vec()) {
-                            Ok(data) => format!("收到(UTF-8): {}", data),
-                            Err(_) => {
-                                // 如果不是有效的UTF-8，则显示为十六进制
-                                let hex_data = bytes_to_hex(&read_buffer[..n]);
-                                format!("收到(非UTF-8数据): {}", hex_data)
-                            }
-                        }
-                    },
-                    EncodingMode::Hex => {
-                        // 十六进制模式下直接显示为十六进制
-                        let hex_data = bytes_to_hex(&read_buffer[..n]);
-                        format!("收到(HEX): {}", hex_data)
-                    }
-                };
+                let current_mode = *lock_or_recover(&encoding_mode);
 
-                // 添加消息到UI并写入文件
-                add_message(&messages, message.clone());
-                log_to_file(&file, &message, &messages).await;
+                // 添加消息到UI并写入文件，非 UTF-8 数据在 UTF-8 模式下显示为十六进制
+                let (timestamp, message) =
+                    add_data_message(&messages, MessageKind::Received, &read_buffer[..n], current_mode.into());
+                log_to_file(&file, &timestamp, &message, &messages).await;
+
+                // 标记条目紧跟在命中的数据之后
+                handle_triggers(&read_buffer[..n], &triggers, &file, &messages, &notifications).await;
+
+                // 检查自动应答规则
+                handle_auto_reply(&read_buffer[..n], &rules, &tx, &mut reply_guard, &messages).await;
 
                 // 如果距离上次UI更新超过100ms，强制更新UI
                 if last_ui_update.elapsed().as_millis() > 100 {
@@ -99,16 +202,20 @@ pub async fn handle_data_reception(
             Err(e) => {
                 // 详细分类错误类型
                 let error_msg = match e.kind() {
-                    std::io::ErrorKind::ConnectionReset => "连接被服务器重置".to_string(),
-                    std::io::ErrorKind::ConnectionAborted => "连接被中止".to_string(),
-                    std::io::ErrorKind::TimedOut => "连接超时".to_string(),
-                    std::io::ErrorKind::WouldBlock => "操作会阻塞".to_string(),
-                    std::io::ErrorKind::Interrupted => "操作被中断".to_string(),
-                    _ => format!("读取错误: {}", e),
+                    std::io::ErrorKind::ConnectionReset => tr("连接被对端重置").to_string(),
+                    std::io::ErrorKind::ConnectionAborted => tr("连接被中止").to_string(),
+                    std::io::ErrorKind::TimedOut => tr("连接超时").to_string(),
+                    std::io::ErrorKind::WouldBlock => tr("操作会阻塞").to_string(),
+                    std::io::ErrorKind::Interrupted => tr("操作被中断").to_string(),
+                    _ => trf!("读取错误: {}", e),
                 };
 
-                add_message(&messages, error_msg.clone());
-                log_to_file(&file, &error_msg, &messages).await;
+                let timestamp = add_message(&messages, MessageKind::Error, error_msg.clone());
+                log_to_file(&file, &timestamp, &error_msg, &messages).await;
+                notify(&notifications, NotificationKind::Error, error_msg.clone());
+                if connection.is_open() {
+                    alerts.disconnected(peer, &error_msg, &messages);
+                }
 
                 // 对于某些错误类型，记录连接中断
                 if matches!(
@@ -117,9 +224,9 @@ pub async fn handle_data_reception(
                         | std::io::ErrorKind::ConnectionAborted
                         | std::io::ErrorKind::BrokenPipe
                 ) {
-                    let conn_msg = "连接中断".to_string();
-                    add_message(&messages, conn_msg.clone());
-                    log_to_file(&file, &conn_msg, &messages).await;
+                    let conn_msg = tr("连接中断").to_string();
+                    let timestamp = add_message(&messages, MessageKind::Error, conn_msg.clone());
+                    log_to_file(&file, &timestamp, &conn_msg, &messages).await;
                 }
 
                 break;
@@ -127,7 +234,9 @@ pub async fn handle_data_reception(
         }
     }
 
-    let message = "数据接收通道已关闭".to_string();
-    add_message(&messages, message.clone());
-    log_to_file(&file, &message, &messages).await;
+    connection.closed();
+    let message = tr("数据接收通道已关闭").to_string();
+    let timestamp = add_message(&messages, MessageKind::Connection, message.clone());
+    log_to_file(&file, &timestamp, &message, &messages).await;
+    repaint.request();
 }
@@ -1,27 +1,199 @@
-use crate::utils::get_timestamp;
+use crate::i18n::{tr, trf};
+use crate::message::SharedMessages;
+use crate::network::services::{port_label, service_name};
+use crate::repaint::Repaint;
+use crate::network::source::{connect_tcp, failure_reason, format_reason, FailureReason};
+use crate::utils::{format_duration, get_timestamp, lock_or_recover, parse_number_input, NumberInput};
 use futures::future::join_all;
-use std::net::Ipv4Addr;
-use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::ops::RangeInclusive;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use tokio::net::TcpStream;
+use std::time::Instant;
+use tokio::sync::{mpsc, Semaphore, SemaphorePermit};
 use tokio::task;
-use tokio::time::{timeout, Duration};
-
-// 将IP地址字符串转换为u32表示
-fn ip_to_u32(ip: &str) -> Option<u32> {
-    match Ipv4Addr::from_str(ip) {
-        Ok(ipv4) => {
-            let octets = ipv4.octets();
-            Some(
-                (octets[0] as u32) << 24
-                    | (octets[1] as u32) << 16
-                    | (octets[2] as u32) << 8
-                    | (octets[3] as u32),
-            )
+use tokio::time::{timeout, Duration, Interval, MissedTickBehavior};
+use tcpcommon::ipnet::{in_ranges, ip_to_u32, parse_ip_range, IpRange};
+
+// 单条扫描结果，保留结构化的IP和端口便于后续直接连接
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScanResult {
+    pub ip: String,
+    pub port: u16,
+    #[serde(default)]
+    pub latency_ms: f64, // 建立连接的耗时，旧的历史记录中没有该字段
+    #[serde(default)]
+    pub mac: Option<String>, // 同网段设备的MAC地址，取自系统ARP表
+}
+
+// 按主机分组的扫描结果
+#[derive(Clone, Debug, PartialEq)]
+pub struct HostResults {
+    pub ip: String,
+    pub mac: Option<String>,
+    pub results: Vec<ScanResult>, // 按端口升序排列
+}
+
+// 将平铺的扫描结果按IP分组，主机按IP数值升序排列
+pub fn group_results_by_host(results: &[ScanResult]) -> Vec<HostResults> {
+    let mut hosts: BTreeMap<u32, HostResults> = BTreeMap::new();
+    for result in results {
+        let key = ip_to_u32(&result.ip).unwrap_or(u32::MAX);
+        let host = hosts.entry(key).or_insert_with(|| HostResults {
+            ip: result.ip.clone(),
+            mac: None,
+            results: Vec::new(),
+        });
+        if host.mac.is_none() {
+            host.mac = result.mac.clone();
         }
-        Err(_) => None,
+        host.results.push(result.clone());
+    }
+
+    let mut grouped: Vec<HostResults> = hosts.into_values().collect();
+    for host in &mut grouped {
+        host.results.sort_by_key(|result| result.port);
+        host.results.dedup_by_key(|result| result.port);
+    }
+    grouped
+}
+
+// 常见厂商的OUI前缀 (MAC地址前三个字节)，只收录局域网中较常见的设备
+const OUI_VENDORS: &[(&str, &str)] = &[
+    ("00:00:0C", "Cisco"),
+    ("00:03:93", "Apple"),
+    ("00:05:69", "VMware"),
+    ("00:08:9B", "QNAP"),
+    ("00:0C:29", "VMware"),
+    ("00:0C:42", "MikroTik"),
+    ("00:11:32", "Synology"),
+    ("00:14:22", "Dell"),
+    ("00:15:5D", "Microsoft Hyper-V"),
+    ("00:16:3E", "Xen"),
+    ("00:1B:21", "Intel"),
+    ("00:1B:63", "Apple"),
+    ("00:1C:42", "Parallels"),
+    ("00:50:56", "VMware"),
+    ("00:E0:4C", "Realtek"),
+    ("00:E0:FC", "Huawei"),
+    ("08:00:27", "VirtualBox"),
+    ("14:CC:20", "TP-Link"),
+    ("24:0A:C4", "Espressif"),
+    ("24:A4:3C", "Ubiquiti"),
+    ("28:CD:C1", "Raspberry Pi"),
+    ("30:AE:A4", "Espressif"),
+    ("44:D9:E7", "Ubiquiti"),
+    ("4C:5E:0C", "MikroTik"),
+    ("50:C7:BF", "TP-Link"),
+    ("52:54:00", "QEMU/KVM"),
+    ("B8:27:EB", "Raspberry Pi"),
+    ("D8:3A:DD", "Raspberry Pi"),
+    ("DC:A6:32", "Raspberry Pi"),
+    ("E4:5F:01", "Raspberry Pi"),
+];
+
+// 根据MAC地址前缀查找厂商名
+pub fn mac_vendor(mac: &str) -> Option<&'static str> {
+    let prefix = mac.get(..8)?;
+    OUI_VENDORS
+        .iter()
+        .find(|(oui, _)| oui.eq_ignore_ascii_case(prefix))
+        .map(|(_, vendor)| *vendor)
+}
+
+// 将 aa-bb-cc-dd-ee-ff / aa:bb:cc:dd:ee:ff 统一为 AA:BB:CC:DD:EE:FF，
+// 无效地址、全零地址 (未解析) 和广播地址返回None
+fn normalize_mac(mac: &str) -> Option<String> {
+    let octets: Vec<&str> = mac.split([':', '-']).collect();
+    if octets.len() != 6
+        || !octets
+            .iter()
+            .all(|o| o.len() == 2 && o.bytes().all(|b| b.is_ascii_hexdigit()))
+    {
+        return None;
     }
+    let mac = octets.join(":").to_ascii_uppercase();
+    if mac == "00:00:00:00:00:00" || mac == "FF:FF:FF:FF:FF:FF" {
+        return None;
+    }
+    Some(mac)
+}
+
+// 读取系统ARP表 (IP -> MAC)
+// ARP表中只有与本机处于同一网段的设备，取不到信息时返回空表
+#[cfg(target_os = "linux")]
+mod arp {
+    use super::normalize_mac;
+    use std::collections::HashMap;
+
+    // 解析 /proc/net/arp，格式为: IP address, HW type, Flags, HW address, Mask, Device
+    pub fn read_arp_table() -> HashMap<String, String> {
+        let Ok(content) = std::fs::read_to_string("/proc/net/arp") else {
+            return HashMap::new();
+        };
+        content
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let mac = normalize_mac(fields.get(3)?)?;
+                Some((fields[0].to_string(), mac))
+            })
+            .collect()
+    }
+}
+
+#[cfg(windows)]
+mod arp {
+    use super::normalize_mac;
+    use std::collections::HashMap;
+    use std::net::Ipv4Addr;
+    use std::process::Command;
+
+    // 解析 `arp -a` 的输出，每行形如: 192.168.1.1    aa-bb-cc-dd-ee-ff    动态
+    pub fn read_arp_table() -> HashMap<String, String> {
+        let Ok(output) = Command::new("arp").arg("-a").output() else {
+            return HashMap::new();
+        };
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let ip = fields.next()?.parse::<Ipv4Addr>().ok()?;
+                let mac = normalize_mac(fields.next()?)?;
+                Some((ip.to_string(), mac))
+            })
+            .collect()
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod arp {
+    use std::collections::HashMap;
+
+    // 其他平台暂不支持读取ARP表
+    pub fn read_arp_table() -> HashMap<String, String> {
+        HashMap::new()
+    }
+}
+
+// 用ARP表中的MAC地址补充扫描结果，返回获取到MAC的主机数量
+fn attach_mac_addresses(results: &mut [ScanResult]) -> usize {
+    let arp_table = arp::read_arp_table();
+    if arp_table.is_empty() {
+        return 0;
+    }
+
+    let mut hosts = std::collections::HashSet::new();
+    for result in results.iter_mut() {
+        if let Some(mac) = arp_table.get(&result.ip) {
+            result.mac = Some(mac.clone());
+            hosts.insert(result.ip.clone());
+        }
+    }
+    hosts.len()
 }
 
 // 将u32转换为IP地址字符串
@@ -33,99 +205,443 @@ fn u32_to_ip(ip: u32) -> String {
     format!("{}.{}.{}.{}", octet1, octet2, octet3, octet4)
 }
 
-// 检查IP地址是否有效
-pub fn is_valid_ip(ip: &str) -> bool {
-    Ipv4Addr::from_str(ip).is_ok()
-}
+// 单次扫描默认允许的最大IP数量 (一个/16网段) 和端口数量 (全部端口)，可在界面中调整
+pub const DEFAULT_MAX_SCAN_IPS: u64 = 65536;
+pub const DEFAULT_MAX_SCAN_PORTS: u64 = 65536;
+
+// 总探测次数超过该值时需要用户确认
+pub const LARGE_SCAN_PROBES: u64 = 1_000_000;
 
-// 检查端口是否有效
-pub fn is_valid_port(port: &str) -> bool {
-    match port.parse::<u16>() {
-        Ok(_) => true,
-        Err(_) => false,
+// 计算闭区间 [start, end] 包含的数量，start > end 时返回None
+// 使用u64计算，避免 0.0.0.0-255.255.255.255 或 0-65535 这类范围在加1时溢出
+fn range_len(start: u64, end: u64) -> Option<u64> {
+    if start <= end {
+        Some(end - start + 1)
+    } else {
+        None
     }
 }
 
-// 检查端口范围是否有效
-pub fn is_valid_port_range(start_port: &str, end_port: &str) -> bool {
-    if !is_valid_port(start_port) || !is_valid_port(end_port) {
-        return false;
+// 端口探测超时时间的允许范围 (毫秒)，过短时所有端口都会被判为关闭，超出范围的输入按边界值扫描
+pub const SCAN_TIMEOUT_RANGE_MS: RangeInclusive<u64> = 10..=60_000;
+
+// 扫描设置表单中的原始输入
+pub struct ScanForm<'a> {
+    pub start_ip: &'a str,
+    pub end_ip: &'a str,
+    pub start_port: &'a str,
+    pub end_port: &'a str,
+    pub timeout_ms: &'a str,
+    pub max_ips: u64,   // 允许的最大IP数量
+    pub max_ports: u64, // 允许的最大端口数量
+}
+
+// 检查通过的扫描范围和超时时间
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanSpec {
+    pub start_ip: String,
+    pub end_ip: String,
+    pub start_port: u16,
+    pub end_port: u16,
+    pub timeout_ms: u64,
+}
+
+fn parse_scan_port(port: &str, what: &str) -> Result<u16, String> {
+    port.parse()
+        .map_err(|_| trf!("{}无效: \"{}\"，应为 0-65535 之间的整数", what, port))
+}
+
+// 检查扫描表单，出错时返回具体原因 (格式错误、范围颠倒或超过上限)，输入两端的空白会被忽略
+pub fn parse_scan_spec(form: &ScanForm) -> Result<ScanSpec, String> {
+    let (start_ip, end_ip) = (form.start_ip.trim(), form.end_ip.trim());
+    let start = ip_to_u32(start_ip).ok_or_else(|| trf!("起始IP格式无效: \"{}\"", start_ip))?;
+    let end = ip_to_u32(end_ip).ok_or_else(|| trf!("结束IP格式无效: \"{}\"", end_ip))?;
+    let ips = range_len(start as u64, end as u64)
+        .ok_or_else(|| trf!("结束IP {} 小于起始IP {}", end_ip, start_ip))?;
+    if ips > form.max_ips {
+        return Err(trf!("IP数量 {} 超过单次扫描上限 {}", ips, form.max_ips));
     }
 
-    let start = start_port.parse::<u16>().unwrap();
-    let end = end_port.parse::<u16>().unwrap();
+    let start_port = parse_scan_port(form.start_port.trim(), tr("起始端口"))?;
+    let end_port = parse_scan_port(form.end_port.trim(), tr("结束端口"))?;
+    let ports = range_len(start_port as u64, end_port as u64)
+        .ok_or_else(|| trf!("结束端口 {} 小于起始端口 {}", end_port, start_port))?;
+    if ports > form.max_ports {
+        return Err(trf!("端口数量 {} 超过单次扫描上限 {}", ports, form.max_ports));
+    }
 
-    // 检查范围是否有效，并限制最大扫描范围为1000个端口
-    start <= end && end - start <= 1000
+    let timeout_ms = match parse_number_input(form.timeout_ms, SCAN_TIMEOUT_RANGE_MS) {
+        NumberInput::Valid(ms) | NumberInput::Clamped(ms) => ms,
+        NumberInput::Invalid => {
+            return Err(trf!("超时时间无效: \"{}\"，应为毫秒数", form.timeout_ms.trim()));
+        }
+    };
+
+    Ok(ScanSpec {
+        start_ip: start_ip.to_string(),
+        end_ip: end_ip.to_string(),
+        start_port,
+        end_port,
+        timeout_ms,
+    })
 }
 
-// 检查IP范围是否有效
-pub fn is_valid_ip_range(start_ip: &str, end_ip: &str) -> bool {
-    if !is_valid_ip(start_ip) || !is_valid_ip(end_ip) {
-        return false;
+// 扫描时跳过的IP区间 (起始, 结束)，包含两端
+pub type ExcludeRange = IpRange;
+
+// 解析排除列表，每行一个IP或CIDR网段，空行和 # 开头的注释行会被忽略
+// 出错时返回具体的行号
+pub fn parse_exclude_list(text: &str) -> Result<Vec<ExcludeRange>, String> {
+    let mut ranges = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match parse_ip_range(line) {
+            Some(range) => ranges.push(range),
+            None => return Err(trf!("排除列表第 {} 行格式无效: {}", index + 1, line)),
+        }
     }
+    Ok(ranges)
+}
 
-    let start = ip_to_u32(start_ip);
-    let end = ip_to_u32(end_ip);
+// 判断IP是否在排除列表中
+fn is_excluded(ip: u32, excludes: &[ExcludeRange]) -> bool {
+    in_ranges(ip, excludes)
+}
+
+// 计算扫描范围内被排除的地址数量，重叠的排除项只计算一次
+fn excluded_count(start: u32, end: u32, excludes: &[ExcludeRange]) -> u64 {
+    let mut clipped: Vec<ExcludeRange> = excludes
+        .iter()
+        .filter(|&&(s, e)| s <= end && e >= start)
+        .map(|&(s, e)| (s.max(start), e.min(end)))
+        .collect();
+    clipped.sort_unstable();
 
-    match (start, end) {
-        (Some(s), Some(e)) => s <= e && e - s <= 1000, // 限制最大扫描范围为1000个IP
-        _ => false,
+    let mut count = 0;
+    let mut covered_until: Option<u32> = None; // 已计入的最大地址
+    for (s, e) in clipped {
+        let s = match covered_until {
+            Some(covered) if covered >= e => continue,
+            Some(covered) if covered >= s => covered + 1,
+            _ => s,
+        };
+        count += range_len(s as u64, e as u64).unwrap_or(0);
+        covered_until = Some(e);
     }
+    count
+}
+
+// 计算一次扫描的总探测次数 (不含重试，已扣除排除的地址)，范围无效时返回0
+pub fn total_probes(config: &ScanConfig) -> u64 {
+    let ips = match (ip_to_u32(&config.start_ip), ip_to_u32(&config.end_ip)) {
+        (Some(start), Some(end)) => range_len(start as u64, end as u64)
+            .map_or(0, |len| len - excluded_count(start, end, &config.excludes)),
+        _ => 0,
+    };
+    let ports = range_len(config.start_port as u64, config.end_port as u64).unwrap_or(0);
+    ips * ports
 }
 
-// 异步检查单个IP和端口是否开放
-async fn check_port(ip: &str, port: u16, timeout_ms: u64) -> bool {
+// 异步检查单个IP和端口是否开放，失败时返回失败原因的分类
+async fn check_port(
+    ip: &str,
+    port: u16,
+    timeout_ms: u64,
+    source: Option<IpAddr>,
+) -> Result<Duration, FailureReason> {
     let addr = format!("{}:{}", ip, port);
-    match timeout(Duration::from_millis(timeout_ms), TcpStream::connect(&addr)).await {
-        Ok(Ok(_)) => true,
-        _ => false,
+    let start = Instant::now();
+    match timeout(Duration::from_millis(timeout_ms), connect_tcp(&addr, source)).await {
+        Ok(Ok(_)) => Ok(start.elapsed()),
+        Ok(Err(e)) => Err(failure_reason(&e)),
+        Err(_) => Err((tr("连接超时"), None)),
+    }
+}
+
+// 连接速率限制器，基于 interval 每个周期发放一个新建连接的令牌
+struct RateLimiter {
+    interval: tokio::sync::Mutex<Interval>,
+}
+
+impl RateLimiter {
+    // per_second 为 0 表示不限速
+    fn new(per_second: u32) -> Option<Self> {
+        if per_second == 0 {
+            return None;
+        }
+
+        let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / per_second as f64));
+        // 落后时不补发令牌，避免瞬间突发大量连接
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Some(Self {
+            interval: tokio::sync::Mutex::new(interval),
+        })
+    }
+
+    // 等待下一个令牌
+    async fn acquire(&self) {
+        self.interval.lock().await.tick().await;
+    }
+}
+
+// 多个IP时每个IP每批并行扫描的端口数，IP之间已经由多个任务并行
+const PORT_CHUNK_SIZE: usize = 50;
+
+// 每批并行扫描的端口数：只扫描一个主机时没有IP级的并行，
+// 按并发上限分批，让端口探测占满所有并发连接
+fn port_chunk_size(single_host: bool, max_connections: usize) -> usize {
+    if single_host {
+        max_connections.clamp(PORT_CHUNK_SIZE, u16::MAX as usize)
+    } else {
+        PORT_CHUNK_SIZE
     }
 }
 
+// 扫描过程中的限流状态：新建连接速率和全局并发连接数
+struct ScanLimits {
+    rate: Option<RateLimiter>,
+    connections: Semaphore, // 限制所有IP上同时进行的连接尝试，避免耗尽文件描述符
+    max_connections: usize,
+    port_chunk_size: usize, // 每个IP每批并行扫描的端口数
+    saturated_waits: AtomicUsize, // 因并发上限而排队等待的次数
+    failures: Mutex<BTreeMap<FailureReason, usize>>, // 重试后仍失败的探测按原因和 errno 计数
+}
+
+impl ScanLimits {
+    fn new(rate_limit: u32, max_connections: usize, single_host: bool) -> Self {
+        let max_connections = max_connections.max(1);
+        Self {
+            rate: RateLimiter::new(rate_limit),
+            connections: Semaphore::new(max_connections),
+            max_connections,
+            port_chunk_size: port_chunk_size(single_host, max_connections),
+            saturated_waits: AtomicUsize::new(0),
+            failures: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    // 记录一次最终失败的探测
+    fn record_failure(&self, reason: FailureReason) {
+        *lock_or_recover(&self.failures).entry(reason).or_insert(0) += 1;
+    }
+
+    // 等待新建连接的速率令牌
+    async fn acquire_rate(&self) {
+        if let Some(rate) = &self.rate {
+            rate.acquire().await;
+        }
+    }
+
+    // 获取并发连接许可，返回许可以及本次是否首次达到并发上限
+    async fn acquire_connection(&self) -> (SemaphorePermit<'_>, bool) {
+        if let Ok(permit) = self.connections.try_acquire() {
+            return (permit, false);
+        }
+
+        let is_first = self.saturated_waits.fetch_add(1, Ordering::Relaxed) == 0;
+        let permit = self
+            .connections
+            .acquire()
+            .await
+            .expect("扫描并发信号量不会被关闭");
+        (permit, is_first)
+    }
+}
+
+// 带重试的端口探测，返回成功时的重试次数 (0 表示首次即成功) 和连接耗时，全部失败返回None
+// 首次探测的速率令牌由调用方获取，重试同样受限速和并发上限约束
+async fn probe_port(
+    ip: &str,
+    port: u16,
+    config: &ScanConfig,
+    limits: &ScanLimits,
+    events: &mpsc::Sender<ScanEvent>,
+) -> Option<(u8, Duration)> {
+    let retries = config.retries.min(MAX_SCAN_RETRIES);
+    for attempt in 0..=retries {
+        if attempt > 0 {
+            limits.acquire_rate().await;
+        }
+
+        let (_permit, is_first_saturation) = limits.acquire_connection().await;
+        if is_first_saturation {
+            let msg = trf!(
+                "并发连接数已达上限 ({}), 后续探测将排队等待",
+                limits.max_connections
+            );
+            let _ = events.send(ScanEvent::Detail(msg)).await;
+        }
+
+        match check_port(ip, port, config.timeout_ms, config.source_addr).await {
+            Ok(latency) => return Some((attempt, latency)),
+            Err(reason) if attempt == retries => limits.record_failure(reason),
+            Err(_) => {}
+        }
+    }
+    None
+}
+
+// 扫描参数
+#[derive(Clone, Debug)]
+pub struct ScanConfig {
+    pub start_ip: String,
+    pub end_ip: String,
+    pub start_port: u16,
+    pub end_port: u16,
+    pub timeout_ms: u64,
+    pub retries: u8,     // 探测失败后的重试次数
+    pub rate_limit: u32, // 每秒最多新建的连接数，0 表示不限速
+    pub max_connections: usize, // 所有IP上同时进行的连接尝试上限
+    pub source_addr: Option<IpAddr>, // 绑定的本地源地址，None 表示使用系统默认路由
+    pub excludes: Vec<ExcludeRange>, // 跳过的IP地址
+    pub verbose_logs: bool,          // 是否在日志中显示进度、限速等详细信息
+}
+
+// 允许的最大重试次数
+pub const MAX_SCAN_RETRIES: u8 = 3;
+
+// 默认的最大并发连接数，低于常见的文件描述符上限 (1024)
+pub const DEFAULT_MAX_CONNECTIONS: usize = 256;
+
+// 扫描引擎推送给调用方的事件
+#[derive(Clone, Debug)]
+pub enum ScanEvent {
+    Log(String),          // 关键事件日志 (开始、错误、取消、完成等)
+    Detail(String),       // 详细日志 (进度、限速、重试等)，只在开启详细日志时显示
+    Found(ScanResult),    // 发现开放端口
+    Probed(u64),          // 又完成了若干次端口探测 (不含重试)
+    Finished(ScanSummary), // 扫描结束，总是最后一个事件
+}
+
+// 扫描结束时的统计信息
+#[derive(Clone, Debug)]
+pub struct ScanSummary {
+    pub scanned_ips: usize,
+    pub open_ports: usize,
+    pub cancelled: bool,
+    pub saturated_waits: usize, // 因并发上限排队等待的探测次数
+    pub failures: Vec<(FailureReason, usize)>, // 未开放端口的失败原因及次数，次数多的在前
+}
+
+// 扫描进度，由 scan_ip_range 维护，界面每帧据此计算耗时和剩余时间
+#[derive(Clone, Debug, Default)]
+pub struct ScanProgress {
+    pub started_at: Option<Instant>,
+    pub completed_probes: u64,
+    pub total_probes: u64,
+    pub finished_elapsed: Option<Duration>, // 扫描结束 (完成或取消) 时的总耗时
+    pub cancelled: bool,
+    pub rate_limit: u32, // 每秒最多新建的连接数，0 表示不限速
+}
+
+impl ScanProgress {
+    // 已耗时，扫描结束后固定为总耗时
+    pub fn elapsed(&self) -> Duration {
+        match (self.finished_elapsed, self.started_at) {
+            (Some(elapsed), _) => elapsed,
+            (None, Some(started_at)) => started_at.elapsed(),
+            (None, None) => Duration::ZERO,
+        }
+    }
+
+    // 按当前平均速率估算剩余时间，限速时速率不超过限速值
+    // 尚无完成的探测或扫描已结束时返回None
+    pub fn eta(&self) -> Option<Duration> {
+        if self.finished_elapsed.is_some() || self.completed_probes == 0 {
+            return None;
+        }
+        let remaining = self.total_probes.saturating_sub(self.completed_probes);
+        let mut rate = self.completed_probes as f64 / self.elapsed().as_secs_f64();
+        if self.rate_limit > 0 {
+            rate = rate.min(self.rate_limit as f64);
+        }
+        Some(Duration::from_secs_f64(remaining as f64 / rate))
+    }
+}
+
+// 扫描事件通道的容量，调用方消费过慢时扫描会等待
+const SCAN_EVENT_CHANNEL_SIZE: usize = 1024;
+
+// 界面保留的扫描日志条数上限，超出时丢弃最旧的日志
+const MAX_SCAN_LOG_ENTRIES: usize = 5000;
+
+// 追加一条扫描日志，并保持日志数量不超过上限
+fn push_scan_log(scan_logs: &Arc<Mutex<Vec<(String, String)>>>, msg: String) {
+    let mut logs = lock_or_recover(scan_logs);
+    logs.push((get_timestamp(), msg));
+    if logs.len() > MAX_SCAN_LOG_ENTRIES {
+        let excess = logs.len() - MAX_SCAN_LOG_ENTRIES;
+        logs.drain(..excess);
+    }
+}
+
+// 启动扫描并返回事件接收端
+// 扫描在后台任务中进行；is_scanning 被置为 false 或接收端被丢弃时扫描会尽快停止
+pub fn start_scan(config: ScanConfig, is_scanning: Arc<AtomicBool>) -> mpsc::Receiver<ScanEvent> {
+    let (tx, rx) = mpsc::channel(SCAN_EVENT_CHANNEL_SIZE);
+    tokio::spawn(run_scan(config, is_scanning, tx));
+    rx
+}
+
 // 并行扫描多个端口
 async fn scan_ports(
     ip: &str,
-    start_port: u16,
-    end_port: u16,
-    timeout_ms: u64,
-    scan_results: &Arc<Mutex<Vec<String>>>,
-    scan_logs: &Arc<Mutex<Vec<(String, String)>>>,
+    config: &Arc<ScanConfig>,
+    events: &mpsc::Sender<ScanEvent>,
     open_ports: &Arc<AtomicUsize>,
-    is_scanning: &Arc<Mutex<bool>>,
+    is_scanning: &Arc<AtomicBool>,
     is_cancelled: &Arc<AtomicBool>,
+    limits: &Arc<ScanLimits>,
 ) -> usize {
     let mut found_count = 0;
     let mut port_tasks = Vec::new();
-    let chunk_size = 50; // 每批并行扫描的端口数
+    let chunk_size = limits.port_chunk_size;
+    let (start_port, end_port) = (config.start_port, config.end_port);
 
     // 分批并行扫描端口
     for port_chunk_start in (start_port..=end_port).step_by(chunk_size) {
-        let port_chunk_end = std::cmp::min(port_chunk_start + chunk_size as u16 - 1, end_port);
+        let port_chunk_end = std::cmp::min(port_chunk_start.saturating_add(chunk_size as u16 - 1), end_port);
 
         for port in port_chunk_start..=port_chunk_end {
-            // 检查是否取消扫描
-            if !*is_scanning.lock().unwrap() || is_cancelled.load(Ordering::Relaxed) {
+            // 检查是否取消扫描，调用方不再接收事件也视为取消
+            if !is_scanning.load(Ordering::Relaxed)
+                || is_cancelled.load(Ordering::Relaxed)
+                || events.is_closed()
+            {
                 is_cancelled.store(true, Ordering::Relaxed);
                 return found_count;
             }
 
+            // 限速时等待令牌后再发起新的探测
+            limits.acquire_rate().await;
+
             let ip = ip.to_string();
-            let scan_results = Arc::clone(scan_results);
-            let scan_logs = Arc::clone(scan_logs);
+            let config = Arc::clone(config);
+            let events = events.clone();
             let open_ports = Arc::clone(open_ports);
+            let limits = Arc::clone(limits);
 
             let task = tokio::spawn(async move {
-                if check_port(&ip, port, timeout_ms).await {
-                    open_ports.fetch_add(1, Ordering::Relaxed);
-                    let result = format!("{} - 端口 {} 开放", ip, port);
-                    scan_results.lock().unwrap().push(result.clone());
-
-                    let found_msg = format!("发现开放端口: {}:{}", ip, port);
-                    scan_logs.lock().unwrap().push((get_timestamp(), found_msg));
-                    true
-                } else {
-                    false
+                match probe_port(&ip, port, &config, &limits, &events).await {
+                    Some((attempt, latency)) => {
+                        open_ports.fetch_add(1, Ordering::Relaxed);
+                        if attempt > 0 {
+                            let retry_msg = trf!("{}:{} 第 {} 次重试成功", ip, port, attempt);
+                            let _ = events.send(ScanEvent::Detail(retry_msg)).await;
+                        }
+                        let _ = events.send(ScanEvent::Found(ScanResult {
+                            ip,
+                            port,
+                            latency_ms: latency.as_secs_f64() * 1000.0,
+                            mac: None,
+                        })).await;
+                        true
+                    }
+                    None => false,
                 }
             });
 
@@ -133,11 +649,11 @@ async fn scan_ports(
         }
 
         // 等待当前批次完成
-        for result in join_all(port_tasks).await {
-            if let Ok(is_open) = result {
-                if is_open {
-                    found_count += 1;
-                }
+        let batch_results = join_all(port_tasks).await;
+        let _ = events.send(ScanEvent::Probed(batch_results.len() as u64)).await;
+        for is_open in batch_results.into_iter().flatten() {
+            if is_open {
+                found_count += 1;
             }
         }
 
@@ -151,157 +667,728 @@ async fn scan_ports(
     found_count
 }
 
-// 执行IP扫描
-pub async fn scan_ip_range(
-    start_ip: &str,
-    end_ip: &str,
-    start_port: u16,
-    end_port: u16,
-    timeout_ms: u64,
-    _messages: Arc<Mutex<Vec<(String, String)>>>,
-    scan_results: Arc<Mutex<Vec<String>>>,
-    scan_logs: Arc<Mutex<Vec<(String, String)>>>,
-    is_scanning: Arc<Mutex<bool>>,
-) {
-    // 清空之前的扫描结果和日志
-    scan_results.lock().unwrap().clear();
-    scan_logs.lock().unwrap().clear();
+// 扫描引擎主体，通过 events 推送日志和结果
+async fn run_scan(config: ScanConfig, is_scanning: Arc<AtomicBool>, events: mpsc::Sender<ScanEvent>) {
+    let log = |msg: String| {
+        let events = events.clone();
+        async move {
+            let _ = events.send(ScanEvent::Log(msg)).await;
+        }
+    };
+    let detail = |msg: String| {
+        let events = events.clone();
+        async move {
+            let _ = events.send(ScanEvent::Detail(msg)).await;
+        }
+    };
 
     // 记录扫描开始
-    let port_range_msg = if start_port == end_port {
-        format!("端口: {}", start_port)
+    let port_range_msg = if config.start_port == config.end_port {
+        trf!("端口: {}", config.start_port)
     } else {
-        format!("端口范围: {} 到 {}", start_port, end_port)
+        trf!("端口范围: {} 到 {}", config.start_port, config.end_port)
     };
 
-    let start_msg = format!("开始扫描IP范围: {} 到 {}, {}", start_ip, end_ip, port_range_msg);
-    scan_logs.lock().unwrap().push((get_timestamp(), start_msg));
+    log(trf!(
+        "开始扫描IP范围: {} 到 {}, {}",
+        config.start_ip, config.end_ip, port_range_msg
+    ))
+    .await;
+    if let Some(source) = config.source_addr {
+        detail(trf!("使用源地址 {} 发起探测", source)).await;
+    }
 
     // 转换IP地址为数字表示
-    if let (Some(start), Some(end)) = (ip_to_u32(start_ip), ip_to_u32(end_ip)) {
-        let total_ips = end - start + 1;
-        let total_ports = (end_port - start_port + 1) as u32;
-        let total_scans = total_ips * total_ports;
-        let total_msg = format!("总共需要扫描 {} 个IP地址, {} 个端口, 共 {} 次扫描", total_ips, total_ports, total_scans);
-        scan_logs.lock().unwrap().push((get_timestamp(), total_msg));
-
-        // 使用原子计数器来跟踪进度和结果
-        let scanned = Arc::new(AtomicUsize::new(0));
-        let open_ports = Arc::new(AtomicUsize::new(0));
-        let is_cancelled = Arc::new(AtomicBool::new(false));
-
-        // 确定线程数量 - 根据IP数量和系统CPU核心数动态调整
-        let cpu_cores = num_cpus::get();
-        let total_ips_usize = total_ips as usize;
-        let batch_size = std::cmp::max(1, total_ips_usize / cpu_cores);
-
-        // 记录使用的线程数
-        let thread_count = std::cmp::min(total_ips_usize, cpu_cores);
-        let thread_msg = format!("使用 {} 个线程进行扫描", thread_count);
-        scan_logs.lock().unwrap().push((get_timestamp(), thread_msg));
-
-        // 创建任务集合
-        let mut tasks = Vec::new();
-
-        // 分批处理IP地址
-        for batch_start in (start..=end).step_by(batch_size) {
-            let batch_end = std::cmp::min(batch_start + batch_size as u32 - 1, end);
-
-            // 克隆所有需要的引用
-            let scan_results = Arc::clone(&scan_results);
-            let scan_logs = Arc::clone(&scan_logs);
-            let is_scanning = Arc::clone(&is_scanning);
-            let scanned = Arc::clone(&scanned);
-            let open_ports = Arc::clone(&open_ports);
-            let is_cancelled = Arc::clone(&is_cancelled);
-            let _batch_size = (batch_end - batch_start + 1) as usize;
-
-            // 创建异步任务
-            let task = task::spawn(async move {
-                for ip_num in batch_start..=batch_end {
-                    // 检查是否取消扫描
-                    if !*is_scanning.lock().unwrap() || is_cancelled.load(Ordering::Relaxed) {
-                        is_cancelled.store(true, Ordering::Relaxed);
-                        break;
-                    }
+    let (start, end) = match (ip_to_u32(&config.start_ip), ip_to_u32(&config.end_ip)) {
+        (Some(start), Some(end)) => (start, end),
+        _ => {
+            log(tr("IP地址格式无效，无法开始扫描").to_string()).await;
+            let _ = events
+                .send(ScanEvent::Finished(ScanSummary {
+                    scanned_ips: 0,
+                    open_ports: 0,
+                    cancelled: false,
+                    saturated_waits: 0,
+                    failures: Vec::new(),
+                }))
+                .await;
+            return;
+        }
+    };
 
-                    let ip_str = u32_to_ip(ip_num);
-                    let current_scanned = scanned.fetch_add(1, Ordering::Relaxed) + 1;
-
-                    // 更新进度 (每5个IP或批次结束时)
-                    if current_scanned % 5 == 0 || current_scanned == total_ips_usize {
-                        let progress_percent = (current_scanned * 100) / total_ips_usize;
-                        let progress_msg = format!(
-                            "扫描进度: {}/{} ({}%)",
-                            current_scanned, total_ips_usize, progress_percent
-                        );
-                        scan_logs.lock().unwrap().push((get_timestamp(), progress_msg));
-                    }
+    let excluded = excluded_count(start, end, &config.excludes);
+    if !config.excludes.is_empty() {
+        log(trf!("已排除 {} 个地址", excluded)).await;
+    }
 
-                    // 使用优化的端口扫描函数
-                    scan_ports(
-                        &ip_str,
-                        start_port,
-                        end_port,
-                        timeout_ms,
-                        &scan_results,
-                        &scan_logs,
-                        &open_ports,
-                        &is_scanning,
-                        &is_cancelled
-                    ).await;
+    let total_ips = range_len(start as u64, end as u64).unwrap_or(0) - excluded;
+    let total_ports = range_len(config.start_port as u64, config.end_port as u64).unwrap_or(0);
+    let total_scans = total_ips * total_ports;
+    let retries = config.retries.min(MAX_SCAN_RETRIES);
+    let total_msg = if retries == 0 {
+        trf!(
+            "总共需要扫描 {} 个IP地址, {} 个端口, 共 {} 次扫描",
+            total_ips, total_ports, total_scans
+        )
+    } else {
+        // 重试只发生在关闭的端口上，因此只能给出最多探测次数
+        trf!(
+            "总共需要扫描 {} 个IP地址, {} 个端口, 共 {} 次扫描 (不含重试; 每次失败最多重试 {} 次, 最多 {} 次探测)",
+            total_ips,
+            total_ports,
+            total_scans,
+            retries,
+            total_scans * (retries as u64 + 1)
+        )
+    };
+    log(total_msg).await;
+
+    // 记录限速配置
+    let limits = Arc::new(ScanLimits::new(config.rate_limit, config.max_connections, total_ips == 1));
+    detail(trf!("最大并发连接数: {}", limits.max_connections)).await;
+    if total_ips == 1 {
+        detail(trf!("单个主机, 按端口并行扫描, 每批 {} 个端口", limits.port_chunk_size)).await;
+    }
+    if config.rate_limit == 0 {
+        log(tr("限速: 不限").to_string()).await;
+    } else {
+        log(trf!(
+            "限速: 每秒最多 {} 个连接, 预计至少需要 {} 秒",
+            config.rate_limit,
+            total_scans.div_ceil(config.rate_limit as u64)
+        ))
+        .await;
+    }
+
+    // 使用原子计数器来跟踪进度和结果
+    let scanned = Arc::new(AtomicUsize::new(0)); // 已完成全部端口探测的IP数
+    let open_ports = Arc::new(AtomicUsize::new(0));
+    let is_cancelled = Arc::new(AtomicBool::new(false));
+    let last_progress = Arc::new(AtomicUsize::new(0)); // 最近一次记录的进度百分比
+
+    // 确定线程数量 - 根据IP数量和系统CPU核心数动态调整
+    let cpu_cores = num_cpus::get();
+    let total_ips_usize = total_ips as usize;
+    let batch_size = std::cmp::max(1, total_ips_usize / cpu_cores);
+
+    // 记录使用的线程数
+    let thread_count = std::cmp::min(total_ips_usize, cpu_cores);
+    detail(trf!("使用 {} 个线程进行扫描", thread_count)).await;
+
+    // 创建任务集合
+    let mut tasks = Vec::new();
+    let config = Arc::new(config);
+
+    // 分批处理IP地址
+    for batch_start in (start..=end).step_by(batch_size) {
+        let batch_end = std::cmp::min(batch_start.saturating_add(batch_size as u32 - 1), end);
+
+        // 克隆所有需要的引用
+        let config = Arc::clone(&config);
+        let events = events.clone();
+        let is_scanning = Arc::clone(&is_scanning);
+        let scanned = Arc::clone(&scanned);
+        let open_ports = Arc::clone(&open_ports);
+        let is_cancelled = Arc::clone(&is_cancelled);
+        let last_progress = Arc::clone(&last_progress);
+        let limits = Arc::clone(&limits);
+
+        // 创建异步任务
+        let task = task::spawn(async move {
+            for ip_num in batch_start..=batch_end {
+                // 检查是否取消扫描
+                if !is_scanning.load(Ordering::Relaxed) || is_cancelled.load(Ordering::Relaxed) {
+                    is_cancelled.store(true, Ordering::Relaxed);
+                    break;
                 }
-            });
 
-            tasks.push(task);
-        }
+                // 跳过排除列表中的地址
+                if is_excluded(ip_num, &config.excludes) {
+                    continue;
+                }
 
-        // 等待所有任务完成
-        join_all(tasks).await;
+                let ip_str = u32_to_ip(ip_num);
 
-        // 检查是否被取消
-        if is_cancelled.load(Ordering::Relaxed) {
-            let cancel_msg = "扫描已取消".to_string();
-            scan_logs.lock().unwrap().push((get_timestamp(), cancel_msg));
-        }
+                // 使用优化的端口扫描函数
+                scan_ports(
+                    &ip_str,
+                    &config,
+                    &events,
+                    &open_ports,
+                    &is_scanning,
+                    &is_cancelled,
+                    &limits,
+                )
+                .await;
 
-        // 获取最终计数
-        let final_scanned = scanned.load(Ordering::Relaxed);
-        let final_open_ports = open_ports.load(Ordering::Relaxed);
+                // 中途取消的IP不计入已扫描数量
+                if is_cancelled.load(Ordering::Relaxed) {
+                    break;
+                }
 
-        // 记录扫描完成
-        let complete_msg = format!(
-            "扫描完成. 共扫描 {} 个IP, 发现 {} 个开放端口",
-            final_scanned, final_open_ports
-        );
-        scan_logs.lock().unwrap().push((get_timestamp(), complete_msg));
-    } else {
-        let error_msg = "IP地址格式无效，无法开始扫描".to_string();
-        scan_logs.lock().unwrap().push((get_timestamp(), error_msg));
+                // 在IP扫描完成后更新进度，多个任务并发时由 compare_exchange 保证每个百分点只记录一次
+                let current_scanned = scanned.fetch_add(1, Ordering::Relaxed) + 1;
+                let progress_percent = (current_scanned * 100) / total_ips_usize;
+                let last = last_progress.load(Ordering::Relaxed);
+                if progress_percent > last
+                    && last_progress
+                        .compare_exchange(last, progress_percent, Ordering::Relaxed, Ordering::Relaxed)
+                        .is_ok()
+                {
+                    let progress_msg = trf!(
+                        "扫描进度: {}/{} ({}%)",
+                        current_scanned, total_ips_usize, progress_percent
+                    );
+                    let _ = events.send(ScanEvent::Detail(progress_msg)).await;
+                }
+            }
+        });
+
+        tasks.push(task);
+    }
+
+    // 等待所有任务完成
+    join_all(tasks).await;
+
+    // 获取最终计数
+    let _ = events
+        .send(ScanEvent::Finished(ScanSummary {
+            scanned_ips: scanned.load(Ordering::Relaxed),
+            open_ports: open_ports.load(Ordering::Relaxed),
+            cancelled: is_cancelled.load(Ordering::Relaxed),
+            saturated_waits: limits.saturated_waits.load(Ordering::Relaxed),
+            failures: sorted_failures(&limits),
+        }))
+        .await;
+}
+
+// 失败原因按次数从多到少排列
+fn sorted_failures(limits: &ScanLimits) -> Vec<(FailureReason, usize)> {
+    let mut failures: Vec<_> = lock_or_recover(&limits.failures)
+        .iter()
+        .map(|(&reason, &count)| (reason, count))
+        .collect();
+    failures.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    failures
+}
+
+// 执行IP扫描 - 将扫描事件写入界面使用的共享列表
+// is_scanning 由界面持有，界面将其置为 false 即取消扫描；扫描结束时这里会将其置为 false
+pub async fn scan_ip_range(
+    config: ScanConfig,
+    _messages: SharedMessages,
+    scan_results: Arc<Mutex<Vec<ScanResult>>>,
+    scan_logs: Arc<Mutex<Vec<(String, String)>>>,
+    is_scanning: Arc<AtomicBool>,
+    progress: Arc<Mutex<ScanProgress>>,
+    repaint: Repaint,
+) {
+    // 清空之前的扫描结果和日志
+    lock_or_recover(&scan_results).clear();
+    lock_or_recover(&scan_logs).clear();
+
+    // 重置进度并开始计时
+    let started_at = Instant::now();
+    *lock_or_recover(&progress) = ScanProgress {
+        started_at: Some(started_at),
+        total_probes: total_probes(&config),
+        rate_limit: config.rate_limit,
+        ..Default::default()
+    };
+
+    let verbose_logs = config.verbose_logs;
+    let mut events = start_scan(config, Arc::clone(&is_scanning));
+    while let Some(event) = events.recv().await {
+        repaint.request();
+        match event {
+            ScanEvent::Log(msg) => {
+                push_scan_log(&scan_logs, msg);
+            }
+            ScanEvent::Detail(msg) => {
+                if verbose_logs {
+                    push_scan_log(&scan_logs, msg);
+                }
+            }
+            ScanEvent::Found(result) => {
+                push_scan_log(
+                    &scan_logs,
+                    trf!(
+                        "发现开放端口: {}:{} ({} ms)",
+                        result.ip,
+                        port_label(result.port),
+                        format!("{:.1}", result.latency_ms)
+                    ),
+                );
+                lock_or_recover(&scan_results).push(result);
+            }
+            ScanEvent::Probed(count) => {
+                lock_or_recover(&progress).completed_probes += count;
+            }
+            ScanEvent::Finished(summary) => {
+                let elapsed = started_at.elapsed();
+                {
+                    let mut progress = lock_or_recover(&progress);
+                    progress.finished_elapsed = Some(elapsed);
+                    progress.cancelled = summary.cancelled;
+                }
+
+                if summary.cancelled {
+                    push_scan_log(
+                        &scan_logs,
+                        trf!("扫描已取消, 已进行 {}", format_duration(elapsed)),
+                    );
+                }
+                if !summary.failures.is_empty() {
+                    let reasons: Vec<String> = summary
+                        .failures
+                        .iter()
+                        .map(|&(reason, count)| format!("{} {}", format_reason(reason), count))
+                        .collect();
+                    push_scan_log(&scan_logs, trf!("未开放端口的失败原因: {}", reasons.join(", ")));
+                }
+                if summary.saturated_waits > 0 {
+                    push_scan_log(
+                        &scan_logs,
+                        trf!(
+                            "共有 {} 次探测因并发连接上限排队等待，可适当调高并发数以加快扫描",
+                            summary.saturated_waits
+                        ),
+                    );
+                }
+
+                // 连接过的同网段设备会出现在ARP表中，补充MAC地址
+                let mac_hosts = attach_mac_addresses(&mut lock_or_recover(&scan_results));
+                if mac_hosts > 0 {
+                    push_scan_log(&scan_logs, trf!("已从ARP表获取 {} 台主机的MAC地址", mac_hosts));
+                }
+
+                // 记录扫描完成
+                push_scan_log(
+                    &scan_logs,
+                    trf!(
+                        "扫描完成. 共扫描 {} 个IP, 发现 {} 个开放端口, 耗时 {}",
+                        summary.scanned_ips,
+                        summary.open_ports,
+                        format_duration(elapsed)
+                    ),
+                );
+                break;
+            }
+        }
     }
 
     // 标记扫描已完成
-    *is_scanning.lock().unwrap() = false;
+    is_scanning.store(false, Ordering::Relaxed);
+    repaint.request();
 }
 
-// 将扫描日志保存到文件 - 保留供将来使用
-#[allow(dead_code)]
+// 转义CSV字段：包含逗号、引号或换行时用引号包裹，内部引号加倍
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// 将扫描日志保存为CSV文件，传入 results 时在日志前先写入扫描结果
 pub fn save_scan_logs_to_file(
-    logs: &Vec<(String, String)>,
+    logs: &[(String, String)],
+    results: Option<&[ScanResult]>,
     file_path: &str,
 ) -> Result<(), std::io::Error> {
     use std::fs::File;
-    use std::io::Write;
+    use std::io::{BufWriter, Write};
+
+    let mut file = BufWriter::new(File::create(file_path)?);
 
-    let mut file = File::create(file_path)?;
+    // 写入扫描结果
+    if let Some(results) = results {
+        writeln!(file, "{}", tr("IP,端口,服务,延迟(ms),MAC"))?;
+        for result in results {
+            writeln!(
+                file,
+                "{},{},{},{:.1},{}",
+                csv_field(&result.ip),
+                result.port,
+                service_name(result.port).unwrap_or(""),
+                result.latency_ms,
+                result.mac.as_deref().unwrap_or("")
+            )?;
+        }
+        writeln!(file)?;
+    }
 
     // 写入标题
-    writeln!(file, "时间,日志内容")?;
+    writeln!(file, "{}", tr("时间,日志内容"))?;
 
     // 写入日志内容
     for (timestamp, message) in logs {
-        writeln!(file, "{},{}", timestamp, message)?;
+        writeln!(file, "{},{}", csv_field(timestamp), csv_field(message))?;
     }
 
-    Ok(())
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::source::{connect_error_label, describe_connect_error};
+
+    #[test]
+    fn ip_u32_conversion_roundtrip() {
+        assert_eq!(ip_to_u32("0.0.0.0"), Some(0));
+        assert_eq!(ip_to_u32("255.255.255.255"), Some(u32::MAX));
+        assert_eq!(ip_to_u32("192.168.1.10"), Some(0xC0A8_010A));
+        assert_eq!(u32_to_ip(0), "0.0.0.0");
+        assert_eq!(u32_to_ip(u32::MAX), "255.255.255.255");
+        assert_eq!(u32_to_ip(ip_to_u32("10.20.30.40").unwrap()), "10.20.30.40");
+    }
+
+    #[test]
+    fn ip_to_u32_rejects_malformed() {
+        for ip in ["", "abc", "1.2.3", "1.2.3.4.5", "256.0.0.1", "1.2.3.-4", " 1.2.3.4"] {
+            assert_eq!(ip_to_u32(ip), None, "{:?} 应被拒绝", ip);
+        }
+    }
+
+    // 只填写端口范围，其余为有效值
+    fn ports(start: &str, end: &str, max_ports: u64) -> Result<ScanSpec, String> {
+        parse_scan_spec(&ScanForm {
+            start_ip: "10.0.0.1",
+            end_ip: "10.0.0.1",
+            start_port: start,
+            end_port: end,
+            timeout_ms: "200",
+            max_ips: 1,
+            max_ports,
+        })
+    }
+
+    // 只填写IP范围，其余为有效值
+    fn ips(start: &str, end: &str, max_ips: u64) -> Result<ScanSpec, String> {
+        parse_scan_spec(&ScanForm {
+            start_ip: start,
+            end_ip: end,
+            start_port: "80",
+            end_port: "80",
+            timeout_ms: "200",
+            max_ips,
+            max_ports: 1,
+        })
+    }
+
+    #[test]
+    fn port_range_boundaries() {
+        assert!(ports("80", "80", 1000).is_ok());
+        assert!(ports("65535", "65535", 1000).is_ok());
+        assert!(ports("0", "0", 1000).is_ok());
+
+        // 恰好1000个端口允许，1001个拒绝
+        assert!(ports("1", "1000", 1000).is_ok());
+        assert!(ports("1", "1001", 1000).is_err());
+        assert!(ports("64536", "65535", 1000).is_ok());
+        assert!(ports("64535", "65535", 1000).is_err());
+
+        // 全端口范围不能溢出
+        assert!(ports("0", "65535", 1000).is_err());
+        assert!(ports("0", "65535", DEFAULT_MAX_SCAN_PORTS).is_ok());
+    }
+
+    #[test]
+    fn port_range_errors_describe_the_problem() {
+        assert_eq!(ports("90", "80", 1000).unwrap_err(), "结束端口 80 小于起始端口 90");
+        assert_eq!(ports("1", "1001", 1000).unwrap_err(), "端口数量 1001 超过单次扫描上限 1000");
+        assert_eq!(
+            ports("", "80", 1000).unwrap_err(),
+            "起始端口无效: \"\"，应为 0-65535 之间的整数"
+        );
+        assert!(ports("80", "abc", 1000).unwrap_err().starts_with("结束端口无效: \"abc\""));
+        assert!(ports("-1", "80", 1000).unwrap_err().starts_with("起始端口无效"));
+        assert!(ports("80", "65536", 1000).unwrap_err().starts_with("结束端口无效"));
+    }
+
+    #[test]
+    fn ip_range_boundaries() {
+        assert!(ips("10.0.0.1", "10.0.0.1", 1000).is_ok());
+        assert!(ips("0.0.0.0", "0.0.0.0", 1000).is_ok());
+        assert!(ips("255.255.255.255", "255.255.255.255", 1000).is_ok());
+
+        // 10.0.0.0 - 10.0.3.231 恰好1000个IP
+        assert!(ips("10.0.0.0", "10.0.3.231", 1000).is_ok());
+        assert!(ips("10.0.0.0", "10.0.3.232", 1000).is_err());
+        assert!(ips("255.255.252.24", "255.255.255.255", 1000).is_ok());
+
+        // 全地址范围不能溢出
+        assert!(ips("0.0.0.0", "255.255.255.255", 1000).is_err());
+        assert!(ips("0.0.0.0", "255.255.255.255", u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn ip_range_errors_describe_the_problem() {
+        assert_eq!(
+            ips("10.0.0.2", "10.0.0.1", 1000).unwrap_err(),
+            "结束IP 10.0.0.1 小于起始IP 10.0.0.2"
+        );
+        assert_eq!(
+            ips("10.0.0.0", "10.0.3.232", 1000).unwrap_err(),
+            "IP数量 1001 超过单次扫描上限 1000"
+        );
+        assert_eq!(ips("", "10.0.0.1", 1000).unwrap_err(), "起始IP格式无效: \"\"");
+        assert_eq!(ips("10.0.0.1", "10.0.0", 1000).unwrap_err(), "结束IP格式无效: \"10.0.0\"");
+        assert_eq!(
+            ips("10.0.0.300", "10.0.1.1", 1000).unwrap_err(),
+            "起始IP格式无效: \"10.0.0.300\""
+        );
+    }
+
+    #[test]
+    fn scan_spec_trims_input_and_checks_timeout() {
+        let form = ScanForm {
+            start_ip: " 192.168.1.1",
+            end_ip: "192.168.1.20 ",
+            start_port: " 22",
+            end_port: "80\n",
+            timeout_ms: " 500 ",
+            max_ips: 100,
+            max_ports: 100,
+        };
+        assert_eq!(
+            parse_scan_spec(&form).unwrap(),
+            ScanSpec {
+                start_ip: "192.168.1.1".to_string(),
+                end_ip: "192.168.1.20".to_string(),
+                start_port: 22,
+                end_port: 80,
+                timeout_ms: 500,
+            }
+        );
+
+        // 超出范围的超时时间按边界值扫描
+        let clamped = |timeout_ms| parse_scan_spec(&ScanForm { timeout_ms, ..form }).unwrap().timeout_ms;
+        assert_eq!(clamped("0"), 10);
+        assert_eq!(clamped("600000"), 60_000);
+
+        let form = ScanForm { timeout_ms: "5s", ..form };
+        assert_eq!(parse_scan_spec(&form).unwrap_err(), "超时时间无效: \"5s\"，应为毫秒数");
+    }
+
+    #[test]
+    fn group_results_sorts_hosts_numerically() {
+        let results = [("10.0.0.10", 443), ("10.0.0.9", 22), ("10.0.0.10", 80), ("10.0.0.9", 22)]
+            .iter()
+            .map(|(ip, port)| ScanResult {
+                ip: ip.to_string(),
+                port: *port,
+                latency_ms: 1.0,
+                mac: None,
+            })
+            .collect::<Vec<_>>();
+
+        let grouped = group_results_by_host(&results);
+        let summary = grouped
+            .iter()
+            .map(|host| {
+                let ports = host.results.iter().map(|r| r.port).collect::<Vec<_>>();
+                (host.ip.as_str(), ports)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![("10.0.0.9", vec![22]), ("10.0.0.10", vec![80, 443])]);
+    }
+
+    #[test]
+    fn mac_normalization_and_vendor() {
+        assert_eq!(normalize_mac("b8-27-eb-01-02-03").as_deref(), Some("B8:27:EB:01:02:03"));
+        assert_eq!(normalize_mac("00:00:00:00:00:00"), None);
+        assert_eq!(normalize_mac("ff-ff-ff-ff-ff-ff"), None);
+        assert_eq!(normalize_mac("incomplete"), None);
+        assert_eq!(mac_vendor("B8:27:EB:01:02:03"), Some("Raspberry Pi"));
+        assert_eq!(mac_vendor("12:34:56:78:9A:BC"), None);
+    }
+
+    #[test]
+    fn exclude_list_parsing() {
+        let text = "10.0.0.1\n\n# 网关\n10.0.1.0/24\n 10.0.2.5/32 \n0.0.0.0/0";
+        assert_eq!(
+            parse_exclude_list(text),
+            Ok(vec![
+                (ip_to_u32("10.0.0.1").unwrap(), ip_to_u32("10.0.0.1").unwrap()),
+                (ip_to_u32("10.0.1.0").unwrap(), ip_to_u32("10.0.1.255").unwrap()),
+                (ip_to_u32("10.0.2.5").unwrap(), ip_to_u32("10.0.2.5").unwrap()),
+                (0, u32::MAX),
+            ])
+        );
+
+        assert_eq!(
+            parse_exclude_list("10.0.0.1\n10.0.0.300"),
+            Err("排除列表第 2 行格式无效: 10.0.0.300".to_string())
+        );
+        assert!(parse_exclude_list("10.0.0.0/33").is_err());
+        assert!(parse_exclude_list("10.0.0.0/").is_err());
+    }
+
+    #[test]
+    fn excluded_count_merges_overlaps() {
+        let start = ip_to_u32("10.0.0.0").unwrap();
+        let end = ip_to_u32("10.0.0.255").unwrap();
+        let excludes = parse_exclude_list("10.0.0.0/30\n10.0.0.2\n10.0.0.254/31\n10.0.1.1").unwrap();
+        // 10.0.0.0-3 共4个, 10.0.0.254-255 共2个, 10.0.1.1 不在范围内
+        assert_eq!(excluded_count(start, end, &excludes), 6);
+        assert!(is_excluded(ip_to_u32("10.0.0.2").unwrap(), &excludes));
+        assert!(!is_excluded(ip_to_u32("10.0.0.4").unwrap(), &excludes));
+    }
+
+    #[test]
+    fn csv_field_escaping() {
+        assert_eq!(csv_field("扫描完成"), "扫描完成");
+        assert_eq!(csv_field("共扫描 1 个IP, 发现 2 个开放端口"), "\"共扫描 1 个IP, 发现 2 个开放端口\"");
+        assert_eq!(csv_field("收到 \"abc\""), "\"收到 \"\"abc\"\"\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+
+    // 接受队列已满的监听端口：新的连接握手不会被响应，探测会一直等到超时
+    fn stalled_port(port: u16) -> Option<(socket2::Socket, std::net::TcpStream)> {
+        let addr: std::net::SocketAddr = ([127, 0, 0, 1], port).into();
+        let socket = socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None).ok()?;
+        socket.bind(&addr.into()).ok()?;
+        socket.listen(0).ok()?;
+        let filler = std::net::TcpStream::connect(addr).ok()?;
+        Some((socket, filler))
+    }
+
+    // 对比单个主机时两种分批方式：每批 50 个端口时每个含慢端口的批次都要等到超时，
+    // 按并发上限分批时这些慢端口在同一批内并行等待
+    #[tokio::test]
+    async fn single_host_scan_batches_ports_by_concurrency() {
+        assert_eq!(port_chunk_size(false, 256), PORT_CHUNK_SIZE);
+        assert_eq!(port_chunk_size(true, 256), 256);
+        assert_eq!(port_chunk_size(true, 10), PORT_CHUNK_SIZE);
+
+        // 本地 echo 服务器，扫描范围从它的端口开始
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (mut reader, mut writer) = socket.split();
+                    let _ = tokio::io::copy(&mut reader, &mut writer).await;
+                });
+            }
+        });
+
+        // 在每 50 个端口中放一个慢端口
+        let stalled: Vec<_> = (0..10u16)
+            .filter_map(|i| stalled_port(port.checked_add(25 + i * 50)?))
+            .collect();
+        let config = Arc::new(ScanConfig {
+            start_ip: "127.0.0.1".to_string(),
+            end_ip: "127.0.0.1".to_string(),
+            start_port: port,
+            end_port: port.saturating_add(499),
+            timeout_ms: 200,
+            retries: 0,
+            rate_limit: 0,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            source_addr: None,
+            excludes: Vec::new(),
+            verbose_logs: false,
+        });
+        let total = (config.end_port - config.start_port) as u64 + 1;
+        let mut elapsed = Vec::new();
+        for single_host in [false, true] {
+            let limits = Arc::new(ScanLimits::new(0, config.max_connections, single_host));
+            let (tx, mut rx) = mpsc::channel(SCAN_EVENT_CHANNEL_SIZE);
+            let started_at = Instant::now();
+            scan_ports(
+                "127.0.0.1",
+                &config,
+                &tx,
+                &Arc::new(AtomicUsize::new(0)),
+                &Arc::new(AtomicBool::new(true)),
+                &Arc::new(AtomicBool::new(false)),
+                &limits,
+            )
+            .await;
+            elapsed.push(started_at.elapsed());
+            drop(tx);
+
+            let (mut found, mut probed) = (false, 0);
+            while let Some(event) = rx.recv().await {
+                match event {
+                    ScanEvent::Found(result) if result.port == port => found = true,
+                    ScanEvent::Probed(n) => probed += n,
+                    _ => {}
+                }
+            }
+            assert!(found);
+            assert_eq!(probed, total);
+        }
+        eprintln!(
+            "单个主机扫描 {} 个端口 (其中 {} 个慢端口): 每批 {} 个 {:?}, 每批 {} 个 {:?}",
+            total,
+            stalled.len(),
+            PORT_CHUNK_SIZE,
+            elapsed[0],
+            DEFAULT_MAX_CONNECTIONS,
+            elapsed[1]
+        );
+        if stalled.len() >= 5 {
+            assert!(elapsed[1] < elapsed[0]);
+        }
+    }
+
+    #[tokio::test]
+    async fn closed_port_reports_refusal() {
+        // 绑定后立即释放，得到一个没有监听的本地端口
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let result = check_port("127.0.0.1", port, 1000, None).await;
+        assert!(matches!(result, Err(("连接被拒绝", Some(_)))));
+        assert_eq!(connect_error_label(std::io::ErrorKind::HostUnreachable), Some("主机不可达"));
+        assert_eq!(connect_error_label(std::io::ErrorKind::Other), None);
+    }
+
+    #[test]
+    fn failure_reasons_show_errno() {
+        assert_eq!(format_reason(("地址不可用", Some(99))), "地址不可用 (errno 99)");
+        assert_eq!(format_reason(("连接超时", None)), "连接超时");
+
+        // 非系统错误没有 errno，保留原始错误文本
+        let e = std::io::Error::new(std::io::ErrorKind::AddrNotAvailable, "没有同类型的地址");
+        assert_eq!(describe_connect_error(&e), "地址不可用: 没有同类型的地址");
+    }
+
+    // errno 的取值因平台而异，13 在类 Unix 系统上是 EACCES
+    #[cfg(unix)]
+    #[test]
+    fn describe_connect_error_moves_errno_forward() {
+        // 系统错误的 errno 紧跟分类，不在原始文本中重复
+        let e = std::io::Error::from_raw_os_error(13);
+        let message = describe_connect_error(&e);
+        assert!(message.starts_with("权限不足 (errno 13): "), "{}", message);
+        assert!(!message.contains("os error"), "{}", message);
+    }
+
+    // 限速时即使开头的探测完成得很快，剩余时间也按限速值估算
+    #[test]
+    fn eta_respects_rate_limit() {
+        let mut progress = ScanProgress {
+            started_at: Some(Instant::now() - Duration::from_secs(1)),
+            completed_probes: 100,
+            total_probes: 300,
+            ..Default::default()
+        };
+        let eta = progress.eta().unwrap();
+        assert!(eta <= Duration::from_secs(3), "{:?}", eta);
+
+        progress.rate_limit = 10;
+        let eta = progress.eta().unwrap();
+        assert!(eta >= Duration::from_secs(20), "{:?}", eta);
+
+        progress.finished_elapsed = Some(Duration::from_secs(1));
+        assert_eq!(progress.eta(), None);
+    }
 }
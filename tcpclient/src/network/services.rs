@@ -0,0 +1,253 @@
+// 常见端口到服务名的映射表，按端口升序排列以便二分查找
+const WELL_KNOWN_SERVICES: &[(u16, &str)] = &[
+    (7, "echo"),
+    (9, "discard"),
+    (13, "daytime"),
+    (17, "qotd"),
+    (19, "chargen"),
+    (20, "ftp-data"),
+    (21, "ftp"),
+    (22, "ssh"),
+    (23, "telnet"),
+    (25, "smtp"),
+    (37, "time"),
+    (43, "whois"),
+    (49, "tacacs"),
+    (53, "dns"),
+    (67, "dhcp"),
+    (68, "dhcp-client"),
+    (69, "tftp"),
+    (70, "gopher"),
+    (79, "finger"),
+    (80, "http"),
+    (81, "http-alt"),
+    (82, "xfer"),
+    (88, "kerberos"),
+    (102, "iso-tsap"),
+    (104, "dicom"),
+    (110, "pop3"),
+    (111, "rpcbind"),
+    (113, "ident"),
+    (119, "nntp"),
+    (123, "ntp"),
+    (135, "msrpc"),
+    (137, "netbios-ns"),
+    (138, "netbios-dgm"),
+    (139, "netbios-ssn"),
+    (143, "imap"),
+    (161, "snmp"),
+    (162, "snmptrap"),
+    (177, "xdmcp"),
+    (179, "bgp"),
+    (194, "irc"),
+    (199, "smux"),
+    (201, "appletalk"),
+    (264, "bgmp"),
+    (318, "tsp"),
+    (389, "ldap"),
+    (427, "svrloc"),
+    (443, "https"),
+    (444, "snpp"),
+    (445, "microsoft-ds"),
+    (464, "kpasswd"),
+    (465, "smtps"),
+    (497, "retrospect"),
+    (500, "isakmp"),
+    (502, "modbus"),
+    (512, "exec"),
+    (513, "login"),
+    (514, "shell"),
+    (515, "printer"),
+    (520, "rip"),
+    (523, "ibm-db2"),
+    (540, "uucp"),
+    (543, "klogin"),
+    (544, "kshell"),
+    (548, "afp"),
+    (554, "rtsp"),
+    (563, "nntps"),
+    (587, "submission"),
+    (593, "http-rpc-epmap"),
+    (623, "ipmi"),
+    (631, "ipp"),
+    (636, "ldaps"),
+    (646, "ldp"),
+    (660, "mac-srv-admin"),
+    (666, "doom"),
+    (873, "rsync"),
+    (902, "vmware-auth"),
+    (989, "ftps-data"),
+    (990, "ftps"),
+    (992, "telnets"),
+    (993, "imaps"),
+    (995, "pop3s"),
+    (1025, "nfs-or-iis"),
+    (1080, "socks"),
+    (1099, "rmiregistry"),
+    (1194, "openvpn"),
+    (1241, "nessus"),
+    (1311, "dell-openmanage"),
+    (1433, "mssql"),
+    (1434, "mssql-monitor"),
+    (1521, "oracle"),
+    (1583, "pervasive-sql"),
+    (1701, "l2tp"),
+    (1723, "pptp"),
+    (1741, "ciscoworks"),
+    (1812, "radius"),
+    (1813, "radius-acct"),
+    (1883, "mqtt"),
+    (1900, "upnp"),
+    (2000, "cisco-sccp"),
+    (2049, "nfs"),
+    (2082, "cpanel"),
+    (2083, "cpanel-ssl"),
+    (2086, "whm"),
+    (2087, "whm-ssl"),
+    (2121, "ftp-proxy"),
+    (2181, "zookeeper"),
+    (2222, "ssh-alt"),
+    (2375, "docker"),
+    (2376, "docker-tls"),
+    (2379, "etcd"),
+    (2380, "etcd-peer"),
+    (2404, "iec-104"),
+    (2483, "oracle-db"),
+    (2484, "oracle-db-ssl"),
+    (3000, "http-dev"),
+    (3050, "firebird"),
+    (3128, "squid"),
+    (3260, "iscsi"),
+    (3268, "globalcatalog"),
+    (3269, "globalcatalog-ssl"),
+    (3306, "mysql"),
+    (3389, "rdp"),
+    (3478, "stun"),
+    (3690, "svn"),
+    (3724, "blizzard"),
+    (4369, "epmd"),
+    (4443, "https-alt"),
+    (4444, "krb524"),
+    (4500, "ipsec-nat-t"),
+    (4505, "salt-publisher"),
+    (4506, "salt-request"),
+    (4567, "tram"),
+    (4711, "mcafee"),
+    (4840, "opc-ua"),
+    (4848, "glassfish"),
+    (5000, "upnp-alt"),
+    (5001, "commplex-link"),
+    (5004, "rtp"),
+    (5060, "sip"),
+    (5061, "sips"),
+    (5222, "xmpp-client"),
+    (5269, "xmpp-server"),
+    (5353, "mdns"),
+    (5355, "llmnr"),
+    (5432, "postgresql"),
+    (5555, "adb"),
+    (5601, "kibana"),
+    (5631, "pcanywhere"),
+    (5666, "nrpe"),
+    (5672, "amqp"),
+    (5683, "coap"),
+    (5800, "vnc-http"),
+    (5900, "vnc"),
+    (5938, "teamviewer"),
+    (5984, "couchdb"),
+    (5985, "winrm"),
+    (5986, "winrm-https"),
+    (6000, "x11"),
+    (6379, "redis"),
+    (6443, "kubernetes-api"),
+    (6514, "syslog-tls"),
+    (6566, "sane"),
+    (6660, "irc-alt"),
+    (6667, "irc"),
+    (6881, "bittorrent"),
+    (7001, "weblogic"),
+    (7077, "spark"),
+    (7474, "neo4j"),
+    (7547, "cwmp"),
+    (7777, "cbt"),
+    (8000, "http-alt"),
+    (8008, "http-alt"),
+    (8009, "ajp13"),
+    (8080, "http-proxy"),
+    (8081, "http-alt"),
+    (8086, "influxdb"),
+    (8088, "radan-http"),
+    (8443, "https-alt"),
+    (8500, "consul"),
+    (8554, "rtsp-alt"),
+    (8649, "ganglia"),
+    (8834, "nessus-web"),
+    (8883, "mqtt-tls"),
+    (8888, "sun-answerbook"),
+    (9000, "cslistener"),
+    (9042, "cassandra"),
+    (9090, "websm"),
+    (9092, "kafka"),
+    (9100, "jetdirect"),
+    (9200, "elasticsearch"),
+    (9300, "elasticsearch-node"),
+    (9418, "git"),
+    (9443, "https-alt"),
+    (9999, "abyss"),
+    (10000, "webmin"),
+    (10250, "kubelet"),
+    (11211, "memcached"),
+    (15672, "rabbitmq-mgmt"),
+    (20000, "dnp3"),
+    (25565, "minecraft"),
+    (27017, "mongodb"),
+    (27018, "mongodb-shard"),
+    (28017, "mongodb-web"),
+    (32400, "plex"),
+    (47808, "bacnet"),
+    (50000, "sap"),
+    (50070, "hdfs-namenode"),
+];
+
+// 查找端口对应的常见服务名，没有匹配时返回None
+pub fn service_name(port: u16) -> Option<&'static str> {
+    WELL_KNOWN_SERVICES
+        .binary_search_by_key(&port, |&(p, _)| p)
+        .ok()
+        .map(|index| WELL_KNOWN_SERVICES[index].1)
+}
+
+// 端口的显示文本，有服务名时附加在括号中，例如 "3306 (mysql)"
+pub fn port_label(port: u16) -> String {
+    match service_name(port) {
+        Some(name) => format!("{} ({})", port, name),
+        None => port.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_is_sorted_and_unique() {
+        assert!(WELL_KNOWN_SERVICES.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn service_name_lookup() {
+        assert_eq!(service_name(22), Some("ssh"));
+        assert_eq!(service_name(3306), Some("mysql"));
+        assert_eq!(service_name(7), Some("echo"));
+        assert_eq!(service_name(50070), Some("hdfs-namenode"));
+        assert_eq!(service_name(0), None);
+        assert_eq!(service_name(12345), None);
+        assert_eq!(service_name(u16::MAX), None);
+    }
+
+    #[test]
+    fn port_label_appends_service() {
+        assert_eq!(port_label(3306), "3306 (mysql)");
+        assert_eq!(port_label(12345), "12345");
+    }
+}
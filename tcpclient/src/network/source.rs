@@ -0,0 +1,93 @@
+use crate::i18n::{tr, trf};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use tokio::net::{lookup_host, TcpSocket, TcpStream};
+
+// 解析源地址设置，留空表示使用系统默认路由
+// 通过尝试绑定该地址确认它属于本机的某个网络接口
+pub fn parse_source_addr(source: &str) -> Result<Option<IpAddr>, String> {
+    let source = source.trim();
+    if source.is_empty() {
+        return Ok(None);
+    }
+
+    let ip: IpAddr = source
+        .parse()
+        .map_err(|_| trf!("源地址格式无效: {}", source))?;
+    UdpSocket::bind(SocketAddr::new(ip, 0))
+        .map_err(|e| trf!("源地址 {} 不属于本机网络接口: {}", ip, e))?;
+    Ok(Some(ip))
+}
+
+// 连接失败原因的分类，连接和扫描共用，未单独分类的错误返回 None
+pub fn connect_error_label(kind: io::ErrorKind) -> Option<&'static str> {
+    let label = match kind {
+        io::ErrorKind::ConnectionRefused => tr("连接被拒绝"),
+        io::ErrorKind::TimedOut => tr("连接超时"),
+        io::ErrorKind::HostUnreachable => tr("主机不可达"),
+        io::ErrorKind::NetworkUnreachable => tr("网络不可达"),
+        io::ErrorKind::ConnectionReset => tr("连接被重置"),
+        io::ErrorKind::ConnectionAborted => tr("连接被中止"),
+        io::ErrorKind::AddrNotAvailable => tr("地址不可用"),
+        io::ErrorKind::PermissionDenied => tr("权限不足"),
+        _ => return None,
+    };
+    Some(label)
+}
+
+// 扫描中一次探测失败的原因：分类和操作系统返回的 errno (超时或非系统错误时为 None)
+// 同一分类下可能有多种 errno，例如源地址问题导致的 EADDRNOTAVAIL 与防火墙导致的 EACCES
+pub type FailureReason = (&'static str, Option<i32>);
+
+pub fn failure_reason(e: &io::Error) -> FailureReason {
+    (connect_error_label(e.kind()).unwrap_or(tr("其他错误")), e.raw_os_error())
+}
+
+// 失败分类的显示文本，errno 作为括号中的补充信息
+pub fn format_reason((label, errno): FailureReason) -> String {
+    match errno {
+        Some(code) => format!("{} (errno {})", label, code),
+        None => label.to_string(),
+    }
+}
+
+// 连接失败时显示的消息：分类和 errno 在前，原始错误在后
+pub fn describe_connect_error(e: &io::Error) -> String {
+    let label = connect_error_label(e.kind()).unwrap_or(tr("连接失败"));
+    let detail = e.to_string();
+    // 系统错误的文本以 " (os error N)" 结尾，errno 已经显示在分类后面
+    let detail = match e.raw_os_error() {
+        Some(code) => detail
+            .trim_end_matches(&format!(" (os error {})", code))
+            .to_string(),
+        None => detail,
+    };
+    format!("{}: {}", format_reason((label, e.raw_os_error())), detail)
+}
+
+// 建立TCP连接，指定源地址时先用 socket2 绑定到该地址再发起连接
+pub async fn connect_tcp(addr: &str, source: Option<IpAddr>) -> io::Result<TcpStream> {
+    let source = match source {
+        Some(source) => source,
+        None => return TcpStream::connect(addr).await,
+    };
+
+    // 只连接与源地址同一地址族的目标地址
+    let target = lookup_host(addr)
+        .await?
+        .find(|target| target.is_ipv4() == source.is_ipv4())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::AddrNotAvailable,
+                trf!("{} 没有与源地址 {} 同类型的地址", addr, source),
+            )
+        })?;
+
+    let socket = Socket::new(Domain::for_address(target), Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&SocketAddr::new(source, 0).into())?;
+
+    let socket = TcpSocket::from_std_stream(socket.into());
+    socket.connect(target).await
+}
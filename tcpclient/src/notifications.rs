@@ -0,0 +1,70 @@
+use crate::i18n::tr;
+use crate::utils::lock_or_recover;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// 通知在屏幕上停留的时间
+pub const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+// 待显示通知的上限，界面长时间未刷新时丢弃最旧的通知
+const MAX_PENDING_NOTIFICATIONS: usize = 32;
+
+// 通知类别，用户可以按类别屏蔽
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NotificationKind {
+    Connection, // 连接建立与断开
+    Scan,       // 扫描完成或取消
+    Error,      // 连接失败、发送失败等错误
+    Trigger,    // 收到的数据命中触发器
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 4] = [
+        NotificationKind::Connection,
+        NotificationKind::Scan,
+        NotificationKind::Error,
+        NotificationKind::Trigger,
+    ];
+
+    // 设置菜单中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationKind::Connection => tr("连接状态"),
+            NotificationKind::Scan => tr("扫描完成"),
+            NotificationKind::Error => tr("错误"),
+            NotificationKind::Trigger => tr("触发器"),
+        }
+    }
+}
+
+// 一条通知
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub text: String,
+    pub created_at: Instant,
+}
+
+impl Notification {
+    // 是否已超过显示时间
+    pub fn is_expired(&self) -> bool {
+        self.created_at.elapsed() >= TOAST_DURATION
+    }
+}
+
+// 网络任务与界面共享的通知队列，界面每帧取出并显示
+pub type NotificationQueue = Arc<Mutex<VecDeque<Notification>>>;
+
+// 向队列中添加一条通知
+pub fn notify(queue: &NotificationQueue, kind: NotificationKind, text: impl Into<String>) {
+    let mut queue = lock_or_recover(queue);
+    queue.push_back(Notification {
+        kind,
+        text: text.into(),
+        created_at: Instant::now(),
+    });
+    while queue.len() > MAX_PENDING_NOTIFICATIONS {
+        queue.pop_front();
+    }
+}
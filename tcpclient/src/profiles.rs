@@ -0,0 +1,114 @@
+use crate::app::{EncodingMode, SessionTag};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// 连接配置文件保存位置
+const PROFILES_FILE: &str = "data/connection_profiles.json";
+
+// 命名的连接配置，选中后填充连接设置面板的所有字段
+// 缺少的字段按默认值读取，旧版本保存的文件仍可加载
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionProfile {
+    pub name: String,
+    pub ip: String,
+    pub port: String,
+    pub encoding: EncodingMode,
+    pub source_addr: String, // 留空表示默认路由
+    pub idle_timeout_secs: u64,
+    pub disconnect_on_idle: bool,
+    pub keepalive_secs: u64, // 0 表示不发送心跳
+    pub keepalive_payload: String,
+    pub keepalive_mode: EncodingMode,
+    pub tag: SessionTag, // 选中配置时一并恢复的会话标签
+}
+
+impl Default for ConnectionProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            ip: "127.0.0.1".to_string(),
+            port: "8888".to_string(),
+            encoding: EncodingMode::default(),
+            source_addr: String::new(),
+            idle_timeout_secs: 0,
+            disconnect_on_idle: false,
+            keepalive_secs: 0,
+            keepalive_payload: String::new(),
+            keepalive_mode: EncodingMode::Utf8,
+            tag: SessionTag::default(),
+        }
+    }
+}
+
+impl ConnectionProfile {
+    // 下拉列表中显示的名称
+    pub fn label(&self) -> String {
+        format!("{} ({}:{})", self.name, self.ip, self.port)
+    }
+}
+
+// 按名称保存配置：同名配置被覆盖，否则追加到末尾；返回配置在列表中的位置
+pub fn upsert_profile(profiles: &mut Vec<ConnectionProfile>, profile: ConnectionProfile) -> usize {
+    match profiles.iter().position(|p| p.name == profile.name) {
+        Some(index) => {
+            profiles[index] = profile;
+            index
+        }
+        None => {
+            profiles.push(profile);
+            profiles.len() - 1
+        }
+    }
+}
+
+// 从文件加载配置，文件不存在或内容损坏时返回空列表
+pub fn load_profiles() -> Vec<ConnectionProfile> {
+    fs::read_to_string(PROFILES_FILE)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// 将配置保存到文件
+pub fn save_profiles(profiles: &[ConnectionProfile]) -> Result<(), std::io::Error> {
+    if let Some(dir) = Path::new(PROFILES_FILE).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let content = serde_json::to_string_pretty(profiles)?;
+    fs::write(PROFILES_FILE, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, port: &str) -> ConnectionProfile {
+        ConnectionProfile {
+            name: name.to_string(),
+            port: port.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn upsert_replaces_profile_with_same_name() {
+        let mut profiles = Vec::new();
+        assert_eq!(upsert_profile(&mut profiles, profile("plc", "502")), 0);
+        assert_eq!(upsert_profile(&mut profiles, profile("gateway", "8080")), 1);
+        assert_eq!(upsert_profile(&mut profiles, profile("plc", "503")), 0);
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].port, "503");
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let profiles: Vec<ConnectionProfile> =
+            serde_json::from_str(r#"[{"name": "plc", "ip": "10.0.0.5", "port": "502"}]"#).unwrap();
+        assert_eq!(profiles[0].encoding, EncodingMode::Utf8);
+        assert_eq!(profiles[0].idle_timeout_secs, 0);
+        assert_eq!(profiles[0].tag, SessionTag::default());
+        assert_eq!(profiles[0].label(), "plc (10.0.0.5:502)");
+    }
+}
@@ -0,0 +1,44 @@
+use eframe::egui;
+use std::time::Duration;
+
+// 后台任务更新共享状态后多久内重绘界面；期间的多次通知合并为一次重绘
+pub const REPAINT_DELAY: Duration = Duration::from_millis(50);
+
+// 网络和扫描任务通知界面重绘的句柄，界面只在有新数据或状态变化时重绘
+// 默认值不关联界面，通知被忽略，用于测试
+#[derive(Clone, Default)]
+pub struct Repaint(Option<egui::Context>);
+
+impl Repaint {
+    pub fn new(ctx: &egui::Context) -> Self {
+        Self(Some(ctx.clone()))
+    }
+
+    // 共享状态已更新，请求在 REPAINT_DELAY 内重绘
+    pub fn request(&self) {
+        if let Some(ctx) = &self.0 {
+            ctx.request_repaint_after(REPAINT_DELAY);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_schedules_repaint_within_delay() {
+        let ctx = egui::Context::default();
+        let delays = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = delays.clone();
+        ctx.set_request_repaint_callback(move |info| recorded.lock().unwrap().push(info.delay));
+
+        Repaint::default().request();
+        assert!(delays.lock().unwrap().is_empty());
+        Repaint::new(&ctx).request();
+        // egui 会减去预计的绘制时间，提前开始重绘
+        let delays = delays.lock().unwrap();
+        assert_eq!(delays.len(), 1);
+        assert!(!delays[0].is_zero() && delays[0] <= REPAINT_DELAY, "{:?}", delays);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::app::EncodingMode;
+use crate::utils::hex_to_bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// 规则文件保存位置
+const RULES_FILE: &str = "data/auto_reply_rules.json";
+
+// 每秒最多触发的自动应答次数，超过后暂停应答以防止死循环
+const MAX_REPLIES_PER_SECOND: usize = 10;
+
+// 自动应答发出后，在此时间内收到相同内容视为对端回显，不再参与规则匹配
+const ECHO_WINDOW: Duration = Duration::from_secs(2);
+
+// 自动应答规则：收到的数据匹配 pattern 时回复 reply
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AutoReplyRule {
+    pub enabled: bool,
+    pub match_mode: EncodingMode, // 匹配内容的格式 (文本子串或十六进制字节序列)
+    pub pattern: String,
+    pub reply_mode: EncodingMode, // 应答内容的格式
+    pub reply: String,
+}
+
+impl Default for AutoReplyRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            match_mode: EncodingMode::Utf8,
+            pattern: String::new(),
+            reply_mode: EncodingMode::Utf8,
+            reply: String::new(),
+        }
+    }
+}
+
+impl AutoReplyRule {
+    // 将匹配内容转换为字节序列
+    fn pattern_bytes(&self) -> Vec<u8> {
+        match self.match_mode {
+            EncodingMode::Utf8 => self.pattern.as_bytes().to_vec(),
+            EncodingMode::Hex => hex_to_bytes(&self.pattern),
+        }
+    }
+
+    // 将应答内容转换为实际发送的字节序列
+    fn reply_bytes(&self) -> Vec<u8> {
+        match self.reply_mode {
+            EncodingMode::Utf8 => self.reply.as_bytes().to_vec(),
+            EncodingMode::Hex => hex_to_bytes(&self.reply),
+        }
+    }
+
+    // 判断收到的数据是否命中该规则
+    pub fn matches(&self, data: &[u8]) -> bool {
+        self.enabled && contains_bytes(data, &self.pattern_bytes())
+    }
+}
+
+// 判断 haystack 中是否包含 needle，空的 needle 不匹配任何数据
+fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
+    !needle.is_empty() && haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+// 查找第一条命中的规则
+pub fn find_matching_rule<'a>(rules: &'a [AutoReplyRule], data: &[u8]) -> Option<&'a AutoReplyRule> {
+    rules.iter().find(|rule| rule.matches(data))
+}
+
+// 自动应答防护：识别对端回显的应答并限制触发频率，避免规则之间互相触发形成死循环
+pub struct AutoReplyGuard {
+    recent_replies: VecDeque<(Instant, Vec<u8>)>,
+    is_suspended: bool,
+}
+
+impl AutoReplyGuard {
+    pub fn new() -> Self {
+        Self {
+            recent_replies: VecDeque::new(),
+            is_suspended: false,
+        }
+    }
+
+    // 清理过期的应答记录
+    fn prune(&mut self, now: Instant) {
+        while let Some((time, _)) = self.recent_replies.front() {
+            if now.duration_since(*time) > ECHO_WINDOW {
+                self.recent_replies.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    // 收到的数据是否只是自己刚发出的应答
+    pub fn is_own_reply(&mut self, data: &[u8]) -> bool {
+        self.prune(Instant::now());
+        self.recent_replies
+            .iter()
+            .any(|(_, reply)| contains_bytes(data, reply))
+    }
+
+    // 尝试登记一次应答，超过频率上限时返回 false
+    pub fn try_record(&mut self, rule: &AutoReplyRule) -> bool {
+        let now = Instant::now();
+        self.prune(now);
+
+        let last_second = self
+            .recent_replies
+            .iter()
+            .filter(|(time, _)| now.duration_since(*time) <= Duration::from_secs(1))
+            .count();
+        if last_second >= MAX_REPLIES_PER_SECOND {
+            self.is_suspended = true;
+            return false;
+        }
+
+        self.is_suspended = false;
+        self.recent_replies.push_back((now, rule.reply_bytes()));
+        true
+    }
+
+    // 是否刚因频率过高暂停了应答
+    pub fn is_suspended(&self) -> bool {
+        self.is_suspended
+    }
+}
+
+// 从文件加载规则，文件不存在或内容损坏时返回空列表
+pub fn load_rules() -> Vec<AutoReplyRule> {
+    fs::read_to_string(RULES_FILE)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// 将规则保存到文件
+pub fn save_rules(rules: &[AutoReplyRule]) -> Result<(), std::io::Error> {
+    if let Some(dir) = Path::new(RULES_FILE).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let content = serde_json::to_string_pretty(rules)?;
+    fs::write(RULES_FILE, content)
+}
@@ -0,0 +1,164 @@
+use crate::network::scanner::{group_results_by_host, mac_vendor, ScanResult};
+use crate::network::services::service_name;
+use crate::utils::{format_duration, get_file_timestamp};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 扫描历史保存目录
+const SCAN_HISTORY_DIR: &str = "data/scans";
+
+// 最多保留的历史记录数量，超出时删除最旧的记录
+const MAX_SCAN_HISTORY: usize = 50;
+
+// 一次扫描的参数与结果
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScanRecord {
+    pub started_at: String, // 扫描开始时间
+    pub start_ip: String,
+    pub end_ip: String,
+    pub start_port: u16,
+    pub end_port: u16,
+    pub elapsed_ms: u64, // 扫描耗时
+    pub results: Vec<ScanResult>,
+}
+
+impl ScanRecord {
+    // 历史列表中显示的摘要
+    pub fn summary(&self) -> String {
+        format!(
+            "{}  {} - {}  端口 {}-{}  {} 个结果 ({:.1}s)",
+            self.started_at,
+            self.start_ip,
+            self.end_ip,
+            self.start_port,
+            self.end_port,
+            self.results.len(),
+            self.elapsed_ms as f64 / 1000.0
+        )
+    }
+
+    // 便于粘贴到工单或报告中的纯文本扫描报告，开放端口按主机分组并附上服务名
+    pub fn report(&self) -> String {
+        let hosts = group_results_by_host(&self.results);
+        let open_ports: usize = hosts.iter().map(|host| host.results.len()).sum();
+        let ports = if self.start_port == self.end_port {
+            format!("端口 {}", self.start_port)
+        } else {
+            format!("端口 {}-{}", self.start_port, self.end_port)
+        };
+
+        let mut lines = vec![
+            format!("扫描时间: {}", self.started_at),
+            format!("扫描范围: {} - {}, {}", self.start_ip, self.end_ip, ports),
+            format!(
+                "耗时: {}",
+                format_duration(std::time::Duration::from_millis(self.elapsed_ms))
+            ),
+            format!("存活主机: {} 台, 开放端口: {} 个", hosts.len(), open_ports),
+        ];
+        for host in &hosts {
+            lines.push(String::new());
+            let mac = host.mac.as_deref().map(|mac| match mac_vendor(mac) {
+                Some(vendor) => format!(" ({}, {})", mac, vendor),
+                None => format!(" ({})", mac),
+            });
+            lines.push(format!("{}{}", host.ip, mac.unwrap_or_default()));
+            for result in &host.results {
+                lines.push(format!(
+                    "  {:<7} {:<12} {:.1} ms",
+                    format!("{}/tcp", result.port),
+                    service_name(result.port).unwrap_or("-"),
+                    result.latency_ms
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+// 列出历史记录文件，按文件名（即时间）从旧到新排序
+fn history_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(SCAN_HISTORY_DIR) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+    files
+}
+
+// 保存一条扫描记录，返回保存的文件路径
+pub fn save_scan_record(record: &ScanRecord) -> Result<String, std::io::Error> {
+    fs::create_dir_all(SCAN_HISTORY_DIR)?;
+
+    let filepath = Path::new(SCAN_HISTORY_DIR).join(format!("scan_{}.json", get_file_timestamp()));
+    let content = serde_json::to_string_pretty(record)?;
+    fs::write(&filepath, content)?;
+
+    // 超出数量上限时清理最旧的记录
+    let files = history_files();
+    if files.len() > MAX_SCAN_HISTORY {
+        for old_file in &files[..files.len() - MAX_SCAN_HISTORY] {
+            let _ = fs::remove_file(old_file);
+        }
+    }
+
+    Ok(filepath.to_string_lossy().to_string())
+}
+
+// 加载所有历史记录，最新的排在最前面，损坏的文件会被跳过
+pub fn load_scan_history() -> Vec<ScanRecord> {
+    history_files()
+        .iter()
+        .rev()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(ip: &str, port: u16, mac: Option<&str>) -> ScanResult {
+        ScanResult {
+            ip: ip.to_string(),
+            port,
+            latency_ms: 1.25,
+            mac: mac.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn report_groups_ports_by_host() {
+        let record = ScanRecord {
+            started_at: "2024-05-01 10:00:00".to_string(),
+            start_ip: "10.0.0.1".to_string(),
+            end_ip: "10.0.0.20".to_string(),
+            start_port: 1,
+            end_port: 1024,
+            elapsed_ms: 2500,
+            results: vec![
+                result("10.0.0.10", 80, None),
+                result("10.0.0.2", 22, Some("00:50:56:aa:bb:cc")),
+                result("10.0.0.10", 40000, None),
+            ],
+        };
+        let expected = "\
+扫描时间: 2024-05-01 10:00:00
+扫描范围: 10.0.0.1 - 10.0.0.20, 端口 1-1024
+耗时: 2.5秒
+存活主机: 2 台, 开放端口: 3 个
+
+10.0.0.2 (00:50:56:aa:bb:cc, VMware)
+  22/tcp  ssh          1.2 ms
+
+10.0.0.10
+  80/tcp  http         1.2 ms
+  40000/tcp -            1.2 ms";
+        assert_eq!(record.report(), expected);
+    }
+}
@@ -0,0 +1,126 @@
+use std::ops::Range;
+
+// 日志搜索状态：关键字、选项和当前定位到的匹配条目
+#[derive(Default)]
+pub struct LogSearch {
+    pub query: String,
+    pub case_sensitive: bool, // 默认不区分大小写
+    pub filter: bool,         // 只显示匹配的条目，否则只高亮
+    pub current: usize,       // 当前定位的匹配条目序号（在所有匹配条目中）
+    pub scroll_to_current: bool, // 下一帧滚动到当前匹配条目
+    pub focus_requested: bool,   // 下一帧把输入焦点移到搜索框
+}
+
+impl LogSearch {
+    // 输入了关键字时才进行搜索
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    // 条目是否包含关键字
+    pub fn matches(&self, text: &str) -> bool {
+        self.is_active() && self.next_match(text, 0).is_some()
+    }
+
+    // 关键字在文本中的所有位置（字节范围，互不重叠），用于高亮
+    pub fn find(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        if !self.is_active() {
+            return ranges;
+        }
+        let mut from = 0;
+        while let Some(range) = self.next_match(text, from) {
+            from = range.end;
+            ranges.push(range);
+        }
+        ranges
+    }
+
+    // 从 from 开始查找下一处匹配；逐字符比较，保证返回的范围落在字符边界上
+    fn next_match(&self, text: &str, from: usize) -> Option<Range<usize>> {
+        text[from..].char_indices().find_map(|(offset, _)| {
+            let start = from + offset;
+            let mut rest = text[start..].chars();
+            let mut len = 0;
+            for q in self.query.chars() {
+                let c = rest.next()?;
+                if !self.chars_equal(c, q) {
+                    return None;
+                }
+                len += c.len_utf8();
+            }
+            Some(start..start + len)
+        })
+    }
+
+    fn chars_equal(&self, a: char, b: char) -> bool {
+        a == b || (!self.case_sensitive && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    // 定位到下一个（forward 为 true）或上一个匹配条目，到头后循环
+    pub fn step(&mut self, match_count: usize, forward: bool) {
+        if match_count == 0 {
+            return;
+        }
+        self.current = if forward {
+            (self.current + 1) % match_count
+        } else {
+            (self.current + match_count - 1) % match_count
+        };
+        self.scroll_to_current = true;
+    }
+
+    // 关键字改变后从第一个匹配条目重新开始
+    pub fn reset(&mut self) {
+        self.current = 0;
+        self.scroll_to_current = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(query: &str) -> LogSearch {
+        LogSearch {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matching_is_case_insensitive_by_default() {
+        let mut s = search("open");
+        assert!(s.matches("192.168.1.10:22 OPEN (ssh)"));
+        assert_eq!(s.find("Open open OPEN"), vec![0..4, 5..9, 10..14]);
+
+        s.case_sensitive = true;
+        assert!(!s.matches("192.168.1.10:22 OPEN (ssh)"));
+        assert_eq!(s.find("Open open OPEN"), vec![5..9]);
+    }
+
+    #[test]
+    fn ranges_fall_on_char_boundaries() {
+        let s = search("端口");
+        let text = "扫描完成, 发现 3 个开放端口";
+        let ranges = s.find(text);
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(&text[ranges[0].clone()], "端口");
+
+        // 匹配不重叠
+        assert_eq!(search("aa").find("aaaa"), vec![0..2, 2..4]);
+        assert!(search("").find("anything").is_empty());
+        assert!(!search("").matches("anything"));
+    }
+
+    #[test]
+    fn step_wraps_around() {
+        let mut s = search("x");
+        s.step(3, false);
+        assert_eq!(s.current, 2);
+        s.step(3, true);
+        assert_eq!(s.current, 0);
+        s.step(0, true);
+        assert_eq!(s.current, 0);
+    }
+}
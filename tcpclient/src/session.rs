@@ -0,0 +1,138 @@
+use crate::app::EncodingMode;
+use crate::message::Message;
+use crate::utils::{get_datetime, get_file_timestamp, lock_or_recover};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+// 会话录制保存目录
+const SESSION_DIR: &str = "data/sessions";
+
+// 录制的一次发送，offset_ms 为相对录制开始的时间
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedSend {
+    pub offset_ms: u64,
+    pub mode: EncodingMode,
+    pub data: String,
+}
+
+// 一次录制的会话，可保存为JSON并在之后回放
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionRecording {
+    pub recorded_at: String, // 录制开始时间
+    pub target: String,      // 录制时连接的地址，仅供参考
+    pub sends: Vec<RecordedSend>,
+}
+
+impl SessionRecording {
+    // 列表中显示的摘要
+    pub fn summary(&self) -> String {
+        let duration_ms = self.sends.last().map_or(0, |send| send.offset_ms);
+        format!(
+            "{}  {}  {} 条 ({:.1}s)",
+            self.recorded_at,
+            self.target,
+            self.sends.len(),
+            duration_ms as f64 / 1000.0
+        )
+    }
+}
+
+// 录制器：记录每次发送及其相对时间
+pub struct SessionRecorder {
+    started: Instant,
+    recording: SessionRecording,
+}
+
+impl SessionRecorder {
+    pub fn new(target: String) -> Self {
+        Self {
+            started: Instant::now(),
+            recording: SessionRecording {
+                recorded_at: get_datetime(),
+                target,
+                sends: Vec::new(),
+            },
+        }
+    }
+
+    // 记录一次发送
+    pub fn record(&mut self, data: &str, mode: EncodingMode) {
+        self.recording.sends.push(RecordedSend {
+            offset_ms: self.started.elapsed().as_millis() as u64,
+            mode,
+            data: data.to_string(),
+        });
+    }
+
+    // 已录制的发送条数
+    pub fn send_count(&self) -> usize {
+        self.recording.sends.len()
+    }
+
+    // 结束录制
+    pub fn finish(self) -> SessionRecording {
+        self.recording
+    }
+}
+
+// 列出已保存的会话文件，按文件名（即时间）从新到旧排序
+fn session_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(SESSION_DIR) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+    files.reverse();
+    files
+}
+
+// 保存录制的会话，返回保存的文件路径
+pub fn save_session(recording: &SessionRecording) -> Result<String, std::io::Error> {
+    fs::create_dir_all(SESSION_DIR)?;
+
+    let filepath = Path::new(SESSION_DIR).join(format!("session_{}.json", get_file_timestamp()));
+    let content = serde_json::to_string_pretty(recording)?;
+    fs::write(&filepath, content)?;
+    Ok(filepath.to_string_lossy().to_string())
+}
+
+// 加载所有已保存的会话，最新的排在最前面，损坏的文件会被跳过
+pub fn load_sessions() -> Vec<SessionRecording> {
+    session_files()
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect()
+}
+
+// 按原始的时间间隔将录制的发送重新放入发送通道，is_replaying 被置为 false 时停止
+pub fn spawn_replay(
+    recording: SessionRecording,
+    tx: mpsc::Sender<Message>,
+    is_replaying: Arc<Mutex<bool>>,
+) {
+    *lock_or_recover(&is_replaying) = true;
+    tokio::spawn(async move {
+        // 从第一条发送开始计时，只保留各条之间的间隔
+        let started = tokio::time::Instant::now();
+        let first_offset = recording.sends.first().map_or(0, |send| send.offset_ms);
+        for send in recording.sends {
+            let delay = Duration::from_millis(send.offset_ms - first_offset);
+            tokio::time::sleep_until(started + delay).await;
+            if !*lock_or_recover(&is_replaying) {
+                break;
+            }
+            if tx.send(Message::Send(send.data, send.mode)).await.is_err() {
+                break;
+            }
+        }
+        *lock_or_recover(&is_replaying) = false;
+    });
+}
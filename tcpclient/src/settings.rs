@@ -0,0 +1,215 @@
+use crate::app::{DisplayMode, EncodingMode, SessionTag, TcpClientApp};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 配置目录下的设置文件名
+const SETTINGS_FILE: &str = "settings.json";
+
+// 退出时保存、启动时恢复的客户端设置
+// 缺少的字段按默认值读取，旧版本保存的文件仍可加载
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientSettings {
+    // 连接设置
+    pub ip: String,
+    pub port: String,
+    pub encoding: EncodingMode,
+    pub display_mode: DisplayMode,
+    pub max_display_len: usize,
+    pub source_addr: String,
+    pub listen_mode: bool,
+    pub session_tag: SessionTag,
+    pub clear_after_send: bool,
+    pub idle_timeout_secs: u64,
+    pub disconnect_on_idle: bool,
+    pub keepalive_secs: u64,
+    pub keepalive_payload: String,
+    pub keepalive_mode: EncodingMode,
+    pub disconnect_desktop_notify: bool, // 连接意外断开时发送桌面通知
+
+    // 扫描设置
+    pub start_ip: String,
+    pub end_ip: String,
+    pub start_port: String,
+    pub end_port: String,
+    pub timeout_ms: String,
+    pub scan_excludes: String,
+    pub scan_retries: u8,
+    pub scan_rate_limit: u32,
+    pub scan_max_connections: usize,
+    pub scan_max_ips: u64,
+    pub scan_max_ports: u64,
+    pub scan_verbose_logs: bool,
+    pub export_results_with_logs: bool,
+}
+
+// 默认值取自界面的初始状态，两处不会出现不一致
+impl Default for ClientSettings {
+    fn default() -> Self {
+        Self::capture(&TcpClientApp::default())
+    }
+}
+
+impl ClientSettings {
+    // 读取界面中的当前设置
+    pub fn capture(app: &TcpClientApp) -> Self {
+        Self {
+            ip: app.ip.clone(),
+            port: app.port.clone(),
+            encoding: app.encoding_mode,
+            display_mode: app.display_mode,
+            max_display_len: app.max_display_len,
+            source_addr: app.source_addr.clone(),
+            listen_mode: app.listen_mode,
+            session_tag: app.session_tag.clone(),
+            clear_after_send: app.clear_after_send,
+            idle_timeout_secs: app.idle_timeout_secs,
+            disconnect_on_idle: app.disconnect_on_idle,
+            keepalive_secs: app.keepalive_secs,
+            keepalive_payload: app.keepalive_payload.clone(),
+            keepalive_mode: app.keepalive_mode,
+            disconnect_desktop_notify: app.disconnect_alerts.is_enabled(),
+            start_ip: app.start_ip.clone(),
+            end_ip: app.end_ip.clone(),
+            start_port: app.start_port.clone(),
+            end_port: app.end_port.clone(),
+            timeout_ms: app.timeout_ms.clone(),
+            scan_excludes: app.scan_excludes.clone(),
+            scan_retries: app.scan_retries,
+            scan_rate_limit: app.scan_rate_limit,
+            scan_max_connections: app.scan_max_connections,
+            scan_max_ips: app.scan_max_ips,
+            scan_max_ports: app.scan_max_ports,
+            scan_verbose_logs: app.scan_verbose_logs,
+            export_results_with_logs: app.export_results_with_logs,
+        }
+    }
+
+    // 填充到界面，编码模式同时同步给接收任务
+    pub fn apply(self, app: &mut TcpClientApp) {
+        app.ip = self.ip;
+        app.port = self.port;
+        app.set_encoding_mode(self.encoding);
+        app.display_mode = self.display_mode;
+        app.max_display_len = self.max_display_len;
+        app.source_addr = self.source_addr;
+        app.listen_mode = self.listen_mode;
+        app.session_tag = self.session_tag;
+        app.clear_after_send = self.clear_after_send;
+        app.idle_timeout_secs = self.idle_timeout_secs;
+        app.disconnect_on_idle = self.disconnect_on_idle;
+        app.keepalive_secs = self.keepalive_secs;
+        app.keepalive_payload = self.keepalive_payload;
+        app.keepalive_mode = self.keepalive_mode;
+        app.disconnect_alerts.set_enabled(self.disconnect_desktop_notify);
+        app.start_ip = self.start_ip;
+        app.end_ip = self.end_ip;
+        app.start_port = self.start_port;
+        app.end_port = self.end_port;
+        app.timeout_ms = self.timeout_ms;
+        app.scan_excludes = self.scan_excludes;
+        app.scan_retries = self.scan_retries;
+        app.scan_rate_limit = self.scan_rate_limit;
+        app.scan_max_connections = self.scan_max_connections;
+        app.scan_max_ips = self.scan_max_ips;
+        app.scan_max_ports = self.scan_max_ports;
+        app.scan_verbose_logs = self.scan_verbose_logs;
+        app.export_results_with_logs = self.export_results_with_logs;
+    }
+}
+
+// 平台惯用的配置目录，如 Linux 下的 ~/.config/tcpclient/settings.json；无法确定主目录时返回 None
+fn settings_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "tcpclient").map(|dirs| dirs.config_dir().join(SETTINGS_FILE))
+}
+
+// 从指定文件加载设置，文件不存在时返回默认设置；内容损坏时用默认设置重建文件，不影响启动
+fn load_settings_from(path: &Path) -> ClientSettings {
+    let Ok(content) = fs::read_to_string(path) else {
+        return ClientSettings::default();
+    };
+    serde_json::from_str(&content).unwrap_or_else(|_| {
+        let settings = ClientSettings::default();
+        let _ = save_settings_to(path, &settings);
+        settings
+    })
+}
+
+fn save_settings_to(path: &Path, settings: &ClientSettings) -> Result<(), std::io::Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let content = serde_json::to_string_pretty(settings)?;
+    fs::write(path, content)
+}
+
+// 加载保存的设置
+pub fn load_settings() -> ClientSettings {
+    settings_path()
+        .map(|path| load_settings_from(&path))
+        .unwrap_or_default()
+}
+
+// 将设置保存到配置文件
+pub fn save_settings(settings: &ClientSettings) -> Result<(), std::io::Error> {
+    match settings_path() {
+        Some(path) => save_settings_to(&path, settings),
+        None => Ok(()),
+    }
+}
+
+// 删除配置文件，文件不存在时视为成功
+pub fn reset_settings() -> Result<(), std::io::Error> {
+    match settings_path().map(fs::remove_file) {
+        Some(Err(e)) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip_through_app() {
+        let mut app = TcpClientApp {
+            ip: "192.168.1.20".to_string(),
+            scan_max_connections: 32,
+            ..Default::default()
+        };
+        app.set_encoding_mode(EncodingMode::Hex);
+
+        let settings = ClientSettings::capture(&app);
+        let json = serde_json::to_string(&settings).unwrap();
+        let mut restored = TcpClientApp::default();
+        serde_json::from_str::<ClientSettings>(&json).unwrap().apply(&mut restored);
+
+        assert_eq!(restored.ip, "192.168.1.20");
+        assert_eq!(restored.display_mode, DisplayMode::Hex);
+        assert_eq!(*restored.shared_encoding_mode.lock().unwrap(), EncodingMode::Hex);
+        assert_eq!(ClientSettings::capture(&restored), settings);
+    }
+
+    #[test]
+    fn corrupt_file_is_rebuilt_with_defaults() {
+        let dir = std::env::temp_dir().join(format!("tcpclient-settings-{}", std::process::id()));
+        let path = dir.join(SETTINGS_FILE);
+        fs::create_dir_all(&dir).unwrap();
+
+        // 缺少的字段使用默认值
+        fs::write(&path, r#"{"ip": "10.0.0.5"}"#).unwrap();
+        let settings = load_settings_from(&path);
+        assert_eq!(settings.ip, "10.0.0.5");
+        assert_eq!(settings.port, ClientSettings::default().port);
+
+        fs::write(&path, "{ not json").unwrap();
+        assert_eq!(load_settings_from(&path), ClientSettings::default());
+        let rebuilt: ClientSettings =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(rebuilt, ClientSettings::default());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
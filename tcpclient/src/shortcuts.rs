@@ -0,0 +1,194 @@
+use crate::i18n::tr;
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// 可绑定快捷键的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ShortcutAction {
+    Send,             // 发送输入框中的内容
+    ClearMessages,    // 清空接收消息
+    ToggleConnection, // 连接或断开，监听模式下为开始或停止监听
+    ConnectionView,   // 切换到连接界面
+    ScanView,         // 切换到扫描界面
+    Search,           // 切换到扫描界面并聚焦日志搜索框
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 6] = [
+        ShortcutAction::Send,
+        ShortcutAction::ClearMessages,
+        ShortcutAction::ToggleConnection,
+        ShortcutAction::ConnectionView,
+        ShortcutAction::ScanView,
+        ShortcutAction::Search,
+    ];
+
+    // 设置窗口中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ShortcutAction::Send => tr("发送"),
+            ShortcutAction::ClearMessages => tr("清空消息"),
+            ShortcutAction::ToggleConnection => tr("连接/断开"),
+            ShortcutAction::ConnectionView => tr("切换到连接界面"),
+            ShortcutAction::ScanView => tr("切换到扫描界面"),
+            ShortcutAction::Search => tr("搜索扫描日志"),
+        }
+    }
+
+    pub fn default_shortcut(&self) -> KeyboardShortcut {
+        let key = match self {
+            ShortcutAction::Send => Key::Enter,
+            ShortcutAction::ClearMessages => Key::L,
+            ShortcutAction::ToggleConnection => Key::K,
+            ShortcutAction::ConnectionView => Key::Num1,
+            ShortcutAction::ScanView => Key::Num2,
+            ShortcutAction::Search => Key::F,
+        };
+        KeyboardShortcut::new(Modifiers::COMMAND, key)
+    }
+
+    // 输入框有焦点时是否仍然生效：Ctrl+K 等在输入框中有编辑功能，
+    // 只有发送和搜索在输入时也响应，发送快捷键本来就是在发送框中使用的
+    pub fn works_while_typing(&self) -> bool {
+        matches!(self, ShortcutAction::Send | ShortcutAction::Search)
+    }
+}
+
+// 修改绑定失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindError {
+    NoModifier,                // 没有 Ctrl/Alt，会与正常输入冲突
+    Reserved,                  // 已用于调整字号
+    Conflict(ShortcutAction),  // 已绑定到其他操作
+}
+
+// 快捷键绑定，由 eframe 保存；只记录修改过的操作，未记录的使用默认绑定，None 表示已禁用
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Shortcuts {
+    bindings: BTreeMap<ShortcutAction, Option<KeyboardShortcut>>,
+}
+
+impl Shortcuts {
+    pub fn get(&self, action: ShortcutAction) -> Option<KeyboardShortcut> {
+        match self.bindings.get(&action) {
+            Some(binding) => *binding,
+            None => Some(action.default_shortcut()),
+        }
+    }
+
+    pub fn disable(&mut self, action: ShortcutAction) {
+        self.bindings.insert(action, None);
+    }
+
+    pub fn reset(&mut self, action: ShortcutAction) {
+        self.bindings.remove(&action);
+    }
+
+    pub fn reset_all(&mut self) {
+        self.bindings.clear();
+    }
+
+    // 修改绑定，必须带 Ctrl/Alt，且不能与字号快捷键或其他操作重复
+    pub fn set(&mut self, action: ShortcutAction, shortcut: KeyboardShortcut) -> Result<(), BindError> {
+        let modifiers = shortcut.modifiers;
+        if !(modifiers.ctrl || modifiers.command || modifiers.mac_cmd || modifiers.alt) {
+            return Err(BindError::NoModifier);
+        }
+        use egui::gui_zoom::kb_shortcuts::{ZOOM_IN, ZOOM_IN_SECONDARY, ZOOM_OUT, ZOOM_RESET};
+        if [ZOOM_IN, ZOOM_IN_SECONDARY, ZOOM_OUT, ZOOM_RESET]
+            .iter()
+            .any(|zoom| same_shortcut(zoom, &shortcut))
+        {
+            return Err(BindError::Reserved);
+        }
+        let conflict = ShortcutAction::ALL
+            .into_iter()
+            .find(|&other| other != action && self.get(other).is_some_and(|bound| same_shortcut(&bound, &shortcut)));
+        if let Some(other) = conflict {
+            return Err(BindError::Conflict(other));
+        }
+        self.bindings.insert(action, Some(shortcut));
+        Ok(())
+    }
+
+    // 本帧按下的快捷键对应的操作，匹配的按键被消耗，不再传给输入框
+    // typing 为 true 表示输入框有焦点，此时只响应 works_while_typing 的操作
+    pub fn take_pressed(&self, input: &mut egui::InputState, typing: bool) -> Vec<ShortcutAction> {
+        // 带 Shift/Alt 的绑定先匹配，避免被只带 Ctrl 的绑定抢先消耗
+        let mut bound: Vec<(ShortcutAction, KeyboardShortcut)> = ShortcutAction::ALL
+            .into_iter()
+            .filter(|action| !typing || action.works_while_typing())
+            .filter_map(|action| self.get(action).map(|shortcut| (action, shortcut)))
+            .collect();
+        bound.sort_by_key(|(_, shortcut)| std::cmp::Reverse(shortcut.modifiers.shift as u8 + shortcut.modifiers.alt as u8));
+        bound
+            .into_iter()
+            .filter(|(_, shortcut)| input.consume_shortcut(shortcut))
+            .map(|(action, _)| action)
+            .collect()
+    }
+}
+
+// Ctrl 与 Command 在非 macOS 平台上等价，按逻辑含义比较
+fn same_shortcut(a: &KeyboardShortcut, b: &KeyboardShortcut) -> bool {
+    a.logical_key == b.logical_key && a.modifiers.matches_exact(b.modifiers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctrl(key: Key) -> KeyboardShortcut {
+        KeyboardShortcut::new(Modifiers::COMMAND, key)
+    }
+
+    #[test]
+    fn bindings_can_be_changed_disabled_and_reset() {
+        let mut shortcuts = Shortcuts::default();
+        assert_eq!(shortcuts.get(ShortcutAction::Send), Some(ctrl(Key::Enter)));
+
+        shortcuts.disable(ShortcutAction::ClearMessages);
+        assert_eq!(shortcuts.get(ShortcutAction::ClearMessages), None);
+        // 已禁用的绑定可以给其他操作使用
+        assert_eq!(shortcuts.set(ShortcutAction::Search, ctrl(Key::L)), Ok(()));
+        assert_eq!(
+            shortcuts.set(ShortcutAction::Send, ctrl(Key::K)),
+            Err(BindError::Conflict(ShortcutAction::ToggleConnection))
+        );
+        assert_eq!(shortcuts.set(ShortcutAction::Send, KeyboardShortcut::new(Modifiers::NONE, Key::S)), Err(BindError::NoModifier));
+        assert_eq!(shortcuts.set(ShortcutAction::Send, ctrl(Key::Num0)), Err(BindError::Reserved));
+
+        // 保存后恢复，未修改的操作继续使用默认绑定
+        let json = serde_json::to_string(&shortcuts).unwrap();
+        let restored: Shortcuts = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, shortcuts);
+        let empty: Shortcuts = serde_json::from_str("{}").unwrap();
+        assert_eq!(empty.get(ShortcutAction::ScanView), Some(ctrl(Key::Num2)));
+
+        shortcuts.reset(ShortcutAction::ClearMessages);
+        assert_eq!(shortcuts.get(ShortcutAction::ClearMessages), Some(ctrl(Key::L)));
+        shortcuts.reset_all();
+        assert_eq!(shortcuts, Shortcuts::default());
+    }
+
+    #[test]
+    fn only_send_and_search_fire_while_typing() {
+        let press = |key: Key| egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: Modifiers::COMMAND,
+        };
+        let shortcuts = Shortcuts::default();
+        let mut input = egui::InputState::default();
+        input.events = vec![press(Key::K), press(Key::Enter)];
+        assert_eq!(shortcuts.take_pressed(&mut input, true), vec![ShortcutAction::Send]);
+        // 输入框中的 Ctrl+K 保留给输入框
+        assert_eq!(input.events.len(), 1);
+        assert_eq!(shortcuts.take_pressed(&mut input, false), vec![ShortcutAction::ToggleConnection]);
+        assert!(input.events.is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::app::EncodingMode;
+use crate::utils::hex_to_bytes;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+// 触发器文件保存位置
+const TRIGGERS_FILE: &str = "data/triggers.json";
+
+// 标记条目的前缀，界面据此找到对应触发器的颜色
+const MARKER_PREFIX: &str = "触发器 #";
+
+// 新建触发器时依次使用的高亮颜色
+const PALETTE: [[u8; 3]; 5] = [
+    [230, 120, 0],
+    [200, 30, 120],
+    [0, 140, 200],
+    [120, 80, 200],
+    [200, 170, 0],
+];
+
+// 触发器：收到的数据包含 pattern 时在消息列表中插入高亮标记并闪烁提醒
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Trigger {
+    pub enabled: bool,
+    pub mode: EncodingMode, // 匹配内容的格式 (文本子串或十六进制字节序列)
+    pub pattern: String,
+    pub color: [u8; 3], // 标记条目和闪烁使用的颜色 (RGB)
+}
+
+impl Trigger {
+    // 按已有触发器数量轮流选取颜色
+    pub fn new(existing: usize) -> Self {
+        Self {
+            enabled: true,
+            mode: EncodingMode::Utf8,
+            pattern: String::new(),
+            color: PALETTE[existing % PALETTE.len()],
+        }
+    }
+
+    // 判断收到的数据是否命中该触发器，空的匹配内容不命中任何数据
+    pub fn matches(&self, data: &[u8]) -> bool {
+        let pattern = match self.mode {
+            EncodingMode::Utf8 => self.pattern.as_bytes().to_vec(),
+            EncodingMode::Hex => hex_to_bytes(&self.pattern),
+        };
+        self.enabled
+            && !pattern.is_empty()
+            && data.windows(pattern.len()).any(|w| w == pattern.as_slice())
+    }
+}
+
+// 最近一次命中，界面据此闪烁
+#[derive(Clone, Copy, Debug)]
+pub struct TriggerHit {
+    pub at: Instant,
+    pub color: [u8; 3],
+}
+
+// 与接收任务共享的触发器列表和最近一次命中
+#[derive(Default)]
+pub struct TriggerSet {
+    pub triggers: Vec<Trigger>,
+    pub last_hit: Option<TriggerHit>,
+}
+
+pub type SharedTriggers = Arc<Mutex<TriggerSet>>;
+
+impl TriggerSet {
+    // 查找所有命中的触发器，返回序号 (从 0 开始)，同时记录最近一次命中
+    pub fn check(&mut self, data: &[u8]) -> Vec<usize> {
+        let hits: Vec<usize> = (0..self.triggers.len())
+            .filter(|&index| self.triggers[index].matches(data))
+            .collect();
+        if let Some(&last) = hits.last() {
+            self.last_hit = Some(TriggerHit {
+                at: Instant::now(),
+                color: self.triggers[last].color,
+            });
+        }
+        hits
+    }
+
+    // 标记条目对应触发器的颜色，不是标记条目或触发器已被删除时返回 None
+    pub fn marker_color(&self, msg: &str) -> Option<[u8; 3]> {
+        parse_marker(msg).and_then(|index| self.triggers.get(index)).map(|t| t.color)
+    }
+}
+
+// 命中时插入消息列表的标记条目
+pub fn marker_text(index: usize, trigger: &Trigger) -> String {
+    format!("{}{} 命中: {}", MARKER_PREFIX, index + 1, trigger.pattern)
+}
+
+// 从标记条目中取出触发器序号 (从 0 开始)
+fn parse_marker(msg: &str) -> Option<usize> {
+    let (number, _) = msg.strip_prefix(MARKER_PREFIX)?.split_once(" 命中:")?;
+    number.parse::<usize>().ok()?.checked_sub(1)
+}
+
+// 从文件加载触发器，文件不存在或内容损坏时返回空列表
+pub fn load_triggers() -> Vec<Trigger> {
+    fs::read_to_string(TRIGGERS_FILE)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// 将触发器保存到文件
+pub fn save_triggers(triggers: &[Trigger]) -> Result<(), std::io::Error> {
+    if let Some(dir) = Path::new(TRIGGERS_FILE).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let content = serde_json::to_string_pretty(triggers)?;
+    fs::write(TRIGGERS_FILE, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(mode: EncodingMode, pattern: &str) -> Trigger {
+        Trigger {
+            mode,
+            pattern: pattern.to_string(),
+            ..Trigger::new(0)
+        }
+    }
+
+    #[test]
+    fn text_and_hex_triggers_match_substrings() {
+        let mut set = TriggerSet {
+            triggers: vec![
+                trigger(EncodingMode::Utf8, "ERROR"),
+                trigger(EncodingMode::Hex, "FF 00"),
+                trigger(EncodingMode::Utf8, ""),
+            ],
+            last_hit: None,
+        };
+        assert_eq!(set.check(b"12:00 ERROR overflow"), vec![0]);
+        assert_eq!(set.check(&[0x01, 0xFF, 0x00, 0x02]), vec![1]);
+        assert!(set.check(b"all good").is_empty());
+
+        set.triggers[0].enabled = false;
+        assert!(set.check(b"ERROR").is_empty());
+    }
+
+    #[test]
+    fn check_records_last_hit() {
+        let mut set = TriggerSet {
+            triggers: vec![trigger(EncodingMode::Utf8, "A"), Trigger::new(1)],
+            last_hit: None,
+        };
+        set.triggers[1].pattern = "B".to_string();
+        assert!(set.check(b"none").is_empty());
+        assert!(set.last_hit.is_none());
+
+        assert_eq!(set.check(b"AB"), vec![0, 1]);
+        assert_eq!(set.last_hit.unwrap().color, PALETTE[1]);
+    }
+
+    #[test]
+    fn marker_maps_back_to_trigger_color() {
+        let set = TriggerSet {
+            triggers: vec![Trigger::new(0), Trigger::new(1)],
+            last_hit: None,
+        };
+        let text = marker_text(1, &set.triggers[1]);
+        assert_eq!(set.marker_color(&text), Some(PALETTE[1]));
+
+        assert_eq!(set.marker_color("触发器 #3 命中: gone"), None);
+        assert_eq!(set.marker_color("触发器 #0 命中: x"), None);
+        assert_eq!(set.marker_color("收到(UTF-8): 触发器 #1 命中: x"), None);
+    }
+}
@@ -1,41 +1,122 @@
-use crate::app::{EncodingMode, TcpClientApp};
-use crate::message::Message;
-use crate::network::scanner::{is_valid_ip, is_valid_ip_range, is_valid_port, is_valid_port_range};
-use crate::ui::styles::{create_message_frame, get_message_background, get_message_color};
+use crate::app::{AppView, BookmarkJump, DisplayMode, EncodingMode, SessionTag, TcpClientApp};
+use crate::batch::{split_batch_lines, BatchProgress};
+use crate::fuzz::{payload_hex, random_payload, MAX_RANDOM_LEN};
+use crate::i18n::{set_language, tr, trf, Language};
+use crate::message::{Message, MessageEntry, MessageKind};
+use crate::message_cache::LineFormat;
+use crate::notifications::{notify, NotificationKind, TOAST_DURATION};
+use crate::network::services::{port_label, service_name};
+use crate::network::source::parse_source_addr;
+use crate::utils::{format_bytes, format_duration, get_file_timestamp, hex_data_range, is_valid_hex_string, lock_or_recover, parse_number_input, printable_text, strip_hex_annotations, truncate_chars, unescape, NumberInput};
+use crate::network::{ConnectOptions, Keepalive};
+use crate::profiles::{save_profiles, upsert_profile};
+use crate::rules::{save_rules, AutoReplyRule};
+use crate::scan_history::load_scan_history;
+use crate::search::LogSearch;
+use crate::triggers::{save_triggers, Trigger};
+use crate::loopback::{spawn_loopback_test, LoopbackStatus};
+use crate::session::{load_sessions, save_session, spawn_replay, SessionRecorder};
+use crate::settings::{reset_settings, ClientSettings};
+use crate::shortcuts::ShortcutAction;
+use crate::network::scanner::{
+    group_results_by_host, mac_vendor, parse_exclude_list, parse_scan_spec, save_scan_logs_to_file,
+    total_probes, ScanConfig, ScanForm, ScanProgress, ScanResult, LARGE_SCAN_PROBES, MAX_SCAN_RETRIES,
+    SCAN_TIMEOUT_RANGE_MS,
+};
+use crate::ui::styles::{
+    apply_theme, create_message_frame, Theme, ThemeChoice, FONT_SCALE_RANGE, FONT_SCALE_STEP,
+};
 use eframe::egui;
-use tokio::sync::mpsc;
+use tcpcommon::hexdump::to_hex;
+use std::ops::RangeInclusive;
+use std::sync::atomic::Ordering;
+use tokio::sync::mpsc::error::TrySendError;
+
+// 扫描结果每页显示的主机数
+const SCAN_RESULTS_PAGE_SIZE: usize = 50;
+
+// 触发器命中后窗口边框闪烁的时间
+const TRIGGER_FLASH_DURATION: std::time::Duration = std::time::Duration::from_millis(1500);
+
+// 跳转到书签后该条消息高亮的时间
+const BOOKMARK_HIGHLIGHT_DURATION: std::time::Duration = std::time::Duration::from_millis(2000);
+
+// 书签列表中每条消息最多显示的字符数
+const BOOKMARK_LABEL_LEN: usize = 40;
 
 // 左侧设置面板
 pub fn render_settings_panel(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     ui.vertical_centered(|ui| {
-        ui.heading("连接设置");
+        ui.heading(tr("连接设置"));
     });
     ui.add_space(15.0);
 
     // 使用eframe 0.31兼容的Frame创建方式
+    let theme = Theme::of(ui.ctx());
     let frame = egui::Frame::new()
-        .fill(egui::Color32::from_rgb(245, 245, 250))
+        .fill(theme.panel_fill)
         .inner_margin(egui::vec2(10.0, 10.0));
 
     frame.show(ui, |ui| {
+        render_profile_selector(app, ui);
+
+        ui.add_space(5.0);
+        ui.separator();
+        ui.add_space(5.0);
+
+        // 会话标签和颜色，随设置和连接配置保存
         ui.horizontal(|ui| {
-            ui.strong("IP 地址:");
+            ui.strong(tr("会话标签:"));
+            ui.color_edit_button_srgb(&mut app.session_tag.color)
+                .on_hover_text(tr("标签颜色"));
+            ui.add(
+                egui::TextEdit::singleline(&mut app.session_tag.label)
+                    .hint_text(tr("如 产线PLC"))
+                    .desired_width(140.0),
+            );
+        });
+
+        ui.add_space(5.0);
+
+        // 客户端主动连接，或作为服务器监听并接受连接，连接或监听期间不能切换
+        ui.add_enabled_ui(!app.is_connected, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong(tr("角色:"));
+                ui.radio_value(&mut app.listen_mode, false, tr("客户端"));
+                ui.radio_value(&mut app.listen_mode, true, tr("服务器"))
+                    .on_hover_text(tr("监听指定地址和端口，接受客户端连接后收发数据"));
+            });
+        });
+
+        ui.add_space(5.0);
+
+        ui.horizontal(|ui| {
+            let (label, hint) = if app.listen_mode {
+                (tr("监听地址:"), tr("如 0.0.0.0"))
+            } else {
+                (tr("IP 地址:"), tr("输入服务器IP"))
+            };
+            ui.strong(label);
             ui.add(
                 egui::TextEdit::singleline(&mut app.ip)
                     .desired_width(120.0)
-                    .hint_text("输入服务器IP"),
+                    .hint_text(hint),
             );
         });
 
         ui.add_space(5.0);
 
         ui.horizontal(|ui| {
-            ui.strong("端口号:");
+            ui.strong(tr("端口号:"));
             ui.add(
                 egui::TextEdit::singleline(&mut app.port)
                     .desired_width(120.0)
-                    .hint_text("输入端口"),
+                    .hint_text(tr("输入端口")),
             );
+            // 常见端口显示对应的服务名
+            if let Some(name) = app.port.trim().parse::<u16>().ok().and_then(service_name) {
+                ui.weak(name);
+            }
         });
 
         ui.add_space(10.0);
@@ -44,66 +125,105 @@ pub fn render_settings_panel(app: &mut TcpClientApp, ui: &mut egui::Ui) {
 
         // 添加数据编码模式选择
         ui.vertical(|ui| {
-            ui.strong("数据编码模式:");
+            ui.strong(tr("数据编码模式:"));
             ui.add_space(5.0);
 
             ui.horizontal(|ui| {
                 // 当用户选择UTF-8模式
                 if ui.radio_value(&mut app.encoding_mode, EncodingMode::Utf8, "UTF-8").clicked() {
                     // 同步到共享的编码模式
-                    *app.shared_encoding_mode.lock().unwrap() = EncodingMode::Utf8;
+                    app.set_encoding_mode(EncodingMode::Utf8);
                 }
 
                 // 当用户选择十六进制模式
-                if ui.radio_value(&mut app.encoding_mode, EncodingMode::Hex, "十六进制(HEX)").clicked() {
+                if ui.radio_value(&mut app.encoding_mode, EncodingMode::Hex, tr("十六进制(HEX)")).clicked() {
                     // 同步到共享的编码模式
-                    *app.shared_encoding_mode.lock().unwrap() = EncodingMode::Hex;
+                    app.set_encoding_mode(EncodingMode::Hex);
                 }
             });
         });
+
+        ui.add_space(10.0);
+        ui.separator();
+        ui.add_space(5.0);
+
+        // 源地址设置，多网卡时指定连接从哪个本地地址发出；监听模式由监听地址决定
+        ui.add_enabled_ui(!app.listen_mode, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong(tr("源地址:"));
+                render_source_addr_input(app, ui);
+            });
+        });
+
+        ui.add_space(5.0);
+
+        // 空闲超时设置，下次连接时生效
+        ui.horizontal(|ui| {
+            ui.strong(tr("空闲超时(秒):"));
+            ui.add(egui::DragValue::new(&mut app.idle_timeout_secs).range(0..=86_400))
+                .on_hover_text(tr("超过该时间未收到数据时发出警告，0 表示不检测，下次连接时生效"));
+        });
+        ui.add_enabled(
+            app.idle_timeout_secs > 0,
+            egui::Checkbox::new(&mut app.disconnect_on_idle, tr("超时后自动断开")),
+        );
+
+        ui.add_space(5.0);
+
+        // 心跳设置，连接空闲时定时发送，下次连接时生效
+        ui.horizontal(|ui| {
+            ui.strong(tr("心跳间隔(秒):"));
+            ui.add(egui::DragValue::new(&mut app.keepalive_secs).range(0..=86_400))
+                .on_hover_text(tr("超过该时间没有发送数据时自动发送心跳内容，手动发送会重新计时，0 表示不发送"));
+        });
+        ui.add_enabled_ui(app.keepalive_secs > 0, |ui| {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut app.keepalive_mode, EncodingMode::Utf8, tr("文本"));
+                ui.radio_value(&mut app.keepalive_mode, EncodingMode::Hex, "HEX");
+                ui.add(
+                    egui::TextEdit::singleline(&mut app.keepalive_payload)
+                        .desired_width(120.0)
+                        .hint_text(tr("心跳内容")),
+                );
+            });
+        });
+
+        ui.add_space(10.0);
+        ui.separator();
+        ui.add_space(5.0);
+
+        // 自动应答规则入口
+        let rule_count = lock_or_recover(&app.auto_reply_rules).len();
+        if ui.button(trf!("自动应答规则 ({})", rule_count)).clicked() {
+            app.show_rules_window = true;
+        }
+
+        // 触发器入口
+        let trigger_count = lock_or_recover(&app.triggers).triggers.len();
+        if ui.button(trf!("触发器 ({})", trigger_count)).clicked() {
+            app.show_triggers_window = true;
+        }
+
+        ui.add_space(5.0);
+        render_session_section(app, ui);
+        render_loopback_section(app, ui);
     });
 
     ui.add_space(15.0);
 
-    // 连接/断开按钮区域
+    // 连接/断开按钮区域，监听模式下为开始/停止监听
     ui.vertical_centered(|ui| {
-        if !app.is_connected {
-            if ui
-                .add(
-                    egui::Button::new("连接")
-                        .fill(egui::Color32::from_rgb(100, 150, 220))
-                        .min_size(egui::vec2(100.0, 30.0)),
-                )
-                .clicked()
-            {
-                if let Ok(port) = app.port.parse::<u16>() {
-                    if let Some(tx) = &app.tx {
-                        let tx = tx.clone();
-                        let ip = app.ip.clone();
-                        tokio::spawn(async move {
-                            let _ = tx.send(Message::Connect(ip, port)).await;
-                        });
-                        app.is_connected = true;
-                    }
-                }
-            }
-        } else {
-            if ui
-                .add(
-                    egui::Button::new("断开")
-                        .fill(egui::Color32::from_rgb(220, 100, 100))
-                        .min_size(egui::vec2(100.0, 30.0)),
-                )
-                .clicked()
-            {
-                if let Some(tx) = &app.tx {
-                    let tx = tx.clone();
-                    tokio::spawn(async move {
-                        let _ = tx.send(Message::Disconnect).await;
-                    });
-                    app.is_connected = false;
-                }
-            }
+        let (label, fill) = match (app.is_connected, app.listen_mode) {
+            (false, false) => (tr("连接"), theme.primary_button),
+            (false, true) => (tr("监听"), theme.primary_button),
+            (true, false) => (tr("断开"), theme.danger_button),
+            (true, true) => (tr("停止监听"), theme.danger_button),
+        };
+        if ui
+            .add(egui::Button::new(label).fill(fill).min_size(egui::vec2(100.0, 30.0)))
+            .clicked()
+        {
+            toggle_connection(app);
         }
     });
 
@@ -112,39 +232,969 @@ pub fn render_settings_panel(app: &mut TcpClientApp, ui: &mut egui::Ui) {
 
     // 连接状态显示
     let status_frame = egui::Frame::new()
-        .fill(egui::Color32::from_rgb(245, 245, 250))
+        .fill(theme.panel_fill)
         .inner_margin(egui::vec2(10.0, 10.0));
 
     status_frame.show(ui, |ui| {
+        if !app.session_tag.label.trim().is_empty() {
+            ui.horizontal(|ui| {
+                ui.strong(tr("会话:"));
+                render_session_tag(&app.session_tag, ui);
+            });
+            ui.add_space(5.0);
+        }
+
         ui.horizontal(|ui| {
-            ui.strong("状态:");
-            let status_text = if app.is_connected {
-                "已连接"
-            } else {
-                "未连接"
+            ui.strong(tr("状态:"));
+            let status_text = match (app.is_connected, app.listen_mode) {
+                (true, true) => tr("监听中"),
+                (true, false) => tr("已连接"),
+                (false, _) => tr("未连接"),
             };
             let status_color = if app.is_connected {
-                egui::Color32::from_rgb(40, 180, 40)
+                theme.online
             } else {
-                egui::Color32::from_rgb(180, 40, 40)
+                theme.offline
             };
             ui.colored_label(status_color, status_text);
         });
 
+        // 连接两端的地址，监听模式下尚无客户端接入时提示等待
+        if app.is_connected {
+            match app.connection_metrics.current_addresses() {
+                Some(addrs) => {
+                    ui.add_space(5.0);
+                    ui.horizontal(|ui| {
+                        ui.strong(tr("本地地址:"));
+                        ui.label(addrs.local.to_string());
+                    });
+                    ui.horizontal(|ui| {
+                        ui.strong(tr("远端地址:"));
+                        ui.label(addrs.peer.to_string());
+                    });
+                }
+                None if app.listen_mode => {
+                    ui.add_space(5.0);
+                    ui.horizontal(|ui| {
+                        ui.strong(tr("客户端:"));
+                        ui.weak(tr("等待接入"));
+                    });
+                }
+                None => {}
+            }
+        }
+
+        // 连接时长，每帧刷新
+        if let Some(connected_at) = app.connected_at {
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.strong(tr("连接时长:"));
+                ui.label(format_duration(connected_at.elapsed()));
+            });
+        }
+
         ui.add_space(5.0);
 
-        let msg_count = app.received_messages.lock().unwrap().len();
+        let msg_count = lock_or_recover(&app.received_messages).len();
         ui.horizontal(|ui| {
-            ui.strong("消息数量:");
+            ui.strong(tr("消息数量:"));
             ui.label(format!("{}", msg_count));
         });
     });
 }
 
+// 未连接时发起连接，已连接时断开；监听模式下为开始或停止监听
+pub fn toggle_connection(app: &mut TcpClientApp) {
+    if !app.is_connected {
+        request_connect(app);
+        return;
+    }
+    let command = if app.listen_mode {
+        Message::StopListening
+    } else {
+        Message::Disconnect
+    };
+    if dispatch_command(app, command) {
+        app.is_connected = false;
+        app.connected_at = None;
+    }
+}
+
+// 按当前IP和端口发起连接，监听模式下开始在该地址监听
+fn request_connect(app: &mut TcpClientApp) {
+    if let Ok(port) = app.port.parse::<u16>() {
+        // 源地址无效时不发起连接，监听模式不使用源地址
+        let source_addr = if app.listen_mode {
+            Ok(None)
+        } else {
+            parse_source_addr(&app.source_addr)
+        };
+        let source_addr = match source_addr {
+            Ok(source_addr) => source_addr,
+            Err(e) => {
+                lock_or_recover(&app.received_messages).push(MessageEntry::new(get_timestamp(), MessageKind::Error, e));
+                app.should_scroll_to_bottom = true;
+                return;
+            }
+        };
+
+        // 心跳内容无效时不发起连接
+        let keepalive = match keepalive_settings(app) {
+            Ok(keepalive) => keepalive,
+            Err(e) => {
+                lock_or_recover(&app.received_messages).push(MessageEntry::new(get_timestamp(), MessageKind::Error, e));
+                app.should_scroll_to_bottom = true;
+                return;
+            }
+        };
+
+        let options = ConnectOptions {
+            idle_timeout_secs: app.idle_timeout_secs,
+            disconnect_on_idle: app.disconnect_on_idle,
+            source_addr,
+            keepalive,
+        };
+        let command = if app.listen_mode {
+            Message::Listen(app.ip.clone(), port, options)
+        } else {
+            Message::Connect(app.ip.clone(), port, options)
+        };
+        if dispatch_command(app, command) {
+            app.is_connected = true;
+            app.connected_at = Some(std::time::Instant::now());
+        }
+    }
+}
+
+// 根据心跳设置生成连接参数，间隔为 0 时不发送心跳
+fn keepalive_settings(app: &TcpClientApp) -> Result<Option<Keepalive>, String> {
+    if app.keepalive_secs == 0 {
+        return Ok(None);
+    }
+    if app.keepalive_payload.trim().is_empty() {
+        return Err(tr("心跳内容为空，请填写心跳内容或将心跳间隔设为 0").to_string());
+    }
+    if app.keepalive_mode == EncodingMode::Hex && !is_valid_hex_string(&app.keepalive_payload) {
+        return Err(trf!("心跳内容不是有效的十六进制数据: {}", app.keepalive_payload));
+    }
+    Ok(Some(Keepalive {
+        interval: std::time::Duration::from_secs(app.keepalive_secs),
+        payload: app.keepalive_payload.clone(),
+        encoding: app.keepalive_mode,
+    }))
+}
+
+// 连接配置：选择已保存的配置填充所有字段，或把当前字段保存为新配置
+fn render_profile_selector(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    let mut selected = None;
+    ui.horizontal(|ui| {
+        ui.strong(tr("连接配置:"));
+        let current = app
+            .selected_profile
+            .and_then(|index| app.profiles.get(index))
+            .map_or_else(|| tr("选择配置").to_string(), |p| p.name.clone());
+        egui::ComboBox::from_id_salt("profile_selector")
+            .width(120.0)
+            .selected_text(current)
+            .show_ui(ui, |ui| {
+                if app.profiles.is_empty() {
+                    ui.weak(tr("暂无配置"));
+                }
+                for (index, profile) in app.profiles.iter().enumerate() {
+                    if ui
+                        .selectable_label(app.selected_profile == Some(index), profile.label())
+                        .clicked()
+                    {
+                        selected = Some(index);
+                    }
+                }
+            });
+        if ui.small_button(tr("管理")).clicked() {
+            app.show_profiles_window = true;
+        }
+    });
+    if let Some(index) = selected {
+        app.apply_profile(index);
+    }
+
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut app.new_profile_name)
+                .desired_width(120.0)
+                .hint_text(tr("配置名称")),
+        );
+        let can_save = !app.new_profile_name.trim().is_empty();
+        if ui
+            .add_enabled(can_save, egui::Button::new(tr("保存为配置")))
+            .on_hover_text(tr("保存当前的地址、端口、编码、源地址、空闲超时和会话标签设置，同名配置会被覆盖"))
+            .clicked()
+        {
+            let profile = app.capture_profile(&app.new_profile_name);
+            let index = upsert_profile(&mut app.profiles, profile);
+            app.selected_profile = Some(index);
+            app.new_profile_name.clear();
+            app.profiles_status = Some(match save_profiles(&app.profiles) {
+                Ok(()) => tr("配置已保存").to_string(),
+                Err(e) => trf!("保存失败: {}", e),
+            });
+        }
+    });
+    if let Some(status) = &app.profiles_status {
+        ui.weak(status);
+    }
+}
+
+// 连接配置管理窗口，可修改或删除已保存的配置
+pub fn render_profiles_window(app: &mut TcpClientApp, ctx: &egui::Context) {
+    if !app.show_profiles_window {
+        return;
+    }
+
+    let mut open = app.show_profiles_window;
+    let mut save_requested = false;
+    let mut remove_index = None;
+    let mut overwrite_index = None;
+
+    egui::Window::new(tr("连接配置"))
+        .open(&mut open)
+        .default_width(520.0)
+        .resizable(true)
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .id_salt("profiles_scroll_area")
+                .show(ui, |ui| {
+                    if app.profiles.is_empty() {
+                        ui.weak(tr("暂无配置，在连接设置中填写名称后点击\"保存为配置\""));
+                    }
+
+                    for (index, profile) in app.profiles.iter_mut().enumerate() {
+                        create_message_frame(Theme::of(ui.ctx()).panel_fill).show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(&mut profile.name).desired_width(90.0));
+                                ui.add(egui::TextEdit::singleline(&mut profile.ip).desired_width(110.0));
+                                ui.label(":");
+                                ui.add(egui::TextEdit::singleline(&mut profile.port).desired_width(50.0));
+                                render_rule_mode_selector(ui, &mut profile.encoding, ("profile_encoding", index));
+
+                                if ui
+                                    .small_button(tr("覆盖"))
+                                    .on_hover_text(tr("用连接设置面板中的当前设置替换该配置"))
+                                    .clicked()
+                                {
+                                    overwrite_index = Some(index);
+                                }
+                                if ui.small_button(tr("删除")).clicked() {
+                                    remove_index = Some(index);
+                                }
+                            });
+                        });
+                    }
+                });
+
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if ui.button(tr("保存")).clicked() {
+                    save_requested = true;
+                }
+                if let Some(status) = &app.profiles_status {
+                    ui.label(status);
+                }
+            });
+        });
+
+    if let Some(index) = overwrite_index {
+        let name = app.profiles[index].name.clone();
+        app.profiles[index] = app.capture_profile(&name);
+    }
+    if let Some(index) = remove_index {
+        app.profiles.remove(index);
+        app.selected_profile = None;
+    }
+    if save_requested {
+        app.profiles_status = Some(match save_profiles(&app.profiles) {
+            Ok(()) => tr("配置已保存").to_string(),
+            Err(e) => trf!("保存失败: {}", e),
+        });
+    }
+
+    app.show_profiles_window = open;
+}
+
+// 本地回环测试：在进程内启动回显服务器并连接，发送测试内容后检查回显和往返时间
+fn render_loopback_section(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    egui::CollapsingHeader::new(tr("本地回环测试"))
+        .id_salt("loopback_section")
+        .show(ui, |ui| {
+            let status = lock_or_recover(&app.loopback_status).clone();
+            let running = status == Some(LoopbackStatus::Running);
+            ui.add_enabled(
+                !running,
+                egui::TextEdit::singleline(&mut app.loopback_payload)
+                    .hint_text(tr("测试内容"))
+                    .desired_width(180.0),
+            );
+            let enabled = !app.is_connected && !running && !app.loopback_payload.is_empty();
+            if ui
+                .add_enabled(enabled, egui::Button::new(tr("开始测试")))
+                .on_hover_text(tr("连接到进程内的回显服务器，检查连接、发送和接收是否正常"))
+                .on_disabled_hover_text(tr("断开当前连接后才能测试"))
+                .clicked()
+            {
+                if let Some(tx) = &app.tx {
+                    spawn_loopback_test(
+                        app.loopback_payload.clone(),
+                        tx.clone(),
+                        app.received_messages.clone(),
+                        app.loopback_status.clone(),
+                    );
+                    app.should_scroll_to_bottom = true;
+                }
+            }
+
+            let theme = Theme::of(ui.ctx());
+            match status {
+                None => {}
+                Some(LoopbackStatus::Running) => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(tr("测试中..."));
+                    });
+                }
+                Some(LoopbackStatus::Passed(elapsed)) => {
+                    let ms = elapsed.as_secs_f64() * 1000.0;
+                    ui.colored_label(theme.online, trf!("✔ 通过，往返 {} ms", format!("{:.2}", ms)));
+                }
+                Some(LoopbackStatus::Failed(e)) => {
+                    ui.colored_label(theme.error, trf!("✘ 失败: {}", e));
+                }
+            }
+        });
+}
+
+// 会话录制与回放：录制每次发送及其时间间隔，之后可按原始节奏重新发送
+fn render_session_section(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    egui::CollapsingHeader::new(tr("会话录制与回放"))
+        .id_salt("session_section")
+        .show(ui, |ui| {
+            match &app.session_recorder {
+                None => {
+                    if ui.button(tr("开始录制")).clicked() {
+                        let target = format!("{}:{}", app.ip, app.port);
+                        app.session_recorder = Some(SessionRecorder::new(target));
+                        app.session_status = None;
+                    }
+                }
+                Some(recorder) => {
+                    let label = trf!("停止并保存 ({} 条)", recorder.send_count());
+                    if ui.button(label).clicked() {
+                        if let Some(recorder) = app.session_recorder.take() {
+                            let recording = recorder.finish();
+                            app.session_status = Some(match save_session(&recording) {
+                                Ok(filepath) => trf!("会话已保存: {}", filepath),
+                                Err(e) => trf!("保存会话失败: {}", e),
+                            });
+                            app.saved_sessions = load_sessions();
+                        }
+                    }
+                    ui.colored_label(Theme::of(ui.ctx()).error, tr("● 正在录制"));
+                }
+            }
+
+            if let Some(status) = &app.session_status {
+                ui.weak(status);
+            }
+
+            ui.add_space(5.0);
+            let is_replaying = *lock_or_recover(&app.is_replaying);
+            if is_replaying {
+                if ui.button(tr("停止回放")).clicked() {
+                    *lock_or_recover(&app.is_replaying) = false;
+                }
+            } else if app.saved_sessions.is_empty() {
+                ui.weak(tr("暂无已保存的会话"));
+            }
+
+            // 已保存的会话，回放时发送到当前连接
+            let mut replay = None;
+            egui::ScrollArea::vertical()
+                .max_height(150.0)
+                .id_salt("session_list_scroll_area")
+                .show(ui, |ui| {
+                    for (index, recording) in app.saved_sessions.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let can_replay = app.is_connected && !is_replaying;
+                            if ui
+                                .add_enabled(can_replay, egui::Button::new(tr("回放")).small())
+                                .on_disabled_hover_text(tr("连接后才能回放"))
+                                .clicked()
+                            {
+                                replay = Some(index);
+                            }
+                            ui.label(egui::RichText::new(recording.summary()).small());
+                        });
+                    }
+                });
+
+            if let (Some(index), Some(tx)) = (replay, &app.tx) {
+                spawn_replay(
+                    app.saved_sessions[index].clone(),
+                    tx.clone(),
+                    app.is_replaying.clone(),
+                );
+            }
+        });
+}
+
+// 主题菜单：跟随系统、浅色或深色，选择后立即生效并在退出时保存
+pub fn render_theme_menu(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    ui.menu_button(tr("主题"), |ui| {
+        for theme in ThemeChoice::ALL {
+            if ui.radio_value(&mut app.theme, theme, theme.label()).clicked() {
+                apply_theme(ui.ctx(), theme);
+                ui.close_menu();
+            }
+        }
+    });
+
+    // 明暗快速切换，按当前实际显示的深浅色取反
+    let dark = ui.visuals().dark_mode;
+    let (icon, target) = if dark {
+        ("☀", ThemeChoice::Light)
+    } else {
+        ("🌙", ThemeChoice::Dark)
+    };
+    if ui.button(icon).on_hover_text(tr("切换明暗主题")).clicked() {
+        app.theme = target;
+        apply_theme(ui.ctx(), target);
+    }
+}
+
+// 主窗口底部的状态栏，连接和扫描界面都显示，内容来自网络任务和扫描任务的共享状态
+pub fn render_status_bar(app: &mut TcpClientApp, ctx: &egui::Context) {
+    egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+        let theme = Theme::of(ui.ctx());
+        ui.horizontal(|ui| {
+            let metrics = &app.connection_metrics;
+            match (app.is_connected, metrics.current_addresses()) {
+                (true, Some(addrs)) => {
+                    ui.colored_label(theme.online, trf!("● 已连接 {}", addrs.peer));
+                    ui.separator();
+                    ui.label(trf!("本地 {}", addrs.local));
+                }
+                (true, None) if app.listen_mode => {
+                    ui.colored_label(theme.online, tr("● 监听中"));
+                    ui.weak(tr("等待接入"));
+                }
+                (true, None) => {
+                    ui.colored_label(theme.online, tr("● 已连接"));
+                }
+                (false, _) => {
+                    ui.colored_label(theme.offline, tr("● 未连接"));
+                }
+            }
+
+            ui.separator();
+            let (sent, received) = metrics.byte_counts();
+            ui.label(trf!("发送 {} / 接收 {}", format_bytes(sent), format_bytes(received)));
+
+            // 点击数据文件路径打开所在目录
+            if let Some(path) = metrics.current_data_file() {
+                ui.separator();
+                if ui
+                    .link(path.display().to_string())
+                    .on_hover_text(tr("打开所在目录"))
+                    .clicked()
+                {
+                    open_containing_dir(app, &path);
+                }
+            }
+
+            if app.is_scanning.load(Ordering::Relaxed) {
+                let progress = lock_or_recover(&app.scan_progress);
+                let percent = match progress.total_probes {
+                    0 => 0,
+                    total => progress.completed_probes * 100 / total,
+                };
+                ui.separator();
+                ui.label(trf!(
+                    "扫描中 {}/{} ({}%)",
+                    progress.completed_probes,
+                    progress.total_probes,
+                    percent
+                ));
+            }
+        });
+    });
+}
+
+// 在文件管理器中打开文件所在的目录，失败时发出通知
+fn open_containing_dir(app: &TcpClientApp, path: &std::path::Path) {
+    let dir = path.parent().unwrap_or(path);
+    let dir = std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    if let Err(e) = open::that_detached(&dir) {
+        notify(&app.notifications, NotificationKind::Error, trf!("打开目录失败: {}", e));
+    }
+}
+
+// 会话标签，以标签颜色的圆点开头；没有填写标签时不显示
+pub fn render_session_tag(tag: &SessionTag, ui: &mut egui::Ui) {
+    let label = tag.label.trim();
+    if !label.is_empty() {
+        ui.colored_label(tag.color32(), egui::RichText::new(format!("● {}", label)).strong());
+    }
+}
+
+// 语言菜单：中文或英文，选择后立即生效并在退出时保存
+pub fn render_language_menu(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    ui.menu_button(tr("语言"), |ui| {
+        for language in Language::ALL {
+            if ui.radio_value(&mut app.language, language, language.label()).clicked() {
+                set_language(language);
+                ui.close_menu();
+            }
+        }
+    });
+}
+
+// 菜单栏中的设置菜单，恢复默认设置会删除配置文件并把连接和扫描设置还原为初始值
+pub fn render_settings_menu(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    ui.menu_button(tr("设置"), |ui| {
+        if ui
+            .button(tr("恢复默认设置"))
+            .on_hover_text(tr("连接和扫描设置恢复为初始值，已保存的连接配置、规则和触发器不受影响"))
+            .clicked()
+        {
+            ClientSettings::default().apply(app);
+            if let Err(e) = reset_settings() {
+                notify(&app.notifications, NotificationKind::Error, trf!("删除配置文件失败: {}", e));
+            }
+            ui.close_menu();
+        }
+        if ui.button(tr("快捷键...")).clicked() {
+            app.show_shortcuts_window = true;
+            ui.close_menu();
+        }
+
+        // 界面字号，滑块按百分比显示
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(tr("字号:"));
+            let mut scale = app.font_scale;
+            let slider = egui::Slider::new(&mut scale, FONT_SCALE_RANGE)
+                .step_by(FONT_SCALE_STEP as f64)
+                .custom_formatter(|value, _| format!("{:.0}%", value * 100.0))
+                .custom_parser(|text| text.trim().trim_end_matches('%').parse::<f64>().ok().map(|v| v / 100.0));
+            if ui.add(slider).changed() {
+                app.set_font_scale(ui.ctx(), scale);
+            }
+            if ui
+                .button(tr("重置"))
+                .on_hover_text(tr("恢复默认字号，也可按 Ctrl+0；Ctrl 加减号调整字号"))
+                .clicked()
+            {
+                app.set_font_scale(ui.ctx(), 1.0);
+            }
+        });
+
+        // 字体文件优先于内置字体使用，用于显示内置字体不包含的文字
+        ui.horizontal(|ui| {
+            ui.label(tr("字体:"));
+            match &app.font_file {
+                Some(path) => {
+                    let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+                    ui.label(name).on_hover_text(path.display().to_string());
+                }
+                None => {
+                    ui.weak(tr("内置字体"));
+                }
+            }
+            if ui
+                .button(tr("选择文件..."))
+                .on_hover_text(tr("使用 TTF/OTF 字体文件，缺少的字符仍由内置字体显示"))
+                .clicked()
+            {
+                ui.close_menu();
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter(tr("字体"), &["ttf", "otf", "ttc"])
+                    .pick_file()
+                {
+                    app.set_font_file(ui.ctx(), Some(path));
+                }
+            }
+            if app.font_file.is_some() && ui.button(tr("使用内置字体")).clicked() {
+                app.set_font_file(ui.ctx(), None);
+                ui.close_menu();
+            }
+        });
+    });
+}
+
+// 菜单栏中的通知设置，可按类别屏蔽通知
+pub fn render_notification_menu(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    ui.menu_button(tr("通知"), |ui| {
+        for kind in NotificationKind::ALL {
+            let mut enabled = !app.muted_notifications.contains(&kind);
+            if ui.checkbox(&mut enabled, kind.label()).changed() {
+                if enabled {
+                    app.muted_notifications.remove(&kind);
+                } else {
+                    app.muted_notifications.insert(kind);
+                }
+            }
+        }
+
+        // 系统桌面通知，窗口不在前台时也能发现连接断开
+        ui.separator();
+        let mut desktop = app.disconnect_alerts.is_enabled();
+        if ui
+            .checkbox(&mut desktop, tr("断线桌面通知"))
+            .on_hover_text(tr("连接被对端关闭或出错断开时发送系统通知，1 分钟内最多通知一次"))
+            .changed()
+        {
+            app.disconnect_alerts.set_enabled(desktop);
+        }
+    });
+}
+
+// 在窗口右下角显示自动消失的通知，点击可立即关闭
+pub fn render_toasts(app: &mut TcpClientApp, ctx: &egui::Context) {
+    // 取出新通知，丢弃已屏蔽的类别
+    let pending: Vec<_> = lock_or_recover(&app.notifications).drain(..).collect();
+    app.toasts.extend(
+        pending
+            .into_iter()
+            .filter(|n| !app.muted_notifications.contains(&n.kind)),
+    );
+    app.toasts.retain(|n| !n.is_expired());
+    if app.toasts.is_empty() {
+        return;
+    }
+
+    let mut dismissed = None;
+    egui::Area::new(egui::Id::new("toast_area"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-15.0, -15.0))
+        .order(egui::Order::Foreground)
+        .interactable(true)
+        .show(ctx, |ui| {
+            let theme = Theme::of(ui.ctx());
+            for (index, toast) in app.toasts.iter().enumerate() {
+                let colors = match toast.kind {
+                    NotificationKind::Connection => theme.toast_connection,
+                    NotificationKind::Scan => theme.toast_scan,
+                    NotificationKind::Error => theme.toast_error,
+                    NotificationKind::Trigger => theme.toast_trigger,
+                };
+                let (fill, text_color) = (colors.background, colors.text);
+
+                // 临近消失时逐渐变淡
+                let remaining = TOAST_DURATION.saturating_sub(toast.created_at.elapsed());
+                let opacity = (remaining.as_secs_f32() / 0.5).min(1.0);
+
+                let response = egui::Frame::new()
+                    .fill(fill.gamma_multiply(opacity))
+                    .stroke(egui::Stroke::new(1.0, text_color.gamma_multiply(opacity * 0.5)))
+                    .inner_margin(egui::vec2(12.0, 8.0))
+                    .corner_radius(6.0)
+                    .show(ui, |ui| {
+                        ui.set_max_width(280.0);
+                        ui.label(
+                            egui::RichText::new(&toast.text).color(text_color.gamma_multiply(opacity)),
+                        );
+                    })
+                    .response
+                    .interact(egui::Sense::click());
+                if response.on_hover_text(tr("点击关闭")).clicked() {
+                    dismissed = Some(index);
+                }
+                ui.add_space(6.0);
+            }
+        });
+
+    if let Some(index) = dismissed {
+        app.toasts.remove(index);
+    }
+
+    // 通知显示期间持续重绘，保证淡出动画流畅且到期后及时消失
+    ctx.request_repaint();
+}
+
+// 源地址输入框，连接和扫描共用同一设置
+fn render_source_addr_input(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    ui.add(
+        egui::TextEdit::singleline(&mut app.source_addr)
+            .desired_width(120.0)
+            .hint_text(tr("默认路由")),
+    )
+    .on_hover_text(tr("绑定的本地IP地址，留空表示由系统选择"));
+}
+
+// 自动应答规则编辑窗口
+pub fn render_rules_window(app: &mut TcpClientApp, ctx: &egui::Context) {
+    if !app.show_rules_window {
+        return;
+    }
+
+    let mut open = app.show_rules_window;
+    let mut save_requested = false;
+
+    egui::Window::new(tr("自动应答规则"))
+        .open(&mut open)
+        .default_width(560.0)
+        .resizable(true)
+        .show(ctx, |ui| {
+            ui.label(tr("收到的数据包含匹配内容时，自动发送对应的应答内容。"));
+            ui.weak(tr("对端回显的应答不会再次触发规则，且每秒最多自动应答10次。"));
+            ui.add_space(5.0);
+
+            let mut rules = lock_or_recover(&app.auto_reply_rules);
+            let mut remove_index = None;
+
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .id_salt("rules_scroll_area")
+                .show(ui, |ui| {
+                    if rules.is_empty() {
+                        ui.weak(tr("暂无规则"));
+                    }
+
+                    for (index, rule) in rules.iter_mut().enumerate() {
+                        create_message_frame(Theme::of(ui.ctx()).panel_fill).show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut rule.enabled, "");
+
+                                ui.label(tr("匹配"));
+                                render_rule_mode_selector(ui, &mut rule.match_mode, ("rule_match", index));
+                                render_rule_text_input(ui, &mut rule.pattern, rule.match_mode, "PING");
+
+                                ui.label(tr("应答"));
+                                render_rule_mode_selector(ui, &mut rule.reply_mode, ("rule_reply", index));
+                                render_rule_text_input(ui, &mut rule.reply, rule.reply_mode, "PONG");
+
+                                if ui.small_button(tr("删除")).clicked() {
+                                    remove_index = Some(index);
+                                }
+                            });
+                        });
+                    }
+                });
+
+            if let Some(index) = remove_index {
+                rules.remove(index);
+            }
+
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if ui.button(tr("添加规则")).clicked() {
+                    rules.push(AutoReplyRule::default());
+                }
+                if ui.button(tr("保存")).clicked() {
+                    save_requested = true;
+                }
+                if let Some(status) = &app.rules_status {
+                    ui.label(status);
+                }
+            });
+        });
+
+    if save_requested {
+        let rules = lock_or_recover(&app.auto_reply_rules).clone();
+        app.rules_status = Some(match save_rules(&rules) {
+            Ok(()) => tr("规则已保存").to_string(),
+            Err(e) => trf!("保存失败: {}", e),
+        });
+    }
+
+    app.show_rules_window = open;
+}
+
+// 触发器编辑窗口
+pub fn render_triggers_window(app: &mut TcpClientApp, ctx: &egui::Context) {
+    if !app.show_triggers_window {
+        return;
+    }
+
+    let mut open = app.show_triggers_window;
+    let mut save_requested = false;
+
+    egui::Window::new(tr("触发器"))
+        .open(&mut open)
+        .default_width(480.0)
+        .resizable(true)
+        .show(ctx, |ui| {
+            ui.label(tr("收到的数据包含匹配内容时，在消息列表中插入高亮标记并闪烁窗口边框。"));
+            ui.add_space(5.0);
+
+            let mut set = lock_or_recover(&app.triggers);
+            let mut remove_index = None;
+
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .id_salt("triggers_scroll_area")
+                .show(ui, |ui| {
+                    if set.triggers.is_empty() {
+                        ui.weak(tr("暂无触发器"));
+                    }
+
+                    for (index, trigger) in set.triggers.iter_mut().enumerate() {
+                        create_message_frame(Theme::of(ui.ctx()).panel_fill).show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut trigger.enabled, format!("#{}", index + 1));
+                                render_rule_mode_selector(ui, &mut trigger.mode, ("trigger_mode", index));
+                                render_rule_text_input(ui, &mut trigger.pattern, trigger.mode, "ERROR");
+                                ui.color_edit_button_srgb(&mut trigger.color)
+                                    .on_hover_text(tr("标记和闪烁的颜色"));
+
+                                if ui.small_button(tr("删除")).clicked() {
+                                    remove_index = Some(index);
+                                }
+                            });
+                        });
+                    }
+                });
+
+            if let Some(index) = remove_index {
+                set.triggers.remove(index);
+            }
+
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if ui.button(tr("添加触发器")).clicked() {
+                    let trigger = Trigger::new(set.triggers.len());
+                    set.triggers.push(trigger);
+                }
+                if ui.button(tr("保存")).clicked() {
+                    save_requested = true;
+                }
+                if let Some(status) = &app.triggers_status {
+                    ui.label(status);
+                }
+            });
+        });
+
+    if save_requested {
+        let triggers = lock_or_recover(&app.triggers).triggers.clone();
+        app.triggers_status = Some(match save_triggers(&triggers) {
+            Ok(()) => tr("触发器已保存").to_string(),
+            Err(e) => trf!("保存失败: {}", e),
+        });
+    }
+
+    app.show_triggers_window = open;
+}
+
+// 快捷键设置窗口：查看、修改、禁用或恢复每个操作的快捷键，新的组合键由 app 的 handle_shortcuts 读取
+pub fn render_shortcuts_window(app: &mut TcpClientApp, ctx: &egui::Context) {
+    if !app.show_shortcuts_window {
+        app.shortcut_capture = None;
+        return;
+    }
+
+    let mut open = app.show_shortcuts_window;
+    egui::Window::new(tr("快捷键"))
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.weak(tr("输入框有焦点时只响应发送和搜索，其余快捷键留给输入框使用。"));
+            ui.add_space(5.0);
+
+            egui::Grid::new("shortcuts_grid").num_columns(3).spacing([15.0, 6.0]).show(ui, |ui| {
+                for action in ShortcutAction::ALL {
+                    ui.label(action.label());
+                    if app.shortcut_capture == Some(action) {
+                        ui.colored_label(Theme::of(ui.ctx()).accent, tr("请按下新的组合键，Esc 取消"));
+                    } else {
+                        match app.shortcuts.get(action) {
+                            Some(shortcut) => ui.monospace(ctx.format_shortcut(&shortcut)),
+                            None => ui.weak(tr("已禁用")),
+                        };
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.small_button(tr("修改")).clicked() {
+                            app.shortcut_capture = Some(action);
+                            app.shortcuts_status = None;
+                        }
+                        if ui.small_button(tr("禁用")).clicked() {
+                            app.shortcuts.disable(action);
+                        }
+                        if ui.small_button(tr("默认")).clicked() {
+                            app.shortcuts.reset(action);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if ui.button(tr("全部恢复默认")).clicked() {
+                    app.shortcuts.reset_all();
+                    app.shortcut_capture = None;
+                    app.shortcuts_status = None;
+                }
+                if let Some(status) = &app.shortcuts_status {
+                    ui.colored_label(Theme::of(ui.ctx()).error, status);
+                }
+            });
+        });
+
+    app.show_shortcuts_window = open;
+}
+
+// 触发器命中后用命中触发器的颜色描绘窗口边框，逐渐淡出
+pub fn render_trigger_flash(app: &mut TcpClientApp, ctx: &egui::Context) {
+    let Some(hit) = lock_or_recover(&app.triggers).last_hit else {
+        return;
+    };
+    let elapsed = hit.at.elapsed();
+    if elapsed >= TRIGGER_FLASH_DURATION {
+        return;
+    }
+
+    // 前半段闪烁两次，之后淡出
+    let progress = elapsed.as_secs_f32() / TRIGGER_FLASH_DURATION.as_secs_f32();
+    let blink = if progress < 0.5 && (progress * 8.0) as u32 % 2 == 1 { 0.3 } else { 1.0 };
+    let opacity = (1.0 - progress) * blink;
+
+    let [r, g, b] = hit.color;
+    let stroke = egui::Stroke::new(6.0, egui::Color32::from_rgb(r, g, b).gamma_multiply(opacity));
+    ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("trigger_flash")))
+        .rect_stroke(ctx.screen_rect(), 0.0, stroke, egui::StrokeKind::Inside);
+    ctx.request_repaint();
+}
+
+// 规则内容格式选择 (文本/十六进制)
+fn render_rule_mode_selector(ui: &mut egui::Ui, mode: &mut EncodingMode, id: (&str, usize)) {
+    let label = |mode: EncodingMode| match mode {
+        EncodingMode::Utf8 => tr("文本"),
+        EncodingMode::Hex => "HEX",
+    };
+
+    egui::ComboBox::from_id_salt(id)
+        .width(60.0)
+        .selected_text(label(*mode))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(mode, EncodingMode::Utf8, label(EncodingMode::Utf8));
+            ui.selectable_value(mode, EncodingMode::Hex, label(EncodingMode::Hex));
+        });
+}
+
+// 规则内容输入框，十六进制格式无效时以红色显示
+fn render_rule_text_input(ui: &mut egui::Ui, text: &mut String, mode: EncodingMode, hint: &str) {
+    let is_invalid = mode == EncodingMode::Hex && !is_valid_hex_string(text);
+    let mut edit = egui::TextEdit::singleline(text)
+        .desired_width(120.0)
+        .hint_text(hint);
+    if is_invalid {
+        edit = edit.text_color(Theme::of(ui.ctx()).error);
+    }
+    ui.add(edit);
+}
+
 // 中央消息面板
 pub fn render_messages_panel(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     ui.vertical_centered(|ui| {
-        ui.heading("接收消息");
+        ui.heading(tr("接收消息"));
     });
     ui.add_space(10.0);
 
@@ -152,62 +1202,385 @@ pub fn render_messages_panel(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     ui.horizontal(|ui| {
         if ui
             .button(if app.should_scroll_to_bottom {
-                "📌 禁用自动滚动"
+                tr("📌 禁用自动滚动")
             } else {
-                "📌 启用自动滚动"
+                tr("📌 启用自动滚动")
             })
             .clicked()
         {
             app.should_scroll_to_bottom = !app.should_scroll_to_bottom;
         }
 
-        if ui.button("🗑️ 清空消息").clicked() {
-            app.received_messages.lock().unwrap().clear();
+        if ui.button(tr("🗑️ 清空消息")).clicked() {
+            request_clear_messages(app);
+        }
+
+        if ui.button(tr("📤 导出消息")).clicked() {
+            save_messages(app);
         }
+
+        render_bookmark_list(app, ui);
+
+        // 只影响显示，历史数据按新的格式重新解码
+        ui.label(tr("显示:"));
+        egui::ComboBox::from_id_salt("display_mode")
+            .width(70.0)
+            .selected_text(display_mode_label(app.display_mode))
+            .show_ui(ui, |ui| {
+                for mode in [DisplayMode::Utf8, DisplayMode::Hex, DisplayMode::Decimal] {
+                    ui.selectable_value(&mut app.display_mode, mode, display_mode_label(mode));
+                }
+            });
+
+        ui.checkbox(&mut app.dual_view, tr("双视图"))
+            .on_hover_text(tr("收发的数据同时显示文本和十六进制"));
+
+        // 距离底部在阈值以内时仍视为停留在底部，新消息会自动滚动
+        ui.label(tr("贴底阈值:"));
+        ui.add(
+            egui::DragValue::new(&mut app.message_follow.threshold)
+                .range(0.0..=500.0)
+                .suffix(" px"),
+        )
+        .on_hover_text(tr("向上翻阅超过该距离后，新消息不再自动滚动到底部"));
+
+        ui.label(tr("最大显示长度:"));
+        ui.add(
+            egui::DragValue::new(&mut app.max_display_len)
+                .range(0..=1_000_000)
+                .speed(10.0)
+                .suffix(tr(" 字符")),
+        )
+        .on_hover_text(tr("超出部分折叠，可点击“展开”查看完整内容；0 表示不限制"));
     });
 
     // 创建带边框的滚动区域显示消息
+    let theme = Theme::of(ui.ctx());
     let messages_frame = egui::Frame::new()
-        .fill(egui::Color32::from_rgb(250, 250, 255))
-        .stroke(egui::Stroke::new(1.0, egui::Color32::from_gray(200)))
+        .fill(theme.list_fill)
+        .stroke(egui::Stroke::new(1.0, theme.border))
         .inner_margin(egui::vec2(10.0, 10.0))
         .outer_margin(egui::vec2(0.0, 5.0));
 
     // 计算合适的区域大小
     let available_height = ui.available_height() - 20.0; // 减去一些边距
 
+    let count = lock_or_recover(&app.received_messages).len();
+    // 跳转到书签的那一帧不再滚动到底部
+    let jumping = app.bookmark_jump.as_ref().is_some_and(|jump| jump.scroll);
+    let scroll_to_bottom = app
+        .message_follow
+        .should_scroll(count, app.should_scroll_to_bottom)
+        && !jumping;
+
+    app.message_cache.sync(
+        count,
+        LineFormat {
+            mode: app.display_mode,
+            max_len: app.max_display_len,
+            dual_view: app.dual_view,
+            language: app.language,
+        },
+    );
+
     messages_frame.show(ui, |ui| {
-        // 使用滑动窗口，固定高度，自动滚动到底部
-        let scroll_area = egui::ScrollArea::vertical()
+        // 每行等高，只绘制可见区域内的消息；双视图下每行多出文本和十六进制两行
+        let line_height = message_line_height(ui);
+        let row_lines = if app.dual_view { 3.0 } else { 1.0 };
+        let row_inner_height = row_lines * line_height;
+        let row_height = row_inner_height + create_message_frame(theme.list_fill).total_margin().sum().y;
+        let row_step = row_height + ui.spacing().item_spacing.y;
+
+        // 使用滑动窗口，固定高度；停留在底部时新消息自动滚动到底部，向上翻阅时保持位置
+        // 不绘制的行无法滚动到自身，按行高计算滚动位置，超出范围的位置由滚动区域限制
+        let mut scroll_area = egui::ScrollArea::vertical()
             .auto_shrink([false; 2])
-            .stick_to_bottom(app.should_scroll_to_bottom)
             .max_height(available_height)
             .id_salt("messages_scroll_area");
+        if scroll_to_bottom {
+            scroll_area = scroll_area.vertical_scroll_offset(row_step * count as f32);
+        } else if let Some(jump) = app.bookmark_jump.as_mut().filter(|jump| jump.scroll) {
+            let offset = jump.index as f32 * row_step - (available_height - row_height) / 2.0;
+            scroll_area = scroll_area.vertical_scroll_offset(offset.max(0.0));
+            jump.scroll = false;
+        }
 
-        scroll_area.show(ui, |ui| {
-            let messages = app.received_messages.lock().unwrap();
-            if messages.is_empty() {
-                ui.weak("暂无消息...");
-            } else {
-                // 设置列表最大高度
-                ui.set_min_height(available_height);
+        // 本帧点击了展开、切换了书签的消息
+        let mut expanded = None;
+        let mut bookmark_toggled = None;
+        let output = if count == 0 {
+            scroll_area.show(ui, |ui| {
+                ui.weak(tr("暂无消息..."));
+            })
+        } else {
+            scroll_area.show_rows(ui, row_height, count, |ui, rows| {
+                let messages = lock_or_recover(&app.received_messages);
+                let triggers = lock_or_recover(&app.triggers);
+                for index in rows {
+                    let Some(entry) = messages.get(index) else {
+                        break;
+                    };
+                    let line = app.message_cache.line(index, entry);
+                    // 根据消息类型获取样式，触发器标记使用触发器自己的颜色
+                    let (color, mut item_bg) = match triggers.marker_color(&line.content) {
+                        Some([r, g, b]) => (
+                            egui::Color32::from_rgb(r, g, b),
+                            egui::Color32::from_rgba_unmultiplied(r, g, b, 50),
+                        ),
+                        None => {
+                            let colors = theme.message(entry, app.display_mode);
+                            (colors.text, colors.background)
+                        }
+                    };
+
+                    // 刚跳转到的书签条目短暂高亮，逐渐淡出
+                    let jump = app.bookmark_jump.as_ref().filter(|jump| jump.index == index);
+                    if let Some(elapsed) = jump.map(|jump| jump.at.elapsed()) {
+                        if elapsed < BOOKMARK_HIGHLIGHT_DURATION {
+                            let fade = 1.0 - elapsed.as_secs_f32() / BOOKMARK_HIGHLIGHT_DURATION.as_secs_f32();
+                            item_bg = theme.accent.gamma_multiply(0.5 * fade);
+                            ui.ctx().request_repaint();
+                        }
+                    }
 
-                for (timestamp, msg) in messages.iter() {
-                    // 根据消息类型获取样式
-                    let color = get_message_color(msg);
-                    let item_bg = get_message_background(msg);
+                    // 带背景色的消息行：书签旗标、[时间戳] 消息内容，双视图下在下方同时显示文本和十六进制
+                    // 各行不换行，超出宽度的部分省略，悬停时显示完整的一行
+                    create_message_frame(item_bg).show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+                        ui.set_min_height(row_inner_height);
+                        ui.spacing_mut().item_spacing.y = 0.0;
+                        ui.horizontal(|ui| {
+                            ui.set_height(line_height);
+                            let (flag_color, flag_tip) = if entry.bookmarked {
+                                (theme.accent, tr("取消书签"))
+                            } else {
+                                (theme.hint.gamma_multiply(0.4), tr("添加书签"))
+                            };
+                            let flag = egui::Button::new(egui::RichText::new("🔖").color(flag_color)).frame(false);
+                            if ui.add(flag).on_hover_text(flag_tip).clicked() {
+                                bookmark_toggled = Some(index);
+                            }
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if line.folded
+                                    && ui.small_button(tr("展开")).on_hover_text(tr("显示完整内容")).clicked()
+                                {
+                                    expanded = Some(index);
+                                }
+                                ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                                    ui.add(egui::Label::new(egui::RichText::new(&line.text).color(color)).truncate());
+                                });
+                            });
+                        });
+                        for dual_line in line.dual.iter().flatten() {
+                            ui.horizontal(|ui| {
+                                ui.set_height(line_height);
+                                ui.add(egui::Label::new(egui::RichText::new(dual_line).monospace()).truncate());
+                            });
+                        }
+                    });
+                }
+            })
+        };
 
-                    // 显示格式：[时间戳] 消息内容
-                    let text = format!("[{}] {}", timestamp, msg);
+        if expanded.is_some() {
+            app.message_detail = expanded;
+        }
+        if let Some(index) = bookmark_toggled {
+            if let Some(entry) = lock_or_recover(&app.received_messages).get_mut(index) {
+                entry.bookmarked = !entry.bookmarked;
+            }
+        }
 
-                    // 创建一个带背景色的消息行
-                    create_message_frame(item_bg).show(ui, |ui| {
-                        ui.colored_label(color, text);
+        app.message_follow.update(
+            output.state.offset.y,
+            output.inner_rect.height(),
+            output.content_size.y,
+            count,
+            scroll_to_bottom,
+        );
+
+        // 向上翻阅时在列表右下角显示新消息数量，点击后跳到底部
+        let unread = app.message_follow.unread(count);
+        if unread > 0 {
+            let size = egui::vec2(120.0, 26.0);
+            let rect = egui::Rect::from_min_size(
+                output.inner_rect.right_bottom() - size - egui::vec2(16.0, 8.0),
+                size,
+            );
+            if ui
+                .put(rect, egui::Button::new(trf!("⬇ {} 条新消息", unread)))
+                .clicked()
+            {
+                app.message_follow.request_jump();
+                ui.ctx().request_repaint();
+            }
+        }
+    });
+
+    render_message_detail(app, ui.ctx());
+    render_clear_messages_confirm(app, ui.ctx());
+}
+
+// 消息列表每一行文字的高度，取正文、等宽字体和按钮中较高的一个，随界面字号变化
+fn message_line_height(ui: &egui::Ui) -> f32 {
+    let spacing = ui.spacing();
+    let body = ui.text_style_height(&egui::TextStyle::Body) + 2.0 * spacing.button_padding.y;
+    let monospace = ui.text_style_height(&egui::TextStyle::Monospace);
+    body.max(monospace).max(spacing.interact_size.y)
+}
+
+// 显示一条消息完整内容的窗口，列表中过长而折叠的消息在这里查看
+fn render_message_detail(app: &mut TcpClientApp, ctx: &egui::Context) {
+    let Some(index) = app.message_detail else {
+        return;
+    };
+    let Some(entry) = lock_or_recover(&app.received_messages).get(index).cloned() else {
+        app.message_detail = None;
+        return;
+    };
+
+    let mut open = true;
+    egui::Window::new(tr("消息详情"))
+        .open(&mut open)
+        .default_size(egui::vec2(560.0, 360.0))
+        .show(ctx, |ui| {
+            ui.label(trf!("时间: {}", entry.timestamp));
+            ui.separator();
+            egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+                ui.add(egui::Label::new(entry.display(app.display_mode)).selectable(true));
+                if let Some(raw) = &entry.raw {
+                    ui.separator();
+                    for (title, text) in [(tr("文本"), printable_text(raw)), ("HEX ", to_hex(raw))] {
+                        ui.label(
+                            egui::RichText::new(format!("{} | {}", title, text)).monospace(),
+                        );
+                    }
+                }
+            });
+        });
+    if !open {
+        app.message_detail = None;
+    }
+}
+
+// 清空消息，有书签时先确认，清空后书签一并丢失
+pub fn request_clear_messages(app: &mut TcpClientApp) {
+    if lock_or_recover(&app.received_messages).iter().any(|entry| entry.bookmarked) {
+        app.confirm_clear_messages = true;
+    } else {
+        clear_messages(app);
+    }
+}
+
+// 清空消息列表以及依赖消息序号的详情窗口、格式化缓存和书签跳转状态
+fn clear_messages(app: &mut TcpClientApp) {
+    lock_or_recover(&app.received_messages).clear();
+    app.message_detail = None;
+    app.message_cache.clear();
+    app.bookmark_jump = None;
+}
+
+// 清空含书签的消息前的确认对话框
+fn render_clear_messages_confirm(app: &mut TcpClientApp, ctx: &egui::Context) {
+    if !app.confirm_clear_messages {
+        return;
+    }
+
+    let count = lock_or_recover(&app.received_messages)
+        .iter()
+        .filter(|entry| entry.bookmarked)
+        .count();
+    let mut confirmed = false;
+    let mut cancelled = false;
+
+    egui::Window::new(tr("确认清空"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label(trf!("消息中有 {} 个书签，清空后书签将一并丢失。", count));
+            ui.label(tr("确定要清空消息吗？"));
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                if ui.button(tr("清空")).clicked() {
+                    confirmed = true;
+                }
+                if ui.button(tr("取消")).clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+
+    if confirmed {
+        app.confirm_clear_messages = false;
+        clear_messages(app);
+    } else if cancelled {
+        app.confirm_clear_messages = false;
+    }
+}
+
+// 书签下拉列表，选中后滚动到该条消息并短暂高亮
+fn render_bookmark_list(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    let bookmarks: Vec<(usize, String)> = lock_or_recover(&app.received_messages)
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.bookmarked)
+        .map(|(index, entry)| {
+            let text = match truncate_chars(&entry.text, BOOKMARK_LABEL_LEN) {
+                Some(prefix) => format!("{}…", prefix),
+                None => entry.text.clone(),
+            };
+            (index, format!("[{}] {}", entry.timestamp, text))
+        })
+        .collect();
+
+    egui::ComboBox::from_id_salt("bookmarks")
+        .width(110.0)
+        .selected_text(trf!("🔖 书签 ({})", bookmarks.len()))
+        .show_ui(ui, |ui| {
+            if bookmarks.is_empty() {
+                ui.weak(tr("点击消息左侧的 🔖 添加书签"));
+            }
+            for (index, label) in bookmarks {
+                if ui.selectable_label(false, label).clicked() {
+                    app.bookmark_jump = Some(BookmarkJump {
+                        index,
+                        at: std::time::Instant::now(),
+                        scroll: true,
                     });
                 }
             }
         });
-    });
+}
+
+// 弹出文件对话框选择保存位置，将消息列表导出为文本，书签条目以星号开头
+fn save_messages(app: &mut TcpClientApp) {
+    let file_name = format!("messages_{}.txt", get_file_timestamp());
+    let Some(path) = rfd::FileDialog::new()
+        .set_file_name(file_name)
+        .add_filter(tr("文本"), &["txt"])
+        .save_file()
+    else {
+        return; // 用户取消了保存
+    };
+
+    let content: String = lock_or_recover(&app.received_messages)
+        .iter()
+        .map(|entry| entry.export_line() + "\n")
+        .collect();
+    let (status, kind) = match std::fs::write(&path, content) {
+        Ok(()) => (trf!("消息已导出: {}", path.display()), NotificationKind::Connection),
+        Err(e) => (trf!("导出消息失败: {}", e), NotificationKind::Error),
+    };
+    notify(&app.notifications, kind, status);
+}
+
+fn display_mode_label(mode: DisplayMode) -> &'static str {
+    match mode {
+        DisplayMode::Utf8 => "UTF-8",
+        DisplayMode::Hex => "HEX",
+        DisplayMode::Decimal => tr("十进制"),
+    }
 }
 
 // 底部发送面板
@@ -227,88 +1600,251 @@ pub fn render_send_panel(app: &mut TcpClientApp, ui: &mut egui::Ui) {
 // 渲染发送面板标题
 fn render_send_panel_header(ui: &mut egui::Ui) {
     ui.vertical_centered(|ui| {
-        ui.heading("发送消息");
+        ui.heading(tr("发送消息"));
     });
     ui.add_space(10.0);
 }
 
 // 渲染消息输入区域
 fn render_message_input_area(app: &mut TcpClientApp, ui: &mut egui::Ui) {
-    let input_frame = create_input_frame();
+    let input_frame = create_input_frame(Theme::of(ui.ctx()));
 
     input_frame.show(ui, |ui| {
         // 根据编码模式显示不同的提示文本
         let hint_text = match app.encoding_mode {
-            EncodingMode::Utf8 => "输入要发送的UTF-8消息...",
-            EncodingMode::Hex => "输入要发送的十六进制数据(如: 48 65 6C 6C 6F)...",
+            EncodingMode::Utf8 => tr("输入要发送的UTF-8消息..."),
+            EncodingMode::Hex => tr("输入要发送的十六进制数据(如: 48 65 6C 6C 6F)..."),
+        };
+
+        // 十六进制模式下高亮显示非法字符
+        let is_hex_mode = app.encoding_mode == EncodingMode::Hex;
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let mut job = if is_hex_mode {
+                hex_highlight_layout_job(text)
+            } else {
+                egui::text::LayoutJob::simple(
+                    text.to_string(),
+                    egui::TextStyle::Body.resolve(ui.style()),
+                    ui.visuals().text_color(),
+                    f32::INFINITY,
+                )
+            };
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|f| f.layout_job(job))
         };
 
         let text_edit = egui::TextEdit::multiline(&mut app.send_text)
             .desired_width(f32::INFINITY)
             .desired_rows(3)
-            .hint_text(hint_text);
+            .hint_text(hint_text)
+            .layouter(&mut layouter);
+
+        ui.add(text_edit);
+
+        // 如果是十六进制模式，显示字节数并提供格式化按钮
+        if is_hex_mode {
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.label(trf!("{} 字节", hex_byte_count(&app.send_text)));
 
-        ui.add(text_edit);
+                if ui.small_button(tr("格式化")).clicked() {
+                    app.send_text = format_hex_string(&app.send_text);
+                }
 
-        // 如果是十六进制模式，验证输入
-        if app.encoding_mode == EncodingMode::Hex && !app.send_text.is_empty() {
-            if !is_valid_hex_string(&app.send_text) {
-                ui.add_space(5.0);
-                ui.colored_label(
-                    egui::Color32::from_rgb(220, 50, 50),
-                    "无效的十六进制格式，请使用空格分隔的十六进制值(如: 48 65 6C 6C 6F)"
-                );
-            }
+                // 验证输入，给出具体的错误原因
+                if let Some(error) = hex_input_error(&app.send_text) {
+                    ui.colored_label(Theme::of(ui.ctx()).error, error);
+                }
+            });
         }
     });
 }
 
-// 验证十六进制字符串是否有效
-fn is_valid_hex_string(s: &str) -> bool {
-    // 允许空格分隔的十六进制字符串
-    let hex_str = s.replace(" ", "");
+// 为十六进制输入构建布局，非法字符以红色背景标出
+fn hex_highlight_layout_job(text: &str) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let normal = egui::TextFormat {
+        font_id: egui::FontId::monospace(14.0),
+        ..Default::default()
+    };
+    let invalid = egui::TextFormat {
+        font_id: egui::FontId::monospace(14.0),
+        color: egui::Color32::WHITE,
+        background: egui::Color32::from_rgb(220, 50, 50),
+        ..Default::default()
+    };
+
+    let annotation = egui::TextFormat {
+        font_id: egui::FontId::monospace(14.0),
+        color: egui::Color32::GRAY,
+        ..Default::default()
+    };
+
+    // 偏移列和注释以灰色显示，不作为非法字符
+    for line in text.split_inclusive('\n') {
+        let data = hex_data_range(line);
+        for (i, c) in line.char_indices() {
+            let format = if !data.contains(&i) && !c.is_whitespace() {
+                annotation.clone()
+            } else if c.is_ascii_hexdigit() || c.is_whitespace() {
+                normal.clone()
+            } else {
+                invalid.clone()
+            };
+            job.append(c.encode_utf8(&mut [0; 4]), 0.0, format);
+        }
+    }
+    job
+}
+
+// 统计十六进制输入中的完整字节数
+fn hex_byte_count(s: &str) -> usize {
+    strip_hex_annotations(s)
+        .chars().filter(|c| c.is_ascii_hexdigit()).count() / 2
+}
+
+// 将十六进制输入整理为大写、空格分隔的两位一组格式，偏移列和注释会被去掉
+fn format_hex_string(s: &str) -> String {
+    let chars: Vec<char> = strip_hex_annotations(s)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    chars
+        .chunks(2)
+        .map(|pair| pair.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-    // 如果去除空格后为空，则返回true
-    if hex_str.is_empty() {
-        return true;
+// 返回十六进制输入的具体错误描述，输入有效时返回None
+fn hex_input_error(s: &str) -> Option<String> {
+    let invalid: Vec<char> = strip_hex_annotations(s)
+        .chars()
+        .filter(|c| !c.is_ascii_hexdigit() && !c.is_whitespace())
+        .collect();
+
+    if !invalid.is_empty() {
+        let mut shown: Vec<String> = invalid.iter().take(5).map(|c| format!("'{}'", c)).collect();
+        if invalid.len() > 5 {
+            shown.push("...".to_string());
+        }
+        return Some(trf!("包含非法字符: {}", shown.join(" ")));
     }
 
-    // 检查长度是否为偶数
-    if hex_str.len() % 2 != 0 {
-        return false;
+    if !is_valid_hex_string(s) {
+        return Some(tr("十六进制位数为奇数，最后一个字节不完整").to_string());
     }
 
-    // 检查每个字符是否是有效的十六进制字符
-    hex_str.chars().all(|c| c.is_digit(16))
+    None
 }
 
 // 创建输入框架
-fn create_input_frame() -> egui::Frame {
+fn create_input_frame(theme: &Theme) -> egui::Frame {
     egui::Frame::new()
-        .fill(egui::Color32::from_rgb(250, 250, 255))
-        .stroke(egui::Stroke::new(1.0, egui::Color32::from_gray(200)))
+        .fill(theme.list_fill)
+        .stroke(egui::Stroke::new(1.0, theme.border))
         .inner_margin(egui::vec2(10.0, 10.0))
 }
 
 // 渲染发送控制按钮
 fn render_send_controls(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    let batch_running = lock_or_recover(&app.batch_progress).running;
+
+    // 批量发送：每行一条，依次发送并显示进度
+    let batch_lines = app
+        .batch_mode
+        .then(|| split_batch_lines(&app.send_text, app.encoding_mode));
+    ui.horizontal(|ui| {
+        ui.add_enabled(
+            !batch_running,
+            egui::Checkbox::new(&mut app.batch_mode, tr("逐行批量发送")),
+        );
+        if app.batch_mode {
+            ui.label(tr("间隔"));
+            ui.add_enabled(
+                !batch_running,
+                egui::DragValue::new(&mut app.batch_interval_ms)
+                    .range(0..=60_000)
+                    .suffix(" ms"),
+            );
+            match &batch_lines {
+                Some(Ok(lines)) if !batch_running => {
+                    ui.weak(trf!("共 {} 条", lines.len()));
+                }
+                Some(Err(line)) => {
+                    ui.colored_label(
+                        Theme::of(ui.ctx()).error,
+                        trf!("第 {} 行十六进制格式无效", line),
+                    );
+                }
+                _ => {}
+            }
+        }
+        if batch_running {
+            let progress = lock_or_recover(&app.batch_progress).clone();
+            ui.add(
+                egui::ProgressBar::new(progress.sent as f32 / progress.total.max(1) as f32)
+                    .desired_width(150.0)
+                    .text(format!("{}/{}", progress.sent, progress.total)),
+            );
+            if ui.button(tr("停止")).clicked() {
+                lock_or_recover(&app.batch_progress).running = false;
+            }
+        }
+    });
+    ui.add_space(5.0);
+
+    // 转义序列：关闭时反斜杠按原样发送，批量发送时不解析
+    if app.encoding_mode == EncodingMode::Utf8 && !app.batch_mode {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut app.escape_sequences, tr("解析转义序列"))
+                .on_hover_text(tr("支持 \\n \\r \\t \\0 \\\\ \\xNN，含转义的内容按十六进制发送"));
+            if app.escape_sequences {
+                if let Err(sequence) = unescape(&app.send_text) {
+                    ui.colored_label(
+                        Theme::of(ui.ctx()).error,
+                        trf!("无效的转义序列: {}", sequence),
+                    );
+                }
+            }
+        });
+        ui.add_space(5.0);
+    }
+
+    // 随机数据：生成指定长度的随机字节直接发送，用于测试设备对异常输入的处理
+    ui.horizontal(|ui| {
+        let random_enabled = app.is_connected && !batch_running;
+        if ui
+            .add_enabled(random_enabled, egui::Button::new(tr("随机数据")))
+            .on_hover_text(tr("生成随机字节并发送，发送记录中以十六进制保存，便于复现"))
+            .clicked()
+        {
+            send_random_payload(app);
+        }
+        ui.add(
+            egui::DragValue::new(&mut app.random_len)
+                .range(1..=MAX_RANDOM_LEN)
+                .suffix(tr(" 字节")),
+        );
+        ui.checkbox(&mut app.random_printable, tr("仅可打印ASCII"));
+    });
+    ui.add_space(5.0);
+
     ui.horizontal(|ui| {
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
             // 清空按钮
             render_clear_button(app, ui);
 
             ui.add_space(10.0);
-
-            // 检查十六进制格式是否有效
-            let hex_valid = if app.encoding_mode == EncodingMode::Hex && !app.send_text.is_empty() {
-                is_valid_hex_string(&app.send_text)
-            } else {
-                true
-            };
+            ui.checkbox(&mut app.clear_after_send, tr("发送后清空"))
+                .on_hover_text(tr("关闭时发送成功后保留输入内容，便于重复发送"));
+            ui.add_space(10.0);
 
             // 发送按钮
-            let send_enabled = !app.send_text.is_empty() && app.is_connected && hex_valid;
-            let send_button = create_send_button();
+            let send_enabled = can_send(app, &batch_lines);
+            let send_button = create_send_button(Theme::of(ui.ctx()));
 
             let send_response = if send_enabled {
                 ui.add(send_button)
@@ -318,18 +1854,51 @@ fn render_send_controls(app: &mut TcpClientApp, ui: &mut egui::Ui) {
 
             // 处理发送按钮点击
             if send_response.clicked() && send_enabled {
-                handle_send_button_click(app);
+                send_input(app);
             }
         });
     });
 }
 
+// 发送框中的内容当前能否发送：已连接、没有进行中的批量发送，且十六进制格式或转义序列有效
+// 批量发送时按行检查
+fn can_send(app: &TcpClientApp, batch_lines: &Option<Result<Vec<String>, usize>>) -> bool {
+    let input_valid = match batch_lines {
+        Some(lines) => lines.as_ref().is_ok_and(|lines| !lines.is_empty()),
+        None if app.encoding_mode == EncodingMode::Hex && !app.send_text.is_empty() => {
+            is_valid_hex_string(&app.send_text)
+        }
+        None if app.encoding_mode == EncodingMode::Utf8 && app.escape_sequences => {
+            unescape(&app.send_text).is_ok()
+        }
+        None => true,
+    };
+    !app.send_text.is_empty()
+        && app.is_connected
+        && input_valid
+        && !lock_or_recover(&app.batch_progress).running
+}
+
+// 发送输入框中的内容，不能发送时忽略；发送按钮和发送快捷键共用
+pub fn send_input(app: &mut TcpClientApp) {
+    let batch_lines = app
+        .batch_mode
+        .then(|| split_batch_lines(&app.send_text, app.encoding_mode));
+    if !can_send(app, &batch_lines) {
+        return;
+    }
+    match batch_lines {
+        Some(Ok(lines)) => start_batch_send(app, lines),
+        _ => handle_send_button_click(app),
+    }
+}
+
 // 渲染清空按钮
 fn render_clear_button(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     if ui
         .add(
-            egui::Button::new("清空")
-                .fill(egui::Color32::from_rgb(150, 150, 150))
+            egui::Button::new(tr("清空"))
+                .fill(Theme::of(ui.ctx()).neutral_button)
                 .min_size(egui::vec2(80.0, 28.0)),
         )
         .clicked()
@@ -339,32 +1908,83 @@ fn render_clear_button(app: &mut TcpClientApp, ui: &mut egui::Ui) {
 }
 
 // 创建发送按钮
-fn create_send_button() -> egui::Button<'static> {
-    egui::Button::new("发送")
-        .fill(egui::Color32::from_rgb(100, 150, 220))
+fn create_send_button(theme: &Theme) -> egui::Button<'static> {
+    egui::Button::new(tr("发送"))
+        .fill(theme.primary_button)
         .min_size(egui::vec2(80.0, 28.0))
 }
 
 // 处理发送按钮点击
 fn handle_send_button_click(app: &mut TcpClientApp) {
     // 如果是十六进制模式，验证输入
-    if app.encoding_mode == EncodingMode::Hex && !app.send_text.is_empty() {
-        if !is_valid_hex_string(&app.send_text) {
-            // 如果十六进制格式无效，不发送
-            app.received_messages.lock().unwrap().push((
-                get_timestamp(),
-                "无法发送: 十六进制格式无效".to_string(),
-            ));
-            return;
+    if app.encoding_mode == EncodingMode::Hex
+        && !app.send_text.is_empty()
+        && !is_valid_hex_string(&app.send_text)
+    {
+        // 如果十六进制格式无效，不发送
+        lock_or_recover(&app.received_messages).push(MessageEntry::new(
+            get_timestamp(),
+            MessageKind::Error,
+            tr("无法发送: 十六进制格式无效").to_string(),
+        ));
+        return;
+    }
+
+    // 解析转义序列后含有转义的内容按十六进制发送，以便发送控制字符和非 UTF-8 字节
+    let (text, encoding_mode) = if app.encoding_mode == EncodingMode::Utf8 && app.escape_sequences {
+        match unescape(&app.send_text) {
+            Ok(bytes) if bytes == app.send_text.as_bytes() => (app.send_text.clone(), EncodingMode::Utf8),
+            Ok(bytes) => (payload_hex(&bytes), EncodingMode::Hex),
+            Err(sequence) => {
+                lock_or_recover(&app.received_messages).push(MessageEntry::new(
+                    get_timestamp(),
+                    MessageKind::Error,
+                    trf!("无法发送: 无效的转义序列 {}", sequence),
+                ));
+                return;
+            }
         }
+    } else {
+        (app.send_text.clone(), app.encoding_mode)
+    };
+    // 发送失败时保留输入内容，方便重试；成功后是否清空由发送后清空选项决定
+    if dispatch_command(app, Message::Send(text.clone(), encoding_mode)) {
+        // 录制中时记录本次发送
+        if let Some(recorder) = &mut app.session_recorder {
+            recorder.record(&text, encoding_mode);
+        }
+        app.finish_send();
     }
+}
 
-    if let Some(tx) = &app.tx {
-        let tx = tx.clone();
-        let text = app.send_text.clone();
-        let encoding_mode = app.encoding_mode;
-        send_message(&tx, text, encoding_mode);
-        app.send_text.clear();
+// 生成随机数据并按十六进制发送，发送记录中的十六进制内容可以直接粘贴重发
+fn send_random_payload(app: &mut TcpClientApp) {
+    let data = random_payload(app.random_len, app.random_printable);
+    let hex = payload_hex(&data);
+    let kind = if app.random_printable { tr("可打印ASCII") } else { tr("任意字节") };
+    lock_or_recover(&app.received_messages).push(MessageEntry::new(
+        get_timestamp(),
+        MessageKind::Info,
+        trf!("生成随机数据: {} 字节 ({})", data.len(), kind),
+    ));
+    if dispatch_command(app, Message::Send(hex.clone(), EncodingMode::Hex)) {
+        if let Some(recorder) = &mut app.session_recorder {
+            recorder.record(&hex, EncodingMode::Hex);
+        }
+    }
+}
+
+// 开始批量发送，保留输入内容以便再次发送同一组消息
+fn start_batch_send(app: &mut TcpClientApp, lines: Vec<String>) {
+    *lock_or_recover(&app.batch_progress) = BatchProgress {
+        running: true,
+        total: lines.len(),
+        ..Default::default()
+    };
+    let interval = std::time::Duration::from_millis(app.batch_interval_ms);
+    let message = Message::SendBatch(lines, app.encoding_mode, interval, app.batch_progress.clone());
+    if !dispatch_command(app, message) {
+        lock_or_recover(&app.batch_progress).running = false;
     }
 }
 
@@ -380,9 +2000,9 @@ pub fn render_scan_panel(app: &mut TcpClientApp, ui: &mut egui::Ui) {
 // 渲染扫描面板标题
 fn render_scan_panel_header(ui: &mut egui::Ui) {
     // 顶部标题和描述 - 使用更现代的设计
-    let header_bg = egui::Color32::from_rgb(41, 128, 185); // 漆蓝色背景
+    let theme = Theme::of(ui.ctx());
     let header = egui::Frame::new()
-        .fill(header_bg)
+        .fill(theme.accent)
         .inner_margin(egui::vec2(20.0, 15.0))
         .outer_margin(egui::vec2(0.0, 0.0));
 
@@ -390,15 +2010,15 @@ fn render_scan_panel_header(ui: &mut egui::Ui) {
         ui.vertical_centered(|ui| {
             ui.horizontal(|ui| {
                 ui.heading(
-                    egui::RichText::new("IP扫描工具")
-                        .color(egui::Color32::WHITE)
+                    egui::RichText::new(tr("IP扫描工具"))
+                        .color(theme.accent_text)
                         .size(24.0),
                 );
             });
             ui.add_space(5.0);
             ui.label(
-                egui::RichText::new("扫描网络中的开放端口，快速发现可用服务")
-                    .color(egui::Color32::WHITE),
+                egui::RichText::new(tr("扫描网络中的开放端口，快速发现可用服务"))
+                    .color(theme.accent_text),
             );
         });
     });
@@ -412,6 +2032,9 @@ pub fn render_scan_left_panel(app: &mut TcpClientApp, ui: &mut egui::Ui) {
         // 扫描设置区域
         render_scan_settings(app, ui);
 
+        // 扫描历史记录
+        render_scan_history_section(app, ui);
+
         // 添加使用说明
         render_scan_help_section(ui);
     });
@@ -419,20 +2042,21 @@ pub fn render_scan_left_panel(app: &mut TcpClientApp, ui: &mut egui::Ui) {
 
 // 渲染扫描设置区域
 fn render_scan_settings(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    let theme = Theme::of(ui.ctx());
     let scan_frame = egui::Frame::new()
-        .fill(egui::Color32::from_rgb(245, 245, 250))
+        .fill(theme.panel_fill)
         .inner_margin(egui::vec2(15.0, 15.0))
         .outer_margin(egui::vec2(0.0, 0.0))
         .corner_radius(8.0)
-        .stroke(egui::Stroke::new(1.0, egui::Color32::from_gray(200)));
+        .stroke(egui::Stroke::new(1.0, theme.border));
 
     scan_frame.show(ui, |ui| {
         // 设置区域标题
         ui.vertical_centered(|ui| {
             ui.add_space(5.0);
             ui.heading(
-                egui::RichText::new("扫描设置")
-                    .color(egui::Color32::from_rgb(41, 128, 185))
+                egui::RichText::new(tr("扫描设置"))
+                    .color(theme.accent)
                     .size(18.0),
             );
         });
@@ -451,17 +2075,40 @@ fn render_scan_settings(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     });
 }
 
+// 整数输入框，不是整数时显示红色边框；返回检查结果，超出 range 时由调用方提示实际使用的值
+fn number_input(ui: &mut egui::Ui, text: &mut String, range: RangeInclusive<u64>, hint: &str) -> NumberInput {
+    let input = parse_number_input(text, range);
+    let theme = Theme::of(ui.ctx());
+    ui.scope(|ui| {
+        if input == NumberInput::Invalid {
+            let stroke = egui::Stroke::new(1.5, theme.error);
+            let visuals = ui.visuals_mut();
+            visuals.widgets.inactive.bg_stroke = stroke;
+            visuals.widgets.hovered.bg_stroke = stroke;
+            visuals.selection.stroke = stroke;
+        }
+        ui.add(
+            egui::TextEdit::singleline(text)
+                .desired_width(150.0)
+                .hint_text(hint)
+                .margin(egui::vec2(8.0, 6.0))
+                .text_color(theme.accent),
+        );
+    });
+    input
+}
+
 // 渲染IP和端口输入区域
 fn render_ip_port_inputs(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     ui.horizontal(|ui| {
         ui.add_space(5.0);
-        ui.strong(egui::RichText::new("起始IP:").size(16.0));
+        ui.strong(egui::RichText::new(tr("起始IP:")).size(16.0));
         ui.add(
             egui::TextEdit::singleline(&mut app.start_ip)
                 .desired_width(150.0)
                 .hint_text("192.168.1.1")
                 .margin(egui::vec2(8.0, 6.0))
-                .text_color(egui::Color32::from_rgb(41, 128, 185)),
+                .text_color(Theme::of(ui.ctx()).accent),
         );
     });
 
@@ -469,13 +2116,13 @@ fn render_ip_port_inputs(app: &mut TcpClientApp, ui: &mut egui::Ui) {
 
     ui.horizontal(|ui| {
         ui.add_space(5.0);
-        ui.strong(egui::RichText::new("结束IP:").size(16.0));
+        ui.strong(egui::RichText::new(tr("结束IP:")).size(16.0));
         ui.add(
             egui::TextEdit::singleline(&mut app.end_ip)
                 .desired_width(150.0)
                 .hint_text("192.168.1.255")
                 .margin(egui::vec2(8.0, 6.0))
-                .text_color(egui::Color32::from_rgb(41, 128, 185)),
+                .text_color(Theme::of(ui.ctx()).accent),
         );
     });
 
@@ -483,13 +2130,13 @@ fn render_ip_port_inputs(app: &mut TcpClientApp, ui: &mut egui::Ui) {
 
     ui.horizontal(|ui| {
         ui.add_space(5.0);
-        ui.strong(egui::RichText::new("起始端口:").size(16.0));
+        ui.strong(egui::RichText::new(tr("起始端口:")).size(16.0));
         ui.add(
             egui::TextEdit::singleline(&mut app.start_port)
                 .desired_width(150.0)
                 .hint_text("8888")
                 .margin(egui::vec2(8.0, 6.0))
-                .text_color(egui::Color32::from_rgb(41, 128, 185)),
+                .text_color(Theme::of(ui.ctx()).accent),
         );
     });
 
@@ -497,43 +2144,110 @@ fn render_ip_port_inputs(app: &mut TcpClientApp, ui: &mut egui::Ui) {
 
     ui.horizontal(|ui| {
         ui.add_space(5.0);
-        ui.strong(egui::RichText::new("结束端口:").size(16.0));
+        ui.strong(egui::RichText::new(tr("结束端口:")).size(16.0));
         ui.add(
             egui::TextEdit::singleline(&mut app.end_port)
                 .desired_width(150.0)
                 .hint_text("8889")
                 .margin(egui::vec2(8.0, 6.0))
-                .text_color(egui::Color32::from_rgb(41, 128, 185)),
+                .text_color(Theme::of(ui.ctx()).accent),
         );
     });
 
     ui.add_space(5.0);
 
-    ui.horizontal(|ui| {
-        ui.add_space(5.0);
-        ui.strong(egui::RichText::new("超时时间(ms):").size(16.0));
-        ui.add(
-            egui::TextEdit::singleline(&mut app.timeout_ms)
-                .desired_width(150.0)
-                .hint_text("500")
-                .margin(egui::vec2(8.0, 6.0))
-                .text_color(egui::Color32::from_rgb(41, 128, 185)),
-        );
-    });
+    let timeout = ui
+        .horizontal(|ui| {
+            ui.add_space(5.0);
+            ui.strong(egui::RichText::new(tr("超时时间(ms):")).size(16.0));
+            number_input(ui, &mut app.timeout_ms, SCAN_TIMEOUT_RANGE_MS, "500")
+        })
+        .inner;
+    let theme = Theme::of(ui.ctx());
+    let (min, max) = SCAN_TIMEOUT_RANGE_MS.into_inner();
+    match timeout {
+        NumberInput::Valid(_) => {}
+        NumberInput::Clamped(ms) => {
+            ui.colored_label(theme.warning, trf!("超出 {}-{} ms 的范围，将按 {} ms 扫描", min, max, ms));
+        }
+        NumberInput::Invalid => {
+            ui.colored_label(theme.error, tr("请输入毫秒数"));
+        }
+    }
+
+    ui.add_space(5.0);
+
+    // 排除列表 - 每行一个IP或CIDR网段
+    ui.strong(tr("排除IP:"));
+    ui.add(
+        egui::TextEdit::multiline(&mut app.scan_excludes)
+            .desired_rows(3)
+            .desired_width(150.0)
+            .hint_text("192.168.1.1\n192.168.1.200/30"),
+    )
+    .on_hover_text(tr("每行一个IP或CIDR网段，扫描时跳过这些地址"));
+    if let Err(e) = parse_exclude_list(&app.scan_excludes) {
+        ui.colored_label(Theme::of(ui.ctx()).error, e);
+    }
+
+    ui.add_space(5.0);
+
+    // 高级设置 - 重试、限速、并发和扫描范围上限
+    egui::CollapsingHeader::new(tr("高级设置"))
+        .id_salt("scan_advanced_settings")
+        .show(ui, |ui| {
+            egui::Grid::new("scan_advanced_grid")
+                .num_columns(2)
+                .spacing([10.0, 6.0])
+                .show(ui, |ui| {
+                    ui.label(tr("重试次数:"));
+                    ui.add(egui::DragValue::new(&mut app.scan_retries).range(0..=MAX_SCAN_RETRIES))
+                        .on_hover_text(tr("端口探测失败后的重试次数，可减少丢包导致的漏报，但会增加扫描时间"));
+                    ui.end_row();
+
+                    ui.label(tr("限速(连接/秒):"));
+                    ui.add(egui::DragValue::new(&mut app.scan_rate_limit).range(0..=100_000))
+                        .on_hover_text(tr("每秒最多新建的连接数，0 表示不限速"));
+                    ui.end_row();
+
+                    ui.label(tr("最大并发:"));
+                    ui.add(egui::DragValue::new(&mut app.scan_max_connections).range(1..=10_000))
+                        .on_hover_text(tr("同时进行的连接尝试上限，过高可能耗尽系统文件描述符导致端口被误判为关闭"));
+                    ui.end_row();
+
+                    ui.label(tr("IP数量上限:"));
+                    ui.add(egui::DragValue::new(&mut app.scan_max_ips).range(1..=u32::MAX as u64 + 1));
+                    ui.end_row();
+
+                    ui.label(tr("端口数量上限:"));
+                    ui.add(egui::DragValue::new(&mut app.scan_max_ports).range(1..=65536));
+                    ui.end_row();
+
+                    ui.label(tr("源地址:"));
+                    render_source_addr_input(app, ui);
+                    ui.end_row();
+
+                    ui.label(tr("详细日志:"));
+                    ui.checkbox(&mut app.scan_verbose_logs, tr("显示进度、限速等信息"))
+                        .on_hover_text(tr("关闭时日志只保留开始、发现端口、错误、取消和完成等关键事件"));
+                    ui.end_row();
+                });
+        });
 }
 
 // 渲染扫描按钮
 fn render_scan_button(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     ui.vertical_centered(|ui| {
-        let button_text = if app.is_scanning {
-            "停止扫描"
+        let button_text = if app.is_scanning.load(Ordering::Relaxed) {
+            tr("停止扫描")
         } else {
-            "开始扫描"
+            tr("开始扫描")
         };
-        let button_color = if app.is_scanning {
-            egui::Color32::from_rgb(220, 100, 100)
+        let theme = Theme::of(ui.ctx());
+        let button_color = if app.is_scanning.load(Ordering::Relaxed) {
+            theme.danger_button
         } else {
-            egui::Color32::from_rgb(100, 150, 220)
+            theme.primary_button
         };
 
         if ui
@@ -545,109 +2259,130 @@ fn render_scan_button(app: &mut TcpClientApp, ui: &mut egui::Ui) {
             )
             .clicked()
         {
-            if !app.is_scanning {
-                // 验证输入
-                if is_valid_ip(&app.start_ip) && is_valid_ip(&app.end_ip) {
-                    if is_valid_port(&app.start_port) && is_valid_port(&app.end_port) {
-                        if is_valid_ip_range(&app.start_ip, &app.end_ip) {
-                            if is_valid_port_range(&app.start_port, &app.end_port) {
-                                if let (Ok(start_port), Ok(end_port)) = (app.start_port.parse::<u16>(), app.end_port.parse::<u16>()) {
-                                    if let Some(tx) = &app.tx {
-                                        let tx = tx.clone();
-                                        let start_ip = app.start_ip.clone();
-                                        let end_ip = app.end_ip.clone();
-
-                                        // 验证超时时间
-                                        if let Ok(timeout_ms) = app.timeout_ms.parse::<u64>() {
-                                            // 发送扫描命令
-                                            let scan_results = app.scan_results.clone();
-                                            let scan_logs = app.scan_logs.clone();
-                                            tokio::spawn(async move {
-                                                let _ = tx
-                                                    .send(Message::ScanIp(
-                                                        start_ip,
-                                                        end_ip,
-                                                        start_port,
-                                                        end_port,
-                                                        timeout_ms,
-                                                        scan_results,
-                                                        scan_logs,
-                                                    ))
-                                                    .await;
-                                            });
-
-                                            app.is_scanning = true;
-                                            app.scan_results.lock().unwrap().clear(); // 清空之前的结果
-                                            app.scan_logs.lock().unwrap().clear(); // 清空之前的日志
-                                        } else {
-                                            // 超时时间格式错误
-                                            let error_msg = "超时时间格式无效";
-                                            let timestamp = get_timestamp();
-                                            app.scan_logs
-                                                .lock()
-                                                .unwrap()
-                                                .push((timestamp.clone(), error_msg.to_string()));
-                                        }
-                                    }
-                                } else {
-                                    // 端口格式错误
-                                    let error_msg = "端口格式无效";
-                                    let timestamp = get_timestamp();
-                                    app.scan_logs
-                                        .lock()
-                                        .unwrap()
-                                        .push((timestamp.clone(), error_msg.to_string()));
-                                }
-                            } else {
-                                // 端口范围无效
-                                let error_msg = "端口范围无效或超过最大扫描范围(1000个端口)";
-                                let timestamp = get_timestamp();
-                                app.scan_logs
-                                    .lock()
-                                    .unwrap()
-                                    .push((timestamp.clone(), error_msg.to_string()));
-                            }
-                        } else {
-                            // IP范围无效
-                            let error_msg = "IP范围无效或超过最大扫描范围(1000个IP)";
-                            let timestamp = get_timestamp();
-                            app.scan_logs
-                                .lock()
-                                .unwrap()
-                                .push((timestamp.clone(), error_msg.to_string()));
-                        }
-                    } else {
-                        // 端口格式错误
-                        let error_msg = "端口格式无效";
-                        let timestamp = get_timestamp();
-                        app.scan_logs
-                            .lock()
-                            .unwrap()
-                            .push((timestamp.clone(), error_msg.to_string()));
+            if !app.is_scanning.load(Ordering::Relaxed) {
+                if app.tx.is_none() {
+                    return;
+                }
+                match scan_config_from_inputs(app) {
+                    // 超大范围扫描需要用户确认
+                    Ok(config) if total_probes(&config) > LARGE_SCAN_PROBES => {
+                        app.pending_scan = Some(config);
                     }
-                } else {
-                    // IP格式错误
-                    let error_msg = "IP地址格式无效";
-                    let timestamp = get_timestamp();
-                    app.scan_logs
-                        .lock()
-                        .unwrap()
-                        .push((timestamp.clone(), error_msg.to_string()));
+                    Ok(config) => start_scan(app, config),
+                    Err(e) => lock_or_recover(&app.scan_logs).push((get_timestamp(), e)),
                 }
             } else {
-                // 停止扫描
-                app.is_scanning = false;
-                let cancel_msg = "用户取消扫描";
+                // 停止扫描，扫描任务持有同一个标志，会尽快退出
+                app.is_scanning.store(false, Ordering::Relaxed);
+                let cancel_msg = tr("用户取消扫描");
                 let timestamp = get_timestamp();
-                app.scan_logs
-                    .lock()
-                    .unwrap()
-                    .push((timestamp.clone(), cancel_msg.to_string()));
+                lock_or_recover(&app.scan_logs).push((timestamp.clone(), cancel_msg.to_string()));
             }
         }
     });
 }
 
+// 按扫描设置区域的输入生成扫描参数，输入无效时返回具体原因
+fn scan_config_from_inputs(app: &TcpClientApp) -> Result<ScanConfig, String> {
+    let spec = parse_scan_spec(&ScanForm {
+        start_ip: &app.start_ip,
+        end_ip: &app.end_ip,
+        start_port: &app.start_port,
+        end_port: &app.end_port,
+        timeout_ms: &app.timeout_ms,
+        max_ips: app.scan_max_ips,
+        max_ports: app.scan_max_ports,
+    })?;
+    Ok(ScanConfig {
+        start_ip: spec.start_ip,
+        end_ip: spec.end_ip,
+        start_port: spec.start_port,
+        end_port: spec.end_port,
+        timeout_ms: spec.timeout_ms,
+        retries: app.scan_retries,
+        rate_limit: app.scan_rate_limit,
+        max_connections: app.scan_max_connections,
+        source_addr: parse_source_addr(&app.source_addr)?,
+        excludes: parse_exclude_list(&app.scan_excludes)?,
+        verbose_logs: app.scan_verbose_logs,
+    })
+}
+
+// 发送扫描命令并更新界面状态
+fn start_scan(app: &mut TcpClientApp, config: ScanConfig) {
+    // 先清空旧状态，避免覆盖网络任务写入的新日志
+    app.scan_results_page = 0;
+    lock_or_recover(&app.scan_results).clear(); // 清空之前的结果
+    lock_or_recover(&app.scan_logs).clear(); // 清空之前的日志
+    *lock_or_recover(&app.scan_progress) = ScanProgress::default();
+    *lock_or_recover(&app.scan_record) = None;
+
+    // 超出范围的超时时间已按边界值调整，在日志中说明
+    if let NumberInput::Clamped(ms) = parse_number_input(&app.timeout_ms, SCAN_TIMEOUT_RANGE_MS) {
+        lock_or_recover(&app.scan_logs).push((
+            get_timestamp(),
+            trf!("超时时间 {} ms 超出范围，已按 {} ms 扫描", app.timeout_ms.trim(), ms),
+        ));
+    }
+
+    let message = Message::ScanIp(
+        config,
+        app.scan_results.clone(),
+        app.scan_logs.clone(),
+        app.scan_progress.clone(),
+        app.is_scanning.clone(),
+        app.scan_record.clone(),
+    );
+    // 先置位再发送，避免扫描任务启动前界面仍显示未在扫描
+    app.is_scanning.store(true, Ordering::Relaxed);
+    if !dispatch_command(app, message) {
+        app.is_scanning.store(false, Ordering::Relaxed);
+        lock_or_recover(&app.scan_logs).push((get_timestamp(), tr("扫描命令发送失败").to_string()));
+    }
+}
+
+// 超大范围扫描的确认对话框
+pub fn render_scan_confirm_window(app: &mut TcpClientApp, ctx: &egui::Context) {
+    let Some(config) = app.pending_scan.clone() else {
+        return;
+    };
+
+    let mut confirmed = false;
+    let mut cancelled = false;
+
+    egui::Window::new(tr("确认扫描"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label(trf!(
+                "本次扫描共需 {} 次探测 ({} - {}, 端口 {} - {})，可能耗时很长并产生大量网络流量。",
+                total_probes(&config),
+                config.start_ip,
+                config.end_ip,
+                config.start_port,
+                config.end_port
+            ));
+            ui.label(tr("确定要开始扫描吗？"));
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                if ui.button(tr("开始扫描")).clicked() {
+                    confirmed = true;
+                }
+                if ui.button(tr("取消")).clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+
+    if confirmed {
+        app.pending_scan = None;
+        start_scan(app, config);
+    } else if cancelled {
+        app.pending_scan = None;
+    }
+}
+
 // 渲染扫描状态显示
 fn render_scan_status(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     ui.add_space(10.0);
@@ -655,77 +2390,170 @@ fn render_scan_status(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     ui.add_space(5.0);
 
     ui.horizontal(|ui| {
-        ui.strong("状态:");
-        let status_text = if app.is_scanning {
-            "正在扫描"
+        ui.strong(tr("状态:"));
+        let status_text = if app.is_scanning.load(Ordering::Relaxed) {
+            tr("正在扫描")
         } else {
-            "就绪"
+            tr("就绪")
         };
-        let status_color = if app.is_scanning {
-            egui::Color32::from_rgb(40, 180, 40)
+        let status_color = if app.is_scanning.load(Ordering::Relaxed) {
+            Theme::of(ui.ctx()).online
         } else {
-            egui::Color32::from_rgb(100, 100, 100)
+            Theme::of(ui.ctx()).idle
         };
         ui.colored_label(status_color, status_text);
     });
 
     // 扫描结果计数
-    let result_count = app.scan_results.lock().unwrap().len();
+    let result_count = lock_or_recover(&app.scan_results).len();
     ui.horizontal(|ui| {
-        ui.strong("发现端口:");
+        ui.strong(tr("发现端口:"));
         ui.label(format!("{}", result_count));
     });
+
+    // 进度条、耗时与剩余时间，扫描结束后显示总耗时
+    let progress = lock_or_recover(&app.scan_progress).clone();
+    if progress.started_at.is_none() {
+        return;
+    }
+    let fraction = if progress.total_probes == 0 {
+        0.0
+    } else {
+        (progress.completed_probes as f64 / progress.total_probes as f64).min(1.0) as f32
+    };
+    ui.add(
+        egui::ProgressBar::new(fraction)
+            .show_percentage()
+            .desired_width(200.0),
+    )
+    .on_hover_text(trf!(
+        "已完成 {} / {} 次探测",
+        progress.completed_probes, progress.total_probes
+    ));
+    ui.horizontal(|ui| {
+        let elapsed = format_duration(progress.elapsed());
+        if progress.finished_elapsed.is_none() {
+            ui.strong(tr("已耗时:"));
+            ui.label(elapsed);
+        } else if progress.cancelled {
+            ui.strong(tr("已取消:"));
+            ui.label(trf!("已进行 {}", elapsed));
+        } else {
+            ui.strong(tr("总耗时:"));
+            ui.label(elapsed);
+        }
+    });
+    if progress.finished_elapsed.is_none() {
+        ui.horizontal(|ui| {
+            ui.strong(tr("预计剩余:"));
+            match progress.eta() {
+                Some(eta) => ui.label(format_duration(eta)),
+                None => ui.weak(tr("计算中...")),
+            };
+        });
+    }
+}
+
+// 渲染扫描历史记录区域
+fn render_scan_history_section(app: &mut TcpClientApp, ui: &mut egui::Ui) {
+    ui.add_space(15.0);
+
+    let header = egui::CollapsingHeader::new(trf!("历史记录 ({})", app.scan_history.len()))
+        .id_salt("scan_history_header")
+        .show(ui, |ui| {
+            if ui.small_button(tr("刷新")).clicked() {
+                app.scan_history = load_scan_history();
+            }
+            ui.add_space(5.0);
+
+            if app.scan_history.is_empty() {
+                ui.weak(tr("暂无历史记录"));
+                return;
+            }
+
+            let mut selected = None;
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .id_salt("scan_history_scroll_area")
+                .show(ui, |ui| {
+                    for (index, record) in app.scan_history.iter().enumerate() {
+                        let response = ui
+                            .add_enabled(
+                                !app.is_scanning.load(Ordering::Relaxed),
+                                egui::Button::new(egui::RichText::new(record.summary()).size(12.0))
+                                    .wrap(),
+                            )
+                            .on_hover_text(tr("加载该次扫描结果"));
+                        if response.clicked() {
+                            selected = Some(index);
+                        }
+                    }
+                });
+
+            if let Some(index) = selected {
+                let record = &app.scan_history[index];
+                *lock_or_recover(&app.scan_results) = record.results.clone();
+                *lock_or_recover(&app.scan_record) = Some(record.clone());
+                lock_or_recover(&app.scan_logs).push((
+                    get_timestamp(),
+                    trf!("已加载历史记录: {}", record.summary()),
+                ));
+            }
+        });
+
+    // 点击标题展开时重新读取历史，以包含刚完成的扫描
+    if header.header_response.clicked() {
+        app.scan_history = load_scan_history();
+    }
 }
 
 // 渲染扫描帮助区域
 fn render_scan_help_section(ui: &mut egui::Ui) {
     ui.add_space(15.0);
+    let theme = Theme::of(ui.ctx());
     let help_frame = egui::Frame::new()
-        .fill(egui::Color32::from_rgb(253, 245, 230))
+        .fill(theme.help_fill)
         .inner_margin(egui::vec2(15.0, 15.0))
         .outer_margin(egui::vec2(0.0, 0.0))
         .corner_radius(8.0)
-        .stroke(egui::Stroke::new(
-            1.0,
-            egui::Color32::from_rgb(210, 180, 140),
-        ));
+        .stroke(egui::Stroke::new(1.0, theme.help_border));
 
     help_frame.show(ui, |ui| {
         ui.vertical_centered(|ui| {
             ui.horizontal(|ui| {
                 ui.add_space(5.0);
-                let info_color = egui::Color32::from_rgb(210, 105, 30);
+                let info_color = theme.help_heading;
                 ui.label(egui::RichText::new("ℹ").size(20.0).color(info_color));
                 ui.add_space(8.0);
-                ui.heading(egui::RichText::new("使用说明").color(info_color).size(18.0));
+                ui.heading(egui::RichText::new(tr("使用说明")).color(info_color).size(18.0));
             });
         });
         ui.add_space(10.0);
 
-        let tip_color = egui::Color32::from_rgb(160, 82, 45);
+        let tip_color = theme.help_text;
         ui.horizontal(|ui| {
             ui.label(egui::RichText::new("•").strong().color(tip_color));
-            ui.label(egui::RichText::new("输入IP范围和端口范围后点击开始扫描。").color(tip_color));
+            ui.label(egui::RichText::new(tr("输入IP范围和端口范围后点击开始扫描。")).color(tip_color));
         });
         ui.add_space(5.0);
         ui.horizontal(|ui| {
             ui.label(egui::RichText::new("•").strong().color(tip_color));
-            ui.label(egui::RichText::new("扫描结果将实时显示在右侧。").color(tip_color));
+            ui.label(egui::RichText::new(tr("扫描结果将实时显示在右侧。")).color(tip_color));
         });
         ui.add_space(5.0);
         ui.horizontal(|ui| {
             ui.label(egui::RichText::new("•").strong().color(tip_color));
-            ui.label(egui::RichText::new("最大扫描范围为1000个IP地址和1000个端口。").color(tip_color));
+            ui.label(egui::RichText::new(tr("默认最多扫描65536个IP地址和全部端口，可在高级设置中调整上限。")).color(tip_color));
         });
         ui.add_space(5.0);
         ui.horizontal(|ui| {
             ui.label(egui::RichText::new("•").strong().color(tip_color));
-            ui.label(egui::RichText::new("多线程扫描可显著提高扫描速度。").color(tip_color));
+            ui.label(egui::RichText::new(tr("多线程扫描可显著提高扫描速度。")).color(tip_color));
         });
         ui.add_space(5.0);
         ui.horizontal(|ui| {
             ui.label(egui::RichText::new("•").strong().color(tip_color));
-            ui.label(egui::RichText::new("超时时间可调整扫描的等待时间，过短可能遗漏端口，过长会降低扫描速度。").color(tip_color));
+            ui.label(egui::RichText::new(tr("超时时间可调整扫描的等待时间，过短可能遗漏端口，过长会降低扫描速度。")).color(tip_color));
         });
     });
 }
@@ -744,19 +2572,78 @@ fn render_scan_right_panel(app: &mut TcpClientApp, ui: &mut egui::Ui) {
 fn render_scan_results(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     ui.vertical_centered(|ui| {
         ui.heading(
-            egui::RichText::new("扫描结果")
-                .color(egui::Color32::from_rgb(39, 174, 96))
+            egui::RichText::new(tr("扫描结果"))
+                .color(Theme::of(ui.ctx()).results_heading)
                 .size(18.0),
         );
     });
     ui.add_space(5.0);
 
+    // 分组时按主机分页，平铺时按结果分页，避免大范围扫描时一次渲染过多行
+    let (hosts, result_count) = {
+        let results = lock_or_recover(&app.scan_results);
+        (group_results_by_host(&results), results.len())
+    };
+    let item_count = if app.scan_results_grouped {
+        hosts.len()
+    } else {
+        result_count
+    };
+    let page_count = item_count.div_ceil(SCAN_RESULTS_PAGE_SIZE).max(1);
+    app.scan_results_page = app.scan_results_page.min(page_count - 1);
+
+    // 全部展开/全部折叠只在点击的这一帧生效，之后各主机可单独折叠
+    let mut force_open = None;
+    if !hosts.is_empty() {
+        ui.horizontal(|ui| {
+            ui.label(trf!("{} 台主机, {} 个开放端口", hosts.len(), result_count));
+            if ui.checkbox(&mut app.scan_results_grouped, tr("按主机分组")).changed() {
+                app.scan_results_page = 0;
+            }
+            if app.scan_results_grouped {
+                if ui.small_button(tr("全部展开")).clicked() {
+                    force_open = Some(true);
+                }
+                if ui.small_button(tr("全部折叠")).clicked() {
+                    force_open = Some(false);
+                }
+            }
+            // 扫描结束后才有完整的记录 (时间、范围与耗时)
+            let record = lock_or_recover(&app.scan_record).clone();
+            if ui
+                .add_enabled(record.is_some(), egui::Button::new(tr("复制报告")).small())
+                .on_hover_text(tr("复制按主机分组的纯文本扫描报告"))
+                .on_disabled_hover_text(tr("扫描结束后可复制报告"))
+                .clicked()
+            {
+                if let Some(record) = record {
+                    ui.ctx().copy_text(record.report());
+                    lock_or_recover(&app.scan_logs).push((get_timestamp(), tr("扫描报告已复制到剪贴板").to_string()));
+                }
+            }
+            if page_count > 1 {
+                ui.separator();
+                if ui
+                    .add_enabled(app.scan_results_page > 0, egui::Button::new(tr("上一页")))
+                    .clicked()
+                {
+                    app.scan_results_page -= 1;
+                }
+                ui.label(trf!("第 {} / {} 页", app.scan_results_page + 1, page_count));
+                if ui
+                    .add_enabled(app.scan_results_page + 1 < page_count, egui::Button::new(tr("下一页")))
+                    .clicked()
+                {
+                    app.scan_results_page += 1;
+                }
+            }
+        });
+    }
+
+    let theme = Theme::of(ui.ctx());
     let results_frame = egui::Frame::new()
-        .fill(egui::Color32::from_rgb(250, 255, 250))
-        .stroke(egui::Stroke::new(
-            1.0,
-            egui::Color32::from_rgb(200, 230, 200),
-        ))
+        .fill(theme.results_fill)
+        .stroke(egui::Stroke::new(1.0, theme.results_border))
         .inner_margin(egui::vec2(15.0, 15.0))
         .outer_margin(egui::vec2(0.0, 5.0))
         .corner_radius(8.0);
@@ -764,6 +2651,9 @@ fn render_scan_results(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     // 计算合适的区域大小
     let available_height = ui.available_height() * 0.7; // 结果区域占据60%的高度
 
+    // 用户在结果行上选择的操作 (结果, 是否立即连接)
+    let mut selected: Option<(ScanResult, bool)> = None;
+
     results_frame.show(ui, |ui| {
         // 使用滑动窗口
         let scroll_area = egui::ScrollArea::vertical()
@@ -772,65 +2662,195 @@ fn render_scan_results(app: &mut TcpClientApp, ui: &mut egui::Ui) {
             .id_salt("scan_results_scroll_area");
 
         scroll_area.show(ui, |ui| {
-            let results = app.scan_results.lock().unwrap();
-            if results.is_empty() {
+            if hosts.is_empty() {
                 ui.vertical_centered(|ui| {
                     ui.add_space(10.0);
-                    if app.is_scanning {
-                        ui.weak("正在扫描中...");
+                    if app.is_scanning.load(Ordering::Relaxed) {
+                        ui.weak(tr("正在扫描中..."));
                         // 添加加载动画
                         let time = ui.input(|i| i.time);
                         let n_dots = ((time * 2.0) as usize) % 4;
                         let dots = "..".chars().take(n_dots).collect::<String>();
-                        ui.label(format!("IP扫描进行中{}", dots));
+                        ui.label(trf!("IP扫描进行中{}", dots));
                     } else {
-                        ui.weak("暂无扫描结果");
-                        ui.label("开始扫描后将在此显示发现的开放端口");
+                        ui.weak(tr("暂无扫描结果"));
+                        ui.label(tr("开始扫描后将在此显示发现的开放端口"));
                     }
                     ui.add_space(10.0);
                 });
+            } else if !app.scan_results_grouped {
+                ui.set_min_height(available_height);
+
+                // 平铺显示，按IP和端口排序
+                let page_start = app.scan_results_page * SCAN_RESULTS_PAGE_SIZE;
+                let flat = hosts.iter().flat_map(|host| &host.results);
+                for result in flat.skip(page_start).take(SCAN_RESULTS_PAGE_SIZE) {
+                    render_scan_result_row(ui, result, true, &mut selected);
+                }
             } else {
                 // 设置列表最大高度
                 ui.set_min_height(available_height);
 
-                for result in results.iter() {
-                    // 创建一个带背景色的结果行
-                    let item_bg = egui::Color32::from_rgba_unmultiplied(230, 255, 230, 255);
-                    create_message_frame(item_bg).show(ui, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.add_space(5.0);
-                            ui.label(
-                                egui::RichText::new("✔")
-                                    .size(16.0)
-                                    .color(egui::Color32::from_rgb(0, 150, 0)),
-                            );
-                            ui.add_space(8.0);
-                            ui.colored_label(egui::Color32::from_rgb(0, 100, 0), result);
+                let page_start = app.scan_results_page * SCAN_RESULTS_PAGE_SIZE;
+                for host in hosts.iter().skip(page_start).take(SCAN_RESULTS_PAGE_SIZE) {
+                    // 每台主机一个折叠标题，有结果的主机默认展开
+                    // 同网段主机附带MAC地址和厂商名
+                    let mac_text = match (&host.mac, host.mac.as_deref().and_then(mac_vendor)) {
+                        (Some(mac), Some(vendor)) => format!("  {} ({})", mac, vendor),
+                        (Some(mac), None) => format!("  {}", mac),
+                        _ => String::new(),
+                    };
+                    let header = egui::RichText::new(trf!(
+                        "{}{}  ({} 个开放端口)",
+                        host.ip,
+                        mac_text,
+                        host.results.len()
+                    ))
+                    .color(Theme::of(ui.ctx()).open_port.text)
+                    .strong();
+
+                    egui::CollapsingHeader::new(header)
+                        .id_salt(("scan_host", &host.ip))
+                        .default_open(!host.results.is_empty())
+                        .open(force_open)
+                        .show(ui, |ui| {
+                            for result in &host.results {
+                                render_scan_result_row(ui, result, false, &mut selected);
+                            }
                         });
-                    });
                 }
             }
         });
     });
+
+    if let Some((result, connect)) = selected {
+        use_scan_result(app, &result, connect);
+    }
+}
+
+// 渲染单个开放端口行，点击文本填充连接参数，点击按钮直接连接
+// 平铺显示时没有主机标题，show_ip 为 true 时在行内显示IP
+fn render_scan_result_row(
+    ui: &mut egui::Ui,
+    result: &ScanResult,
+    show_ip: bool,
+    selected: &mut Option<(ScanResult, bool)>,
+) {
+    let theme = Theme::of(ui.ctx());
+    create_message_frame(theme.open_port.background).show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.add_space(5.0);
+            ui.label(
+                egui::RichText::new("✔")
+                    .size(16.0)
+                    .color(theme.open_port_mark),
+            );
+            ui.add_space(8.0);
+
+            let text = if show_ip {
+                trf!("{}:{} 开放", result.ip, port_label(result.port))
+            } else {
+                trf!("端口 {} 开放", port_label(result.port))
+            };
+            if ui
+                .add(
+                    egui::Label::new(
+                        egui::RichText::new(text).color(theme.open_port.text),
+                    )
+                    .sense(egui::Sense::click()),
+                )
+                .on_hover_text(tr("点击填入连接参数"))
+                .clicked()
+            {
+                *selected = Some((result.clone(), false));
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button(tr("连接")).clicked() {
+                    *selected = Some((result.clone(), true));
+                }
+                // 历史记录中没有耗时数据时不显示
+                if result.latency_ms > 0.0 {
+                    ui.label(
+                        egui::RichText::new(format!("{:.1} ms", result.latency_ms))
+                            .monospace()
+                            .color(theme.latency(result.latency_ms)),
+                    );
+                }
+            });
+        });
+    });
+}
+
+// 将扫描结果填入连接参数并切换到连接界面，可选直接发起连接
+fn use_scan_result(app: &mut TcpClientApp, result: &ScanResult, connect: bool) {
+    app.ip = result.ip.clone();
+    app.port = result.port.to_string();
+    app.current_view = AppView::Connection;
+
+    if connect {
+        request_connect(app);
+    }
 }
 
 // 渲染扫描日志区域
+// 弹出文件对话框选择保存位置，将扫描日志导出为CSV
+fn save_scan_logs(app: &mut TcpClientApp) {
+    let file_name = format!("scan_logs_{}.csv", get_file_timestamp());
+    let Some(path) = rfd::FileDialog::new()
+        .set_file_name(file_name)
+        .add_filter("CSV", &["csv"])
+        .save_file()
+    else {
+        return; // 用户取消了保存
+    };
+
+    let logs = lock_or_recover(&app.scan_logs).clone();
+    let results = app
+        .export_results_with_logs
+        .then(|| lock_or_recover(&app.scan_results).clone());
+    let path_str = path.to_string_lossy().to_string();
+
+    let (status, kind) = match save_scan_logs_to_file(&logs, results.as_deref(), &path_str) {
+        Ok(()) => (trf!("日志已保存: {}", path_str), NotificationKind::Scan),
+        Err(e) => (trf!("保存日志失败: {}", e), NotificationKind::Error),
+    };
+    notify(&app.notifications, kind, status.clone());
+    app.scan_logs_status = Some(status);
+}
+
 pub fn render_scan_logs(app: &mut TcpClientApp, ui: &mut egui::Ui) {
     ui.vertical_centered(|ui| {
         ui.heading(
-            egui::RichText::new("扫描日志")
-                .color(egui::Color32::from_rgb(100, 120, 150))
+            egui::RichText::new(tr("扫描日志"))
+                .color(Theme::of(ui.ctx()).hint)
                 .size(18.0),
         );
     });
     ui.add_space(5.0);
 
+    // 导出日志为CSV，可选同时导出扫描结果
+    ui.horizontal(|ui| {
+        if ui.button(tr("保存日志")).clicked() {
+            save_scan_logs(app);
+        }
+        ui.checkbox(&mut app.export_results_with_logs, tr("同时导出扫描结果"));
+        if let Some(status) = &app.scan_logs_status {
+            ui.weak(status);
+        }
+    });
+
+    // 搜索栏：输入关键字后高亮或过滤匹配的日志，并可在匹配项之间跳转
+    let match_count = {
+        let logs = lock_or_recover(&app.scan_logs);
+        logs.iter().filter(|(_, log)| app.scan_log_search.matches(log)).count()
+    };
+    render_scan_log_search_bar(&mut app.scan_log_search, match_count, ui);
+
+    let theme = Theme::of(ui.ctx());
     let logs_frame = egui::Frame::new()
-        .fill(egui::Color32::from_rgb(245, 245, 250))
-        .stroke(egui::Stroke::new(
-            1.0,
-            egui::Color32::from_rgb(200, 200, 230),
-        ))
+        .fill(theme.panel_fill)
+        .stroke(egui::Stroke::new(1.0, theme.logs_border))
         .inner_margin(egui::vec2(15.0, 15.0))
         .outer_margin(egui::vec2(0.0, 5.0))
         .corner_radius(8.0);
@@ -847,56 +2867,165 @@ pub fn render_scan_logs(app: &mut TcpClientApp, ui: &mut egui::Ui) {
             .id_salt("scan_logs_scroll_area");
 
         scroll_area.show(ui, |ui| {
-            let logs = app.scan_logs.lock().unwrap();
+            let logs = lock_or_recover(&app.scan_logs);
             if logs.is_empty() {
                 ui.vertical_centered(|ui| {
                     ui.add_space(10.0);
-                    ui.weak("暂无扫描日志");
+                    ui.weak(tr("暂无扫描日志"));
                     ui.add_space(5.0);
-                    ui.label("开始扫描后将在此显示详细日志");
+                    ui.label(tr("开始扫描后将在此显示详细日志"));
                     ui.add_space(10.0);
                 });
             } else {
                 // 设置列表最大高度
                 ui.set_min_height(available_height);
 
+                let search = &mut app.scan_log_search;
+                let mut match_index = 0;
                 for (timestamp, log) in logs.iter() {
-                    // 创建一个带背景色的日志行
-                    let item_bg = egui::Color32::from_rgba_unmultiplied(245, 245, 250, 255);
-                    create_message_frame(item_bg).show(ui, |ui| {
+                    let is_match = search.matches(log);
+                    if search.is_active() && search.filter && !is_match {
+                        continue;
+                    }
+                    let is_current = is_match && match_index == search.current;
+                    if is_match {
+                        match_index += 1;
+                    }
+
+                    // 创建一个带背景色的日志行，当前定位的匹配项使用醒目的背景
+                    let item_bg = if is_current {
+                        theme.log_current
+                    } else {
+                        theme.panel_fill
+                    };
+                    let response = create_message_frame(item_bg).show(ui, |ui| {
                         ui.horizontal(|ui| {
                             ui.add_space(5.0);
                             ui.label(
                                 egui::RichText::new("•")
                                     .size(16.0)
-                                    .color(egui::Color32::from_rgb(100, 100, 150)),
+                                    .color(theme.log_meta),
                             );
                             ui.add_space(8.0);
                             ui.label(
                                 egui::RichText::new(format!("[{}]", timestamp))
                                     .size(14.0)
-                                    .color(egui::Color32::from_rgb(100, 100, 150)),
+                                    .color(theme.log_meta),
                             );
                             ui.add_space(5.0);
-                            ui.colored_label(egui::Color32::from_rgb(80, 80, 100), log);
+                            if is_match {
+                                ui.label(search_highlight_layout_job(theme, log, &search.find(log)));
+                            } else {
+                                ui.colored_label(theme.log_text, log);
+                            }
                         });
                     });
+                    if is_current && search.scroll_to_current {
+                        response.response.scroll_to_me(Some(egui::Align::Center));
+                        search.scroll_to_current = false;
+                    }
                 }
             }
         });
     });
 }
 
+// 扫描日志搜索栏
+fn render_scan_log_search_bar(search: &mut LogSearch, match_count: usize, ui: &mut egui::Ui) {
+    // 日志被清空或减少时，把当前定位限制在匹配项范围内
+    search.current = search.current.min(match_count.saturating_sub(1));
+
+    ui.horizontal(|ui| {
+        ui.label("🔍");
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut search.query)
+                .desired_width(200.0)
+                .hint_text(tr("搜索日志")),
+        );
+        if response.changed() {
+            search.reset();
+        }
+        if search.focus_requested {
+            search.focus_requested = false;
+            response.request_focus();
+        }
+        // 在搜索框中按回车跳到下一个匹配项
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            search.step(match_count, true);
+            response.request_focus();
+        }
+
+        ui.add_enabled_ui(match_count > 0, |ui| {
+            if ui.button("⬆").on_hover_text(tr("上一个匹配项")).clicked() {
+                search.step(match_count, false);
+            }
+            if ui.button("⬇").on_hover_text(tr("下一个匹配项")).clicked() {
+                search.step(match_count, true);
+            }
+        });
+
+        if ui.checkbox(&mut search.case_sensitive, tr("区分大小写")).changed() {
+            search.reset();
+        }
+        ui.checkbox(&mut search.filter, tr("只显示匹配项"));
+
+        if search.is_active() {
+            if match_count > 0 {
+                ui.weak(trf!("{}/{} 条匹配", search.current + 1, match_count));
+            } else {
+                ui.colored_label(Theme::of(ui.ctx()).error, tr("无匹配"));
+            }
+            if ui.small_button("✖").on_hover_text(tr("清除搜索")).clicked() {
+                search.query.clear();
+                search.reset();
+            }
+        }
+    });
+}
+
+// 构建高亮显示关键字的日志文本
+fn search_highlight_layout_job(theme: &Theme, text: &str, ranges: &[std::ops::Range<usize>]) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let normal = egui::TextFormat {
+        color: theme.log_text,
+        ..Default::default()
+    };
+    let highlight = egui::TextFormat {
+        color: theme.search_highlight.text,
+        background: theme.search_highlight.background,
+        ..Default::default()
+    };
+
+    let mut last = 0;
+    for range in ranges {
+        job.append(&text[last..range.start], 0.0, normal.clone());
+        job.append(&text[range.clone()], 0.0, highlight.clone());
+        last = range.end;
+    }
+    job.append(&text[last..], 0.0, normal);
+    job
+}
+
 // 获取时间戳函数
 fn get_timestamp() -> String {
     use crate::utils::get_timestamp;
     get_timestamp()
 }
 
-// 发送消息的工具函数
-pub fn send_message(tx: &mpsc::Sender<Message>, text: String, encoding_mode: EncodingMode) {
-    let tx = tx.clone();
-    tokio::spawn(async move {
-        let _ = tx.send(Message::Send(text, encoding_mode)).await;
-    });
+// 向网络任务发送命令，使用 try_send 避免队列已满时静默丢弃
+// 发送失败时在消息区和通知中提示用户，返回是否发送成功
+fn dispatch_command(app: &mut TcpClientApp, message: Message) -> bool {
+    let Some(tx) = &app.tx else {
+        return false;
+    };
+
+    let error_msg = match tx.try_send(message) {
+        Ok(()) => return true,
+        Err(TrySendError::Full(_)) => tr("命令队列已满，请稍后重试"),
+        Err(TrySendError::Closed(_)) => tr("网络任务已停止，无法执行操作"),
+    };
+    lock_or_recover(&app.received_messages).push(MessageEntry::new(get_timestamp(), MessageKind::Error, error_msg.to_string()));
+    app.should_scroll_to_bottom = true;
+    notify(&app.notifications, NotificationKind::Error, error_msg);
+    false
 }
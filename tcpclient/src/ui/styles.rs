@@ -1,32 +1,121 @@
+use crate::app::DisplayMode;
+use crate::i18n::{tr, trf};
+use crate::message::{MessageEntry, MessageKind};
 use eframe::egui;
-use egui::epaint::text::{FontInsert, InsertFontFamily};
-
-// 设置应用的UI样式
-pub fn setup_style(ctx: &egui::Context) {
-    // 加载自定义宋体字体 - 直接从编译时嵌入字体
-    ctx.add_font(FontInsert::new(
-        "stsong",
-        egui::FontData::from_static(include_bytes!("../../font/STSong.ttf")),
-        vec![
-            InsertFontFamily {
-                family: egui::FontFamily::Proportional,
-                priority: egui::epaint::text::FontPriority::Highest,
-            },
-            InsertFontFamily {
-                family: egui::FontFamily::Monospace,
-                priority: egui::epaint::text::FontPriority::Highest,
-            },
-        ],
-    ));
-
-    // 设置应用样式
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::Arc;
+
+// 界面主题，用户选择后由 eframe 保存，下次启动时恢复
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemeChoice {
+    #[default]
+    System, // 跟随系统的深浅色设置
+    Light,
+    Dark,
+}
+
+impl ThemeChoice {
+    pub const ALL: [ThemeChoice; 3] = [ThemeChoice::System, ThemeChoice::Light, ThemeChoice::Dark];
+
+    // 菜单中显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThemeChoice::System => tr("跟随系统"),
+            ThemeChoice::Light => tr("浅色"),
+            ThemeChoice::Dark => tr("深色"),
+        }
+    }
+
+    // 是否使用深色主题；跟随系统时设置了 NO_COLOR 或无法检测系统设置则使用浅色
+    pub fn is_dark(&self) -> bool {
+        match self {
+            ThemeChoice::Light => false,
+            ThemeChoice::Dark => true,
+            ThemeChoice::System => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                !no_color && matches!(dark_light::detect(), Ok(dark_light::Mode::Dark))
+            }
+        }
+    }
+}
+
+// 内置的宋体字体，覆盖界面中的中文；关闭 embedded-font 特性编译时不打包，只使用 egui 默认字体
+#[cfg(feature = "embedded-font")]
+const EMBEDDED_FONT: Option<&[u8]> = Some(include_bytes!("../../font/STSong.ttf"));
+#[cfg(not(feature = "embedded-font"))]
+const EMBEDDED_FONT: Option<&[u8]> = None;
+
+// 检查字体数据能否解析，egui 遇到无法解析的字体会直接 panic
+fn validate_font(data: &[u8]) -> Result<(), String> {
+    ab_glyph::FontRef::try_from_slice(data)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+// 读取用户指定的字体文件 (TTF/OTF)，无法读取或解析时返回错误提示
+pub fn load_font_file(path: &Path) -> Result<Vec<u8>, String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string());
+    data.and_then(|data| validate_font(&data).map(|()| data))
+        .map_err(|e| trf!("无法加载字体文件 {}: {}", path.display(), e))
+}
+
+// 生成字体设置：用户指定的字体优先，其次是内置宋体，最后是 egui 默认字体
+// 无法读取或解析的字体被跳过，返回的警告说明原因；缺少中文字体时中文会显示为方框
+pub fn font_definitions(user_font: Option<&Path>) -> (egui::FontDefinitions, Vec<String>) {
+    let mut fonts = egui::FontDefinitions::default();
+    let mut warnings = Vec::new();
+    let install = |fonts: &mut egui::FontDefinitions, name: &str, data: egui::FontData| {
+        fonts.font_data.insert(name.to_string(), Arc::new(data));
+        for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+            fonts.families.entry(family).or_default().insert(0, name.to_string());
+        }
+    };
+
+    match EMBEDDED_FONT.map(|data| validate_font(data).map(|()| data)) {
+        Some(Ok(data)) => install(&mut fonts, "stsong", egui::FontData::from_static(data)),
+        Some(Err(e)) => warnings.push(trf!("内置字体无法加载，使用默认字体，中文可能无法显示: {}", e)),
+        None => {}
+    }
+
+    // 后安装的排在最前面，用户字体覆盖内置字体，缺少的字符仍由内置字体补全
+    if let Some(path) = user_font {
+        match load_font_file(path) {
+            Ok(data) => install(&mut fonts, "user", egui::FontData::from_owned(data)),
+            Err(e) => warnings.push(e),
+        }
+    }
+    (fonts, warnings)
+}
+
+// 设置界面字体，返回加载失败的警告；切换用户字体时也调用这里
+pub fn apply_fonts(ctx: &egui::Context, user_font: Option<&Path>) -> Vec<String> {
+    let (fonts, warnings) = font_definitions(user_font);
+    ctx.set_fonts(fonts);
+    warnings
+}
+
+// 设置应用的UI样式，返回字体加载失败的警告
+pub fn setup_style(ctx: &egui::Context, theme: ThemeChoice, user_font: Option<&Path>) -> Vec<String> {
+    let warnings = apply_fonts(ctx, user_font);
+    apply_theme(ctx, theme);
+    warnings
+}
+
+// 按主题设置界面颜色，切换主题时也调用这里
+pub fn apply_theme(ctx: &egui::Context, theme: ThemeChoice) {
     let mut style = (*ctx.style()).clone();
     style.spacing.item_spacing = egui::vec2(10.0, 10.0);
-    style.visuals = egui::Visuals::light(); // 使用浅色主题
-    style.visuals.widgets.noninteractive.bg_fill = egui::Color32::from_rgb(240, 240, 245);
-    style.visuals.widgets.inactive.bg_fill = egui::Color32::from_rgb(230, 230, 235);
-    style.visuals.widgets.active.bg_fill = egui::Color32::from_rgb(210, 210, 220);
-    style.visuals.widgets.hovered.bg_fill = egui::Color32::from_rgb(220, 220, 230);
+    if theme.is_dark() {
+        style.visuals = egui::Visuals::dark();
+    } else {
+        style.visuals = egui::Visuals::light(); // 使用浅色主题
+        style.visuals.widgets.noninteractive.bg_fill = egui::Color32::from_rgb(240, 240, 245);
+        style.visuals.widgets.inactive.bg_fill = egui::Color32::from_rgb(230, 230, 235);
+        style.visuals.widgets.active.bg_fill = egui::Color32::from_rgb(210, 210, 220);
+        style.visuals.widgets.hovered.bg_fill = egui::Color32::from_rgb(220, 220, 230);
+    }
 
     // 在eframe 0.31中，window_shadow的属性是不同类型的
     style.visuals.window_shadow.offset = [2, 2]; // 使用i8数组而不是vec2
@@ -36,6 +125,28 @@ pub fn setup_style(ctx: &egui::Context) {
     ctx.set_style(style);
 }
 
+// 界面字号相对 egui 默认字号的缩放比例，1.0 为默认字号
+pub const FONT_SCALE_RANGE: RangeInclusive<f32> = 0.8..=2.0;
+pub const FONT_SCALE_STEP: f32 = 0.1;
+
+// 按比例设置各类文字的字号，消息列表、按钮和输入框同时生效；主题等其余样式不变
+pub fn apply_font_scale(ctx: &egui::Context, scale: f32) {
+    let defaults = egui::Style::default().text_styles;
+    ctx.style_mut(|style| {
+        for (text_style, font) in style.text_styles.iter_mut() {
+            if let Some(default) = defaults.get(text_style) {
+                font.size = default.size * scale;
+            }
+        }
+    });
+}
+
+// 按步长增减缩放比例，保留一位小数并限制在允许的范围内
+pub fn step_font_scale(scale: f32, steps: i32) -> f32 {
+    let scaled = ((scale + steps as f32 * FONT_SCALE_STEP) * 10.0).round() / 10.0;
+    scaled.clamp(*FONT_SCALE_RANGE.start(), *FONT_SCALE_RANGE.end())
+}
+
 // 创建消息列表项框架
 pub fn create_message_frame(item_bg: egui::Color32) -> egui::Frame {
     egui::Frame::new()
@@ -44,46 +155,292 @@ pub fn create_message_frame(item_bg: egui::Color32) -> egui::Frame {
         .outer_margin(egui::vec2(0.0, 1.0))
 }
 
-// 获取消息颜色
-pub fn get_message_color(msg: &str) -> egui::Color32 {
-    if msg.starts_with("收到(UTF-8):") {
-        egui::Color32::from_rgb(0, 120, 0) // 深绿色用于UTF-8接收消息
-    } else if msg.starts_with("收到(HEX):") {
-        egui::Color32::from_rgb(128, 0, 128) // 紫色用于十六进制接收消息
-    } else if msg.starts_with("收到(非UTF-8数据):") {
-        egui::Color32::from_rgb(160, 82, 45) // 棕色用于非UTF-8数据
-    } else if msg.starts_with("收到:") {
-        egui::Color32::from_rgb(0, 100, 0) // 原始的接收消息颜色
-    } else if msg.starts_with("已发送(UTF-8):") {
-        egui::Color32::from_rgb(0, 0, 180) // 蓝色用于UTF-8发送消息
-    } else if msg.starts_with("已发送(HEX):") {
-        egui::Color32::from_rgb(70, 30, 180) // 深蓝紫色用于十六进制发送消息
-    } else if msg.starts_with("已发送:") {
-        egui::Color32::from_rgb(0, 0, 150) // 原始的发送消息颜色
-    } else if msg.contains("失败") || msg.contains("错误") || msg.contains("中断") {
-        egui::Color32::from_rgb(180, 0, 0) // 红色用于错误消息
-    } else if msg.contains("连接到") {
-        egui::Color32::from_rgb(0, 128, 128) // 青色用于连接消息
-    } else {
-        egui::Color32::GRAY // 灰色用于其他消息
+const fn rgb(r: u8, g: u8, b: u8) -> egui::Color32 {
+    egui::Color32::from_rgb(r, g, b)
+}
+
+// 一类条目的前景色和背景色
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItemColors {
+    pub text: egui::Color32,
+    pub background: egui::Color32,
+}
+
+const fn item(text: egui::Color32, background: egui::Color32) -> ItemColors {
+    ItemColors { text, background }
+}
+
+// 界面中所有自定义的颜色，按当前的深浅色主题取值
+// 绘制时通过 Theme::of 获取，切换主题后已显示的内容在下一帧即使用新颜色
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    // 区块
+    pub panel_fill: egui::Color32, // 设置区域、列表项等的底色
+    pub list_fill: egui::Color32,  // 消息列表和输入区域的底色
+    pub border: egui::Color32,
+    pub accent: egui::Color32,      // 扫描标题栏底色和强调文字
+    pub accent_text: egui::Color32, // 标题栏上的文字
+    pub hint: egui::Color32,        // 次要标题
+
+    // 按钮和状态
+    pub primary_button: egui::Color32,
+    pub danger_button: egui::Color32,
+    pub neutral_button: egui::Color32,
+    pub error: egui::Color32, // 输入校验错误、录制标记等
+    pub warning: egui::Color32, // 输入超出范围已被调整等提示
+    pub online: egui::Color32,
+    pub offline: egui::Color32,
+    pub idle: egui::Color32,
+
+    // 消息列表，按消息类别区分
+    pub received_utf8: ItemColors,
+    pub received_hex: ItemColors,
+    pub received_binary: ItemColors, // 不是合法 UTF-8 的数据
+    pub sent_utf8: ItemColors,
+    pub sent_hex: ItemColors,
+    pub error_message: ItemColors,
+    pub connection_message: ItemColors,
+    pub other_message: ItemColors,
+
+    // 通知
+    pub toast_connection: ItemColors,
+    pub toast_scan: ItemColors,
+    pub toast_error: ItemColors,
+    pub toast_trigger: ItemColors,
+
+    // 扫描界面
+    pub help_fill: egui::Color32,
+    pub help_border: egui::Color32,
+    pub help_heading: egui::Color32,
+    pub help_text: egui::Color32,
+    pub results_heading: egui::Color32,
+    pub results_fill: egui::Color32,
+    pub results_border: egui::Color32,
+    pub open_port: ItemColors, // 开放端口条目，文字也用于主机标题
+    pub open_port_mark: egui::Color32,
+    pub latency_fast: egui::Color32,
+    pub latency_slow: egui::Color32,
+    pub latency_very_slow: egui::Color32,
+    pub logs_border: egui::Color32,
+    pub log_current: egui::Color32, // 当前定位的搜索匹配行底色
+    pub log_meta: egui::Color32,    // 日志的圆点和时间戳
+    pub log_text: egui::Color32,
+    pub search_highlight: ItemColors,
+}
+
+pub const LIGHT_THEME: Theme = Theme {
+    panel_fill: rgb(245, 245, 250),
+    list_fill: rgb(250, 250, 255),
+    border: rgb(200, 200, 200),
+    accent: rgb(41, 128, 185), // 漆蓝色
+    accent_text: egui::Color32::WHITE,
+    hint: rgb(100, 120, 150),
+
+    primary_button: rgb(100, 150, 220),
+    danger_button: rgb(220, 100, 100),
+    neutral_button: rgb(150, 150, 150),
+    error: rgb(220, 50, 50),
+    warning: rgb(205, 125, 0),
+    online: rgb(40, 180, 40),
+    offline: rgb(180, 40, 40),
+    idle: rgb(100, 100, 100),
+
+    received_utf8: item(rgb(0, 120, 0), rgb(230, 255, 230)), // 深绿色
+    received_hex: item(rgb(128, 0, 128), rgb(245, 230, 255)), // 紫色
+    received_binary: item(rgb(160, 82, 45), rgb(255, 240, 230)), // 棕色
+    sent_utf8: item(rgb(0, 0, 180), rgb(230, 230, 255)), // 蓝色
+    sent_hex: item(rgb(70, 30, 180), rgb(235, 230, 250)), // 深蓝紫色
+    error_message: item(rgb(180, 0, 0), rgb(255, 230, 230)), // 红色
+    connection_message: item(rgb(0, 128, 128), rgb(245, 245, 250)), // 青色
+    other_message: item(egui::Color32::GRAY, rgb(245, 245, 250)),
+
+    toast_connection: item(rgb(40, 80, 160), rgb(230, 240, 255)),
+    toast_scan: item(rgb(0, 110, 0), rgb(230, 255, 230)),
+    toast_error: item(rgb(180, 30, 30), rgb(255, 230, 230)),
+    toast_trigger: item(rgb(170, 90, 0), rgb(255, 245, 220)),
+
+    help_fill: rgb(253, 245, 230),
+    help_border: rgb(210, 180, 140),
+    help_heading: rgb(210, 105, 30),
+    help_text: rgb(160, 82, 45),
+    results_heading: rgb(39, 174, 96),
+    results_fill: rgb(250, 255, 250),
+    results_border: rgb(200, 230, 200),
+    open_port: item(rgb(0, 100, 0), rgb(230, 255, 230)),
+    open_port_mark: rgb(0, 150, 0),
+    latency_fast: rgb(0, 150, 0),
+    latency_slow: rgb(210, 120, 0),
+    latency_very_slow: rgb(200, 40, 40),
+    logs_border: rgb(200, 200, 230),
+    log_current: rgb(255, 240, 200),
+    log_meta: rgb(100, 100, 150),
+    log_text: rgb(80, 80, 100),
+    search_highlight: item(egui::Color32::BLACK, rgb(255, 210, 80)),
+};
+
+pub const DARK_THEME: Theme = Theme {
+    panel_fill: rgb(36, 36, 42),
+    list_fill: rgb(27, 27, 32),
+    border: rgb(70, 70, 78),
+    accent: rgb(41, 110, 160),
+    accent_text: egui::Color32::WHITE,
+    hint: rgb(140, 160, 190),
+
+    primary_button: rgb(55, 100, 165),
+    danger_button: rgb(160, 65, 65),
+    neutral_button: rgb(85, 85, 90),
+    error: rgb(255, 105, 105),
+    warning: rgb(240, 175, 70),
+    online: rgb(90, 205, 90),
+    offline: rgb(230, 90, 90),
+    idle: rgb(150, 150, 150),
+
+    received_utf8: item(rgb(125, 220, 125), rgb(30, 48, 32)),
+    received_hex: item(rgb(215, 155, 235), rgb(46, 32, 56)),
+    received_binary: item(rgb(235, 165, 115), rgb(56, 40, 30)),
+    sent_utf8: item(rgb(135, 165, 255), rgb(30, 36, 62)),
+    sent_hex: item(rgb(175, 145, 255), rgb(40, 33, 64)),
+    error_message: item(rgb(255, 115, 115), rgb(62, 30, 30)),
+    connection_message: item(rgb(95, 205, 205), rgb(40, 40, 46)),
+    other_message: item(rgb(165, 165, 165), rgb(40, 40, 46)),
+
+    toast_connection: item(rgb(145, 180, 255), rgb(30, 40, 62)),
+    toast_scan: item(rgb(125, 220, 125), rgb(30, 50, 32)),
+    toast_error: item(rgb(255, 125, 125), rgb(62, 30, 30)),
+    toast_trigger: item(rgb(240, 180, 90), rgb(60, 48, 25)),
+
+    help_fill: rgb(50, 43, 32),
+    help_border: rgb(110, 90, 60),
+    help_heading: rgb(240, 150, 80),
+    help_text: rgb(220, 165, 115),
+    results_heading: rgb(80, 200, 125),
+    results_fill: rgb(27, 35, 28),
+    results_border: rgb(60, 90, 62),
+    open_port: item(rgb(135, 215, 135), rgb(30, 48, 32)),
+    open_port_mark: rgb(100, 220, 100),
+    latency_fast: rgb(95, 210, 95),
+    latency_slow: rgb(240, 170, 60),
+    latency_very_slow: rgb(240, 95, 95),
+    logs_border: rgb(70, 70, 95),
+    log_current: rgb(72, 62, 35),
+    log_meta: rgb(150, 150, 200),
+    log_text: rgb(190, 190, 205),
+    search_highlight: item(egui::Color32::BLACK, rgb(255, 210, 80)),
+};
+
+impl Theme {
+    // 与当前界面样式一致的配色，apply_theme 设置的 dark_mode 决定使用哪一套
+    pub fn of(ctx: &egui::Context) -> &'static Theme {
+        if ctx.style().visuals.dark_mode {
+            &DARK_THEME
+        } else {
+            &LIGHT_THEME
+        }
+    }
+
+    // 按消息类别取消息的颜色，收发的数据再按显示格式区分
+    pub fn message(&self, entry: &MessageEntry, mode: DisplayMode) -> ItemColors {
+        let binary = || {
+            mode == DisplayMode::Utf8 && entry.raw.as_deref().is_some_and(|bytes| std::str::from_utf8(bytes).is_err())
+        };
+        match entry.kind {
+            MessageKind::Received if binary() => self.received_binary,
+            MessageKind::Received if mode == DisplayMode::Utf8 => self.received_utf8,
+            MessageKind::Received => self.received_hex,
+            MessageKind::Sent if mode == DisplayMode::Utf8 && !binary() => self.sent_utf8,
+            MessageKind::Sent => self.sent_hex,
+            MessageKind::Error => self.error_message,
+            MessageKind::Connection => self.connection_message,
+            MessageKind::Info | MessageKind::Keepalive => self.other_message,
+        }
+    }
+
+    // 按连接耗时区分扫描结果的颜色：快速响应为绿色，较慢为橙色，很慢为红色
+    pub fn latency(&self, latency_ms: f64) -> egui::Color32 {
+        if latency_ms < 50.0 {
+            self.latency_fast
+        } else if latency_ms < 200.0 {
+            self.latency_slow
+        } else {
+            self.latency_very_slow
+        }
     }
 }
 
-// 获取消息背景颜色
-pub fn get_message_background(msg: &str) -> egui::Color32 {
-    if msg.starts_with("收到(UTF-8):") || msg.starts_with("收到:") {
-        egui::Color32::from_rgba_unmultiplied(230, 255, 230, 255) // 浅绿色背景用于UTF-8接收消息
-    } else if msg.starts_with("收到(HEX):") {
-        egui::Color32::from_rgba_unmultiplied(245, 230, 255, 255) // 浅紫色背景用于十六进制接收消息
-    } else if msg.starts_with("收到(非UTF-8数据):") {
-        egui::Color32::from_rgba_unmultiplied(255, 240, 230, 255) // 浅棕色背景用于非UTF-8数据
-    } else if msg.starts_with("已发送(UTF-8):") || msg.starts_with("已发送:") {
-        egui::Color32::from_rgba_unmultiplied(230, 230, 255, 255) // 浅蓝色背景用于UTF-8发送消息
-    } else if msg.starts_with("已发送(HEX):") {
-        egui::Color32::from_rgba_unmultiplied(235, 230, 250, 255) // 浅蓝紫色背景用于十六进制发送消息
-    } else if msg.contains("失败") || msg.contains("错误") || msg.contains("中断") {
-        egui::Color32::from_rgba_unmultiplied(255, 230, 230, 255) // 浅红色背景用于错误消息
-    } else {
-        egui::Color32::from_rgba_unmultiplied(245, 245, 250, 255) // 浅灰色背景用于其他消息
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_colors_follow_kind_and_theme() {
+        let t = || "12:00:00.000".to_string();
+        let received = MessageEntry::data(t(), MessageKind::Received, b"hi".to_vec(), DisplayMode::Utf8);
+        let binary = MessageEntry::data(t(), MessageKind::Received, vec![0xFF], DisplayMode::Utf8);
+        let sent = MessageEntry::data(t(), MessageKind::Sent, b"hi".to_vec(), DisplayMode::Hex);
+        assert_eq!(LIGHT_THEME.message(&received, DisplayMode::Utf8), LIGHT_THEME.received_utf8);
+        assert_eq!(LIGHT_THEME.message(&received, DisplayMode::Decimal), LIGHT_THEME.received_hex);
+        assert_eq!(LIGHT_THEME.message(&binary, DisplayMode::Utf8), LIGHT_THEME.received_binary);
+        assert_eq!(DARK_THEME.message(&sent, DisplayMode::Hex), DARK_THEME.sent_hex);
+
+        // 提示的颜色只取决于类别，与文字内容无关
+        let error = MessageEntry::new(t(), MessageKind::Error, "Connection failed".to_string());
+        let info = MessageEntry::new(t(), MessageKind::Info, "连接失败次数: 0".to_string());
+        assert_eq!(DARK_THEME.message(&error, DisplayMode::Utf8), DARK_THEME.error_message);
+        assert_eq!(DARK_THEME.message(&info, DisplayMode::Utf8), DARK_THEME.other_message);
+
+        // 深色主题下消息的背景比文字暗，浅色主题相反
+        for (theme, dark) in [(&LIGHT_THEME, false), (&DARK_THEME, true)] {
+            let colors = theme.message(&received, DisplayMode::Utf8);
+            let brightness = |c: egui::Color32| c.r() as u32 + c.g() as u32 + c.b() as u32;
+            assert_eq!(brightness(colors.background) < brightness(colors.text), dark);
+        }
+    }
+
+    #[test]
+    fn unreadable_fonts_fall_back_to_defaults() {
+        let defaults = egui::FontDefinitions::default();
+        let (fonts, warnings) = font_definitions(None);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(fonts.font_data.len(), defaults.font_data.len() + EMBEDDED_FONT.iter().count());
+
+        // 无法解析的字体不会交给 egui，只产生警告
+        let path = std::env::temp_dir().join(format!("tcpclient_font_test_{}.ttf", std::process::id()));
+        std::fs::write(&path, b"not a font").unwrap();
+        let (broken, warnings) = font_definitions(Some(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(broken.font_data.len(), fonts.font_data.len());
+        assert!(validate_font(b"not a font").is_err());
+
+        let (_, warnings) = font_definitions(Some(Path::new("/nonexistent/font.ttf")));
+        assert_eq!(warnings.len(), 1);
+
+        // 可以解析的用户字体排在最前面
+        if let Some(data) = EMBEDDED_FONT {
+            std::fs::write(&path, data).unwrap();
+            let (user, warnings) = font_definitions(Some(&path));
+            std::fs::remove_file(&path).unwrap();
+            assert!(warnings.is_empty());
+            assert_eq!(user.families[&egui::FontFamily::Proportional][..2], ["user", "stsong"]);
+        }
+    }
+
+    #[test]
+    fn font_scale_steps_stay_in_range() {
+        assert_eq!(step_font_scale(1.0, 1), 1.1);
+        assert_eq!(step_font_scale(1.0, -2), 0.8);
+        assert_eq!(step_font_scale(0.8, -1), 0.8);
+        assert_eq!(step_font_scale(1.95, 1), 2.0);
+
+        let ctx = egui::Context::default();
+        apply_font_scale(&ctx, 1.5);
+        let body = |ctx: &egui::Context| egui::TextStyle::Body.resolve(&ctx.style()).size;
+        let default_body = egui::TextStyle::Body.resolve(&egui::Style::default()).size;
+        assert_eq!(body(&ctx), default_body * 1.5);
+        // 切换主题不影响已设置的字号
+        apply_theme(&ctx, ThemeChoice::Dark);
+        assert_eq!(body(&ctx), default_body * 1.5);
+        apply_font_scale(&ctx, 1.0);
+        assert_eq!(body(&ctx), default_body);
     }
 }
@@ -1,7 +1,22 @@
-use chrono;
-use std::fs::{self, File};
-use std::io::Write;
+use crate::i18n::trf;
+use std::fs::File;
+use std::ops::{Range, RangeInclusive};
 use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use tcpcommon::datafile;
+use tcpcommon::hexdump::parse_hex;
+
+pub use tcpcommon::datafile::{get_file_timestamp, write_to_file};
+
+// 获取互斥锁；持有锁的任务 panic 导致锁中毒时，打印警告并继续使用其中的数据，
+// 避免一个任务的 panic 让之后所有加锁的地方（包括界面）跟着崩溃
+pub fn lock_or_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        eprintln!("警告: 互斥锁因其他任务 panic 而中毒，已恢复并继续使用其中的数据");
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
 
 // 获取当前时间字符串 (用于UI显示)
 pub fn get_timestamp() -> String {
@@ -10,32 +25,264 @@ pub fn get_timestamp() -> String {
     datetime.format("%H:%M:%S").to_string()
 }
 
-// 获取用于文件名的时间戳字符串
-pub fn get_file_timestamp() -> String {
+// 获取完整的日期时间字符串 (用于记录)
+pub fn get_datetime() -> String {
     let now = std::time::SystemTime::now();
     let datetime = chrono::DateTime::<chrono::Local>::from(now);
-    datetime.format("%Y%m%d_%H%M%S").to_string()
+    datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// 整数输入的检查结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumberInput {
+    Valid(u64),
+    Clamped(u64), // 超出范围，已调整为最近的边界值
+    Invalid,      // 不是非负整数
+}
+
+// 解析整数输入并限制在 range 内，输入两端的空白会被忽略
+pub fn parse_number_input(text: &str, range: RangeInclusive<u64>) -> NumberInput {
+    match text.trim().parse::<u64>() {
+        Ok(value) if range.contains(&value) => NumberInput::Valid(value),
+        Ok(value) => NumberInput::Clamped(value.clamp(*range.start(), *range.end())),
+        Err(_) => NumberInput::Invalid,
+    }
+}
+
+// 将字节数格式化为便于阅读的字符串，例如 "1.5 KB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+// 将时长格式化为便于阅读的字符串，例如 "1小时02分03秒"
+pub fn format_duration(duration: std::time::Duration) -> String {
+    let total_secs = duration.as_secs();
+    let (hours, minutes, seconds) = (total_secs / 3600, total_secs / 60 % 60, total_secs % 60);
+    if hours > 0 {
+        trf!("{}小时{}分{}秒", hours, format!("{:02}", minutes), format!("{:02}", seconds))
+    } else if minutes > 0 {
+        trf!("{}分{}秒", minutes, format!("{:02}", seconds))
+    } else {
+        trf!("{}秒", format!("{:.1}", duration.as_secs_f64()))
+    }
 }
 
-// 创建并打开一个文件用于写入数据
-pub fn create_data_file(ip: &str, port: u16) -> Result<(File, String), std::io::Error> {
-    // 创建data目录（如果不存在）
-    let data_dir = "data";
-    if !Path::new(data_dir).exists() {
-        fs::create_dir_all(data_dir)?;
+// 连接数据文件保存的目录，相对于工作目录
+pub const DATA_DIR: &str = "data";
+
+// 在 dir 下创建并打开一个文件用于写入数据，文件名为 ip_port_timestamp.txt
+pub fn create_data_file(dir: &Path, ip: &str, port: u16) -> Result<(File, String), std::io::Error> {
+    let (file, filepath) = datafile::create_data_file(dir, ip, port, "txt")?;
+    Ok((file, filepath.to_string_lossy().to_string()))
+}
+
+// 十六进制文本中一行的数据部分在该行中的范围，去掉 ; 或 # 开始的注释和行首的偏移列
+// 偏移列是以冒号结尾的第一列（如 "0010:"、"0x0010:"），
+// 或者至少 4 位、后面隔两个以上空格或制表符再跟数据的第一列（如 "0010  48 65"）
+pub fn hex_data_range(line: &str) -> Range<usize> {
+    let end = line.find([';', '#']).unwrap_or(line.len());
+    let content = &line[..end];
+    let start = content.len() - content.trim_start().len();
+
+    let rest = &content[start..];
+    let token_len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let token = &rest[..token_len];
+    let after = &rest[token_len..];
+    let is_offset = match token.strip_suffix(':') {
+        Some(offset) => {
+            let digits = offset.strip_prefix("0x").unwrap_or(offset);
+            !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => {
+            token.len() >= 4
+                && token.chars().all(|c| c.is_ascii_hexdigit())
+                && (after.starts_with("  ") || after.starts_with('\t'))
+                && !after.trim().is_empty()
+        }
+    };
+
+    if is_offset {
+        start + token_len..end
+    } else {
+        start..end
     }
+}
+
+// 去掉每行的偏移列和注释，只保留十六进制数据，便于直接粘贴带注释的协议报文
+pub fn strip_hex_annotations(hex_str: &str) -> String {
+    hex_str
+        .lines()
+        .map(|line| &line[hex_data_range(line)])
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// 验证十六进制字符串是否有效，允许空白字符分隔，忽略偏移列和注释
+pub fn is_valid_hex_string(s: &str) -> bool {
+    parse_hex(&strip_hex_annotations(s)).is_some()
+}
+
+// 十六进制转换函数，支持带偏移列和注释的多行报文；内容无效时返回空的字节序列
+pub fn hex_to_bytes(hex_str: &str) -> Vec<u8> {
+    parse_hex(&strip_hex_annotations(hex_str)).unwrap_or_default()
+}
 
-    // 生成文件名：ip_port_timestamp.txt
-    let filename = format!("{}_{}_{}.txt", ip, port, get_file_timestamp());
-    let filepath = format!("{}/{}", data_dir, filename);
+// 按 UTF-8 显示原始数据，非法字节替换为 U+FFFD，换行等控制字符显示为转义序列
+pub fn printable_text(data: &[u8]) -> String {
+    String::from_utf8_lossy(data)
+        .chars()
+        .map(|c| if c.is_control() { c.escape_debug().to_string() } else { c.to_string() })
+        .collect()
+}
 
-    // 创建并打开文件
-    let file = File::create(&filepath)?;
+// 超过 max_chars 个字符时返回截断后的前缀，按字符边界截断；未超过或 max_chars 为 0 时返回 None
+pub fn truncate_chars(text: &str, max_chars: usize) -> Option<&str> {
+    if max_chars == 0 {
+        return None;
+    }
+    text.char_indices().nth(max_chars).map(|(end, _)| &text[..end])
+}
 
-    Ok((file, filepath))
+// 解析 C 风格转义序列 (\n \r \t \0 \\ \xNN)，用于在 UTF-8 模式下发送控制字符和任意字节
+// 遇到未知的转义或不完整的 \x 时返回出错的转义序列
+pub fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('r') => bytes.push(b'\r'),
+            Some('t') => bytes.push(b'\t'),
+            Some('0') => bytes.push(0),
+            Some('\\') => bytes.push(b'\\'),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                let valid = digits.len() == 2 && digits.chars().all(|c| c.is_ascii_hexdigit());
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if valid => bytes.push(byte),
+                    _ => return Err(format!("\\x{}", digits)),
+                }
+            }
+            Some(other) => return Err(format!("\\{}", other)),
+            None => return Err("\\".to_string()),
+        }
+    }
+    Ok(bytes)
 }
 
-// 将数据写入文件
-pub fn write_to_file(file: &mut File, data: &str) -> Result<(), std::io::Error> {
-    writeln!(file, "[{}] {}", get_timestamp(), data)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn lock_or_recover_survives_poisoned_mutex() {
+        let shared = Arc::new(Mutex::new(vec![1]));
+
+        // 持有锁时 panic，使锁中毒
+        let poisoner = Arc::clone(&shared);
+        let _ = std::thread::spawn(move || {
+            let mut data = poisoner.lock().unwrap();
+            data.push(2);
+            panic!("模拟工作任务 panic");
+        })
+        .join();
+        assert!(shared.is_poisoned());
+
+        // 恢复后数据仍可用，且中毒标记被清除
+        lock_or_recover(&shared).push(3);
+        assert_eq!(*lock_or_recover(&shared), vec![1, 2, 3]);
+        assert!(!shared.is_poisoned());
+    }
+
+    #[test]
+    fn number_input_is_clamped_to_range() {
+        assert_eq!(parse_number_input(" 500 ", 10..=60_000), NumberInput::Valid(500));
+        assert_eq!(parse_number_input("0", 10..=60_000), NumberInput::Clamped(10));
+        assert_eq!(parse_number_input("600000", 10..=60_000), NumberInput::Clamped(60_000));
+        assert_eq!(parse_number_input("5s", 10..=60_000), NumberInput::Invalid);
+        assert_eq!(parse_number_input("-1", 10..=60_000), NumberInput::Invalid);
+    }
+
+    #[test]
+    fn format_bytes_picks_readable_unit() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MB");
+    }
+
+    #[test]
+    fn hex_to_bytes_accepts_plain_hex() {
+        assert_eq!(hex_to_bytes("48 65 6c 6c 6f"), b"Hello");
+        assert_eq!(hex_to_bytes("48656C6C6F"), b"Hello");
+        // 单个空格分隔的 4 位分组不是偏移列
+        assert_eq!(hex_to_bytes("4865 6c6c"), b"Hell");
+    }
+
+    #[test]
+    fn hex_to_bytes_strips_offsets_and_comments() {
+        // Wireshark 风格：偏移列后隔两个空格
+        let dump = "0000  48 65 6c 6c 6f 2c 20 57  ; Hello, W\n\
+                    0008  6f 72 6c 64 21        ; orld!\n";
+        assert_eq!(hex_to_bytes(dump), b"Hello, World!");
+
+        // 以冒号结尾的偏移列和 # 注释
+        let dump = "# 登录请求\n\
+                    00000000: 01 00 0a   # 类型 + 长度\n\
+                    0x0003: 61 64 6d 69 6e\t# 用户名 admin\n\
+                    \n\
+                    0008:\t00 ff\n";
+        assert_eq!(hex_to_bytes(dump), [0x01, 0x00, 0x0a, b'a', b'd', b'm', b'i', b'n', 0x00, 0xff]);
+    }
+
+    #[test]
+    fn hex_data_range_keeps_data_only() {
+        let line = "0010  de ad be ef  ; payload";
+        assert_eq!(&line[hex_data_range(line)], "  de ad be ef  ");
+        assert_eq!(strip_hex_annotations("; 只有注释\nAA BB"), "\nAA BB");
+        // 只有一列时按数据处理
+        assert_eq!(strip_hex_annotations("deadbeef"), "deadbeef");
+    }
+
+    #[test]
+    fn truncate_chars_cuts_at_char_boundary() {
+        assert_eq!(truncate_chars("你好世界", 2), Some("你好"));
+        assert_eq!(truncate_chars("你好", 2), None);
+        assert_eq!(truncate_chars("abc", 0), None);
+    }
+
+    #[test]
+    fn printable_text_escapes_control_characters() {
+        assert_eq!(printable_text(b"OK\r\n"), "OK\\r\\n");
+        assert_eq!(printable_text("温度\t25".as_bytes()), "温度\\t25");
+        assert_eq!(printable_text(&[0x41, 0x00, 0xFF]), "A\\0\u{FFFD}");
+    }
+
+    #[test]
+    fn unescape_decodes_c_style_sequences() {
+        assert_eq!(unescape("AT\\r\\n").unwrap(), b"AT\r\n");
+        assert_eq!(unescape("a\\0b\\tc\\\\").unwrap(), b"a\0b\tc\\");
+        assert_eq!(unescape("\\x00\\xFF\\x7f").unwrap(), [0x00, 0xFF, 0x7F]);
+        assert_eq!(unescape("你好").unwrap(), "你好".as_bytes());
+        assert_eq!(unescape("\\q").unwrap_err(), "\\q");
+        assert_eq!(unescape("\\x4").unwrap_err(), "\\x4");
+        assert_eq!(unescape("\\x+1").unwrap_err(), "\\x+1");
+        assert_eq!(unescape("end\\").unwrap_err(), "\\");
+    }
 }
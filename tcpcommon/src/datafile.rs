@@ -0,0 +1,40 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// 获取用于文件名的时间戳字符串
+pub fn get_file_timestamp() -> String {
+    chrono::Local::now().format("%Y%m%d_%H%M%S").to_string()
+}
+
+// 在 dir 下创建 ip_port_timestamp.<extension> 文件用于写入数据，目录不存在时自动创建
+pub fn create_data_file(
+    dir: &Path,
+    ip: &str,
+    port: u16,
+    extension: &str,
+) -> Result<(File, PathBuf), std::io::Error> {
+    fs::create_dir_all(dir)?;
+
+    // IPv6 地址中的冒号不能出现在 Windows 文件名中
+    let ip = ip.replace(':', "-");
+    let filepath = dir.join(format!(
+        "{}_{}_{}.{}",
+        ip,
+        port,
+        get_file_timestamp(),
+        extension
+    ));
+    let file = File::create(&filepath)?;
+    Ok((file, filepath))
+}
+
+// 将一行数据写入文件
+// timestamp 由调用方传入，保证与界面上显示的时间完全一致
+pub fn write_to_file(
+    file: &mut impl Write,
+    timestamp: &str,
+    data: &str,
+) -> Result<(), std::io::Error> {
+    writeln!(file, "[{}] {}", timestamp, data)
+}
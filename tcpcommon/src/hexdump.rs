@@ -0,0 +1,91 @@
+use std::fmt::Write;
+
+// 每行显示的字节数
+const BYTES_PER_LINE: usize = 16;
+
+// 大写、空格分隔的十六进制，如 "48 65 6C"，与 tcpclient 十六进制模式的显示和输入格式一致
+pub fn to_hex(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() * 3);
+    for (i, byte) in data.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let _ = write!(out, "{:02X}", byte);
+    }
+    out
+}
+
+// 解析十六进制字符串，字节之间可以有空白，如 "01 02 03" 或 "010203"，大小写均可
+// 位数为奇数或含非十六进制字符时返回 None，空字符串解析为空的字节序列
+pub fn parse_hex(value: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u32> = value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16))
+        .collect::<Option<_>>()?;
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    Some(digits.chunks(2).map(|pair| (pair[0] << 4 | pair[1]) as u8).collect())
+}
+
+// 按 hexdump -C 的格式显示数据：偏移 + 16 个十六进制字节 + ASCII，不可打印字符显示为 '.'
+// limit 限制最多显示的字节数，超出部分只在末尾注明省略了多少字节
+pub fn hex_dump(data: &[u8], limit: Option<usize>) -> String {
+    let shown = limit.map_or(data.len(), |limit| limit.min(data.len()));
+    let mut out = String::new();
+    for (line, chunk) in data[..shown].chunks(BYTES_PER_LINE).enumerate() {
+        if line > 0 {
+            out.push('\n');
+        }
+        let _ = write!(out, "{:08x} ", line * BYTES_PER_LINE);
+        for i in 0..BYTES_PER_LINE {
+            // 每 8 个字节之间多留一个空格
+            if i % 8 == 0 {
+                out.push(' ');
+            }
+            match chunk.get(i) {
+                Some(byte) => {
+                    let _ = write!(out, "{:02x} ", byte);
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str(" |");
+        out.extend(chunk.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        }));
+        out.push('|');
+    }
+    if shown < data.len() {
+        let _ = write!(out, "\n... 共 {} 字节, 省略 {} 字节", data.len(), data.len() - shown);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_accepts_spaced_and_packed_digits() {
+        assert_eq!(parse_hex("48 65 6c 6C 6f"), Some(b"Hello".to_vec()));
+        assert_eq!(parse_hex("48656C6C6F"), Some(b"Hello".to_vec()));
+        assert_eq!(parse_hex(" 4865\n6c6c\t"), Some(b"Hell".to_vec()));
+        assert_eq!(parse_hex(""), Some(Vec::new()));
+        assert_eq!(parse_hex(&to_hex(&[0x00, 0x7F, 0xFF])), Some(vec![0x00, 0x7F, 0xFF]));
+    }
+
+    #[test]
+    fn parse_hex_rejects_odd_length_and_bad_digits() {
+        assert_eq!(parse_hex("0D0"), None);
+        assert_eq!(parse_hex("0 D 0"), None);
+        assert_eq!(parse_hex("0G"), None);
+        assert_eq!(parse_hex("0x01"), None);
+        assert_eq!(parse_hex("张三"), None);
+    }
+}
@@ -0,0 +1,29 @@
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+
+// IPv4 地址区间 (起始, 结束)，包含两端
+pub type IpRange = (u32, u32);
+
+// 将 IPv4 地址字符串转换为 u32，格式无效时返回 None
+pub fn ip_to_u32(ip: &str) -> Option<u32> {
+    Ipv4Addr::from_str(ip).ok().map(u32::from)
+}
+
+// 解析单个 IP 或 CIDR 网段 (如 10.0.0.0/8)，格式无效时返回 None
+pub fn parse_ip_range(text: &str) -> Option<IpRange> {
+    match text.split_once('/') {
+        Some((ip, prefix)) => match (ip_to_u32(ip.trim()), prefix.trim().parse::<u32>()) {
+            (Some(ip), Ok(prefix)) if prefix <= 32 => {
+                let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+                Some((ip & mask, (ip & mask) | !mask))
+            }
+            _ => None,
+        },
+        None => ip_to_u32(text).map(|ip| (ip, ip)),
+    }
+}
+
+// 判断地址是否落在任一区间内
+pub fn in_ranges(ip: u32, ranges: &[IpRange]) -> bool {
+    ranges.iter().any(|&(start, end)| start <= ip && ip <= end)
+}
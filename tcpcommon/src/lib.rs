@@ -0,0 +1,4 @@
+// tcpclient、tcpserver 与 servertest 共用的工具
+pub mod datafile;
+pub mod hexdump;
+pub mod ipnet;
@@ -0,0 +1,32 @@
+use std::net::IpAddr;
+use tcpcommon::ipnet::{in_ranges, IpRange};
+
+// 按对端地址过滤连接的黑白名单 (只支持 IPv4 网段)
+#[derive(Debug, Clone, Default)]
+pub struct AccessList {
+    pub allow: Vec<IpRange>, // 为空时不限制
+    pub deny: Vec<IpRange>,
+}
+
+impl AccessList {
+    // 没有配置任何规则
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    // 检查对端地址，拒绝时返回原因
+    // deny 优先于 allow；IPv4 映射的 IPv6 地址按 IPv4 处理，其余 IPv6 地址不在任何网段内
+    pub fn check(&self, ip: IpAddr) -> Result<(), &'static str> {
+        let ip = match ip {
+            IpAddr::V4(ip) => Some(u32::from(ip)),
+            IpAddr::V6(ip) => ip.to_ipv4_mapped().map(u32::from),
+        };
+        if ip.is_some_and(|ip| in_ranges(ip, &self.deny)) {
+            return Err("命中禁止列表");
+        }
+        if !self.allow.is_empty() && !ip.is_some_and(|ip| in_ranges(ip, &self.allow)) {
+            return Err("不在允许列表中");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+use crate::shutdown::Shutdown;
+use crate::traffic_log::{Direction, TrafficLog};
+use std::collections::BTreeMap;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::Instant;
+use tcpcommon::hexdump::parse_hex;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader, ReadBuf};
+use tokio::sync::{mpsc, watch};
+
+pub const HELP: &str = "管理命令:
+  list                 列出当前连接及收发统计
+  kick <addr>          断开指定客户端 (addr 为 list 中显示的 IP:端口)
+  say <addr> <text>    向指定客户端发送 text 并追加换行，hex:<十六进制> 发送原始字节
+  quit                 与 Ctrl+C 相同，停止接受新连接并退出
+  help                 显示本帮助";
+
+// 注册表中的一个连接
+struct Entry {
+    kick: watch::Sender<bool>,
+    say: mpsc::UnboundedSender<Vec<u8>>,
+    log: Option<TrafficLog>, // black-hole 连接不收发数据，没有统计
+    connected_at: Instant,
+}
+
+// 当前连接的注册表，管理命令通过它找到连接的控制端
+#[derive(Default)]
+pub struct Registry {
+    conns: Mutex<BTreeMap<SocketAddr, Entry>>,
+}
+
+// 连接的登记凭证，被丢弃时从注册表中移除
+pub struct Registration {
+    registry: Arc<Registry>,
+    addr: SocketAddr,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.registry.lock().remove(&self.addr);
+    }
+}
+
+impl Registry {
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<SocketAddr, Entry>> {
+        self.conns.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // 登记一个新连接，返回登记凭证、连接使用的退出信号 (服务器退出或被 kick 时触发)
+    // 和 say 命令的数据 (由 Injected 写给客户端)
+    pub fn register(
+        self: &Arc<Self>,
+        addr: SocketAddr,
+        log: Option<TrafficLog>,
+        shutdown: &Shutdown,
+    ) -> (Registration, Shutdown, mpsc::UnboundedReceiver<Vec<u8>>) {
+        let (kick, shutdown) = shutdown.for_connection();
+        let (say_tx, say_rx) = mpsc::unbounded_channel();
+        let entry = Entry {
+            kick,
+            say: say_tx,
+            log,
+            connected_at: Instant::now(),
+        };
+        self.lock().insert(addr, entry);
+        let registration = Registration {
+            registry: Arc::clone(self),
+            addr,
+        };
+        (registration, shutdown, say_rx)
+    }
+
+    fn list(&self) {
+        let conns = self.lock();
+        if conns.is_empty() {
+            println!("当前没有连接");
+            return;
+        }
+        println!("当前 {} 个连接:", conns.len());
+        for (addr, entry) in conns.iter() {
+            match &entry.log {
+                Some(log) => println!("  {}", log.stats_line(*addr)),
+                None => println!(
+                    "  peer={} duration={}s (black-hole)",
+                    addr,
+                    entry.connected_at.elapsed().as_secs()
+                ),
+            }
+        }
+    }
+
+    fn kick(&self, addr: SocketAddr) {
+        match self.lock().get(&addr) {
+            Some(entry) => {
+                let _ = entry.kick.send(true);
+                println!("已断开 {}", addr);
+            }
+            None => println!("没有来自 {} 的连接", addr),
+        }
+    }
+
+    fn say(&self, addr: SocketAddr, data: Vec<u8>) {
+        match self.lock().get(&addr) {
+            Some(entry) => {
+                let len = data.len();
+                // black-hole 连接不读写数据，登记时没有保留 say 的接收端
+                match entry.say.send(data) {
+                    Ok(()) => println!("已向 {} 发送 {} 字节", addr, len),
+                    Err(_) => println!("{} 不能发送数据 (black-hole 模式不读写)", addr),
+                }
+            }
+            None => println!("没有来自 {} 的连接", addr),
+        }
+    }
+}
+
+// 管理命令
+enum Command {
+    List,
+    Kick(SocketAddr),
+    Say(SocketAddr, Vec<u8>),
+    Quit,
+    Help,
+}
+
+// 解析一行命令，出错时返回原因
+fn parse_command(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim_start();
+    let parse_addr = |value: &str| {
+        value
+            .parse::<SocketAddr>()
+            .map_err(|_| format!("无效的客户端地址: {}", value))
+    };
+    match name {
+        "list" => Ok(Command::List),
+        "quit" | "exit" => Ok(Command::Quit),
+        "help" | "?" => Ok(Command::Help),
+        "kick" if !rest.is_empty() => Ok(Command::Kick(parse_addr(rest)?)),
+        "say" => {
+            let (addr, text) = rest
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| "用法: say <addr> <text>".to_string())?;
+            let data = match text.strip_prefix("hex:") {
+                Some(hex) => parse_hex(hex).ok_or_else(|| format!("无效的十六进制: {}", hex))?,
+                None => format!("{}\n", text).into_bytes(),
+            };
+            Ok(Command::Say(parse_addr(addr)?, data))
+        }
+        "kick" => Err("用法: kick <addr>".to_string()),
+        _ => Err(format!("未知命令: {}", name)),
+    }
+}
+
+// 读取 stdin 的管理命令，quit 时通知主任务退出；stdin 关闭时结束
+pub fn spawn_console(registry: Arc<Registry>, quit: mpsc::Sender<()>) {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match parse_command(&line) {
+                Ok(Command::List) => registry.list(),
+                Ok(Command::Kick(addr)) => registry.kick(addr),
+                Ok(Command::Say(addr, data)) => registry.say(addr, data),
+                Ok(Command::Quit) => {
+                    let _ = quit.send(()).await;
+                    break;
+                }
+                Ok(Command::Help) => println!("{}", HELP),
+                Err(e) => println!("{}\n{}", e, HELP),
+            }
+        }
+    });
+}
+
+// 包装客户端连接，把 say 命令的数据插入到发送流中
+// 读取时也会尝试写出待发送的数据，因此只读不写的模式同样能收到 say 的内容
+pub struct Injected<S> {
+    inner: S,
+    say: mpsc::UnboundedReceiver<Vec<u8>>,
+    pending: Vec<u8>, // 还没写完的 say 数据
+    log: TrafficLog,
+}
+
+impl<S: AsyncWrite + Unpin> Injected<S> {
+    pub fn new(inner: S, say: mpsc::UnboundedReceiver<Vec<u8>>, log: TrafficLog) -> Self {
+        Self {
+            inner,
+            say,
+            pending: Vec::new(),
+            log,
+        }
+    }
+
+    // 取出内部连接和 say 的接收端，由需要自己组织发送格式的模式 (如 WebSocket) 使用
+    pub fn into_parts(self) -> (S, mpsc::UnboundedReceiver<Vec<u8>>) {
+        (self.inner, self.say)
+    }
+
+    // 写出所有待发送的 say 数据，写不动时返回 Pending
+    fn poll_injected(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            if self.pending.is_empty() {
+                match self.say.poll_recv(cx) {
+                    Poll::Ready(Some(data)) => self.pending = data,
+                    _ => return Poll::Ready(Ok(())),
+                }
+                continue;
+            }
+            let n = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.pending))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.log.record(Direction::Sent, &self.pending[..n]);
+            self.pending.drain(..n);
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for Injected<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if let Poll::Ready(Err(e)) = this.poll_injected(cx) {
+            return Poll::Ready(Err(e));
+        }
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Injected<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        // 先写完 say 的数据，避免与正常数据交错
+        let this = self.get_mut();
+        ready!(this.poll_injected(cx))?;
+        Pin::new(&mut this.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_parse_addresses_and_payloads() {
+        assert!(matches!(parse_command("  list "), Ok(Command::List)));
+        assert!(matches!(parse_command("exit"), Ok(Command::Quit)));
+        let addr: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        assert!(matches!(parse_command("kick 127.0.0.1:50000"), Ok(Command::Kick(a)) if a == addr));
+        assert!(matches!(
+            parse_command("say 127.0.0.1:50000 hello world"),
+            Ok(Command::Say(a, data)) if a == addr && data == b"hello world\n"
+        ));
+        assert!(matches!(
+            parse_command("say 127.0.0.1:50000 hex:0D 0A"),
+            Ok(Command::Say(_, data)) if data == b"\r\n"
+        ));
+    }
+
+    // 输入错误时返回原因，由控制台打印帮助，不会 panic
+    #[test]
+    fn bad_commands_return_errors() {
+        assert_eq!(parse_command("kick").err().unwrap(), "用法: kick <addr>");
+        assert_eq!(parse_command("say 127.0.0.1:50000").err().unwrap(), "用法: say <addr> <text>");
+        assert_eq!(parse_command("kick 127.0.0.1").err().unwrap(), "无效的客户端地址: 127.0.0.1");
+        assert_eq!(parse_command("say client hi").err().unwrap(), "无效的客户端地址: client");
+        assert_eq!(
+            parse_command("say 127.0.0.1:50000 hex:0G").err().unwrap(),
+            "无效的十六进制: 0G"
+        );
+        assert_eq!(parse_command("stop").err().unwrap(), "未知命令: stop");
+        assert!(parse_command("").is_err());
+        assert!(parse_command("   ").is_err());
+    }
+}
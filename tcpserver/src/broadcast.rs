@@ -0,0 +1,139 @@
+use crate::modes::{idle_expired, idle_notice, over_limit, ReadOptions, Stream};
+use crate::shutdown::Shutdown;
+use crate::traffic_log::{Direction, TrafficLog};
+use std::collections::HashMap;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::{mpsc, Notify};
+use tokio::time::Instant;
+
+// 每个客户端待发送数据的队列长度，队列满说明客户端接收过慢
+const CLIENT_QUEUE_SIZE: usize = 64;
+
+// 空闲超时后等待写任务发出提示的最长时间
+const IDLE_NOTICE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// 在线客户端的发送端
+struct ClientHandle {
+    tx: mpsc::Sender<Arc<[u8]>>,
+    kick: Arc<Notify>, // 通知该客户端的处理任务断开连接
+}
+
+// 广播中心：维护在线连接表，把任一客户端的数据转发给其他所有客户端
+#[derive(Default)]
+pub struct Hub {
+    clients: Mutex<HashMap<SocketAddr, ClientHandle>>,
+}
+
+impl Hub {
+    // 某个客户端任务持锁时 panic 不应让其他客户端的广播也跟着 panic
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<SocketAddr, ClientHandle>> {
+        self.clients.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // 当前在线的客户端数量
+    fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    // 把数据放入除发送者外所有客户端的队列，队列已满的客户端会被移出并断开
+    // 返回被断开的慢客户端
+    fn broadcast(&self, from: SocketAddr, data: &Arc<[u8]>) -> Vec<SocketAddr> {
+        let mut clients = self.lock();
+        let slow: Vec<SocketAddr> = clients
+            .iter()
+            .filter(|(addr, _)| **addr != from)
+            .filter(|(_, client)| client.tx.try_send(Arc::clone(data)).is_err())
+            .map(|(addr, _)| *addr)
+            .collect();
+
+        for addr in &slow {
+            if let Some(client) = clients.remove(addr) {
+                client.kick.notify_one();
+            }
+        }
+        slow
+    }
+}
+
+// 广播模式下处理单个客户端连接
+pub async fn handle_client(
+    socket: impl Stream,
+    addr: SocketAddr,
+    hub: Arc<Hub>,
+    log: &TrafficLog,
+    shutdown: &Shutdown,
+    read: ReadOptions,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (mut reader, mut writer) = tokio::io::split(socket);
+    let (tx, mut rx) = mpsc::channel::<Arc<[u8]>>(CLIENT_QUEUE_SIZE);
+    let kick = Arc::new(Notify::new());
+    let notice_tx = tx.clone(); // 空闲超时时用来发送提示
+
+    hub.lock().insert(
+        addr,
+        ClientHandle {
+            tx,
+            kick: Arc::clone(&kick),
+        },
+    );
+    println!("[broadcast] {} 加入, 当前在线 {} 个客户端", addr, hub.len());
+
+    // 单独的写任务，避免慢客户端阻塞广播循环
+    let writer_log = log.clone();
+    let mut writer_task = tokio::spawn(async move {
+        while let Some(data) = rx.recv().await {
+            if writer.write_all(&data).await.is_err() {
+                break;
+            }
+            writer_log.record(Direction::Sent, &data);
+        }
+    });
+
+    let mut buffer = vec![0; read.buf_size];
+    let mut idle_closed = false;
+    let result = loop {
+        let idle_deadline = read.idle_timeout.map(|timeout| Instant::now() + timeout);
+        let n = tokio::select! {
+            read = reader.read(&mut buffer) => match read {
+                Ok(0) => break Ok(()),
+                Ok(n) => n,
+                Err(e) => break Err(e.into()),
+            },
+            _ = kick.notified() => {
+                println!("[broadcast] {} 接收过慢，已断开", addr);
+                break Ok(());
+            }
+            _ = shutdown.wait() => break Ok(()),
+            _ = idle_expired(idle_deadline) => {
+                println!("{} idle timeout, closing", addr);
+                let _ = notice_tx.try_send(idle_notice());
+                idle_closed = true;
+                break Ok(());
+            }
+        };
+
+        if let Err(max) = log.record_received(&buffer[..n]) {
+            over_limit(addr, max);
+            break Ok(());
+        }
+        let data: Arc<[u8]> = Arc::from(&buffer[..n]);
+        for slow in hub.broadcast(addr, &data) {
+            println!("[broadcast] {} 的发送队列已满，将被断开", slow);
+        }
+    };
+
+    hub.lock().remove(&addr);
+
+    // 空闲超时时等写任务把队列中的数据和提示发完（所有发送端都已丢弃，写任务会自行结束）
+    drop(notice_tx);
+    if idle_closed {
+        let _ = tokio::time::timeout(IDLE_NOTICE_TIMEOUT, &mut writer_task).await;
+    }
+    writer_task.abort();
+    println!("[broadcast] {} 离开, 当前在线 {} 个客户端", addr, hub.len());
+    result
+}
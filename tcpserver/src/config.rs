@@ -0,0 +1,566 @@
+use crate::access::AccessList;
+use crate::delay::Delay;
+use crate::limit::FullPolicy;
+use crate::modes::ReadOptions;
+use crate::rules::DefaultReply;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use tcpcommon::ipnet::parse_ip_range;
+
+// 默认监听地址和端口，绑定失败时退回 0.0.0.0
+pub const DEFAULT_BIND: &str = "127.0.0.1";
+pub const FALLBACK_BIND: &str = "0.0.0.0";
+pub const DEFAULT_PORT: u16 = 8888;
+
+// chargen 模式默认每次发送的字节数
+pub const DEFAULT_CHUNK_SIZE: usize = 1024;
+
+// echo/broadcast 模式默认的读缓冲大小
+pub const DEFAULT_BUF_SIZE: usize = 1024;
+
+pub const USAGE: &str = "用法: tcpserver [选项]
+
+选项:
+  --bind <IP>      监听地址 (默认 127.0.0.1，绑定失败时退回 0.0.0.0)
+  --port <端口>    监听端口，可重复指定以同时监听多个端口 (默认 8888)
+  --mode <模式>    工作模式 (默认 echo)
+                     echo       原样返回收到的数据
+                     broadcast  把任一客户端的数据转发给其他所有客户端
+                     sink       只接收不回复，定期打印累计接收字节数
+                     chargen    持续发送字符流
+                     time       发送当前时间后关闭连接
+                     discard    读取并丢弃数据，不打印也不回复
+                     black-hole 接受连接后从不读写，用于测试客户端的背压和读超时
+  --rate <字节/秒> chargen 模式的发送速率上限，0 表示不限速 (默认 0)
+  --chunk-size <字节>
+                   chargen 模式每次发送的字节数 (默认 1024)
+  --tls            使用 TLS 监听，需要同时指定 --cert 和 --key
+  --cert <文件>    PEM 格式的证书链
+  --key <文件>     PEM 格式的私钥
+  --log-dir <目录> 为每个连接创建 peerip_port_时间戳.log，记录收发的数据
+  --allow <CIDR>   只接受来自该网段的连接，可重复指定 (如 10.0.0.0/8 或单个 IP)
+  --deny <CIDR>    拒绝来自该网段的连接，可重复指定，优先于 --allow
+  --max-conns <N>  最大并发连接数，0 表示不限制 (默认 0)
+  --on-full <策略> 连接数已满时的处理方式 (默认 wait)
+                     wait    暂停接受新连接，直到有连接断开
+                     reject  接受后发送提示 (如果有) 并立即关闭
+  --full-message <文本>
+                   reject 策略下关闭连接前发送给客户端的提示
+  --idle-timeout <秒>
+                   超过指定时间未收到数据时发送提示并关闭连接，
+                   适用于 echo/broadcast/sink/discard 模式 (默认不启用)
+  --buf-size <字节>
+                   echo/broadcast 模式每次读取的缓冲区大小 (默认 1024)
+  --max-msg-bytes <字节>
+                   单个连接累计接收超过该字节数后断开连接，启用 --log-dir 时
+                   在记录中标注截断位置 (默认不限制)
+  --hex-dump       echo 模式收到数据时按 hexdump 格式 (偏移 + 十六进制 + ASCII)
+                   打印，而不是按文本打印；启用 --rules 时打印每次收到的数据，
+                   启用 --forward 时打印双向转发的数据
+  --hex-limit <字节>
+                   配合 --hex-dump，每条数据最多打印的字节数 (默认不限制)
+  --stats-interval <秒>
+                   定期打印当前连接数和总流量 (默认不打印)
+  --no-console     不从标准输入读取管理命令 (list/kick/say/quit)，
+                   在后台运行或 stdin 不可用时使用
+  --metrics-port <端口>
+                   在监听地址的该端口上提供 HTTP 指标接口 (GET /metrics，
+                   Prometheus 文本格式)，端口被占用时只打印警告 (默认不启用)
+  --delay-ms <毫秒>
+                   echo 模式回包前的延迟 (默认 0)
+  --delay-jitter <毫秒>
+                   在 delay-ms 上下随机浮动的范围 (默认 0)
+  --udp            在相同地址和端口上同时监听 UDP，把收到的数据报原样回发给来源地址
+                   (只能用于 echo 模式，UDP 不使用 TLS、延迟和规则应答)
+  --ws             echo 模式按 WebSocket 处理连接：完成 HTTP Upgrade 握手后回显
+                   Text/Binary 帧并响应 Ping/Close，非 WebSocket 请求返回 400
+                   (配合 --tls 即为 wss，不能和延迟、限速和规则应答同时使用)
+  --forward <主机:端口>
+                   转发模式：每个连接向目标另建一条连接并双向转发数据，任一侧关闭
+                   时两侧一起关闭，配合 --hex-dump 打印双向的流量
+                   (只能用于 echo 模式，不能和 --udp、--ws、延迟、限速和规则应答同时使用)
+  --throttle <KB/s>
+                   echo 模式下每个连接的回写速率上限，可以是小数，0 表示不限 (默认 0)
+  --rules <文件>   echo 模式下按 TOML 规则文件自动应答，每次收到的数据按顺序匹配规则
+  --default <处理> 没有规则命中时的处理方式 (默认 echo)
+                     echo          原样返回
+                     ignore        不回复
+                     text:<文本>   回复固定文本
+                     hex:<十六进制> 回复固定字节
+  -h, --help       显示帮助信息";
+
+// 服务器工作模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Echo,      // 原样返回收到的数据
+    Broadcast, // 转发给其他所有客户端
+    Sink,      // 只收不回
+    Chargen,   // 持续发送字符流
+    Time,      // 发送当前时间后关闭
+    Discard,   // 读取并丢弃
+    BlackHole, // 从不读写
+}
+
+impl Mode {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "echo" => Some(Mode::Echo),
+            "broadcast" => Some(Mode::Broadcast),
+            "sink" => Some(Mode::Sink),
+            "chargen" => Some(Mode::Chargen),
+            "time" => Some(Mode::Time),
+            "discard" => Some(Mode::Discard),
+            "black-hole" => Some(Mode::BlackHole),
+            _ => None,
+        }
+    }
+
+    // 启动信息中显示的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Echo => "echo",
+            Mode::Broadcast => "broadcast",
+            Mode::Sink => "sink",
+            Mode::Chargen => "chargen",
+            Mode::Time => "time",
+            Mode::Discard => "discard",
+            Mode::BlackHole => "black-hole",
+        }
+    }
+
+    // 启动时打印的模式说明
+    pub fn description(&self) -> &'static str {
+        match self {
+            Mode::Echo => "原样返回收到的数据",
+            Mode::Broadcast => "把任一客户端的数据转发给其他所有客户端",
+            Mode::Sink => "只接收不回复，定期打印累计接收字节数",
+            Mode::Chargen => "持续发送字符流",
+            Mode::Time => "发送当前时间后关闭连接",
+            Mode::Discard => "读取并丢弃数据",
+            Mode::BlackHole => "接受连接后从不读写",
+        }
+    }
+}
+
+// 服务器启动参数
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub bind: Option<IpAddr>, // None 表示使用默认地址并允许退回
+    pub ports: Vec<u16>,
+    pub mode: Mode,
+    pub rate: u64,                // chargen 每秒字节数，0 表示不限速
+    pub chunk_size: usize,        // chargen 每次发送的字节数
+    pub tls: Option<TlsFiles>,    // None 表示明文监听
+    pub log_dir: Option<PathBuf>, // 流量记录目录，None 表示不记录
+    pub access: AccessList,       // 按对端地址过滤 TCP 连接
+    pub max_conns: usize,         // 0 表示不限制
+    pub on_full: FullPolicy,
+    pub full_message: Option<String>,
+    pub idle_timeout: Option<Duration>, // None 表示不检查空闲连接
+    pub stats_interval: Option<Duration>, // None 表示不定期打印统计
+    pub metrics_port: Option<u16>,      // HTTP 指标接口端口，None 表示不启用
+    pub console: bool,                  // 是否从 stdin 读取管理命令
+    pub delay: Delay,                   // echo 回包延迟，为 0 时不启用
+    pub rules: Option<PathBuf>,         // 自动应答规则文件
+    pub default_reply: DefaultReply,    // 没有规则命中时的处理方式
+    pub udp: bool,                      // 是否同时监听 UDP echo
+    pub ws: bool,                       // echo 模式按 WebSocket 帧回显
+    pub forward: Option<String>,        // 转发目标 (主机:端口)，None 表示不转发
+    pub throttle: u64,                  // echo 每个连接每秒最多回写的字节数，0 表示不限速
+    pub buf_size: usize,                // echo/broadcast 的读缓冲大小
+    pub max_msg_bytes: Option<u64>,     // 单个连接累计接收字节数上限，None 表示不限制
+    pub hex_dump: bool,                 // echo 收到的数据按 hexdump 格式打印
+    pub hex_limit: Option<usize>,       // hexdump 每条数据最多打印的字节数，None 表示不限制
+}
+
+impl ServerConfig {
+    // 连接处理函数使用的读取参数
+    pub fn read_options(&self) -> ReadOptions {
+        ReadOptions {
+            buf_size: self.buf_size,
+            idle_timeout: self.idle_timeout,
+            hex_dump: self.hex_dump.then_some(self.hex_limit),
+        }
+    }
+}
+
+// TLS 证书和私钥文件
+#[derive(Debug, Clone)]
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+// 参数解析失败的原因
+#[derive(Debug)]
+pub enum ArgsError {
+    Help,            // 用户请求显示帮助
+    Invalid(String), // 参数非法
+}
+
+// 取出选项的值，支持 "--port 9000" 和 "--port=9000" 两种写法
+fn option_value(
+    name: &str,
+    inline: Option<&str>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, ArgsError> {
+    match inline {
+        Some(value) => Ok(value.to_string()),
+        None => args
+            .next()
+            .ok_or_else(|| ArgsError::Invalid(format!("{} 缺少参数值", name))),
+    }
+}
+
+// 解析命令行参数 (不含程序名)
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<ServerConfig, ArgsError> {
+    let mut config = ServerConfig {
+        bind: None,
+        ports: Vec::new(),
+        mode: Mode::Echo,
+        rate: 0,
+        chunk_size: DEFAULT_CHUNK_SIZE,
+        tls: None,
+        log_dir: None,
+        access: AccessList::default(),
+        max_conns: 0,
+        on_full: FullPolicy::Wait,
+        full_message: None,
+        idle_timeout: None,
+        stats_interval: None,
+        metrics_port: None,
+        console: true,
+        delay: Delay::default(),
+        rules: None,
+        default_reply: DefaultReply::default(),
+        udp: false,
+        ws: false,
+        forward: None,
+        throttle: 0,
+        buf_size: DEFAULT_BUF_SIZE,
+        max_msg_bytes: None,
+        hex_dump: false,
+        hex_limit: None,
+    };
+    let mut has_default = false;
+
+    let mut tls = false;
+    let mut cert = None;
+    let mut key = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value)),
+            None => (arg.clone(), None),
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => return Err(ArgsError::Help),
+            "--bind" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let ip = value
+                    .parse()
+                    .map_err(|_| ArgsError::Invalid(format!("无效的监听地址: {}", value)))?;
+                config.bind = Some(ip);
+            }
+            "--port" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let port = value
+                    .parse()
+                    .map_err(|_| ArgsError::Invalid(format!("无效的端口: {}", value)))?;
+                if !config.ports.contains(&port) {
+                    config.ports.push(port);
+                }
+            }
+            "--mode" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.mode = Mode::parse(&value)
+                    .ok_or_else(|| ArgsError::Invalid(format!("未知的工作模式: {}", value)))?;
+            }
+            "--rate" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.rate = value
+                    .parse()
+                    .map_err(|_| ArgsError::Invalid(format!("无效的发送速率: {}", value)))?;
+            }
+            "--chunk-size" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.chunk_size = value
+                    .parse()
+                    .ok()
+                    .filter(|&size| size > 0)
+                    .ok_or_else(|| ArgsError::Invalid(format!("无效的块大小: {}", value)))?;
+            }
+            "--tls" => tls = true,
+            "--udp" => config.udp = true,
+            "--ws" => config.ws = true,
+            "--forward" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let valid = value
+                    .rsplit_once(':')
+                    .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+                if !valid {
+                    return Err(ArgsError::Invalid(format!("无效的转发目标: {}", value)));
+                }
+                config.forward = Some(value);
+            }
+            "--no-console" => config.console = false,
+            "--allow" | "--deny" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let range = parse_ip_range(value.trim())
+                    .ok_or_else(|| ArgsError::Invalid(format!("无效的网段: {}", value)))?;
+                if name == "--allow" {
+                    config.access.allow.push(range);
+                } else {
+                    config.access.deny.push(range);
+                }
+            }
+            "--cert" => cert = Some(PathBuf::from(option_value(&name, inline, &mut args)?)),
+            "--key" => key = Some(PathBuf::from(option_value(&name, inline, &mut args)?)),
+            "--log-dir" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.log_dir = Some(PathBuf::from(value));
+            }
+            "--max-conns" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.max_conns = value
+                    .parse()
+                    .map_err(|_| ArgsError::Invalid(format!("无效的最大连接数: {}", value)))?;
+            }
+            "--on-full" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.on_full = FullPolicy::parse(&value).ok_or_else(|| {
+                    ArgsError::Invalid(format!("未知的连接数已满策略: {}", value))
+                })?;
+            }
+            "--full-message" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.full_message = Some(value);
+            }
+            "--idle-timeout" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let secs = value
+                    .parse()
+                    .ok()
+                    .filter(|&secs| secs > 0)
+                    .ok_or_else(|| ArgsError::Invalid(format!("无效的空闲超时: {}", value)))?;
+                config.idle_timeout = Some(Duration::from_secs(secs));
+            }
+            "--buf-size" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.buf_size =
+                    value.parse().ok().filter(|&size| size > 0).ok_or_else(|| {
+                        ArgsError::Invalid(format!("无效的缓冲区大小: {}", value))
+                    })?;
+            }
+            "--max-msg-bytes" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let max = value
+                    .parse()
+                    .ok()
+                    .filter(|&max| max > 0)
+                    .ok_or_else(|| ArgsError::Invalid(format!("无效的接收上限: {}", value)))?;
+                config.max_msg_bytes = Some(max);
+            }
+            "--hex-dump" => config.hex_dump = true,
+            "--hex-limit" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let limit = value
+                    .parse()
+                    .ok()
+                    .filter(|&limit| limit > 0)
+                    .ok_or_else(|| ArgsError::Invalid(format!("无效的打印上限: {}", value)))?;
+                config.hex_limit = Some(limit);
+            }
+            "--stats-interval" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let secs = value
+                    .parse()
+                    .ok()
+                    .filter(|&secs| secs > 0)
+                    .ok_or_else(|| ArgsError::Invalid(format!("无效的统计间隔: {}", value)))?;
+                config.stats_interval = Some(Duration::from_secs(secs));
+            }
+            "--metrics-port" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let port = value
+                    .parse()
+                    .map_err(|_| ArgsError::Invalid(format!("无效的指标端口: {}", value)))?;
+                config.metrics_port = Some(port);
+            }
+            "--delay-ms" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let ms = value
+                    .parse()
+                    .map_err(|_| ArgsError::Invalid(format!("无效的延迟: {}", value)))?;
+                config.delay.base = Duration::from_millis(ms);
+            }
+            "--delay-jitter" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let ms = value
+                    .parse()
+                    .map_err(|_| ArgsError::Invalid(format!("无效的延迟浮动范围: {}", value)))?;
+                config.delay.jitter = Duration::from_millis(ms);
+            }
+            "--throttle" => {
+                let value = option_value(&name, inline, &mut args)?;
+                let kb: f64 = value
+                    .parse()
+                    .ok()
+                    .filter(|kb: &f64| kb.is_finite() && *kb >= 0.0)
+                    .ok_or_else(|| ArgsError::Invalid(format!("无效的限速: {}", value)))?;
+                config.throttle = (kb * 1024.0).round() as u64;
+            }
+            "--rules" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.rules = Some(PathBuf::from(value));
+            }
+            "--default" => {
+                let value = option_value(&name, inline, &mut args)?;
+                config.default_reply = DefaultReply::parse(&value)
+                    .ok_or_else(|| ArgsError::Invalid(format!("无效的默认处理方式: {}", value)))?;
+                has_default = true;
+            }
+            _ => return Err(ArgsError::Invalid(format!("未知参数: {}", arg))),
+        }
+    }
+
+    // --tls 必须和证书、私钥一起使用
+    config.tls = match (tls, cert, key) {
+        (true, Some(cert), Some(key)) => Some(TlsFiles { cert, key }),
+        (true, _, _) => {
+            return Err(ArgsError::Invalid(
+                "--tls 需要同时指定 --cert 和 --key".to_string(),
+            ))
+        }
+        (false, None, None) => None,
+        (false, _, _) => {
+            return Err(ArgsError::Invalid(
+                "--cert 和 --key 需要配合 --tls 使用".to_string(),
+            ))
+        }
+    };
+
+    // 规则应答替代 echo 的回包逻辑
+    if config.rules.is_some() {
+        if config.mode != Mode::Echo {
+            return Err(ArgsError::Invalid("--rules 只能用于 echo 模式".to_string()));
+        }
+        if !config.delay.is_zero() || config.throttle > 0 {
+            return Err(ArgsError::Invalid(
+                "--rules 不能和 --delay-ms/--delay-jitter/--throttle 同时使用".to_string(),
+            ));
+        }
+    } else if has_default {
+        return Err(ArgsError::Invalid(
+            "--default 需要配合 --rules 使用".to_string(),
+        ));
+    }
+
+    if !config.delay.is_zero() && config.mode != Mode::Echo {
+        return Err(ArgsError::Invalid(
+            "--delay-ms/--delay-jitter 只能用于 echo 模式".to_string(),
+        ));
+    }
+    if config.throttle > 0 && config.mode != Mode::Echo {
+        return Err(ArgsError::Invalid(
+            "--throttle 只能用于 echo 模式".to_string(),
+        ));
+    }
+    if config.udp && config.mode != Mode::Echo {
+        return Err(ArgsError::Invalid("--udp 只能用于 echo 模式".to_string()));
+    }
+    if config.ws {
+        if config.mode != Mode::Echo {
+            return Err(ArgsError::Invalid("--ws 只能用于 echo 模式".to_string()));
+        }
+        if config.rules.is_some() || !config.delay.is_zero() || config.throttle > 0 {
+            return Err(ArgsError::Invalid(
+                "--ws 不能和 --rules/--delay-ms/--delay-jitter/--throttle 同时使用".to_string(),
+            ));
+        }
+    }
+    if config.forward.is_some() {
+        if config.mode != Mode::Echo {
+            return Err(ArgsError::Invalid(
+                "--forward 只能用于 echo 模式".to_string(),
+            ));
+        }
+        if config.udp
+            || config.ws
+            || config.rules.is_some()
+            || !config.delay.is_zero()
+            || config.throttle > 0
+        {
+            return Err(ArgsError::Invalid(
+                "--forward 不能和 --udp/--ws/--rules/--delay-ms/--delay-jitter/--throttle 同时使用"
+                    .to_string(),
+            ));
+        }
+    }
+    if config.hex_dump && config.mode != Mode::Echo {
+        return Err(ArgsError::Invalid(
+            "--hex-dump 只能用于 echo 模式".to_string(),
+        ));
+    }
+    if config.hex_limit.is_some() && !config.hex_dump {
+        return Err(ArgsError::Invalid(
+            "--hex-limit 需要配合 --hex-dump 使用".to_string(),
+        ));
+    }
+
+    if config.ports.is_empty() {
+        config.ports.push(DEFAULT_PORT);
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<ServerConfig, ArgsError> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    // 返回参数非法时的错误信息
+    fn invalid(args: &[&str]) -> String {
+        match parse(args) {
+            Err(ArgsError::Invalid(message)) => message,
+            other => panic!("unexpected result: {:?}", other.map(|config| config.ports)),
+        }
+    }
+
+    #[test]
+    fn options_accept_both_value_forms() {
+        let config = parse(&["--port", "9000", "--port=9001", "--port", "9000", "--mode=sink"]).unwrap();
+        assert_eq!(config.ports, [9000, 9001]);
+        assert_eq!(config.mode, Mode::Sink);
+        assert_eq!(parse(&[]).unwrap().ports, [DEFAULT_PORT]);
+        assert!(matches!(parse(&["--port", "80", "-h"]), Err(ArgsError::Help)));
+        assert_eq!(invalid(&["--port"]), "--port 缺少参数值");
+    }
+
+    #[test]
+    fn conflicting_options_are_rejected() {
+        assert_eq!(invalid(&["--tls", "--cert", "a.pem"]), "--tls 需要同时指定 --cert 和 --key");
+        assert_eq!(invalid(&["--key", "a.key"]), "--cert 和 --key 需要配合 --tls 使用");
+        assert_eq!(invalid(&["--default", "ignore"]), "--default 需要配合 --rules 使用");
+        assert_eq!(invalid(&["--rules", "r.toml", "--mode", "sink"]), "--rules 只能用于 echo 模式");
+        assert_eq!(
+            invalid(&["--rules", "r.toml", "--delay-ms", "10"]),
+            "--rules 不能和 --delay-ms/--delay-jitter/--throttle 同时使用"
+        );
+        assert_eq!(invalid(&["--hex-limit", "16"]), "--hex-limit 需要配合 --hex-dump 使用");
+        assert_eq!(invalid(&["--udp", "--mode", "chargen"]), "--udp 只能用于 echo 模式");
+        assert_eq!(
+            invalid(&["--mode", "sink", "--delay-jitter", "5"]),
+            "--delay-ms/--delay-jitter 只能用于 echo 模式"
+        );
+
+        let config = parse(&["--rules", "r.toml", "--default", "text:busy"]).unwrap();
+        assert!(matches!(config.default_reply, DefaultReply::Fixed(ref bytes) if bytes == b"busy"));
+    }
+}
@@ -0,0 +1,30 @@
+use rand::Rng;
+use std::time::Duration;
+
+// echo 回包前的人为延迟：在 base ± jitter 范围内均匀随机，下限为 0
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Delay {
+    pub base: Duration,
+    pub jitter: Duration,
+}
+
+impl Delay {
+    // 两个参数都为 0 时不启用延迟
+    pub fn is_zero(&self) -> bool {
+        self.base.is_zero() && self.jitter.is_zero()
+    }
+
+    // 可能的最大延迟
+    pub fn max(&self) -> Duration {
+        self.base + self.jitter
+    }
+
+    // 取一次延迟时间
+    pub fn sample(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return self.base;
+        }
+        let min = self.base.saturating_sub(self.jitter);
+        rand::thread_rng().gen_range(min..=self.max())
+    }
+}
@@ -0,0 +1,136 @@
+use crate::modes::{ignore_disconnect, over_limit, ReadOptions, Stream};
+use crate::shutdown::Shutdown;
+use crate::traffic_log::{Direction, TrafficLog};
+use std::error::Error;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{self, Instant};
+
+// 连接转发目标最多等待的时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 单个方向的拷贝结束的原因
+enum End {
+    Eof,            // 发送方关闭了连接
+    OverLimit(u64), // 客户端累计发送超过 --max-msg-bytes
+}
+
+// 把 reader 读到的数据写到 writer，直到 reader 关闭
+// direction 为 Received 时是客户端到目标的方向，受接收上限约束
+async fn pipe(
+    reader: &mut (impl AsyncRead + Unpin),
+    writer: &mut (impl AsyncWrite + Unpin),
+    direction: Direction,
+    route: &str,
+    log: &TrafficLog,
+    read: ReadOptions,
+    last_active: &Mutex<Instant>,
+) -> io::Result<End> {
+    let mut buffer = vec![0; read.buf_size];
+    loop {
+        let n = reader.read(&mut buffer).await?;
+        if n == 0 {
+            return Ok(End::Eof);
+        }
+        *last_active.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+        let data = &buffer[..n];
+        match direction {
+            Direction::Received => {
+                if let Err(max) = log.record_received(data) {
+                    return Ok(End::OverLimit(max));
+                }
+            }
+            Direction::Sent => log.record(Direction::Sent, data),
+        }
+        if read.hex_dump.is_some() {
+            println!("{} {} 字节:{}", route, n, read.shown(data));
+        }
+        writer.write_all(data).await?;
+    }
+}
+
+// 两个方向都超过空闲时间没有数据时返回；未启用空闲超时时永不返回
+async fn idle_watch(last_active: &Mutex<Instant>, idle_timeout: Option<Duration>) {
+    let Some(timeout) = idle_timeout else {
+        return std::future::pending().await;
+    };
+    loop {
+        let deadline = *last_active.lock().unwrap_or_else(|e| e.into_inner()) + timeout;
+        if Instant::now() >= deadline {
+            return;
+        }
+        time::sleep_until(deadline).await;
+    }
+}
+
+// 转发模式：为每个客户端连接向目标建立一条连接并双向拷贝数据，任一侧关闭时两侧一起关闭
+// 目标连接失败时立即关闭客户端连接
+pub async fn forward(
+    socket: impl Stream,
+    addr: SocketAddr,
+    target: &str,
+    log: &TrafficLog,
+    shutdown: &Shutdown,
+    read: ReadOptions,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (mut client_reader, mut client_writer) = tokio::io::split(socket);
+    let upstream = match time::timeout(CONNECT_TIMEOUT, TcpStream::connect(target)).await {
+        Ok(Ok(upstream)) => upstream,
+        Ok(Err(e)) => {
+            println!(
+                "{} 无法连接转发目标 {}: {}, 关闭客户端连接",
+                addr, target, e
+            );
+            let _ = client_writer.shutdown().await;
+            return Ok(());
+        }
+        Err(_) => {
+            println!(
+                "{} 连接转发目标 {} 超时 ({} 秒), 关闭客户端连接",
+                addr,
+                target,
+                CONNECT_TIMEOUT.as_secs()
+            );
+            let _ = client_writer.shutdown().await;
+            return Ok(());
+        }
+    };
+    let upstream_addr = upstream.peer_addr()?;
+    println!("{} 已转发到 {}", addr, upstream_addr);
+    let (mut target_reader, mut target_writer) = upstream.into_split();
+
+    let to_target = format!("{} -> {}", addr, upstream_addr);
+    let to_client = format!("{} <- {}", addr, upstream_addr);
+    let last_active = Mutex::new(Instant::now());
+    let result = tokio::select! {
+        end = pipe(&mut client_reader, &mut target_writer, Direction::Received, &to_target, log, read, &last_active) => {
+            match end {
+                Ok(End::Eof) => println!("{} 客户端关闭连接, 同时关闭到 {} 的连接", addr, upstream_addr),
+                Ok(End::OverLimit(max)) => over_limit(addr, max),
+                Err(_) => {}
+            }
+            end.map(|_| ())
+        }
+        end = pipe(&mut target_reader, &mut client_writer, Direction::Sent, &to_client, log, read, &last_active) => {
+            if let Ok(End::Eof) = end {
+                println!("{} 转发目标 {} 关闭连接, 同时关闭客户端连接", addr, upstream_addr);
+            }
+            end.map(|_| ())
+        }
+        _ = shutdown.wait() => Ok(()),
+        _ = idle_watch(&last_active, read.idle_timeout) => {
+            println!("{} idle timeout, closing", addr);
+            Ok(())
+        }
+    };
+
+    // 关闭两侧的写方向，对端随后会读到 EOF
+    let _ = target_writer.shutdown().await;
+    let _ = client_writer.shutdown().await;
+    println!("Client disconnected");
+    ignore_disconnect(result)
+}
@@ -0,0 +1,124 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+// 连接数已满时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FullPolicy {
+    Wait,   // 暂停 accept，直到有连接断开
+    Reject, // 照常 accept，发送可选的提示后立即关闭
+}
+
+impl FullPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "wait" => Some(FullPolicy::Wait),
+            "reject" => Some(FullPolicy::Reject),
+            _ => None,
+        }
+    }
+}
+
+// 并发连接数限制，所有监听端口共享
+pub struct ConnLimiter {
+    max: usize,                        // 0 表示不限制
+    semaphore: Option<Arc<Semaphore>>, // 不限制时为 None
+    policy: FullPolicy,
+    reject_message: Option<String>, // 拒绝连接前发送给客户端的提示
+    active: AtomicUsize,            // 当前连接数，用于打印和退出时等待
+}
+
+// 一个已接纳的连接，drop 时释放许可并更新连接数
+// 处理任务无论正常结束还是出错都会 drop 它，因此许可总会被归还
+pub struct ConnGuard {
+    limiter: Arc<ConnLimiter>,
+    addr: SocketAddr,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl ConnLimiter {
+    pub fn new(max: usize, policy: FullPolicy, reject_message: Option<String>) -> Self {
+        Self {
+            max,
+            semaphore: (max > 0).then(|| Arc::new(Semaphore::new(max))),
+            policy,
+            reject_message,
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    // 连接数显示，如 "3/10"，不限制时只显示当前数量
+    fn count_label(&self, active: usize) -> String {
+        if self.max > 0 {
+            format!("{}/{}", active, self.max)
+        } else {
+            active.to_string()
+        }
+    }
+
+    // 当前连接数
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    // 等待策略下在 accept 前先占一个空位，没有空位时一直等待
+    pub async fn wait_for_slot(&self) -> Option<OwnedSemaphorePermit> {
+        match (&self.semaphore, self.policy) {
+            (Some(semaphore), FullPolicy::Wait) => Arc::clone(semaphore).acquire_owned().await.ok(),
+            _ => None,
+        }
+    }
+
+    // 接纳一个新连接；reserved 为 accept 前占到的空位，没有时尝试立即获取
+    // 连接数已满时返回 None
+    pub fn admit(
+        self: &Arc<Self>,
+        reserved: Option<OwnedSemaphorePermit>,
+        addr: SocketAddr,
+    ) -> Option<ConnGuard> {
+        let permit = match (reserved, &self.semaphore) {
+            (Some(permit), _) => Some(permit),
+            (None, Some(semaphore)) => Some(Arc::clone(semaphore).try_acquire_owned().ok()?),
+            (None, None) => None,
+        };
+
+        let active = self.active.fetch_add(1, Ordering::Relaxed) + 1;
+        println!(
+            "New client connected: {}, 当前连接数: {}",
+            addr,
+            self.count_label(active)
+        );
+        Some(ConnGuard {
+            limiter: Arc::clone(self),
+            addr,
+            _permit: permit,
+        })
+    }
+
+    // 拒绝连接：发送提示（如果有）后关闭
+    pub async fn reject(&self, mut socket: TcpStream, addr: SocketAddr) {
+        println!(
+            "连接数已满 ({}), 拒绝客户端 {}",
+            self.count_label(self.active.load(Ordering::Relaxed)),
+            addr
+        );
+        if let Some(message) = &self.reject_message {
+            let _ = socket.write_all(message.as_bytes()).await;
+        }
+        let _ = socket.shutdown().await;
+    }
+}
+
+impl Drop for ConnGuard {
+    fn drop(&mut self) {
+        let active = self.limiter.active.fetch_sub(1, Ordering::Relaxed) - 1;
+        println!(
+            "Client {} closed, 当前连接数: {}",
+            self.addr,
+            self.limiter.count_label(active)
+        );
+    }
+}
@@ -1,72 +1,423 @@
+mod access;
+mod admin;
+mod broadcast;
+mod config;
+mod delay;
+mod forward;
+mod limit;
+mod metrics;
+mod modes;
+mod rules;
+mod shutdown;
+mod stats;
+mod throttle;
+mod tls;
+mod traffic_log;
+mod udp;
+mod ws;
+
+use admin::{Injected, Registry};
+use broadcast::Hub;
+use config::{parse_args, ArgsError, Mode, ServerConfig, DEFAULT_BIND, FALLBACK_BIND, USAGE};
+use limit::ConnLimiter;
+use metrics::MetricsState;
+use modes::Stream;
+use rules::RuleSet;
+use shutdown::Shutdown;
+use stats::{ServerStats, UdpStats};
 use std::error::Error;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use throttle::Throttle;
+use tokio::net::{TcpListener, UdpSocket};
+use tokio_rustls::TlsAcceptor;
+use traffic_log::{LogWriters, TrafficLog};
+
+// 收到 Ctrl+C 后等待连接结束的最长时间
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+// 所有接收循环和连接任务共享的服务器状态
+struct Server {
+    config: ServerConfig,
+    acceptor: Option<TlsAcceptor>, // 启用 TLS 时的握手器
+    rules: Option<RuleSet>,        // 启用规则应答时加载的规则
+    hub: Arc<Hub>,                 // 广播模式的在线连接表
+    limiter: Arc<ConnLimiter>,     // 所有端口共享的连接数限制
+    writers: Arc<LogWriters>,      // 流量记录的写文件任务，退出前等待写完
+    stats: Arc<ServerStats>,
+    registry: Arc<Registry>, // 当前连接，供 stdin 管理命令使用
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    // 尝试绑定到一个高端口（8888）来避免权限问题
-    let addr = "127.0.0.1:8888";
-    let listener = match TcpListener::bind(addr).await {
-        Ok(listener) => {
-            println!("Server running on {}", addr);
-            listener
+    let started_at = Instant::now();
+
+    // 解析命令行参数，参数非法时打印用法并退出
+    let config = match parse_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(ArgsError::Help) => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Err(ArgsError::Invalid(e)) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    // 启用 TLS 时先加载证书，失败直接退出
+    let acceptor = match &config.tls {
+        Some(files) => {
+            let acceptor = tls::load_acceptor(&files.cert, &files.key).unwrap_or_else(|e| {
+                eprintln!("无法启用 TLS: {}", e);
+                std::process::exit(1);
+            });
+            println!("TLS 已启用, 证书: {}", files.cert.display());
+            Some(acceptor)
+        }
+        None => None,
+    };
+
+    // 启用规则应答时加载规则文件，失败直接退出
+    let rules = config.rules.as_ref().map(|path| {
+        let rules = RuleSet::load(path, config.default_reply.clone()).unwrap_or_else(|e| {
+            eprintln!("无法加载规则文件 {}: {}", path.display(), e);
+            std::process::exit(1);
+        });
+        println!("已加载 {} 条应答规则: {}", rules.len(), path.display());
+        rules
+    });
+
+    // 绑定所有端口，任何一个失败都直接退出
+    let mut listeners = Vec::new();
+    for &port in &config.ports {
+        listeners.push(bind_listener(config.bind, port).await?);
+    }
+
+    // UDP 绑定到 TCP 实际监听的地址，保证两者地址和端口相同
+    let mut udp_sockets = Vec::new();
+    if config.udp {
+        for listener in &listeners {
+            let addr = listener.local_addr()?;
+            let socket = UdpSocket::bind(addr).await.map_err(|e| {
+                eprintln!("无法绑定 UDP 端口 {}: {}", addr, e);
+                e
+            })?;
+            udp_sockets.push(socket);
+        }
+    }
+
+    for listener in &listeners {
+        println!(
+            "Server running on {} ({} 模式)",
+            listener.local_addr()?,
+            config.mode.name()
+        );
+    }
+    for socket in &udp_sockets {
+        println!("UDP echo running on {}", socket.local_addr()?);
+    }
+
+    println!(
+        "工作模式: {} ({})",
+        config.mode.name(),
+        config.mode.description()
+    );
+    if !config.access.is_empty() {
+        println!(
+            "访问控制: 允许 {} 个网段, 禁止 {} 个网段",
+            config.access.allow.len(),
+            config.access.deny.len()
+        );
+    }
+    if let Some(target) = &config.forward {
+        println!("端口转发: 每个连接转发到 {}", target);
+    }
+    if config.ws {
+        println!("WebSocket: 完成 HTTP Upgrade 握手后按帧回显, 其他请求返回 400");
+    }
+    if config.max_conns > 0 {
+        println!("最大并发连接数: {}", config.max_conns);
+    }
+    if !config.delay.is_zero() {
+        println!(
+            "回包延迟: {}±{} 毫秒",
+            config.delay.base.as_millis(),
+            config.delay.jitter.as_millis()
+        );
+    }
+    if config.throttle > 0 {
+        println!(
+            "回写限速: {:.1} KB/s (每个连接)",
+            config.throttle as f64 / 1024.0
+        );
+    }
+    if let Some(max) = config.max_msg_bytes {
+        println!("单个连接累计接收上限: {} 字节", max);
+    }
+    if let Some(timeout) = config.idle_timeout {
+        println!("空闲超时: {} 秒", timeout.as_secs());
+    }
+
+    // 每个监听端口一个接收循环，所有端口共享连接数限制，广播模式下还共享同一个在线连接表
+    let limiter = Arc::new(ConnLimiter::new(
+        config.max_conns,
+        config.on_full,
+        config.full_message.clone(),
+    ));
+    let stats = Arc::new(ServerStats {
+        udp: config.udp.then(|| Arc::new(UdpStats::default())),
+        ..Default::default()
+    });
+    let writers = Arc::new(LogWriters::default());
+    let server = Arc::new(Server {
+        config,
+        acceptor,
+        rules,
+        hub: Arc::new(Hub::default()),
+        limiter: Arc::clone(&limiter),
+        writers: Arc::clone(&writers),
+        stats: Arc::clone(&stats),
+        registry: Arc::default(),
+    });
+    let (shutdown_tx, shutdown) = shutdown::channel();
+    // stdin 管理命令，quit 与 Ctrl+C 走相同的退出流程
+    let (quit_tx, mut quit_rx) = tokio::sync::mpsc::channel(1);
+    if server.config.console {
+        admin::spawn_console(Arc::clone(&server.registry), quit_tx);
+        println!("输入 help 查看管理命令");
+    }
+    if let Some(interval) = server.config.stats_interval {
+        spawn_stats_reporter(interval, Arc::clone(&stats), Arc::clone(&limiter));
+    }
+    if let Some(port) = server.config.metrics_port {
+        // 与主服务使用相同的监听地址
+        let ip = listeners[0].local_addr()?.ip();
+        let state = MetricsState {
+            stats: Arc::clone(&stats),
+            limiter: Arc::clone(&limiter),
+            started_at,
+        };
+        metrics::start_metrics_server(ip, port, state).await;
+    }
+    if let Some(udp_stats) = &stats.udp {
+        for socket in udp_sockets {
+            tokio::spawn(udp::echo(socket, Arc::clone(udp_stats), shutdown.clone()));
         }
+    }
+    let accept_tasks: Vec<_> = listeners
+        .into_iter()
+        .map(|listener| tokio::spawn(accept_loop(listener, Arc::clone(&server), shutdown.clone())))
+        .collect();
+
+    let serve = async {
+        for task in accept_tasks {
+            task.await??;
+        }
+        Ok::<_, Box<dyn Error>>(())
+    };
+    let reason = tokio::select! {
+        result = serve => return result,
+        _ = tokio::signal::ctrl_c() => "收到 Ctrl+C",
+        Some(()) = quit_rx.recv() => "收到 quit 命令",
+    };
+
+    // 停止接受新连接并通知所有连接任务结束，再等流量记录写完剩余数据和会话统计，共最多等待 SHUTDOWN_GRACE
+    println!(
+        "\n{}, 停止接受新连接, 等待 {} 个连接结束 (最多 {} 秒, 再按一次 Ctrl+C 立即退出)",
+        reason,
+        limiter.active(),
+        SHUTDOWN_GRACE.as_secs()
+    );
+    let _ = shutdown_tx.send(true);
+    let drained = async {
+        while limiter.active() > 0 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        writers.wait().await;
+    };
+    tokio::select! {
+        _ = drained => {}
+        _ = tokio::time::sleep(SHUTDOWN_GRACE) => {}
+        _ = tokio::signal::ctrl_c() => println!("再次收到 Ctrl+C, 立即退出"),
+    }
+
+    let remaining = limiter.active();
+    if remaining > 0 {
+        println!("仍有 {} 个连接未结束, 强制关闭", remaining);
+    }
+    println!("{}", stats.summary());
+
+    // 超时或再次按下 Ctrl+C 时不再等待未结束的连接任务和写文件任务
+    std::process::exit(0);
+}
+
+// 定期打印全局统计，第一次在启动 interval 之后
+fn spawn_stats_reporter(interval: Duration, stats: Arc<ServerStats>, limiter: Arc<ConnLimiter>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            println!("{}", stats.interval_line(limiter.active()));
+        }
+    });
+}
+
+// 绑定监听端口；未指定 --bind 时先尝试 127.0.0.1，失败后退回 0.0.0.0
+async fn bind_listener(bind: Option<IpAddr>, port: u16) -> Result<TcpListener, Box<dyn Error>> {
+    if let Some(ip) = bind {
+        let addr = SocketAddr::new(ip, port);
+        return TcpListener::bind(addr).await.map_err(|e| {
+            eprintln!("无法绑定到 {}: {}", addr, e);
+            e.into()
+        });
+    }
+
+    // 尝试绑定到一个高端口（默认8888）来避免权限问题
+    let addr = format!("{}:{}", DEFAULT_BIND, port);
+    match TcpListener::bind(&addr).await {
+        Ok(listener) => Ok(listener),
         Err(e) => {
             eprintln!("无法绑定到 {}: {}", addr, e);
-            eprintln!("尝试绑定到备用端口 0.0.0.0:8888（允许从任何网络接口访问）");
+            eprintln!(
+                "尝试绑定到备用地址 {}:{}（允许从任何网络接口访问）",
+                FALLBACK_BIND, port
+            );
 
             // 尝试使用 0.0.0.0 而不是 127.0.0.1
-            let backup_addr = "0.0.0.0:8888";
-            match TcpListener::bind(backup_addr).await {
-                Ok(listener) => {
-                    println!("Server running on {}", backup_addr);
-                    listener
-                }
+            let backup_addr = format!("{}:{}", FALLBACK_BIND, port);
+            match TcpListener::bind(&backup_addr).await {
+                Ok(listener) => Ok(listener),
                 Err(e) => {
                     eprintln!("无法绑定到 {}: {}", backup_addr, e);
-                    return Err(e.into());
+                    Err(e.into())
                 }
             }
         }
-    };
+    }
+}
 
-    // 循环接收新的连接
+// 循环接收新的连接
+async fn accept_loop(
+    listener: TcpListener,
+    server: Arc<Server>,
+    shutdown: Shutdown,
+) -> Result<(), std::io::Error> {
+    let limiter = &server.limiter;
     loop {
-        // 当有新连接时，获取stream和客户端地址
-        let (socket, addr) = listener.accept().await?;
-        println!("New client connected: {}", addr);
+        let next = async {
+            // 等待策略下连接数已满时在这里暂停，直到有连接断开
+            let reserved = limiter.wait_for_slot().await;
+
+            // 当有新连接时，获取stream和客户端地址
+            let (socket, addr) = listener.accept().await?;
+            Ok::<_, std::io::Error>((reserved, socket, addr))
+        };
+
+        // 服务器退出时停止接受新连接，监听端口随 listener 一起关闭
+        let (reserved, socket, addr) = tokio::select! {
+            next = next => next?,
+            _ = shutdown.wait() => return Ok(()),
+        };
+        // 黑白名单不通过的连接直接关闭，不占用连接数
+        if let Err(reason) = server.config.access.check(addr.ip()) {
+            println!("拒绝客户端 {} ({})", addr, reason);
+            continue;
+        }
+        let Some(guard) = limiter.admit(reserved, addr) else {
+            let limiter = Arc::clone(limiter);
+            tokio::spawn(async move { limiter.reject(socket, addr).await });
+            continue;
+        };
+        server.stats.connections.fetch_add(1, Ordering::Relaxed);
 
-        // 为每个新连接创建一个新的任务
+        // 为每个新连接创建一个新的任务，任务结束时 guard 被释放
+        let server = Arc::clone(&server);
+        let shutdown = shutdown.clone();
         tokio::spawn(async move {
-            // 处理这个客户端连接
-            if let Err(e) = process_socket(socket).await {
+            let _guard = guard;
+            let result = match (&server.acceptor, server.config.mode) {
+                // black-hole 模式从不读写，也就不进行 TLS 握手
+                (_, Mode::BlackHole) => {
+                    let (_registration, shutdown, _) =
+                        server.registry.register(addr, None, &shutdown);
+                    modes::black_hole(socket, addr, &shutdown).await
+                }
+                (Some(acceptor), _) => match tls::handshake(acceptor, socket, addr).await {
+                    Some(stream) => process_socket(stream, addr, &server, &shutdown).await,
+                    None => Ok(()),
+                },
+                (None, _) => process_socket(socket, addr, &server, &shutdown).await,
+            };
+            if let Err(e) = result {
                 eprintln!("Error processing connection from {}: {}", addr, e);
             }
         });
     }
 }
 
-// 处理单个客户端连接的函数
-async fn process_socket(mut socket: TcpStream) -> Result<(), Box<dyn Error>> {
-    let mut buffer = vec![0; 1024];
-
-    // 循环读取客户端发送的数据
-    loop {
-        // 从socket中读取数据
-        let n = socket.read(&mut buffer).await?;
+// 处理单个客户端连接的函数，按工作模式分发
+// 明文和 TLS 连接共用这里的逻辑
+async fn process_socket(
+    socket: impl Stream,
+    addr: SocketAddr,
+    server: &Server,
+    shutdown: &Shutdown,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let config = &server.config;
+    let stats = Arc::clone(&server.stats);
+    let log = TrafficLog::open(
+        config.log_dir.as_deref(),
+        addr,
+        stats,
+        config.max_msg_bytes,
+        &server.writers,
+    );
+    let read = config.read_options();
 
-        // 如果读取到0字节，表示客户端已关闭连接
-        if n == 0 {
-            println!("Client disconnected");
-            return Ok(());
+    // 登记到注册表，kick 通过连接自己的退出信号断开，say 的数据插入到发送流中
+    let (_registration, shutdown, say) =
+        server.registry.register(addr, Some(log.clone()), shutdown);
+    let shutdown = &shutdown;
+    let socket = Injected::new(socket, say, log.clone());
+    let result = match (config.mode, &server.rules, config.forward.as_deref()) {
+        // 启用规则应答时由规则决定回复内容
+        (Mode::Echo, Some(rules), _) => {
+            modes::auto_reply(socket, addr, &log, shutdown, read, rules).await
         }
-
-        // 将收到的数据原样发送回客户端
-        println!(
-            "Received {} bytes, echoing back: {}",
-            n,
-            String::from_utf8_lossy(&buffer[0..n])
-        );
-        socket.write_all(&buffer[0..n]).await?;
-    }
+        // 转发模式把数据交给目标，不在本地回包
+        (Mode::Echo, None, Some(target)) => {
+            forward::forward(socket, addr, target, &log, shutdown, read).await
+        }
+        // WebSocket 按帧收发，say 的数据也要封装成帧
+        (Mode::Echo, None, None) if config.ws => {
+            let (socket, say) = socket.into_parts();
+            ws::echo(socket, addr, &log, shutdown, read, say).await
+        }
+        // 未设置延迟和限速时走不带额外等待的快速路径
+        (Mode::Echo, None, None) if config.delay.is_zero() && config.throttle == 0 => {
+            modes::echo(socket, addr, &log, shutdown, read).await
+        }
+        (Mode::Echo, None, None) => {
+            let throttle = Throttle::new(config.throttle);
+            modes::echo_paced(socket, addr, &log, shutdown, read, config.delay, throttle).await
+        }
+        (Mode::Broadcast, ..) => {
+            let hub = Arc::clone(&server.hub);
+            broadcast::handle_client(socket, addr, hub, &log, shutdown, read).await
+        }
+        (Mode::Sink, ..) => modes::sink(socket, addr, &log, shutdown, read).await,
+        (Mode::Chargen, ..) => {
+            modes::chargen(socket, addr, config.rate, config.chunk_size, &log, shutdown).await
+        }
+        (Mode::Time, ..) => modes::send_time(socket, &log).await,
+        (Mode::Discard, ..) => modes::discard(socket, addr, &log, shutdown, read).await,
+        (Mode::BlackHole, ..) => unreachable!("black-hole 连接在握手前处理"),
+    };
+    println!("{}", log.stats_line(addr));
+    log.finish().await;
+    result
 }
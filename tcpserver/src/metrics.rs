@@ -0,0 +1,84 @@
+use crate::limit::ConnLimiter;
+use crate::stats::ServerStats;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// 请求头的最大长度，超过后不再读取
+const MAX_REQUEST_HEAD: usize = 4096;
+
+// 读取请求的超时时间，避免连接后不发请求的客户端一直占用任务
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 指标接口读取的共享状态，计数器与主服务是同一份
+#[derive(Clone)]
+pub struct MetricsState {
+    pub stats: Arc<ServerStats>,
+    pub limiter: Arc<ConnLimiter>,
+    pub started_at: Instant,
+}
+
+// 读取请求行，返回 (方法, 路径)
+async fn read_request_line(socket: &mut TcpStream) -> Option<(String, String)> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_HEAD {
+        let n = socket.read(&mut buffer).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buffer[..n]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut parts = head.lines().next()?.split_whitespace();
+    Some((parts.next()?.to_string(), parts.next()?.to_string()))
+}
+
+// 处理一个 HTTP 请求：GET /metrics 返回指标，其余返回错误
+async fn handle_request(mut socket: TcpStream, state: MetricsState) {
+    let request = tokio::time::timeout(REQUEST_TIMEOUT, read_request_line(&mut socket)).await;
+    let Ok(Some((method, path))) = request else {
+        return;
+    };
+
+    let (status, body) = match (method.as_str(), path.as_str()) {
+        ("GET", "/metrics") => (
+            "200 OK",
+            state
+                .stats
+                .prometheus_text(state.limiter.active(), state.started_at.elapsed()),
+        ),
+        ("GET", _) => ("404 Not Found", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "method not allowed\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+// 在 ip:port 上启动指标接口；绑定失败时只打印警告，不影响主服务
+pub async fn start_metrics_server(ip: IpAddr, port: u16, state: MetricsState) {
+    let addr = SocketAddr::new(ip, port);
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("警告: 指标接口启动失败 ({}): {}, 服务器继续运行", addr, e);
+            return;
+        }
+    };
+    println!("指标接口: http://{}/metrics", addr);
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            tokio::spawn(handle_request(socket, state.clone()));
+        }
+    });
+}
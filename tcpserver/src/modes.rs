@@ -0,0 +1,582 @@
+use crate::delay::Delay;
+use crate::rules::{DefaultReply, RuleSet};
+use crate::shutdown::Shutdown;
+use crate::throttle::Throttle;
+use crate::traffic_log::{Direction, TrafficLog};
+use std::error::Error;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tcpcommon::hexdump::hex_dump;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::{self, Instant, MissedTickBehavior};
+
+// 明文 TCP 流和 TLS 流共用同一套处理逻辑，只要求能异步读写
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> Stream for T {}
+
+// sink 模式打印累计接收字节数的间隔
+const SINK_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+// black-hole 模式检查对端是否关闭的间隔
+const BLACK_HOLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// 空闲超时关闭连接前发送给客户端的提示，以及发送提示最多等待的时间
+const IDLE_NOTICE: &[u8] = b"idle timeout, closing\r\n";
+const IDLE_NOTICE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// 带延迟的 echo 中等待回写的数据块上限，排满后暂停读取
+const DELAY_QUEUE_SIZE: usize = 1024;
+
+// chargen 每行的字符数（不含换行），与 RFC 864 一致
+const CHARGEN_LINE_WIDTH: usize = 72;
+
+// 客户端提前断开或重置连接导致的错误，按正常断开处理
+fn is_disconnect(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::BrokenPipe
+            | ErrorKind::UnexpectedEof
+    )
+}
+
+// 把断开类错误转换为正常结束
+pub fn ignore_disconnect(result: std::io::Result<()>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match result {
+        Err(e) if !is_disconnect(&e) => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+// 连接的读取参数
+#[derive(Debug, Clone, Copy)]
+pub struct ReadOptions {
+    pub buf_size: usize,                 // echo/broadcast 每次读取的缓冲区大小
+    pub idle_timeout: Option<Duration>,  // None 表示不检查空闲连接
+    pub hex_dump: Option<Option<usize>>, // 按 hexdump 格式打印收到的数据，内层为每条最多打印的字节数
+}
+
+impl ReadOptions {
+    // 收到的数据在 stdout 中的显示内容：默认按文本显示，启用 --hex-dump 时另起一行按 hexdump 格式显示
+    pub fn shown(&self, data: &[u8]) -> String {
+        match self.hex_dump {
+            Some(limit) => format!("\n{}", hex_dump(data, limit)),
+            None => format!(" {}", String::from_utf8_lossy(data)),
+        }
+    }
+}
+
+// 一次读取的结果
+enum ReadEvent {
+    Data(usize), // 读到并已记录的字节数，0 表示客户端已关闭
+    Shutdown,    // 服务器正在退出
+    Idle,        // 超过空闲时间未收到数据
+    OverLimit,   // 累计接收超过上限
+}
+
+// 读取数据并记录，同时等待退出信号和空闲超时
+async fn next_read(
+    reader: &mut (impl AsyncRead + Unpin),
+    buffer: &mut [u8],
+    addr: SocketAddr,
+    log: &TrafficLog,
+    shutdown: &Shutdown,
+    idle_timeout: Option<Duration>,
+) -> std::io::Result<ReadEvent> {
+    let n = tokio::select! {
+        read = reader.read(buffer) => read?,
+        _ = shutdown.wait() => return Ok(ReadEvent::Shutdown),
+        _ = idle_expired(idle_timeout.map(|timeout| Instant::now() + timeout)) => return Ok(ReadEvent::Idle),
+    };
+    match log.record_received(&buffer[..n]) {
+        Ok(()) => Ok(ReadEvent::Data(n)),
+        Err(max) => {
+            over_limit(addr, max);
+            Ok(ReadEvent::OverLimit)
+        }
+    }
+}
+
+// 累计接收超过 --max-msg-bytes 时打印断开原因
+pub fn over_limit(addr: SocketAddr, max: u64) {
+    println!("{} 累计接收超过上限 {} 字节, 断开连接", addr, max);
+}
+
+// 等到空闲截止时间；未启用空闲超时时永不返回
+pub async fn idle_expired(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+// 空闲超时：先尝试发送提示再关闭，客户端不接收时最多等待 IDLE_NOTICE_TIMEOUT
+async fn close_idle(writer: &mut (impl AsyncWrite + Unpin), addr: SocketAddr, log: &TrafficLog) {
+    println!("{} idle timeout, closing", addr);
+    let notice = async {
+        writer.write_all(IDLE_NOTICE).await?;
+        log.record(Direction::Sent, IDLE_NOTICE);
+        writer.shutdown().await
+    };
+    let _ = time::timeout(IDLE_NOTICE_TIMEOUT, notice).await;
+}
+
+// 广播模式的写任务在另一个任务中，只能通过队列发送提示
+pub fn idle_notice() -> Arc<[u8]> {
+    Arc::from(IDLE_NOTICE)
+}
+
+// echo 模式：将收到的数据原样发送回客户端
+pub async fn echo(
+    mut socket: impl Stream,
+    addr: SocketAddr,
+    log: &TrafficLog,
+    shutdown: &Shutdown,
+    read: ReadOptions,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut buffer = vec![0; read.buf_size];
+
+    // 循环读取客户端发送的数据
+    let result = async {
+        loop {
+            // 从socket中读取数据，服务器退出、空闲超时或超过接收上限时主动关闭连接
+            let event = next_read(
+                &mut socket,
+                &mut buffer,
+                addr,
+                log,
+                shutdown,
+                read.idle_timeout,
+            );
+            let n = match event.await? {
+                // 如果读取到0字节，表示客户端已关闭连接
+                ReadEvent::Data(0) => return Ok(()),
+                ReadEvent::Data(n) => n,
+                ReadEvent::Shutdown | ReadEvent::OverLimit => return socket.shutdown().await,
+                ReadEvent::Idle => {
+                    close_idle(&mut socket, addr, log).await;
+                    return Ok(());
+                }
+            };
+
+            println!(
+                "Received {} bytes, echoing back:{}",
+                n,
+                read.shown(&buffer[0..n])
+            );
+            socket.write_all(&buffer[0..n]).await?;
+            log.record(Direction::Sent, &buffer[0..n]);
+        }
+    }
+    .await;
+
+    println!("Client disconnected");
+    ignore_disconnect(result)
+}
+
+// 规则应答：每次收到的数据按顺序匹配规则，命中时回复规则中的内容，未命中时按默认方式处理
+pub async fn auto_reply(
+    mut socket: impl Stream,
+    addr: SocketAddr,
+    log: &TrafficLog,
+    shutdown: &Shutdown,
+    read: ReadOptions,
+    rules: &RuleSet,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut buffer = vec![0; read.buf_size];
+    let result = async {
+        loop {
+            let event = next_read(
+                &mut socket,
+                &mut buffer,
+                addr,
+                log,
+                shutdown,
+                read.idle_timeout,
+            );
+            let n = match event.await? {
+                ReadEvent::Data(0) => return Ok(()),
+                ReadEvent::Data(n) => n,
+                ReadEvent::Shutdown | ReadEvent::OverLimit => return socket.shutdown().await,
+                ReadEvent::Idle => {
+                    close_idle(&mut socket, addr, log).await;
+                    return Ok(());
+                }
+            };
+            let data = &buffer[..n];
+            if let Some(limit) = read.hex_dump {
+                println!("{} 收到 {} 字节:\n{}", addr, n, hex_dump(data, limit));
+            }
+
+            let reply = match rules.find(data) {
+                Some((index, reply)) => {
+                    println!("{} 收到 {} 字节, 命中规则 #{}", addr, n, index);
+                    reply
+                }
+                None => match rules.default_reply() {
+                    DefaultReply::Echo => data,
+                    DefaultReply::Ignore => continue,
+                    DefaultReply::Fixed(reply) => reply,
+                },
+            };
+            if !reply.is_empty() {
+                socket.write_all(reply).await?;
+                log.record(Direction::Sent, reply);
+            }
+        }
+    }
+    .await;
+
+    println!("Client disconnected");
+    ignore_disconnect(result)
+}
+
+// 带延迟或限速的 echo：读取和回写分开进行，每块数据从收到时起延迟一段时间后按顺序发回
+// 延迟和限速期间继续读取，收到的数据在队列中排队而不是被丢弃，因此能及时发现客户端断开
+pub async fn echo_paced(
+    socket: impl Stream,
+    addr: SocketAddr,
+    log: &TrafficLog,
+    shutdown: &Shutdown,
+    read: ReadOptions,
+    delay: Delay,
+    throttle: Option<Throttle>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (mut reader, mut writer) = tokio::io::split(socket);
+    let (tx, mut rx) = mpsc::channel::<(Instant, Vec<u8>)>(DELAY_QUEUE_SIZE);
+    let throttled = throttle.is_some();
+    let pending = Arc::new(AtomicUsize::new(0)); // 已排队但还没发完的字节数
+
+    // 写任务：等到每块数据的发送时间再回写，队列关闭后发完剩余数据并关闭写端
+    let writer_log = log.clone();
+    let writer_pending = Arc::clone(&pending);
+    let mut writer_task = tokio::spawn(async move {
+        let mut throttle = throttle;
+        while let Some((due, data)) = rx.recv().await {
+            time::sleep_until(due).await;
+            match &mut throttle {
+                Some(throttle) => throttle.write(&mut writer, &data, &writer_log).await?,
+                None => {
+                    writer.write_all(&data).await?;
+                    writer_log.record(Direction::Sent, &data);
+                }
+            }
+            writer_pending.fetch_sub(data.len(), Ordering::Relaxed);
+        }
+        writer.shutdown().await
+    });
+
+    let mut buffer = vec![0; read.buf_size];
+    let mut idle = false;
+    let mut stopped = false; // 客户端已断开、超过接收上限或服务器正在退出
+    let result = loop {
+        let event = next_read(
+            &mut reader,
+            &mut buffer,
+            addr,
+            log,
+            shutdown,
+            read.idle_timeout,
+        );
+        let n = match event.await {
+            Ok(ReadEvent::Data(0)) | Ok(ReadEvent::Shutdown) | Ok(ReadEvent::OverLimit) => {
+                stopped = true;
+                break Ok(());
+            }
+            Ok(ReadEvent::Data(n)) => n,
+            Ok(ReadEvent::Idle) => {
+                // 提示排在未发完的数据之后
+                println!("{} idle timeout, closing", addr);
+                let _ = tx.send((Instant::now(), IDLE_NOTICE.to_vec())).await;
+                idle = true;
+                break Ok(());
+            }
+            Err(e) => {
+                stopped = true;
+                break Err(e);
+            }
+        };
+
+        let wait = delay.sample();
+        let due = Instant::now() + wait;
+        println!(
+            "Received {} bytes, echoing back in {} ms:{}",
+            n,
+            wait.as_millis(),
+            read.shown(&buffer[0..n])
+        );
+        // 写任务已经出错结束时停止读取
+        pending.fetch_add(n, Ordering::Relaxed);
+        if tx.send((due, buffer[0..n].to_vec())).await.is_err() {
+            break Ok(());
+        }
+    };
+    drop(tx);
+
+    // 等待排队的数据发完；空闲超时时客户端可能已经不再接收，只等待有限的时间
+    let written = if idle {
+        let limit = delay.max() + IDLE_NOTICE_TIMEOUT;
+        let written = time::timeout(limit, &mut writer_task).await;
+        writer_task.abort();
+        written.unwrap_or(Ok(Ok(())))
+    } else if throttled && stopped && pending.load(Ordering::Relaxed) > 0 {
+        // 限速时剩余数据可能要很久才能发完，不再等待
+        println!(
+            "{} 限速回写未完成, 丢弃 {} 字节",
+            addr,
+            pending.load(Ordering::Relaxed)
+        );
+        writer_task.abort();
+        Ok(Ok(()))
+    } else {
+        (&mut writer_task).await
+    };
+
+    println!("Client disconnected");
+    ignore_disconnect(result)?;
+    ignore_disconnect(written.unwrap_or(Ok(())))
+}
+
+// sink 模式：只接收不回复，定期打印累计接收的字节数
+pub async fn sink(
+    mut socket: impl Stream,
+    addr: SocketAddr,
+    log: &TrafficLog,
+    shutdown: &Shutdown,
+    read: ReadOptions,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let idle_timeout = read.idle_timeout;
+    let mut buffer = vec![0; 64 * 1024];
+    let started = Instant::now();
+    let mut idle_deadline = idle_timeout.map(|timeout| started + timeout);
+    let mut total: u64 = 0;
+    let mut last_reported: u64 = 0;
+
+    let mut report = time::interval(SINK_REPORT_INTERVAL);
+    report.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    report.tick().await; // 第一次 tick 立即返回
+
+    let result = loop {
+        tokio::select! {
+            read = socket.read(&mut buffer) => match read {
+                Ok(0) => break Ok(()),
+                Ok(n) => {
+                    total += n as u64;
+                    if let Err(max) = log.record_received(&buffer[..n]) {
+                        over_limit(addr, max);
+                        break socket.shutdown().await;
+                    }
+                    idle_deadline = idle_timeout.map(|timeout| Instant::now() + timeout);
+                }
+                Err(e) => break Err(e),
+            },
+            _ = shutdown.wait() => break socket.shutdown().await,
+            _ = idle_expired(idle_deadline) => {
+                close_idle(&mut socket, addr, log).await;
+                break Ok(());
+            }
+            _ = report.tick() => {
+                // 没有新数据时不重复打印
+                if total != last_reported {
+                    let rate = (total - last_reported) as f64 / SINK_REPORT_INTERVAL.as_secs_f64();
+                    println!("[sink] {} 累计接收 {} 字节 ({:.0} 字节/秒)", addr, total, rate);
+                    last_reported = total;
+                }
+            }
+        }
+    };
+
+    let elapsed = started.elapsed().as_secs_f64();
+    println!(
+        "[sink] {} 断开, 共接收 {} 字节, 平均 {:.0} 字节/秒",
+        addr,
+        total,
+        if elapsed > 0.0 {
+            total as f64 / elapsed
+        } else {
+            0.0
+        }
+    );
+    ignore_disconnect(result)
+}
+
+// discard 模式：读取并丢弃数据，不打印也不回复
+pub async fn discard(
+    mut socket: impl Stream,
+    addr: SocketAddr,
+    log: &TrafficLog,
+    shutdown: &Shutdown,
+    read: ReadOptions,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut buffer = vec![0; 64 * 1024];
+    let result = async {
+        loop {
+            let event = next_read(
+                &mut socket,
+                &mut buffer,
+                addr,
+                log,
+                shutdown,
+                read.idle_timeout,
+            );
+            match event.await? {
+                ReadEvent::Data(0) => return Ok(()),
+                ReadEvent::Data(_) => {}
+                ReadEvent::Shutdown | ReadEvent::OverLimit => return socket.shutdown().await,
+                ReadEvent::Idle => {
+                    close_idle(&mut socket, addr, log).await;
+                    return Ok(());
+                }
+            }
+        }
+    }
+    .await;
+    ignore_disconnect(result)
+}
+
+// black-hole 模式：接受连接后从不读写，客户端的发送会因接收窗口占满而阻塞
+// 启用 TLS 时也不进行握手，因此直接使用 TCP 连接
+// 只通过 peek 定期检查对端是否已关闭；对端发送过数据后无法再察觉关闭，连接会一直保留
+pub async fn black_hole(
+    socket: TcpStream,
+    addr: SocketAddr,
+    shutdown: &Shutdown,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    println!("[black-hole] {} 已接受，不会读写任何数据", addr);
+    let mut probe = [0; 1];
+    let peer_closed = async {
+        loop {
+            match socket.peek(&mut probe).await {
+                Ok(0) => return Ok(()),
+                Ok(_) => time::sleep(BLACK_HOLE_CHECK_INTERVAL).await,
+                Err(e) => return Err(e),
+            }
+        }
+    };
+
+    tokio::select! {
+        result = peer_closed => {
+            ignore_disconnect(result)?;
+            println!("[black-hole] {} 已关闭连接", addr);
+        }
+        _ = shutdown.wait() => println!("[black-hole] {} 因服务器退出或被 kick 而关闭", addr),
+    }
+    Ok(())
+}
+
+// chargen 的字符流：可打印 ASCII 字符循环，每行起始字符依次后移
+struct Chargen {
+    line: usize,   // 当前行号，决定行首字符
+    column: usize, // 当前行已输出的字符数
+}
+
+impl Chargen {
+    fn new() -> Self {
+        Self { line: 0, column: 0 }
+    }
+
+    // 用字符流填满缓冲区
+    fn fill(&mut self, buffer: &mut [u8]) {
+        const PRINTABLE: usize = (b'~' - b' ' + 1) as usize;
+        for byte in buffer.iter_mut() {
+            *byte = match self.column {
+                CHARGEN_LINE_WIDTH => b'\r',
+                n if n > CHARGEN_LINE_WIDTH => b'\n',
+                n => b' ' + ((self.line + n) % PRINTABLE) as u8,
+            };
+            self.column += 1;
+            if self.column > CHARGEN_LINE_WIDTH + 1 {
+                self.column = 0;
+                self.line += 1;
+            }
+        }
+    }
+}
+
+// chargen 模式：持续发送字符流，rate 为每秒字节数 (0 表示不限速)
+pub async fn chargen(
+    socket: impl Stream,
+    addr: SocketAddr,
+    rate: u64,
+    chunk_size: usize,
+    log: &TrafficLog,
+    shutdown: &Shutdown,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (mut reader, mut writer) = tokio::io::split(socket);
+    let mut generator = Chargen::new();
+    let mut chunk = vec![0; chunk_size];
+    let started = Instant::now();
+    let mut total: u64 = 0;
+
+    // 客户端发来的数据直接丢弃，读到 EOF 说明客户端已关闭
+    let reader_log = log.clone();
+    let mut closed = tokio::spawn(async move {
+        let mut discard = vec![0; 1024];
+        loop {
+            let n = reader.read(&mut discard).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            if let Err(max) = reader_log.record_received(&discard[..n]) {
+                over_limit(addr, max);
+                return Ok(());
+            }
+        }
+    });
+
+    let result = loop {
+        // 服务器退出时在两块数据之间停止，不会打断正在发送的一块
+        if shutdown.is_triggered() {
+            break writer.shutdown().await;
+        }
+
+        // 限速：按已发送的字节数计算这一块最早的发送时间
+        if rate > 0 {
+            let due = started + Duration::from_secs_f64(total as f64 / rate as f64);
+            tokio::select! {
+                _ = time::sleep_until(due) => {}
+                _ = shutdown.wait() => continue,
+                read = &mut closed => break read.unwrap_or(Ok(())),
+            }
+        }
+
+        generator.fill(&mut chunk);
+        tokio::select! {
+            write = writer.write_all(&chunk) => match write {
+                Ok(()) => {
+                    total += chunk.len() as u64;
+                    log.record(Direction::Sent, &chunk);
+                }
+                Err(e) => break Err(e),
+            },
+            read = &mut closed => break read.unwrap_or(Ok(())),
+        }
+    };
+    closed.abort();
+
+    println!("[chargen] {} 断开, 共发送 {} 字节", addr, total);
+    ignore_disconnect(result)
+}
+
+// time 模式：连接后发送当前时间并关闭
+pub async fn send_time(
+    mut socket: impl Stream,
+    log: &TrafficLog,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f %:z\r\n");
+    let result = async {
+        let now = now.to_string();
+        socket.write_all(now.as_bytes()).await?;
+        log.record(Direction::Sent, now.as_bytes());
+        socket.shutdown().await
+    }
+    .await;
+    ignore_disconnect(result)
+}
@@ -0,0 +1,222 @@
+use serde::Deserialize;
+use std::path::Path;
+use tcpcommon::hexdump::parse_hex;
+use toml::Spanned;
+
+// 规则的匹配方式
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MatchKind {
+    Prefix, // 收到的数据以文本开头
+    Exact,  // 收到的数据与文本完全相同
+    Hex,    // 收到的数据以十六进制字节序列开头
+}
+
+// 规则文件中的一条规则，应答内容为 reply (文本) 或 reply_hex (十六进制) 之一
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleEntry {
+    #[serde(rename = "match")]
+    kind: MatchKind,
+    pattern: Spanned<String>,
+    reply: Option<String>,
+    reply_hex: Option<Spanned<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    rule: Vec<Spanned<RuleEntry>>,
+}
+
+// 解析后的规则
+struct Rule {
+    kind: MatchKind,
+    pattern: Vec<u8>,
+    reply: Vec<u8>,
+}
+
+impl Rule {
+    fn matches(&self, data: &[u8]) -> bool {
+        match self.kind {
+            MatchKind::Prefix | MatchKind::Hex => data.starts_with(&self.pattern),
+            MatchKind::Exact => data == self.pattern.as_slice(),
+        }
+    }
+}
+
+// 没有规则命中时的处理方式
+#[derive(Debug, Clone, Default)]
+pub enum DefaultReply {
+    #[default]
+    Echo, // 原样返回
+    Ignore,         // 不回复
+    Fixed(Vec<u8>), // 回复固定内容
+}
+
+impl DefaultReply {
+    // 解析 --default 的值: echo、ignore、text:<文本> 或 hex:<十六进制>
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "echo" => Some(DefaultReply::Echo),
+            "ignore" => Some(DefaultReply::Ignore),
+            _ => {
+                if let Some(text) = value.strip_prefix("text:") {
+                    Some(DefaultReply::Fixed(text.as_bytes().to_vec()))
+                } else {
+                    parse_hex(value.strip_prefix("hex:")?).map(DefaultReply::Fixed)
+                }
+            }
+        }
+    }
+}
+
+// 从规则文件加载的应答规则，按文件中的顺序匹配
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    default: DefaultReply,
+}
+
+impl RuleSet {
+    // 读取并校验规则文件，错误信息包含出错的行号
+    pub fn load(path: &Path, default: DefaultReply) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let file: RulesFile = toml::from_str(&content).map_err(|e| e.to_string())?;
+
+        let line_of = |offset: usize| content[..offset].matches('\n').count() + 1;
+        let mut rules = Vec::new();
+        for entry in file.rule {
+            let line = line_of(entry.span().start);
+            let entry = entry.into_inner();
+
+            let pattern = match entry.kind {
+                MatchKind::Prefix | MatchKind::Exact => entry.pattern.get_ref().as_bytes().to_vec(),
+                MatchKind::Hex => parse_hex(entry.pattern.get_ref()).ok_or_else(|| {
+                    format!(
+                        "第 {} 行: 无效的十六进制匹配内容",
+                        line_of(entry.pattern.span().start)
+                    )
+                })?,
+            };
+            if pattern.is_empty() {
+                return Err(format!("第 {} 行: 匹配内容为空", line));
+            }
+
+            let reply = match (entry.reply, entry.reply_hex) {
+                (Some(text), None) => text.into_bytes(),
+                (None, Some(hex)) => parse_hex(hex.get_ref()).ok_or_else(|| {
+                    format!(
+                        "第 {} 行: 无效的十六进制应答内容",
+                        line_of(hex.span().start)
+                    )
+                })?,
+                (Some(_), Some(_)) => {
+                    return Err(format!("第 {} 行: reply 和 reply_hex 只能指定一个", line))
+                }
+                (None, None) => return Err(format!("第 {} 行: 缺少 reply 或 reply_hex", line)),
+            };
+
+            rules.push(Rule {
+                kind: entry.kind,
+                pattern,
+                reply,
+            });
+        }
+        Ok(Self { rules, default })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    // 按顺序查找第一条命中的规则，返回规则序号 (从 1 开始) 和应答内容
+    pub fn find(&self, data: &[u8]) -> Option<(usize, &[u8])> {
+        self.rules
+            .iter()
+            .position(|rule| rule.matches(data))
+            .map(|index| (index + 1, self.rules[index].reply.as_slice()))
+    }
+
+    pub fn default_reply(&self) -> &DefaultReply {
+        &self.default
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 把规则写入临时文件后加载，name 区分同时运行的测试
+    fn load(name: &str, content: &str) -> Result<RuleSet, String> {
+        let path = std::env::temp_dir().join(format!("tcpserver-rules-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        let rules = RuleSet::load(&path, DefaultReply::Ignore);
+        std::fs::remove_file(&path).unwrap();
+        rules
+    }
+
+    #[test]
+    fn rules_match_in_file_order() {
+        let rules = load(
+            "valid",
+            r#"
+[[rule]]
+match = "exact"
+pattern = "PING"
+reply = "PONG"
+
+[[rule]]
+match = "prefix"
+pattern = "GET"
+reply = "OK"
+
+[[rule]]
+match = "hex"
+pattern = "01 03"
+reply_hex = "01 83 02"
+"#,
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules.find(b"PING"), Some((1, &b"PONG"[..])));
+        assert_eq!(rules.find(b"PING\r\n"), None);
+        assert_eq!(rules.find(b"GET /status"), Some((2, &b"OK"[..])));
+        assert_eq!(rules.find(&[0x01, 0x03, 0x00]), Some((3, &[0x01, 0x83, 0x02][..])));
+        assert!(matches!(rules.default_reply(), DefaultReply::Ignore));
+    }
+
+    #[test]
+    fn errors_report_the_line_of_the_bad_rule() {
+        let missing_reply = "[[rule]]\nmatch = \"prefix\"\npattern = \"A\"\nreply = \"B\"\n\n[[rule]]\nmatch = \"prefix\"\npattern = \"C\"\n";
+        assert_eq!(load("missing", missing_reply).err().unwrap(), "第 6 行: 缺少 reply 或 reply_hex");
+
+        let both = "[[rule]]\nmatch = \"exact\"\npattern = \"A\"\nreply = \"B\"\nreply_hex = \"42\"\n";
+        assert_eq!(load("both", both).err().unwrap(), "第 1 行: reply 和 reply_hex 只能指定一个");
+
+        let bad_pattern = "[[rule]]\nmatch = \"hex\"\npattern = \"0G\"\nreply = \"B\"\n";
+        assert_eq!(load("pattern", bad_pattern).err().unwrap(), "第 3 行: 无效的十六进制匹配内容");
+
+        // 未知字段由 toml 报告，同样带有行号
+        let unknown = "[[rule]]\nmatch = \"exact\"\npattern = \"A\"\nreplay = \"B\"\n";
+        assert!(load("unknown", unknown).err().unwrap().contains("line 4"));
+    }
+
+    #[test]
+    fn bad_hex_reply_is_rejected() {
+        let odd = "[[rule]]\nmatch = \"exact\"\npattern = \"A\"\n\nreply_hex = \"01 2\"\n";
+        assert_eq!(load("odd", odd).err().unwrap(), "第 5 行: 无效的十六进制应答内容");
+        let invalid = "[[rule]]\nmatch = \"exact\"\npattern = \"A\"\nreply_hex = \"zz\"\n";
+        assert_eq!(load("invalid", invalid).err().unwrap(), "第 4 行: 无效的十六进制应答内容");
+    }
+
+    #[test]
+    fn default_reply_parses_each_form() {
+        assert!(matches!(DefaultReply::parse("echo"), Some(DefaultReply::Echo)));
+        assert!(matches!(DefaultReply::parse("ignore"), Some(DefaultReply::Ignore)));
+        assert!(matches!(DefaultReply::parse("text:busy"), Some(DefaultReply::Fixed(bytes)) if bytes == b"busy"));
+        assert!(matches!(DefaultReply::parse("hex:0D 0A"), Some(DefaultReply::Fixed(bytes)) if bytes == b"\r\n"));
+        assert!(DefaultReply::parse("hex:0D0").is_none());
+        assert!(DefaultReply::parse("reply").is_none());
+    }
+}
@@ -0,0 +1,53 @@
+use tokio::sync::watch;
+
+// 优雅退出信号，主任务触发后所有接收循环和连接任务都能收到
+// 单个连接使用的信号还会在被管理命令踢掉时触发
+#[derive(Clone)]
+pub struct Shutdown {
+    rx: watch::Receiver<bool>,
+    kick: Option<watch::Receiver<bool>>,
+}
+
+// 创建退出信号，返回触发端和监听端
+pub fn channel() -> (watch::Sender<bool>, Shutdown) {
+    let (tx, rx) = watch::channel(false);
+    (tx, Shutdown { rx, kick: None })
+}
+
+// 等待信号被触发；触发端被丢弃时视为永不触发
+async fn wait_triggered(rx: &watch::Receiver<bool>) {
+    let mut rx = rx.clone();
+    if rx.wait_for(|&triggered| triggered).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+impl Shutdown {
+    // 派生单个连接使用的信号：服务器退出或返回的触发端被触发时都会触发
+    pub fn for_connection(&self) -> (watch::Sender<bool>, Shutdown) {
+        let (tx, kick) = watch::channel(false);
+        let shutdown = Shutdown {
+            rx: self.rx.clone(),
+            kick: Some(kick),
+        };
+        (tx, shutdown)
+    }
+
+    // 是否已经开始退出
+    pub fn is_triggered(&self) -> bool {
+        *self.rx.borrow() || self.kick.as_ref().is_some_and(|kick| *kick.borrow())
+    }
+
+    // 等待退出信号
+    pub async fn wait(&self) {
+        match &self.kick {
+            Some(kick) => {
+                tokio::select! {
+                    _ = wait_triggered(&self.rx) => {}
+                    _ = wait_triggered(kick) => {}
+                }
+            }
+            None => wait_triggered(&self.rx).await,
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// 服务器全局统计，各连接任务共享；只统计 TCP 流量
+#[derive(Default)]
+pub struct ServerStats {
+    pub connections: AtomicU64, // 累计服务过的连接数
+    pub bytes_received: AtomicU64,
+    pub bytes_sent: AtomicU64,
+    pub udp: Option<Arc<UdpStats>>, // 启用 --udp 时单独统计的 UDP 流量
+}
+
+// UDP echo 的统计
+#[derive(Default)]
+pub struct UdpStats {
+    pub datagrams: AtomicU64, // 累计收到的数据报数
+    pub bytes_received: AtomicU64,
+    pub bytes_sent: AtomicU64,
+}
+
+impl ServerStats {
+    // --stats-interval 定期打印的全局汇总
+    pub fn interval_line(&self, active: usize) -> String {
+        let mut line = format!(
+            "[stats] active={} total={} rx={} tx={}",
+            active,
+            self.connections.load(Ordering::Relaxed),
+            self.bytes_received.load(Ordering::Relaxed),
+            self.bytes_sent.load(Ordering::Relaxed)
+        );
+        if let Some(udp) = &self.udp {
+            line.push_str(&format!(
+                " udp_datagrams={} udp_rx={} udp_tx={}",
+                udp.datagrams.load(Ordering::Relaxed),
+                udp.bytes_received.load(Ordering::Relaxed),
+                udp.bytes_sent.load(Ordering::Relaxed)
+            ));
+        }
+        line
+    }
+
+    // 指标接口输出的 Prometheus 文本格式
+    pub fn prometheus_text(&self, active: usize, uptime: Duration) -> String {
+        let mut text = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+            let _ = write!(
+                text,
+                "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n"
+            );
+        };
+        metric(
+            "tcpserver_active_connections",
+            "gauge",
+            "当前连接数",
+            active.to_string(),
+        );
+        metric(
+            "tcpserver_connections_total",
+            "counter",
+            "累计服务过的连接数",
+            self.connections.load(Ordering::Relaxed).to_string(),
+        );
+        metric(
+            "tcpserver_bytes_received_total",
+            "counter",
+            "TCP 累计收到的字节数",
+            self.bytes_received.load(Ordering::Relaxed).to_string(),
+        );
+        metric(
+            "tcpserver_bytes_sent_total",
+            "counter",
+            "TCP 累计发送的字节数",
+            self.bytes_sent.load(Ordering::Relaxed).to_string(),
+        );
+        metric(
+            "tcpserver_uptime_seconds",
+            "gauge",
+            "服务器已运行的秒数",
+            format!("{:.3}", uptime.as_secs_f64()),
+        );
+        if let Some(udp) = &self.udp {
+            metric(
+                "tcpserver_udp_datagrams_total",
+                "counter",
+                "UDP 累计收到的数据报数",
+                udp.datagrams.load(Ordering::Relaxed).to_string(),
+            );
+            metric(
+                "tcpserver_udp_bytes_received_total",
+                "counter",
+                "UDP 累计收到的字节数",
+                udp.bytes_received.load(Ordering::Relaxed).to_string(),
+            );
+            metric(
+                "tcpserver_udp_bytes_sent_total",
+                "counter",
+                "UDP 累计回发的字节数",
+                udp.bytes_sent.load(Ordering::Relaxed).to_string(),
+            );
+        }
+        text
+    }
+
+    // 退出时打印的汇总
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "共服务 {} 个连接, 收到 {} 字节, 发送 {} 字节",
+            self.connections.load(Ordering::Relaxed),
+            self.bytes_received.load(Ordering::Relaxed),
+            self.bytes_sent.load(Ordering::Relaxed)
+        );
+        if let Some(udp) = &self.udp {
+            summary.push_str(&format!(
+                "; UDP 收到 {} 个数据报共 {} 字节, 回发 {} 字节",
+                udp.datagrams.load(Ordering::Relaxed),
+                udp.bytes_received.load(Ordering::Relaxed),
+                udp.bytes_sent.load(Ordering::Relaxed)
+            ));
+        }
+        summary
+    }
+}
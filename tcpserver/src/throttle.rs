@@ -0,0 +1,62 @@
+use crate::traffic_log::{Direction, TrafficLog};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::time::{self, Duration, Instant};
+
+// 每秒大约拆成的块数，块越小速率越平滑
+const CHUNKS_PER_SECOND: u64 = 20;
+
+// 单块的字节数上限
+const MAX_CHUNK_SIZE: usize = 4096;
+
+// 令牌桶限速：按 rate 字节/秒补充令牌，桶容量为一块，不允许突发
+pub struct Throttle {
+    rate: u64,
+    chunk_size: usize,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Throttle {
+    // rate 为每秒字节数，0 表示不限速
+    pub fn new(rate: u64) -> Option<Self> {
+        if rate == 0 {
+            return None;
+        }
+        let chunk_size = (rate / CHUNKS_PER_SECOND).clamp(1, MAX_CHUNK_SIZE as u64) as usize;
+        Some(Self {
+            rate,
+            chunk_size,
+            tokens: chunk_size as f64,
+            refilled: Instant::now(),
+        })
+    }
+
+    // 按经过的时间补充令牌
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.chunk_size as f64);
+        self.refilled = now;
+    }
+
+    // 把数据拆成小块写出，每块等到令牌足够后再发送
+    pub async fn write(
+        &mut self,
+        writer: &mut (impl AsyncWrite + Unpin),
+        data: &[u8],
+        log: &TrafficLog,
+    ) -> std::io::Result<()> {
+        for chunk in data.chunks(self.chunk_size) {
+            self.refill();
+            let missing = chunk.len() as f64 - self.tokens;
+            if missing > 0.0 {
+                time::sleep(Duration::from_secs_f64(missing / self.rate as f64)).await;
+                self.refill();
+            }
+            self.tokens -= chunk.len() as f64;
+            writer.write_all(chunk).await?;
+            log.record(Direction::Sent, chunk);
+        }
+        Ok(())
+    }
+}
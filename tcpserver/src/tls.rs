@@ -0,0 +1,52 @@
+use std::error::Error;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+// 握手超时，避免不发 ClientHello 的连接一直占用连接数
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 从 PEM 格式的证书链和私钥创建 TLS acceptor
+pub fn load_acceptor(cert: &Path, key: &Path) -> Result<TlsAcceptor, Box<dyn Error>> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("无法读取证书 {}: {}", cert.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("证书文件 {} 中没有证书", cert.display()).into());
+    }
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|e| format!("无法读取私钥 {}: {}", key.display(), e))?;
+
+    let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+// 完成 TLS 握手，失败时打印对端地址和错误并返回 None
+pub async fn handshake(
+    acceptor: &TlsAcceptor,
+    socket: TcpStream,
+    addr: SocketAddr,
+) -> Option<TlsStream<TcpStream>> {
+    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(socket)).await {
+        Ok(Ok(stream)) => Some(stream),
+        Ok(Err(e)) => {
+            eprintln!("TLS 握手失败 {}: {}", addr, e);
+            None
+        }
+        Err(_) => {
+            eprintln!("TLS 握手超时 {}", addr);
+            None
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use crate::stats::ServerStats;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tcpcommon::datafile::{create_data_file, write_to_file};
+use tcpcommon::hexdump::to_hex;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+// 待写入记录的队列长度，写文件跟不上时丢弃记录而不是阻塞收发
+const LOG_QUEUE_SIZE: usize = 1024;
+
+// 数据方向
+#[derive(Debug, Clone, Copy)]
+pub enum Direction {
+    Received, // 从客户端收到
+    Sent,     // 发送给客户端
+}
+
+impl Direction {
+    fn label(&self) -> &'static str {
+        match self {
+            Direction::Received => "收到",
+            Direction::Sent => "发送",
+        }
+    }
+}
+
+// 写文件任务处理的记录
+enum Entry {
+    Data {
+        timestamp: String,
+        direction: Direction,
+        data: Vec<u8>,
+    },
+    Note(String),   // 事件说明，例如截断位置
+    Finish(String), // 会话统计，写入后结束
+}
+
+// 收发字节数与次数，在收发路径上累计
+#[derive(Default)]
+struct Stats {
+    received_bytes: AtomicU64,
+    received_count: AtomicU64,
+    sent_bytes: AtomicU64,
+    sent_count: AtomicU64,
+    dropped: AtomicU64, // 因队列已满未能写入的记录数
+}
+
+// 所有连接的写文件任务，退出时等待它们写完剩余记录和会话统计
+#[derive(Default)]
+pub struct LogWriters(Mutex<Vec<JoinHandle<()>>>);
+
+impl LogWriters {
+    // 登记新的写文件任务，顺便清理已结束的任务，长时间运行时列表不会一直增长
+    fn add(&self, handle: JoinHandle<()>) {
+        let mut handles = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        handles.retain(|handle| !handle.is_finished());
+        handles.push(handle);
+    }
+
+    // 等待所有已登记的写文件任务结束；连接仍未结束时对应的任务不会结束，调用方需限制等待时间
+    pub async fn wait(&self) {
+        loop {
+            let handle = self.0.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            match handle {
+                Some(handle) => {
+                    let _ = handle.await;
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+// 单个连接的流量记录：总是累计收发字节数（同时计入全局统计），指定 --log-dir 时还会写入文件
+// 可以克隆给同一连接的多个任务使用，文件在独立任务中写入
+#[derive(Clone)]
+pub struct TrafficLog {
+    tx: Option<mpsc::Sender<Entry>>,
+    stats: Arc<Stats>,
+    totals: Arc<ServerStats>,
+    started: Instant,
+    max_received: Option<u64>, // 单个连接累计接收字节数上限
+}
+
+impl TrafficLog {
+    // 在 dir 下为连接创建 peerip_port_时间戳.log，未指定目录或创建失败时只做计数
+    // 写文件任务登记到 writers，退出时等待它写完
+    pub fn open(
+        dir: Option<&Path>,
+        addr: SocketAddr,
+        totals: Arc<ServerStats>,
+        max_received: Option<u64>,
+        writers: &LogWriters,
+    ) -> Self {
+        let mut log = Self {
+            tx: None,
+            stats: Arc::default(),
+            totals,
+            started: Instant::now(),
+            max_received,
+        };
+        let Some(dir) = dir else {
+            return log;
+        };
+
+        let (file, filepath) =
+            match create_data_file(dir, &addr.ip().to_string(), addr.port(), "log") {
+                Ok(created) => created,
+                Err(e) => {
+                    eprintln!("无法为 {} 创建流量记录文件: {}", addr, e);
+                    return log;
+                }
+            };
+        println!("{} 的流量记录写入 {}", addr, filepath.display());
+
+        let (tx, rx) = mpsc::channel(LOG_QUEUE_SIZE);
+        writers.add(tokio::task::spawn_blocking(move || {
+            if let Err(e) = write_entries(file, rx) {
+                eprintln!("写入流量记录 {} 失败: {}", filepath.display(), e);
+            }
+        }));
+
+        log.tx = Some(tx);
+        log
+    }
+
+    // 记录一次收发的数据，不会等待文件写入
+    pub fn record(&self, direction: Direction, data: &[u8]) {
+        let (bytes, count, total) = match direction {
+            Direction::Received => (
+                &self.stats.received_bytes,
+                &self.stats.received_count,
+                &self.totals.bytes_received,
+            ),
+            Direction::Sent => (
+                &self.stats.sent_bytes,
+                &self.stats.sent_count,
+                &self.totals.bytes_sent,
+            ),
+        };
+        bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+        count.fetch_add(1, Ordering::Relaxed);
+        total.fetch_add(data.len() as u64, Ordering::Relaxed);
+
+        let Some(tx) = &self.tx else {
+            return;
+        };
+
+        let entry = Entry::Data {
+            timestamp: chrono::Local::now()
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+                .to_string(),
+            direction,
+            data: data.to_vec(),
+        };
+        if tx.try_send(entry).is_err() {
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // 记录收到的数据；累计接收超过上限时只记录上限以内的部分，
+    // 在记录文件中标注截断位置，并返回 Err(上限) 通知调用方断开连接
+    pub fn record_received(&self, data: &[u8]) -> Result<(), u64> {
+        let Some(max) = self.max_received else {
+            self.record(Direction::Received, data);
+            return Ok(());
+        };
+        let before = self.stats.received_bytes.load(Ordering::Relaxed);
+        if before + data.len() as u64 <= max {
+            self.record(Direction::Received, data);
+            return Ok(());
+        }
+
+        let allowed = max.saturating_sub(before) as usize;
+        if allowed > 0 {
+            self.record(Direction::Received, &data[..allowed]);
+        }
+        self.note(format!(
+            "累计接收超过上限 {} 字节, 在第 {} 字节处截断, 本次收到的 {} 字节中丢弃 {} 字节",
+            max,
+            max,
+            data.len(),
+            data.len() - allowed
+        ));
+        Err(max)
+    }
+
+    // 在记录文件中写入一条事件说明
+    fn note(&self, text: String) {
+        if let Some(tx) = &self.tx {
+            if tx.try_send(Entry::Note(text)).is_err() {
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    // 连接结束时调用，在文件末尾写入会话统计
+    pub async fn finish(self) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+        let _ = tx.send(Entry::Finish(self.summary())).await;
+    }
+
+    // 连接关闭时打印的结构化统计，msgs 为收到数据的次数
+    pub fn stats_line(&self, addr: SocketAddr) -> String {
+        format!(
+            "peer={} duration={}s rx={} tx={} msgs={}",
+            addr,
+            self.started.elapsed().as_secs(),
+            self.stats.received_bytes.load(Ordering::Relaxed),
+            self.stats.sent_bytes.load(Ordering::Relaxed),
+            self.stats.received_count.load(Ordering::Relaxed)
+        )
+    }
+
+    fn summary(&self) -> String {
+        let stats = &self.stats;
+        let duration = self.started.elapsed();
+        let mut summary = format!(
+            "会话结束: 持续 {:.1} 秒, 收到 {} 字节 ({} 次), 发送 {} 字节 ({} 次)",
+            duration.as_secs_f64(),
+            stats.received_bytes.load(Ordering::Relaxed),
+            stats.received_count.load(Ordering::Relaxed),
+            stats.sent_bytes.load(Ordering::Relaxed),
+            stats.sent_count.load(Ordering::Relaxed)
+        );
+        let dropped = stats.dropped.load(Ordering::Relaxed);
+        if dropped > 0 {
+            summary.push_str(&format!(", {} 条记录因写入过慢被丢弃", dropped));
+        }
+        summary
+    }
+}
+
+// 合法的 UTF-8 按转义后的文本记录，否则按十六进制记录，保证原始数据可以还原
+fn format_data(data: &[u8]) -> String {
+    match std::str::from_utf8(data) {
+        Ok(text) => format!("{:?}", text),
+        Err(_) => format!("HEX {}", to_hex(data)),
+    }
+}
+
+// 写文件任务：队列暂时为空时 flush，收到会话统计或所有发送端关闭后结束
+fn write_entries(file: File, mut rx: mpsc::Receiver<Entry>) -> Result<(), std::io::Error> {
+    let mut writer = BufWriter::new(file);
+    while let Some(entry) = rx.blocking_recv() {
+        match entry {
+            Entry::Data {
+                timestamp,
+                direction,
+                data,
+            } => {
+                let line = format!(
+                    "{} {} 字节: {}",
+                    direction.label(),
+                    data.len(),
+                    format_data(&data)
+                );
+                write_to_file(&mut writer, &timestamp, &line)?;
+            }
+            Entry::Note(text) => {
+                let timestamp = chrono::Local::now()
+                    .format("%Y-%m-%d %H:%M:%S%.3f")
+                    .to_string();
+                write_to_file(&mut writer, &timestamp, &text)?;
+            }
+            Entry::Finish(summary) => {
+                let timestamp = chrono::Local::now()
+                    .format("%Y-%m-%d %H:%M:%S%.3f")
+                    .to_string();
+                write_to_file(&mut writer, &timestamp, &summary)?;
+                break;
+            }
+        }
+        if rx.is_empty() {
+            writer.flush()?;
+        }
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // finish 只把会话统计放入队列，等待写文件任务后文件中才有完整的记录
+    #[tokio::test]
+    async fn writers_flush_records_and_summary_before_exit() {
+        let dir = std::env::temp_dir().join(format!("tcpserver-traffic-{}", std::process::id()));
+        let writers = LogWriters::default();
+        let addr = "127.0.0.1:9000".parse().unwrap();
+        let log = TrafficLog::open(Some(&dir), addr, Arc::default(), None, &writers);
+        log.record(Direction::Received, b"hello");
+        log.record(Direction::Sent, &[0xFF]);
+        log.finish().await;
+        writers.wait().await;
+
+        let path = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(content.contains("收到 5 字节: \"hello\""), "{}", content);
+        assert!(content.contains("发送 1 字节: HEX FF"), "{}", content);
+        assert!(content.lines().last().unwrap().contains("会话结束"), "{}", content);
+    }
+}
@@ -0,0 +1,39 @@
+use crate::shutdown::Shutdown;
+use crate::stats::UdpStats;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+
+// 接收缓冲区按 UDP 数据报的最大长度分配，保证数据报不会被截断
+const MAX_DATAGRAM_SIZE: usize = 65536;
+
+// UDP echo：把收到的每个数据报原样回发给来源地址，直到服务器退出
+// UDP 没有连接，单个数据报收发失败只打印错误，不影响后续数据报
+pub async fn echo(socket: UdpSocket, stats: Arc<UdpStats>, shutdown: Shutdown) {
+    let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+    loop {
+        let received = tokio::select! {
+            received = socket.recv_from(&mut buffer) => received,
+            _ = shutdown.wait() => return,
+        };
+
+        // 某些平台会把上一次回发触发的 ICMP 端口不可达报告为这里的错误
+        let (n, addr) = match received {
+            Ok(received) => received,
+            Err(e) => {
+                eprintln!("[udp] 接收失败: {}", e);
+                continue;
+            }
+        };
+        stats.datagrams.fetch_add(1, Ordering::Relaxed);
+        stats.bytes_received.fetch_add(n as u64, Ordering::Relaxed);
+        println!("[udp] {} 收到 {} 字节", addr, n);
+
+        match socket.send_to(&buffer[..n], addr).await {
+            Ok(sent) => {
+                stats.bytes_sent.fetch_add(sent as u64, Ordering::Relaxed);
+            }
+            Err(e) => eprintln!("[udp] 向 {} 回发失败: {}", addr, e),
+        }
+    }
+}
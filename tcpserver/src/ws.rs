@@ -0,0 +1,160 @@
+use crate::modes::{idle_expired, ignore_disconnect, over_limit, ReadOptions, Stream};
+use crate::shutdown::Shutdown;
+use crate::traffic_log::{Direction, TrafficLog};
+use futures_util::{SinkExt, StreamExt};
+use std::error::Error;
+use std::net::SocketAddr;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+// 不是 WebSocket 握手的请求收到的回复
+const BAD_REQUEST: &[u8] =
+    b"HTTP/1.1 400 Bad Request\r\nConnection: close\r\nContent-Type: text/plain\r\nContent-Length: 28\r\n\r\nexpected WebSocket upgrade\r\n";
+
+// 握手请求中客户端提供的子协议列表
+fn offered_protocols(request: &Request) -> Vec<String> {
+    request
+        .headers()
+        .get_all("Sec-WebSocket-Protocol")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|protocol| protocol.trim().to_string())
+        .filter(|protocol| !protocol.is_empty())
+        .collect()
+}
+
+// WebSocket echo：完成 HTTP Upgrade 握手后把收到的 Text/Binary 帧原样发回
+// Ping 由 tungstenite 自动回复 Pong，收到 Close 时回复 Close 后结束；
+// say 命令的数据以帧的形式发送，不会破坏帧格式
+pub async fn echo(
+    mut socket: impl Stream,
+    addr: SocketAddr,
+    log: &TrafficLog,
+    shutdown: &Shutdown,
+    read: ReadOptions,
+    mut say: mpsc::UnboundedReceiver<Vec<u8>>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // 记录握手路径，客户端提供子协议时选择第一个
+    let mut path = String::new();
+    let mut protocol = None;
+    // 回调的错误类型由 tungstenite 规定，无法缩小
+    #[allow(clippy::result_large_err)]
+    let callback = |request: &Request, mut response: Response| {
+        path = request.uri().to_string();
+        protocol = offered_protocols(request).into_iter().next();
+        if let Some(value) = protocol.as_deref().and_then(|p| p.parse().ok()) {
+            response
+                .headers_mut()
+                .insert("Sec-WebSocket-Protocol", value);
+        }
+        Ok(response)
+    };
+    let mut ws = match tokio_tungstenite::accept_hdr_async(&mut socket, callback).await {
+        Ok(ws) => ws,
+        Err(tungstenite::Error::Io(e)) => return ignore_disconnect(Err(e)),
+        Err(e) => {
+            println!("{} 不是 WebSocket 握手 ({}), 返回 400", addr, e);
+            let _ = socket.write_all(BAD_REQUEST).await;
+            log.record(Direction::Sent, BAD_REQUEST);
+            let _ = socket.shutdown().await;
+            return Ok(());
+        }
+    };
+    println!(
+        "{} WebSocket 握手完成, path={} 子协议={}",
+        addr,
+        path,
+        protocol.as_deref().unwrap_or("(无)")
+    );
+
+    let result = async {
+        let mut deadline = read.idle_timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let message = tokio::select! {
+                message = ws.next() => message,
+                Some(data) = say.recv() => {
+                    log.record(Direction::Sent, &data);
+                    // 合法的 UTF-8 按 Text 帧发送，其余按 Binary 帧发送
+                    let message = match String::from_utf8(data) {
+                        Ok(text) => Message::text(text),
+                        Err(e) => Message::binary(e.into_bytes()),
+                    };
+                    ws.send(message).await?;
+                    continue;
+                }
+                _ = shutdown.wait() => {
+                    return ws.close(None).await;
+                }
+                _ = idle_expired(deadline) => {
+                    println!("{} idle timeout, closing", addr);
+                    let frame = CloseFrame {
+                        code: CloseCode::Away,
+                        reason: "idle timeout".into(),
+                    };
+                    return ws.close(Some(frame)).await;
+                }
+            };
+            // 客户端未发送 Close 直接断开时视为正常结束
+            let Some(message) = message else {
+                return Ok(());
+            };
+            let message = match message {
+                Err(tungstenite::Error::Protocol(
+                    tungstenite::error::ProtocolError::ResetWithoutClosingHandshake,
+                )) => return Ok(()),
+                other => other?,
+            };
+            let data = match &message {
+                Message::Text(text) => text.as_bytes(),
+                Message::Binary(data) => data.as_ref(),
+                Message::Ping(_) => {
+                    println!("{} Ping", addr);
+                    continue;
+                }
+                Message::Close(frame) => {
+                    match frame {
+                        Some(frame) => println!("{} Close ({} {})", addr, frame.code, frame.reason),
+                        None => println!("{} Close", addr),
+                    }
+                    continue;
+                }
+                Message::Pong(_) | Message::Frame(_) => continue,
+            };
+            deadline = read.idle_timeout.map(|timeout| Instant::now() + timeout);
+            if let Err(max) = log.record_received(data) {
+                over_limit(addr, max);
+                let frame = CloseFrame {
+                    code: CloseCode::Size,
+                    reason: "message limit exceeded".into(),
+                };
+                return ws.close(Some(frame)).await;
+            }
+
+            let kind = if message.is_text() { "Text" } else { "Binary" };
+            println!(
+                "Received {} frame ({} bytes), echoing back:{}",
+                kind,
+                data.len(),
+                read.shown(data)
+            );
+            log.record(Direction::Sent, data);
+            ws.send(message).await?;
+        }
+    }
+    .await;
+
+    println!("Client disconnected");
+    match result {
+        Ok(())
+        | Err(tungstenite::Error::ConnectionClosed)
+        | Err(tungstenite::Error::AlreadyClosed) => Ok(()),
+        Err(tungstenite::Error::Io(e)) => ignore_disconnect(Err(e)),
+        Err(e) => Err(e.into()),
+    }
+}